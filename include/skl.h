@@ -0,0 +1,101 @@
+#ifndef SKL_H
+#define SKL_H
+
+/* Generated by cbindgen from src/ffi.rs, do not edit. */
+
+#include <stddef.h>
+#include <stdint.h>
+
+/**
+ * The function succeeded.
+ */
+#define SKL_OK 0
+
+/**
+ * The function failed, e.g. a pointer argument is null, the ARENA is full, or the map is
+ * read-only.
+ */
+#define SKL_ERROR -1
+
+/**
+ * An opaque iterator over the latest version of the entries of a map, in key order.
+ */
+typedef struct SklIter SklIter;
+
+/**
+ * An opaque handle of a [`SkipMap<u64>`](SkipMap).
+ */
+typedef struct SklMap SklMap;
+
+/**
+ * Creates a new map whose ARENA has `capacity` bytes, returns null if the map cannot be
+ * created.
+ *
+ * The map must be released by [`skl_free`].
+ */
+SklMap *skl_new(uint32_t capacity);
+
+/**
+ * Releases the map, the ARENA is released once all the iterators of the map are released.
+ */
+void skl_free(SklMap *map);
+
+/**
+ * Returns the number of the entries of the map, all versions and removed entries included
+ * (see [`SkipMap::len`]), or `0` if `map` is null.
+ */
+size_t skl_len(const SklMap *map);
+
+/**
+ * Upserts the value of the key with the version, see [`SkipMap::insert`].
+ */
+int skl_insert(const SklMap *map,
+               uint64_t version,
+               const uint8_t *key,
+               size_t key_len,
+               const uint8_t *value,
+               size_t value_len);
+
+/**
+ * Removes the key by writing a removed entry with the version, see [`SkipMap::remove`].
+ */
+int skl_remove(const SklMap *map, uint64_t version, const uint8_t *key, size_t key_len);
+
+/**
+ * Looks up the newest value of the key with the version less or equal to `version`.
+ *
+ * Returns `1` and writes the value to `value` and `value_len` if the key exists, `0` if it
+ * does not, or [`SKL_ERROR`] if an argument is invalid. The out pointers may be null.
+ */
+int skl_get(const SklMap *map,
+            uint64_t version,
+            const uint8_t *key,
+            size_t key_len,
+            const uint8_t **value,
+            size_t *value_len);
+
+/**
+ * Creates an iterator over the latest version (less or equal to `version`) of the entries of
+ * the map, returns null if `map` is null.
+ *
+ * The iterator keeps the ARENA alive, and must be released by [`skl_iter_free`].
+ */
+SklIter *skl_iter_new(const SklMap *map, uint64_t version);
+
+/**
+ * Advances the iterator, returns `1` and writes the entry to the out pointers, or `0` if the
+ * iterator is exhausted (or null). The out pointers may be null.
+ */
+int skl_iter_next(SklIter *iter,
+                  const uint8_t **key,
+                  size_t *key_len,
+                  const uint8_t **value,
+                  size_t *value_len,
+                  uint64_t *version);
+
+/**
+ * Releases the iterator.
+ */
+void skl_iter_free(SklIter *iter);
+
+#endif /* SKL_H */
@@ -0,0 +1,47 @@
+//! The [`Arena`] is a lock-free bump allocator over a refcounted region of memory (heap,
+//! anonymous memory map or file backed memory map), which the [`SkipMap`](crate::SkipMap)
+//! lives in.
+//!
+//! The ARENA is usable on its own, or shared with a map (see
+//! [`SkipMap::allocator`](crate::SkipMap::allocator)) to co-locate adjacent structures, e.g. hash
+//! indexes or queues, with the entries:
+//!
+//! - typed allocations with [`Arena::alloc`] and [`Arena::alloc_aligned_bytes`], and raw bytes
+//!   with [`Arena::alloc_bytes`];
+//! - the allocations are addressed by their offsets, which stay valid when the ARENA is memory
+//!   mapped at another address, see [`Arena::get_pointer`] and [`Arena::get_bytes`];
+//! - the ARENA is cloned by reference counting, and the region is released when the last
+//!   clone (including the maps) is dropped, see [`Arena::refs`].
+//!
+//! The allocations are returned to the ARENA when their handles are dropped, unless they are
+//! detached, and the detached allocations are never reclaimed until the ARENA is cleared.
+//!
+//! # Example
+//!
+//! ```rust
+//! use skl::{arena::Arena, SkipMap};
+//!
+//! let l = SkipMap::new().unwrap();
+//! l.insert(0, b"a", b"a").unwrap();
+//!
+//! // allocate a counter next to the entries
+//! let arena: Arena = l.allocator().clone();
+//! let mut counter = arena.alloc::<u64>().unwrap();
+//! let offset = counter.offset();
+//! unsafe {
+//!   counter.write(1);
+//!   counter.detach();
+//!   assert_eq!(*arena.get_pointer(offset).cast::<u64>(), 1);
+//! }
+//!
+//! // the region is shared with the map, and released with the last clone
+//! assert_eq!(arena.refs(), 2);
+//! drop(l);
+//! assert_eq!(arena.refs(), 1);
+//! ```
+
+pub use rarena_allocator::{Arena, ArenaOptions, Error, Freelist};
+
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
+pub use rarena_allocator::{MmapOptions, OpenOptions};
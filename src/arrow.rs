@@ -0,0 +1,161 @@
+use core::ops::RangeBounds;
+use std::{sync::Arc, vec::Vec};
+
+use arrow_array::{
+  builder::{BinaryBuilder, UInt64Builder},
+  ArrayRef, RecordBatch,
+};
+use arrow_schema::{ArrowError, DataType, Field, Schema, SchemaRef};
+
+use super::{map::EntryRef, Comparator, SkipMap, Trailer};
+
+/// Describes how to build the Arrow [`RecordBatch`]es from a range scan.
+///
+/// The produced schema always has the non-null `key` and `value` binary columns, the nullable
+/// `ttl` column is included if [`with_ttl`](RecordBatchHint::with_ttl) is set, and the non-null
+/// `seq` column (the version of the trailer) is included if [`with_seq`](RecordBatchHint::with_seq) is `true`.
+#[derive(Debug, Clone, Copy)]
+pub struct RecordBatchHint<T> {
+  batch_size: usize,
+  seq: bool,
+  ttl: Option<fn(&T) -> Option<u64>>,
+}
+
+impl<T> Default for RecordBatchHint<T> {
+  #[inline]
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<T> RecordBatchHint<T> {
+  /// Creates a new hint with the default values.
+  #[inline]
+  pub const fn new() -> Self {
+    Self {
+      batch_size: 1024,
+      seq: true,
+      ttl: None,
+    }
+  }
+
+  /// Set how many rows at most a [`RecordBatch`] contains.
+  ///
+  /// The default value is `1024`.
+  #[inline]
+  pub const fn with_batch_size(mut self, batch_size: usize) -> Self {
+    self.batch_size = if batch_size == 0 { 1 } else { batch_size };
+    self
+  }
+
+  /// Set whether to include the `seq` column.
+  ///
+  /// The default value is `true`.
+  #[inline]
+  pub const fn with_seq(mut self, seq: bool) -> Self {
+    self.seq = seq;
+    self
+  }
+
+  /// Set the function to extract the TTL from the trailer, and include the `ttl` column.
+  ///
+  /// The default value is `None`.
+  #[inline]
+  pub const fn with_ttl(mut self, ttl: fn(&T) -> Option<u64>) -> Self {
+    self.ttl = Some(ttl);
+    self
+  }
+
+  /// Returns the schema of the [`RecordBatch`]es built by this hint.
+  pub fn schema(&self) -> SchemaRef {
+    let mut fields = std::vec![
+      Field::new("key", DataType::Binary, false),
+      Field::new("value", DataType::Binary, false),
+    ];
+    if self.ttl.is_some() {
+      fields.push(Field::new("ttl", DataType::UInt64, true));
+    }
+    if self.seq {
+      fields.push(Field::new("seq", DataType::UInt64, false));
+    }
+    Arc::new(Schema::new(fields))
+  }
+}
+
+impl<T: Trailer, C: Comparator> SkipMap<T, C> {
+  /// Scans the latest version (less or equal to `version`) of the entries within the range,
+  /// and yields them as Arrow [`RecordBatch`]es, so analytical consumers can scan the map
+  /// without per-row overhead.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use skl::{arrow::RecordBatchHint, SkipMap};
+  ///
+  /// let l = SkipMap::new().unwrap();
+  /// for i in 0..10u8 {
+  ///   l.insert(0, &[i], &[i]).unwrap();
+  /// }
+  ///
+  /// let hint = RecordBatchHint::new().with_batch_size(4);
+  /// let rows = l
+  ///   .range_to_record_batch(0, ..=[9u8].as_slice(), hint)
+  ///   .map(|batch| batch.unwrap().num_rows())
+  ///   .collect::<Vec<_>>();
+  /// assert_eq!(rows, [4, 4, 2]);
+  /// ```
+  pub fn range_to_record_batch<'a, Q, R>(
+    &'a self,
+    version: u64,
+    range: R,
+    hint: RecordBatchHint<T>,
+  ) -> impl Iterator<Item = Result<RecordBatch, ArrowError>> + 'a
+  where
+    T: 'a,
+    &'a [u8]: PartialOrd<Q>,
+    Q: ?Sized + PartialOrd<&'a [u8]> + 'a,
+    R: RangeBounds<Q> + 'a,
+  {
+    let schema = hint.schema();
+    let mut iter = self.range(version, range).peekable();
+    core::iter::from_fn(move || {
+      iter.peek()?;
+      Some(build_batch(
+        &schema,
+        &hint,
+        iter.by_ref().take(hint.batch_size),
+      ))
+    })
+  }
+}
+
+fn build_batch<'a, T: Trailer + 'a>(
+  schema: &SchemaRef,
+  hint: &RecordBatchHint<T>,
+  entries: impl Iterator<Item = EntryRef<'a, T>>,
+) -> Result<RecordBatch, ArrowError> {
+  let mut keys = BinaryBuilder::new();
+  let mut values = BinaryBuilder::new();
+  let mut ttls = UInt64Builder::with_capacity(hint.batch_size);
+  let mut seqs = UInt64Builder::with_capacity(hint.batch_size);
+
+  for ent in entries {
+    keys.append_value(ent.key());
+    values.append_value(ent.value());
+    if let Some(ttl) = hint.ttl {
+      ttls.append_option(ttl(ent.trailer()));
+    }
+    if hint.seq {
+      seqs.append_value(ent.version());
+    }
+  }
+
+  let mut columns: Vec<ArrayRef> = std::vec![Arc::new(keys.finish()), Arc::new(values.finish())];
+  if hint.ttl.is_some() {
+    columns.push(Arc::new(ttls.finish()));
+  }
+  if hint.seq {
+    columns.push(Arc::new(seqs.finish()));
+  }
+  RecordBatch::try_new(schema.clone(), columns)
+}
@@ -0,0 +1,141 @@
+pub use super::arena::ArenaOptions;
+
+use super::{map::Error, Arena};
+
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+use super::{MmapOptions, OpenOptions};
+
+/// The memory backend of a [`SkipMap`](crate::SkipMap), which creates the ARENA the map lives
+/// in, see [`SkipMap::with_backend`](crate::SkipMap::with_backend).
+///
+/// The crate implements the backends of the std platforms: [`Heap`], and with the `memmap`
+/// feature [`AnonymousMmap`] and [`FileMmap`], which map the memory with `mmap` on unix and
+/// `MapViewOfFile` on Windows. Exotic targets can implement their own backend, e.g. to pick
+/// one of the backends at runtime, to prepare the file before it is mapped, or to report the
+/// failures with their own error type.
+///
+/// # Example
+///
+/// ```rust
+/// use core::sync::atomic::{AtomicUsize, Ordering};
+/// use skl::{
+///   backend::{ArenaOptions, Heap, StorageBackend},
+///   map::Error,
+///   Arena, Options, SkipMap,
+/// };
+///
+/// /// A heap backend which counts the created ARENAs.
+/// #[derive(Default)]
+/// struct Counted(AtomicUsize);
+///
+/// impl StorageBackend for Counted {
+///   type Error = Error;
+///
+///   fn create_arena(&self, opts: ArenaOptions) -> Result<Arena, Error> {
+///     self.0.fetch_add(1, Ordering::Relaxed);
+///     Heap.create_arena(opts)
+///   }
+/// }
+///
+/// let backend = Counted::default();
+/// let l = SkipMap::<u64>::with_backend(&backend, Options::new()).unwrap();
+/// l.insert(0, b"a", b"a").unwrap();
+/// assert_eq!(backend.0.load(Ordering::Relaxed), 1);
+/// ```
+pub trait StorageBackend {
+  /// The error returned when the ARENA or the map cannot be created.
+  type Error: From<Error>;
+
+  /// Creates the ARENA, the options are derived from the [`Options`](crate::Options) of the map.
+  fn create_arena(&self, opts: ArenaOptions) -> Result<Arena, Self::Error>;
+
+  /// Returns `true` if the memory outlives the process (e.g. a file), so the meta of the map
+  /// must be stored in the ARENA, see [`Options::with_unify`](crate::Options::with_unify).
+  ///
+  /// Default is `false`.
+  #[inline]
+  fn persistent(&self) -> bool {
+    false
+  }
+}
+
+impl<B: StorageBackend + ?Sized> StorageBackend for &B {
+  type Error = B::Error;
+
+  #[inline]
+  fn create_arena(&self, opts: ArenaOptions) -> Result<Arena, Self::Error> {
+    (**self).create_arena(opts)
+  }
+
+  #[inline]
+  fn persistent(&self) -> bool {
+    (**self).persistent()
+  }
+}
+
+/// The heap backend, the ARENA is allocated by the global allocator, same as
+/// [`SkipMap::new`](crate::SkipMap::new).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Heap;
+
+impl StorageBackend for Heap {
+  type Error = Error;
+
+  #[inline]
+  fn create_arena(&self, opts: ArenaOptions) -> Result<Arena, Error> {
+    Ok(Arena::new(opts))
+  }
+}
+
+/// The anonymous memory map backend, same as [`SkipMap::map_anon`](crate::SkipMap::map_anon).
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
+#[derive(Debug, Clone)]
+pub struct AnonymousMmap(pub MmapOptions);
+
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+impl StorageBackend for AnonymousMmap {
+  type Error = std::io::Error;
+
+  #[inline]
+  fn create_arena(&self, opts: ArenaOptions) -> Result<Arena, Self::Error> {
+    Arena::map_anon(opts, self.0.clone())
+  }
+}
+
+/// The file backed memory map backend, the file is created or truncated and the map is
+/// initialized, same as [`SkipMap::map_mut`](crate::SkipMap::map_mut).
+///
+/// Reopening an existing file is not a backend concern, use [`SkipMap::map`](crate::SkipMap::map),
+/// which also checks the versions of the file.
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
+#[derive(Debug, Clone)]
+pub struct FileMmap<P> {
+  /// The path of the file.
+  pub path: P,
+  /// The options to open the file.
+  pub open_options: OpenOptions,
+  /// The options to map the file.
+  pub mmap_options: MmapOptions,
+}
+
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+impl<P: AsRef<std::path::Path>> StorageBackend for FileMmap<P> {
+  type Error = std::io::Error;
+
+  #[inline]
+  fn create_arena(&self, opts: ArenaOptions) -> Result<Arena, Self::Error> {
+    Arena::map_mut(
+      self.path.as_ref(),
+      opts,
+      self.open_options.clone(),
+      self.mmap_options.clone(),
+    )
+  }
+
+  #[inline]
+  fn persistent(&self) -> bool {
+    true
+  }
+}
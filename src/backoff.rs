@@ -0,0 +1,71 @@
+/// The largest exponent used when spinning, so a misconfigured spin limit cannot overflow the shift.
+const MAX_SPIN_LIMIT: u8 = 16;
+
+/// Exponential backoff used by the CAS retry loops of the [`SkipMap`](crate::SkipMap).
+///
+/// The first retries spin for an exponentially growing number of iterations,
+/// once the spin limit is reached, the current thread yields (under `std`).
+#[derive(Debug)]
+pub(crate) struct Backoff {
+  step: u8,
+  spin_limit: u8,
+  yield_limit: u8,
+}
+
+impl Backoff {
+  #[inline]
+  pub(crate) const fn new(spin_limit: u8, yield_limit: u8) -> Self {
+    Self {
+      step: 0,
+      spin_limit: if spin_limit > MAX_SPIN_LIMIT {
+        MAX_SPIN_LIMIT
+      } else {
+        spin_limit
+      },
+      yield_limit,
+    }
+  }
+
+  /// Backs off in a CAS retry loop.
+  #[inline]
+  pub(crate) fn snooze(&mut self) {
+    if self.step <= self.spin_limit {
+      for _ in 0..1u32 << self.step {
+        core::hint::spin_loop();
+      }
+    } else {
+      #[cfg(feature = "std")]
+      std::thread::yield_now();
+
+      #[cfg(not(feature = "std"))]
+      for _ in 0..1u32 << self.spin_limit {
+        core::hint::spin_loop();
+      }
+    }
+
+    if self.step <= self.yield_limit {
+      self.step = self.step.saturating_add(1);
+    }
+  }
+}
+
+#[cfg(test)]
+#[test]
+fn test_backoff() {
+  let mut backoff = Backoff::new(u8::MAX, 2);
+  // the spin limit is clamped, so the shift cannot overflow
+  assert_eq!(backoff.spin_limit, MAX_SPIN_LIMIT);
+  for step in 1..=3 {
+    backoff.snooze();
+    assert_eq!(backoff.step, step);
+  }
+  // the backoff stops growing after the yield limit
+  backoff.snooze();
+  assert_eq!(backoff.step, 3);
+
+  let mut backoff = Backoff::new(0, u8::MAX);
+  for _ in 0..300 {
+    backoff.snooze();
+  }
+  assert_eq!(backoff.step, u8::MAX);
+}
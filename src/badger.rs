@@ -0,0 +1,349 @@
+use super::{map::EntryRef, TooLarge, VacantBuffer};
+
+/// The maximum encoded size of a `u64` uvarint.
+const MAX_VARINT_LEN64: usize = 10;
+
+/// The bit of the meta which marks the entry as deleted, same as Badger's `bitDelete`.
+pub const BIT_DELETE: u8 = 1 << 0;
+
+/// The bit of the meta which marks the value as a value pointer (`ValuePointer` with the
+/// `value-log` feature), same as Badger's `bitValuePointer`.
+pub const BIT_VALUE_POINTER: u8 = 1 << 1;
+
+/// The bit of the meta which allows the older versions of the key to be discarded, same as
+/// Badger's `bitDiscardEarlierVersions`.
+pub const BIT_DISCARD_EARLIER_VERSIONS: u8 = 1 << 2;
+
+/// The bit of the meta which marks the value as a merge operand, same as Badger's
+/// `bitMergeEntry`.
+pub const BIT_MERGE_ENTRY: u8 = 1 << 3;
+
+/// The bit of the meta which marks the entry as a part of a transaction, same as Badger's
+/// `bitTxn`.
+pub const BIT_TXN: u8 = 1 << 6;
+
+/// The bit of the meta which marks the end of a transaction, same as Badger's `bitFinTxn`.
+pub const BIT_FIN_TXN: u8 = 1 << 7;
+
+/// A pointer to a value stored in a value log file, which matches Badger's `valuePointer`,
+/// so the large values can be stored out of the ARENA (WiscKey-style), and only the pointer
+/// lives in the map.
+///
+/// The encoded layout is the file id, the length and the offset, as little endian `u32`s, same
+/// as `valuePointer.Encode` on the little endian platforms.
+///
+/// The map does not know about the value log: the value pointer is stored as the value of a
+/// [`ValueStruct`] like any other bytes, and [`SkipMap::get`](crate::SkipMap::get) returns the
+/// encoded value struct in both forms. Use [`ValueStruct::value_pointer`] (or
+/// [`ValueStruct::is_value_pointer`]) on the decoded value to tell the pointer form from an
+/// inline value, and read the value from the value log.
+///
+/// # Example
+///
+/// ```rust
+/// use skl::{badger::{ValuePointer, ValueStruct}, SkipMap};
+///
+/// let l = SkipMap::new().unwrap();
+/// let vp = ValuePointer::new(1, 4096, 128);
+/// let encoded = vp.encode();
+/// let vs = ValueStruct::new(b"").with_value_pointer(&encoded);
+///
+/// l.insert_with_value(0, b"k", vs.encoded_size() as u32, |buf| vs.encode(buf))
+///   .unwrap();
+///
+/// let ent = l.get(0, b"k").unwrap();
+/// let vs = ValueStruct::decode(ent.value()).unwrap();
+/// assert_eq!(vs.value_pointer(), Some(vp));
+/// ```
+#[cfg(feature = "value-log")]
+#[cfg_attr(docsrs, doc(cfg(feature = "value-log")))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ValuePointer {
+  fid: u32,
+  len: u32,
+  offset: u32,
+}
+
+#[cfg(feature = "value-log")]
+impl ValuePointer {
+  /// The encoded size of the value pointer.
+  pub const ENCODED_SIZE: usize = 12;
+
+  /// Creates a new value pointer.
+  #[inline]
+  pub const fn new(fid: u32, len: u32, offset: u32) -> Self {
+    Self { fid, len, offset }
+  }
+
+  /// Returns the id of the value log file.
+  #[inline]
+  pub const fn fid(&self) -> u32 {
+    self.fid
+  }
+
+  /// Returns the length of the value in the value log file.
+  #[inline]
+  pub const fn len(&self) -> u32 {
+    self.len
+  }
+
+  /// Returns `true` if the length of the value is zero.
+  #[inline]
+  pub const fn is_empty(&self) -> bool {
+    self.len == 0
+  }
+
+  /// Returns the offset of the value in the value log file.
+  #[inline]
+  pub const fn offset(&self) -> u32 {
+    self.offset
+  }
+
+  /// Encodes the value pointer.
+  #[inline]
+  pub fn encode(&self) -> [u8; Self::ENCODED_SIZE] {
+    let mut buf = [0; Self::ENCODED_SIZE];
+    buf[..4].copy_from_slice(&self.fid.to_le_bytes());
+    buf[4..8].copy_from_slice(&self.len.to_le_bytes());
+    buf[8..].copy_from_slice(&self.offset.to_le_bytes());
+    buf
+  }
+
+  /// Decodes the value pointer from the bytes, returns `None` if the size of the bytes is not
+  /// [`ENCODED_SIZE`](ValuePointer::ENCODED_SIZE).
+  #[inline]
+  pub fn decode(src: &[u8]) -> Option<Self> {
+    if src.len() != Self::ENCODED_SIZE {
+      return None;
+    }
+
+    let u32_at = |i: usize| u32::from_le_bytes([src[i], src[i + 1], src[i + 2], src[i + 3]]);
+    Some(Self {
+      fid: u32_at(0),
+      len: u32_at(4),
+      offset: u32_at(8),
+    })
+  }
+}
+
+/// A value encoding which matches Badger's `y.ValueStruct`, so Go/Rust hybrid systems or
+/// Badger data importers can reuse the existing parsing.
+///
+/// The layout is:
+///
+/// ```text
+/// +----------+---------------+-----------------------+-------------+
+/// | meta: u8 | user_meta: u8 | expires_at: uvarint64 | value bytes |
+/// +----------+---------------+-----------------------+-------------+
+/// ```
+///
+/// # Example
+///
+/// ```rust
+/// use skl::{badger::ValueStruct, SkipMap};
+///
+/// let l = SkipMap::new().unwrap();
+/// let vs = ValueStruct::new(b"alice").with_user_meta(1).with_expires_at(1_700_000_000);
+///
+/// l.insert_with_value(0, b"k", vs.encoded_size() as u32, |buf| vs.encode(buf))
+///   .unwrap();
+///
+/// let ent = l.get(0, b"k").unwrap();
+/// assert_eq!(ValueStruct::decode(ent.value()), Some(vs));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ValueStruct<'a> {
+  meta: u8,
+  user_meta: u8,
+  expires_at: u64,
+  value: &'a [u8],
+}
+
+impl<'a> ValueStruct<'a> {
+  /// Creates a new value struct with the given value, the other fields are zero.
+  #[inline]
+  pub const fn new(value: &'a [u8]) -> Self {
+    Self {
+      meta: 0,
+      user_meta: 0,
+      expires_at: 0,
+      value,
+    }
+  }
+
+  /// Set the meta of the value struct.
+  #[inline]
+  pub const fn with_meta(mut self, meta: u8) -> Self {
+    self.meta = meta;
+    self
+  }
+
+  /// Set the user meta of the value struct.
+  #[inline]
+  pub const fn with_user_meta(mut self, user_meta: u8) -> Self {
+    self.user_meta = user_meta;
+    self
+  }
+
+  /// Set the expiration time (unix timestamp in seconds) of the value struct, `0` means never expire.
+  #[inline]
+  pub const fn with_expires_at(mut self, expires_at: u64) -> Self {
+    self.expires_at = expires_at;
+    self
+  }
+
+  /// Set the value to the encoded [`ValuePointer`], and marks the meta with
+  /// [`BIT_VALUE_POINTER`].
+  #[cfg(feature = "value-log")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "value-log")))]
+  #[inline]
+  pub const fn with_value_pointer(mut self, vp: &'a [u8; ValuePointer::ENCODED_SIZE]) -> Self {
+    self.meta |= BIT_VALUE_POINTER;
+    self.value = vp;
+    self
+  }
+
+  /// Returns `true` if the meta is marked with [`BIT_VALUE_POINTER`], so the value is a value
+  /// pointer rather than the value itself.
+  #[inline]
+  pub const fn is_value_pointer(&self) -> bool {
+    self.meta & BIT_VALUE_POINTER != 0
+  }
+
+  /// Returns the [`ValuePointer`] if the value is a value pointer, `None` if the value is
+  /// stored inline or the pointer is corrupted.
+  #[cfg(feature = "value-log")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "value-log")))]
+  #[inline]
+  pub fn value_pointer(&self) -> Option<ValuePointer> {
+    if self.is_value_pointer() {
+      ValuePointer::decode(self.value)
+    } else {
+      None
+    }
+  }
+
+  /// Returns the meta of the value struct.
+  #[inline]
+  pub const fn meta(&self) -> u8 {
+    self.meta
+  }
+
+  /// Returns the user meta of the value struct.
+  #[inline]
+  pub const fn user_meta(&self) -> u8 {
+    self.user_meta
+  }
+
+  /// Returns the expiration time of the value struct.
+  #[inline]
+  pub const fn expires_at(&self) -> u64 {
+    self.expires_at
+  }
+
+  /// Returns the value of the value struct.
+  #[inline]
+  pub const fn value(&self) -> &'a [u8] {
+    self.value
+  }
+
+  /// Returns the encoded size of the value struct.
+  #[inline]
+  pub const fn encoded_size(&self) -> usize {
+    2 + varint_len(self.expires_at) + self.value.len()
+  }
+
+  /// Encodes the value struct to the buffer.
+  pub fn encode(&self, buf: &mut VacantBuffer<'_>) -> Result<(), TooLarge> {
+    let mut varint = [0; MAX_VARINT_LEN64];
+    let len = encode_varint(self.expires_at, &mut varint);
+    buf.write(&[self.meta, self.user_meta])?;
+    buf.write(&varint[..len])?;
+    buf.write(self.value)
+  }
+
+  /// Decodes the value struct from the bytes, returns `None` if the bytes are corrupted.
+  pub fn decode(src: &'a [u8]) -> Option<Self> {
+    if src.len() < 2 {
+      return None;
+    }
+
+    let (expires_at, len) = decode_varint(&src[2..])?;
+    Some(Self {
+      meta: src[0],
+      user_meta: src[1],
+      expires_at,
+      value: &src[2 + len..],
+    })
+  }
+}
+
+impl<'a, T> EntryRef<'a, T> {
+  /// Decodes the value of the entry as a [`ValueStruct`], so the meta and the user meta are
+  /// available on the reads and in the iterators, returns `None` if the value is not a valid
+  /// value struct.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use skl::{badger::{ValueStruct, BIT_MERGE_ENTRY}, SkipMap};
+  ///
+  /// let l = SkipMap::new().unwrap();
+  /// let vs = ValueStruct::new(b"+1").with_meta(BIT_MERGE_ENTRY).with_user_meta(7);
+  /// l.insert_with_value(0, b"k", vs.encoded_size() as u32, |buf| vs.encode(buf))
+  ///   .unwrap();
+  ///
+  /// for ent in l.iter(0) {
+  ///   let vs = ent.value_struct().unwrap();
+  ///   assert_eq!(vs.meta() & BIT_MERGE_ENTRY, BIT_MERGE_ENTRY);
+  ///   assert_eq!(vs.user_meta(), 7);
+  /// }
+  /// ```
+  #[inline]
+  pub fn value_struct(&self) -> Option<ValueStruct<'a>> {
+    ValueStruct::decode(self.value())
+  }
+}
+
+#[inline]
+const fn varint_len(mut x: u64) -> usize {
+  let mut len = 1;
+  while x >= 0x80 {
+    x >>= 7;
+    len += 1;
+  }
+  len
+}
+
+#[inline]
+fn encode_varint(mut x: u64, buf: &mut [u8; MAX_VARINT_LEN64]) -> usize {
+  let mut i = 0;
+  while x >= 0x80 {
+    buf[i] = (x as u8) | 0x80;
+    x >>= 7;
+    i += 1;
+  }
+  buf[i] = x as u8;
+  i + 1
+}
+
+#[inline]
+fn decode_varint(buf: &[u8]) -> Option<(u64, usize)> {
+  let mut x = 0u64;
+  let mut shift = 0;
+  for (i, &b) in buf.iter().enumerate() {
+    if i == MAX_VARINT_LEN64 {
+      return None;
+    }
+
+    if b < 0x80 {
+      if i == MAX_VARINT_LEN64 - 1 && b > 1 {
+        return None;
+      }
+      return Some((x | ((b as u64) << shift), i + 1));
+    }
+    x |= ((b & 0x7f) as u64) << shift;
+    shift += 7;
+  }
+  None
+}
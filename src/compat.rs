@@ -0,0 +1,193 @@
+use core::ops::RangeBounds;
+
+use super::{
+  map::{EntryRef, Error},
+  Ascend, Comparator, Options, SkipMap,
+};
+
+#[cfg(feature = "compat-crossbeam")]
+mod crossbeam;
+#[cfg(feature = "compat-crossbeam")]
+#[cfg_attr(docsrs, doc(cfg(feature = "compat-crossbeam")))]
+pub use crossbeam::*;
+
+/// A wrapper of [`SkipMap`] which exposes the method names and signatures of
+/// [`BTreeMap`](std::collections::BTreeMap), so code using ordered maps can migrate with
+/// minimal edits.
+///
+/// Entries are stored without versions (the trailer is `()`), and all methods take `&self`
+/// because the underlying [`SkipMap`] is concurrent.
+///
+/// # Example
+///
+/// ```rust
+/// use skl::compat::OrderedMap;
+///
+/// let map = OrderedMap::new().unwrap();
+/// map.insert(b"b", b"2").unwrap();
+/// map.insert(b"a", b"1").unwrap();
+///
+/// assert_eq!(map.get(b"a"), Some(&b"1"[..]));
+/// assert_eq!(map.keys().collect::<Vec<_>>(), [b"a", b"b"]);
+///
+/// map.remove(b"a").unwrap();
+/// assert!(!map.contains_key(b"a"));
+/// ```
+#[derive(Debug)]
+pub struct OrderedMap<C = Ascend> {
+  map: SkipMap<(), C>,
+}
+
+impl<C: Clone> Clone for OrderedMap<C> {
+  #[inline]
+  fn clone(&self) -> Self {
+    Self {
+      map: self.map.clone(),
+    }
+  }
+}
+
+impl<C> From<SkipMap<(), C>> for OrderedMap<C> {
+  #[inline]
+  fn from(map: SkipMap<(), C>) -> Self {
+    Self { map }
+  }
+}
+
+impl OrderedMap {
+  /// Creates a new map with the default options.
+  #[inline]
+  pub fn new() -> Result<Self, Error> {
+    SkipMap::new().map(Self::from)
+  }
+
+  /// Creates a new map with the given options.
+  #[inline]
+  pub fn with_options(opts: Options) -> Result<Self, Error> {
+    SkipMap::with_options(opts).map(Self::from)
+  }
+}
+
+impl<C> OrderedMap<C> {
+  /// Creates a new map with the given comparator.
+  #[inline]
+  pub fn with_comparator(cmp: C) -> Result<Self, Error> {
+    SkipMap::with_comparator(cmp).map(Self::from)
+  }
+
+  /// Returns the inner [`SkipMap`].
+  #[inline]
+  pub const fn as_inner(&self) -> &SkipMap<(), C> {
+    &self.map
+  }
+
+  /// Consumes the map and returns the inner [`SkipMap`].
+  #[inline]
+  pub fn into_inner(self) -> SkipMap<(), C> {
+    self.map
+  }
+
+  /// Returns the number of elements in the map.
+  #[inline]
+  pub fn len(&self) -> usize {
+    self.map.len()
+  }
+
+  /// Returns `true` if the map contains no elements.
+  #[inline]
+  pub fn is_empty(&self) -> bool {
+    self.map.is_empty()
+  }
+}
+
+impl<C: Comparator> OrderedMap<C> {
+  /// Inserts a key-value pair into the map, returns the old entry if the key was present.
+  #[inline]
+  pub fn insert<'a, 'b: 'a>(
+    &'a self,
+    key: &'b [u8],
+    value: &'b [u8],
+  ) -> Result<Option<EntryRef<'a, ()>>, Error> {
+    self.map.insert((), key, value)
+  }
+
+  /// Returns the value corresponding to the key.
+  #[inline]
+  pub fn get(&self, key: &[u8]) -> Option<&[u8]> {
+    self.map.get_in(0, key).map(|ent| ent.into_key_value().1)
+  }
+
+  /// Returns the key-value pair corresponding to the key.
+  #[inline]
+  pub fn get_key_value(&self, key: &[u8]) -> Option<(&[u8], &[u8])> {
+    self.map.get_in(0, key).map(EntryRef::into_key_value)
+  }
+
+  /// Returns `true` if the map contains a value for the specified key.
+  #[inline]
+  pub fn contains_key(&self, key: &[u8]) -> bool {
+    self.map.get_in(0, key).is_some()
+  }
+
+  /// Removes a key from the map, returns the entry observed before the removal if the key was present.
+  pub fn remove<'a, 'b: 'a>(&'a self, key: &'b [u8]) -> Result<Option<EntryRef<'a, ()>>, Error> {
+    let old = self.map.get_in(0, key);
+    if old.is_none() {
+      return Ok(None);
+    }
+
+    self
+      .map
+      .compare_remove(
+        (),
+        key,
+        core::sync::atomic::Ordering::AcqRel,
+        core::sync::atomic::Ordering::Relaxed,
+      )
+      .map(|_| old)
+  }
+
+  /// Returns the first key-value pair in the map.
+  #[inline]
+  pub fn first_key_value(&self) -> Option<(&[u8], &[u8])> {
+    self.map.first(0).map(EntryRef::into_key_value)
+  }
+
+  /// Returns the last key-value pair in the map.
+  #[inline]
+  pub fn last_key_value(&self) -> Option<(&[u8], &[u8])> {
+    self.map.last(0).map(EntryRef::into_key_value)
+  }
+
+  /// Gets an iterator over the entries of the map, sorted by key.
+  #[inline]
+  pub fn iter(&self) -> impl DoubleEndedIterator<Item = (&[u8], &[u8])> {
+    self.map.iter(0).map(EntryRef::into_key_value)
+  }
+
+  /// Gets an iterator over the keys of the map, in sorted order.
+  #[inline]
+  pub fn keys(&self) -> impl DoubleEndedIterator<Item = &[u8]> {
+    self.map.iter(0).map(|ent| ent.into_key_value().0)
+  }
+
+  /// Gets an iterator over the values of the map, in order by key.
+  #[inline]
+  pub fn values(&self) -> impl DoubleEndedIterator<Item = &[u8]> {
+    self.map.iter(0).map(|ent| ent.into_key_value().1)
+  }
+
+  /// Constructs a double-ended iterator over a sub-range of entries in the map.
+  #[inline]
+  pub fn range<'a, Q, R>(
+    &'a self,
+    range: R,
+  ) -> impl DoubleEndedIterator<Item = (&'a [u8], &'a [u8])>
+  where
+    &'a [u8]: PartialOrd<Q>,
+    Q: ?Sized + PartialOrd<&'a [u8]> + 'a,
+    R: RangeBounds<Q> + 'a,
+  {
+    self.map.range(0, range).map(EntryRef::into_key_value)
+  }
+}
@@ -0,0 +1,184 @@
+use core::ops::{Bound, RangeBounds};
+
+use super::super::{
+  map::{EntryRef, Error, Iter},
+  Ascend, Comparator, Options, SkipMap,
+};
+
+/// A wrapper of [`SkipMap`] which exposes the method names and shapes of
+/// [`crossbeam_skiplist::SkipMap`](https://docs.rs/crossbeam-skiplist/latest/crossbeam_skiplist/struct.SkipMap.html),
+/// so concurrent code using it can switch to the ARENA backed map with minimal edits.
+///
+/// The entries are stored without versions (the trailer is `()`), and the returned
+/// [`EntryRef`]s have the `key()` and `value()` accessors of the crossbeam entries. The
+/// differences are:
+///
+/// - the keys and the values are byte slices, copied into the ARENA;
+/// - the writes return a [`Result`], as the ARENA can be full, and
+///   [`insert`](CrossbeamMap::insert) does not return the new entry, use
+///   [`get`](CrossbeamMap::get) to read it back;
+/// - a removed entry is not reclaimed until the ARENA is, so the entries returned before the
+///   removal stay readable.
+///
+/// # Example
+///
+/// ```rust
+/// use skl::compat::CrossbeamMap;
+///
+/// let map = CrossbeamMap::new().unwrap();
+/// map.insert(b"b", b"2").unwrap();
+/// map.insert(b"a", b"1").unwrap();
+///
+/// assert_eq!(map.get(b"a").unwrap().value(), b"1");
+/// assert_eq!(map.front().unwrap().key(), b"a");
+/// assert_eq!(map.back().unwrap().key(), b"b");
+/// assert_eq!(map.iter().count(), 2);
+///
+/// map.remove(b"a").unwrap();
+/// assert!(!map.contains_key(b"a"));
+/// ```
+#[derive(Debug)]
+pub struct CrossbeamMap<C = Ascend> {
+  map: SkipMap<(), C>,
+}
+
+impl<C: Clone> Clone for CrossbeamMap<C> {
+  #[inline]
+  fn clone(&self) -> Self {
+    Self {
+      map: self.map.clone(),
+    }
+  }
+}
+
+impl<C> From<SkipMap<(), C>> for CrossbeamMap<C> {
+  #[inline]
+  fn from(map: SkipMap<(), C>) -> Self {
+    Self { map }
+  }
+}
+
+impl CrossbeamMap {
+  /// Creates a new map with the default options.
+  #[inline]
+  pub fn new() -> Result<Self, Error> {
+    SkipMap::new().map(Self::from)
+  }
+
+  /// Creates a new map with the given options.
+  #[inline]
+  pub fn with_options(opts: Options) -> Result<Self, Error> {
+    SkipMap::with_options(opts).map(Self::from)
+  }
+}
+
+impl<C> CrossbeamMap<C> {
+  /// Creates a new map with the given comparator.
+  #[inline]
+  pub fn with_comparator(cmp: C) -> Result<Self, Error> {
+    SkipMap::with_comparator(cmp).map(Self::from)
+  }
+
+  /// Returns the inner [`SkipMap`].
+  #[inline]
+  pub const fn as_inner(&self) -> &SkipMap<(), C> {
+    &self.map
+  }
+
+  /// Consumes the map and returns the inner [`SkipMap`].
+  #[inline]
+  pub fn into_inner(self) -> SkipMap<(), C> {
+    self.map
+  }
+
+  /// Returns the number of entries in the map.
+  #[inline]
+  pub fn len(&self) -> usize {
+    self.map.len()
+  }
+
+  /// Returns `true` if the map is empty.
+  #[inline]
+  pub fn is_empty(&self) -> bool {
+    self.map.is_empty()
+  }
+}
+
+impl<C: Comparator> CrossbeamMap<C> {
+  /// Inserts a key-value pair into the map, replacing the value of the existing key.
+  #[inline]
+  pub fn insert(&self, key: &[u8], value: &[u8]) -> Result<(), Error> {
+    self.map.insert((), key, value).map(|_| ())
+  }
+
+  /// Removes the key from the map, returns the removed entry if the key was present.
+  pub fn remove<'a, 'b: 'a>(&'a self, key: &'b [u8]) -> Result<Option<EntryRef<'a, ()>>, Error> {
+    let old = self.map.get_in(0, key);
+    if old.is_none() {
+      return Ok(None);
+    }
+
+    self
+      .map
+      .compare_remove(
+        (),
+        key,
+        core::sync::atomic::Ordering::AcqRel,
+        core::sync::atomic::Ordering::Relaxed,
+      )
+      .map(|_| old)
+  }
+
+  /// Returns the entry of the key.
+  #[inline]
+  pub fn get<'a, 'b: 'a>(&'a self, key: &'b [u8]) -> Option<EntryRef<'a, ()>> {
+    self.map.get_in(0, key)
+  }
+
+  /// Returns `true` if the map contains the key.
+  #[inline]
+  pub fn contains_key(&self, key: &[u8]) -> bool {
+    self.map.get_in(0, key).is_some()
+  }
+
+  /// Returns the entry with the smallest key.
+  #[inline]
+  pub fn front(&self) -> Option<EntryRef<'_, ()>> {
+    self.map.first(0)
+  }
+
+  /// Returns the entry with the largest key.
+  #[inline]
+  pub fn back(&self) -> Option<EntryRef<'_, ()>> {
+    self.map.last(0)
+  }
+
+  /// Returns the entry with the smallest key above the bound.
+  #[inline]
+  pub fn lower_bound<'a, 'b: 'a>(&'a self, bound: Bound<&'b [u8]>) -> Option<EntryRef<'a, ()>> {
+    self.map.lower_bound(0, bound)
+  }
+
+  /// Returns the entry with the largest key below the bound.
+  #[inline]
+  pub fn upper_bound<'a, 'b: 'a>(&'a self, bound: Bound<&'b [u8]>) -> Option<EntryRef<'a, ()>> {
+    self.map.upper_bound(0, bound)
+  }
+
+  /// Returns an iterator over the entries of the map, sorted by key.
+  #[inline]
+  pub const fn iter(&self) -> Iter<'_, (), C> {
+    self.map.iter(0)
+  }
+
+  /// Returns an iterator over the entries of the map within the range, sorted by key.
+  #[inline]
+  pub fn range<'a, Q, R>(&'a self, range: R) -> Iter<'a, (), C, Q, R>
+  where
+    &'a [u8]: PartialOrd<Q>,
+    Q: ?Sized + PartialOrd<&'a [u8]>,
+    R: RangeBounds<Q> + 'a,
+  {
+    self.map.range(0, range)
+  }
+}
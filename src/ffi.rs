@@ -0,0 +1,247 @@
+//! The C API of the [`SkipMap`], to embed the map in C/C++ storage engines.
+//!
+//! The map is [`SkipMap<u64>`](SkipMap), the trailer is the version of the entry. The maps
+//! and the iterators are opaque pointers, created by `skl_new` and `skl_iter_new`, and
+//! released by `skl_free` and `skl_iter_free`. The functions which may fail return
+//! [`SKL_OK`] or [`SKL_ERROR`].
+//!
+//! The keys and the values returned by `skl_get` and `skl_iter_next` point into the ARENA
+//! without copying, they are valid as long as the map or any iterator of the map is alive.
+//!
+//! `include/skl.h` is generated from this module with `cbindgen --config cbindgen.toml`.
+//!
+//! # Example
+//!
+//! ```rust
+//! use skl::ffi::*;
+//!
+//! unsafe {
+//!   let map = skl_new(1 << 20);
+//!   assert!(!map.is_null());
+//!   assert_eq!(skl_insert(map, 0, b"a".as_ptr(), 1, b"a1".as_ptr(), 2), SKL_OK);
+//!
+//!   let (mut value, mut value_len) = (core::ptr::null(), 0);
+//!   assert_eq!(skl_get(map, 0, b"a".as_ptr(), 1, &mut value, &mut value_len), 1);
+//!   assert_eq!(core::slice::from_raw_parts(value, value_len), b"a1");
+//!
+//!   skl_free(map);
+//! }
+//! ```
+
+use core::ptr;
+use std::{boxed::Box, os::raw::c_int};
+
+use super::{map::IntoIter, Ascend, Options, SkipMap};
+
+/// The function succeeded.
+pub const SKL_OK: c_int = 0;
+
+/// The function failed, e.g. a pointer argument is null, the ARENA is full, or the map is
+/// read-only.
+pub const SKL_ERROR: c_int = -1;
+
+/// An opaque handle of a [`SkipMap<u64>`](SkipMap).
+pub struct SklMap {
+  map: SkipMap<u64, Ascend>,
+}
+
+/// An opaque iterator over the latest version of the entries of a map, in key order.
+pub struct SklIter {
+  iter: IntoIter<u64, Ascend>,
+}
+
+/// Returns the slice of the raw parts, which may be null if `len` is `0`.
+///
+/// # Safety
+/// - If `len` is not `0`, `ptr` must be valid for reads of `len` bytes.
+#[inline]
+unsafe fn slice<'a>(ptr: *const u8, len: usize) -> Option<&'a [u8]> {
+  if len == 0 {
+    Some(&[])
+  } else if ptr.is_null() {
+    None
+  } else {
+    Some(core::slice::from_raw_parts(ptr, len))
+  }
+}
+
+/// Writes the slice to the out pointers, if they are not null.
+///
+/// # Safety
+/// - The out pointers must be null or valid for writes.
+#[inline]
+unsafe fn write_slice(src: &[u8], ptr: *mut *const u8, len: *mut usize) {
+  if !ptr.is_null() {
+    *ptr = src.as_ptr();
+  }
+  if !len.is_null() {
+    *len = src.len();
+  }
+}
+
+/// Creates a new map whose ARENA has `capacity` bytes, returns null if the map cannot be
+/// created.
+///
+/// The map must be released by [`skl_free`].
+#[no_mangle]
+pub extern "C" fn skl_new(capacity: u32) -> *mut SklMap {
+  match SkipMap::with_options(Options::new().with_capacity(capacity)) {
+    Ok(map) => Box::into_raw(Box::new(SklMap { map })),
+    Err(_) => ptr::null_mut(),
+  }
+}
+
+/// Releases the map, the ARENA is released once all the iterators of the map are released.
+///
+/// # Safety
+/// - `map` must be null or returned by [`skl_new`], and must not be used after this call.
+#[no_mangle]
+pub unsafe extern "C" fn skl_free(map: *mut SklMap) {
+  if !map.is_null() {
+    drop(Box::from_raw(map));
+  }
+}
+
+/// Returns the number of the entries of the map, all versions and removed entries included
+/// (see [`SkipMap::len`]), or `0` if `map` is null.
+///
+/// # Safety
+/// - `map` must be null or a live map returned by [`skl_new`].
+#[no_mangle]
+pub unsafe extern "C" fn skl_len(map: *const SklMap) -> usize {
+  map.as_ref().map_or(0, |m| m.map.len())
+}
+
+/// Upserts the value of the key with the version, see [`SkipMap::insert`].
+///
+/// # Safety
+/// - `map` must be null or a live map returned by [`skl_new`].
+/// - `key` and `value` must be valid for reads of `key_len` and `value_len` bytes, they may
+///   be null if the length is `0`.
+#[no_mangle]
+pub unsafe extern "C" fn skl_insert(
+  map: *const SklMap,
+  version: u64,
+  key: *const u8,
+  key_len: usize,
+  value: *const u8,
+  value_len: usize,
+) -> c_int {
+  let (map, key, value) = match (map.as_ref(), slice(key, key_len), slice(value, value_len)) {
+    (Some(map), Some(key), Some(value)) => (map, key, value),
+    _ => return SKL_ERROR,
+  };
+  match map.map.insert(version, key, value) {
+    Ok(_) => SKL_OK,
+    Err(_) => SKL_ERROR,
+  }
+}
+
+/// Removes the key by writing a removed entry with the version, see [`SkipMap::remove`].
+///
+/// # Safety
+/// - `map` must be null or a live map returned by [`skl_new`].
+/// - `key` must be valid for reads of `key_len` bytes, it may be null if `key_len` is `0`.
+#[no_mangle]
+pub unsafe extern "C" fn skl_remove(
+  map: *const SklMap,
+  version: u64,
+  key: *const u8,
+  key_len: usize,
+) -> c_int {
+  let (map, key) = match (map.as_ref(), slice(key, key_len)) {
+    (Some(map), Some(key)) => (map, key),
+    _ => return SKL_ERROR,
+  };
+  match map.map.remove(version, key) {
+    Ok(_) => SKL_OK,
+    Err(_) => SKL_ERROR,
+  }
+}
+
+/// Looks up the newest value of the key with the version less or equal to `version`.
+///
+/// Returns `1` and writes the value to `value` and `value_len` if the key exists, `0` if it
+/// does not, or [`SKL_ERROR`] if an argument is invalid. The out pointers may be null.
+///
+/// # Safety
+/// - `map` must be null or a live map returned by [`skl_new`].
+/// - `key` must be valid for reads of `key_len` bytes, it may be null if `key_len` is `0`.
+/// - `value` and `value_len` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn skl_get(
+  map: *const SklMap,
+  version: u64,
+  key: *const u8,
+  key_len: usize,
+  value: *mut *const u8,
+  value_len: *mut usize,
+) -> c_int {
+  let (map, key) = match (map.as_ref(), slice(key, key_len)) {
+    (Some(map), Some(key)) => (map, key),
+    _ => return SKL_ERROR,
+  };
+  match map.map.get(version, key) {
+    Some(ent) => {
+      write_slice(ent.value(), value, value_len);
+      1
+    }
+    None => 0,
+  }
+}
+
+/// Creates an iterator over the latest version (less or equal to `version`) of the entries of
+/// the map, returns null if `map` is null.
+///
+/// The iterator keeps the ARENA alive, and must be released by [`skl_iter_free`].
+///
+/// # Safety
+/// - `map` must be null or a live map returned by [`skl_new`].
+#[no_mangle]
+pub unsafe extern "C" fn skl_iter_new(map: *const SklMap, version: u64) -> *mut SklIter {
+  match map.as_ref() {
+    Some(map) => Box::into_raw(Box::new(SklIter {
+      iter: map.map.clone().into_iter_at(version),
+    })),
+    None => ptr::null_mut(),
+  }
+}
+
+/// Advances the iterator, returns `1` and writes the entry to the out pointers, or `0` if the
+/// iterator is exhausted (or null). The out pointers may be null.
+///
+/// # Safety
+/// - `iter` must be null or a live iterator returned by [`skl_iter_new`].
+/// - The out pointers must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn skl_iter_next(
+  iter: *mut SklIter,
+  key: *mut *const u8,
+  key_len: *mut usize,
+  value: *mut *const u8,
+  value_len: *mut usize,
+  version: *mut u64,
+) -> c_int {
+  let ent = match iter.as_mut().and_then(|it| it.iter.next()) {
+    Some(ent) => ent,
+    None => return 0,
+  };
+  write_slice(ent.key(), key, key_len);
+  write_slice(ent.value(), value, value_len);
+  if !version.is_null() {
+    *version = *ent.trailer();
+  }
+  1
+}
+
+/// Releases the iterator.
+///
+/// # Safety
+/// - `iter` must be null or returned by [`skl_iter_new`], and must not be used after this
+///   call.
+#[no_mangle]
+pub unsafe extern "C" fn skl_iter_free(iter: *mut SklIter) {
+  if !iter.is_null() {
+    drop(Box::from_raw(iter));
+  }
+}
@@ -0,0 +1,367 @@
+use std::{io::Write, vec::Vec};
+
+use super::{Comparator, SkipMap, Trailer};
+
+/// The magic number at the end of the table.
+pub const MAGIC: u64 = u64::from_le_bytes(*b"skltable");
+
+/// The size of the footer at the end of the table.
+pub const FOOTER_SIZE: usize = 24;
+
+/// The block type byte of an uncompressed block.
+pub const BLOCK_UNCOMPRESSED: u8 = 0;
+
+/// The block type byte of a block compressed by [`FlushOptions::with_compressor`].
+pub const BLOCK_COMPRESSED: u8 = 1;
+
+/// The maximum encoded size of a `u64` uvarint.
+const MAX_VARINT_LEN64: usize = 10;
+
+/// The options of [`SkipMap::flush_to`] and [`TableWriter`].
+#[derive(Debug, Clone, Copy)]
+pub struct FlushOptions {
+  version: u64,
+  block_size: usize,
+  restart_interval: usize,
+  compressor: Option<fn(&[u8]) -> Option<Vec<u8>>>,
+}
+
+impl Default for FlushOptions {
+  #[inline]
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl FlushOptions {
+  /// Creates the default options.
+  #[inline]
+  pub const fn new() -> Self {
+    Self {
+      version: u64::MAX,
+      block_size: 4096,
+      restart_interval: 16,
+      compressor: None,
+    }
+  }
+
+  /// Set the version to flush, the entries with greater versions are not written.
+  ///
+  /// The default value is `u64::MAX`.
+  #[inline]
+  pub const fn with_version(mut self, version: u64) -> Self {
+    self.version = version;
+    self
+  }
+
+  /// Set the size of the data blocks, a block is written when its size reaches it.
+  ///
+  /// The default value is `4096`.
+  #[inline]
+  pub const fn with_block_size(mut self, block_size: usize) -> Self {
+    self.block_size = block_size;
+    self
+  }
+
+  /// Set the number of the entries between two restart points, the keys are prefix
+  /// compressed against the previous key, except at the restart points.
+  ///
+  /// The default value is `16`.
+  #[inline]
+  pub const fn with_restart_interval(mut self, restart_interval: usize) -> Self {
+    self.restart_interval = if restart_interval == 0 {
+      1
+    } else {
+      restart_interval
+    };
+    self
+  }
+
+  /// Set the function to compress the blocks, the block is written uncompressed if it returns
+  /// `None` or does not shrink the block.
+  ///
+  /// The default value is `None`, which means the blocks are not compressed.
+  #[inline]
+  pub const fn with_compressor(mut self, compressor: fn(&[u8]) -> Option<Vec<u8>>) -> Self {
+    self.compressor = Some(compressor);
+    self
+  }
+
+  /// Returns the version to flush.
+  #[inline]
+  pub const fn version(&self) -> u64 {
+    self.version
+  }
+
+  /// Returns the size of the data blocks.
+  #[inline]
+  pub const fn block_size(&self) -> usize {
+    self.block_size
+  }
+
+  /// Returns the number of the entries between two restart points.
+  #[inline]
+  pub const fn restart_interval(&self) -> usize {
+    self.restart_interval
+  }
+}
+
+/// The summary of a written table, returned by [`TableWriter::finish`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FlushStats {
+  /// The number of the entries.
+  pub entries: usize,
+  /// The number of the data blocks.
+  pub blocks: usize,
+  /// The number of the compressed data blocks.
+  pub compressed_blocks: usize,
+  /// The size of the table in bytes.
+  pub bytes: u64,
+}
+
+#[derive(Default)]
+struct BlockBuilder {
+  buf: Vec<u8>,
+  restarts: Vec<u32>,
+  counter: usize,
+  last_key: Vec<u8>,
+}
+
+impl BlockBuilder {
+  #[inline]
+  fn is_empty(&self) -> bool {
+    self.buf.is_empty()
+  }
+
+  #[inline]
+  fn estimated_size(&self) -> usize {
+    self.buf.len() + (self.restarts.len() + 1) * 4
+  }
+
+  fn add(&mut self, key: &[u8], version: u64, value: Option<&[u8]>, restart_interval: usize) {
+    let shared = if self.is_empty() || self.counter >= restart_interval {
+      self.restarts.push(self.buf.len() as u32);
+      self.counter = 0;
+      0
+    } else {
+      self
+        .last_key
+        .iter()
+        .zip(key)
+        .take_while(|(a, b)| a == b)
+        .count()
+    };
+
+    put_uvarint(&mut self.buf, shared as u64);
+    put_uvarint(&mut self.buf, (key.len() - shared) as u64);
+    put_uvarint(&mut self.buf, value.map_or(0, |v| v.len()) as u64);
+    self.buf.extend_from_slice(&key[shared..]);
+    self.buf.extend_from_slice(&version.to_le_bytes());
+    self.buf.push(value.is_some() as u8);
+    if let Some(value) = value {
+      self.buf.extend_from_slice(value);
+    }
+
+    self.last_key.clear();
+    self.last_key.extend_from_slice(key);
+    self.counter += 1;
+  }
+
+  fn finish(&mut self) -> Vec<u8> {
+    let mut buf = core::mem::take(&mut self.buf);
+    for restart in &self.restarts {
+      buf.extend_from_slice(&restart.to_le_bytes());
+    }
+    buf.extend_from_slice(&(self.restarts.len() as u32).to_le_bytes());
+    self.restarts.clear();
+    self.counter = 0;
+    self.last_key.clear();
+    buf
+  }
+}
+
+fn put_uvarint(buf: &mut Vec<u8>, mut v: u64) {
+  let mut tmp = [0; MAX_VARINT_LEN64];
+  let mut i = 0;
+  while v >= 0x80 {
+    tmp[i] = (v as u8) | 0x80;
+    v >>= 7;
+    i += 1;
+  }
+  tmp[i] = v as u8;
+  buf.extend_from_slice(&tmp[..=i]);
+}
+
+/// Writes the entries, sorted by key (and by version descending for the same key), into a
+/// simple block-based table, so a [`SkipMap`] can be flushed as the memtable stage of an
+/// LSM engine, see [`SkipMap::flush_to`].
+///
+/// The layout is (all the integers are little endian):
+///
+/// ```text
+/// table  := data block* | index block | footer
+/// block  := contents | type: u8
+/// contents := entry* | restarts: [u32; n] | n: u32
+/// entry  := shared: uvarint | unshared: uvarint | value_len: uvarint
+///           | key[shared..] | version: u64 | kind: u8 | value
+/// footer := index offset: u64 | index size: u64 | magic: u64
+/// ```
+///
+/// - The key of an entry shares `shared` bytes with the key of the previous entry, except at
+///   the restart points, whose offsets are listed at the end of the block.
+/// - `kind` is `0` for the removed entries, which have no value, and `1` otherwise.
+/// - The type of the block is [`BLOCK_UNCOMPRESSED`] or [`BLOCK_COMPRESSED`], the compressed
+///   contents are produced by [`FlushOptions::with_compressor`].
+/// - The index block has one entry per data block, the key and the version of the entry
+///   are the ones of the last entry of the data block, and the value is the offset and the
+///   size (including the type byte) of the data block, as two `u64`s.
+///
+/// # Example
+///
+/// ```rust
+/// use skl::flush::{FlushOptions, TableWriter, MAGIC};
+///
+/// let mut buf = Vec::new();
+/// let mut w = TableWriter::new(&mut buf, FlushOptions::new());
+/// w.add(b"a", 2, Some(b"a2")).unwrap();
+/// w.add(b"a", 1, None).unwrap();
+/// w.add(b"b", 1, Some(b"b1")).unwrap();
+///
+/// let stats = w.finish().unwrap();
+/// assert_eq!((stats.entries, stats.blocks), (3, 1));
+/// assert_eq!(stats.bytes, buf.len() as u64);
+/// assert_eq!(buf[buf.len() - 8..], MAGIC.to_le_bytes());
+/// ```
+pub struct TableWriter<W> {
+  writer: W,
+  opts: FlushOptions,
+  block: BlockBuilder,
+  index: BlockBuilder,
+  last_version: u64,
+  stats: FlushStats,
+}
+
+impl<W: Write> TableWriter<W> {
+  /// Creates a new table writer.
+  #[inline]
+  pub fn new(writer: W, opts: FlushOptions) -> Self {
+    Self {
+      writer,
+      opts,
+      block: BlockBuilder::default(),
+      index: BlockBuilder::default(),
+      last_version: 0,
+      stats: FlushStats::default(),
+    }
+  }
+
+  /// Appends an entry, `None` value means the entry is removed.
+  ///
+  /// The entries must be appended in the order of the map, the writer does not check it.
+  pub fn add(&mut self, key: &[u8], version: u64, value: Option<&[u8]>) -> std::io::Result<()> {
+    self
+      .block
+      .add(key, version, value, self.opts.restart_interval);
+    self.last_version = version;
+    self.stats.entries += 1;
+
+    if self.block.estimated_size() >= self.opts.block_size {
+      self.flush_block()?;
+    }
+    Ok(())
+  }
+
+  /// Writes the pending data block, the index block and the footer, and flushes the writer.
+  pub fn finish(mut self) -> std::io::Result<FlushStats> {
+    self.flush_block()?;
+
+    let index = self.index.finish();
+    let (offset, size) = self.write_block(&index, false)?;
+    let mut footer = [0; FOOTER_SIZE];
+    footer[..8].copy_from_slice(&offset.to_le_bytes());
+    footer[8..16].copy_from_slice(&size.to_le_bytes());
+    footer[16..].copy_from_slice(&MAGIC.to_le_bytes());
+    self.writer.write_all(&footer)?;
+    self.writer.flush()?;
+
+    self.stats.bytes += FOOTER_SIZE as u64;
+    Ok(self.stats)
+  }
+
+  fn flush_block(&mut self) -> std::io::Result<()> {
+    if self.block.is_empty() {
+      return Ok(());
+    }
+
+    let last_key = core::mem::take(&mut self.block.last_key);
+    let contents = self.block.finish();
+    let (offset, size) = self.write_block(&contents, true)?;
+    self.stats.blocks += 1;
+
+    let mut handle = [0; 16];
+    handle[..8].copy_from_slice(&offset.to_le_bytes());
+    handle[8..].copy_from_slice(&size.to_le_bytes());
+    self
+      .index
+      .add(&last_key, self.last_version, Some(&handle), 1);
+    Ok(())
+  }
+
+  /// Writes the block and its type, returns the offset and the size of the block.
+  fn write_block(&mut self, contents: &[u8], compress: bool) -> std::io::Result<(u64, u64)> {
+    let compressed = match self.opts.compressor {
+      Some(compressor) if compress => {
+        compressor(contents).filter(|compressed| compressed.len() < contents.len())
+      }
+      _ => None,
+    };
+
+    let (data, ty) = match &compressed {
+      Some(compressed) => {
+        self.stats.compressed_blocks += 1;
+        (compressed.as_slice(), BLOCK_COMPRESSED)
+      }
+      None => (contents, BLOCK_UNCOMPRESSED),
+    };
+    self.writer.write_all(data)?;
+    self.writer.write_all(&[ty])?;
+
+    let offset = self.stats.bytes;
+    let size = data.len() as u64 + 1;
+    self.stats.bytes += size;
+    Ok((offset, size))
+  }
+}
+
+impl<T: Trailer, C: Comparator> SkipMap<T, C> {
+  /// Writes all the versions (less or equal to the version of the options) of all the
+  /// entries, including the removed ones, into a block-based table, see [`TableWriter`] for
+  /// the format.
+  ///
+  /// The entries are streamed in the order of the map, so the memory usage does not grow
+  /// with the size of the map. To drop the shadowed versions or the expired entries, feed a
+  /// [`CompactionIter`](crate::map::CompactionIter) to a [`TableWriter`] instead.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use skl::{flush::FlushOptions, SkipMap};
+  ///
+  /// let l = SkipMap::new().unwrap();
+  /// for i in 0..1000u32 {
+  ///   l.insert(0, &i.to_be_bytes(), &[0; 16]).unwrap();
+  /// }
+  ///
+  /// let mut buf = Vec::new();
+  /// let stats = l.flush_to(&mut buf, FlushOptions::new()).unwrap();
+  /// assert_eq!(stats.entries, 1000);
+  /// assert!(stats.blocks > 1);
+  /// ```
+  pub fn flush_to<W: Write>(&self, writer: W, opts: FlushOptions) -> std::io::Result<FlushStats> {
+    let mut w = TableWriter::new(writer, opts);
+    for ent in self.iter_all_versions(opts.version) {
+      w.add(ent.key(), ent.version(), ent.value())?;
+    }
+    w.finish()
+  }
+}
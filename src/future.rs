@@ -0,0 +1,164 @@
+use core::ops::Bound;
+use std::vec::Vec;
+
+use super::{map::Error, Comparator, SkipMap, Trailer};
+
+/// How many entries will be processed by one blocking task before yielding back to the executor.
+const CHECKPOINT: usize = 1024;
+
+/// An async facade of [`SkipMap`] for services running on the [`tokio`] runtime.
+///
+/// Cheap operations (e.g. [`get`](SkipMap::get)) can be called on the inner map directly by
+/// [`as_inner`](AsyncSkipMap::as_inner). Large operations run on the blocking thread pool via
+/// [`tokio::task::spawn_blocking`], and yield back to the executor every `1024` entries,
+/// so they will not stall the executor.
+#[derive(Debug)]
+pub struct AsyncSkipMap<T = u64, C = crate::Ascend> {
+  map: SkipMap<T, C>,
+}
+
+impl<T, C: Clone> Clone for AsyncSkipMap<T, C> {
+  #[inline]
+  fn clone(&self) -> Self {
+    Self {
+      map: self.map.clone(),
+    }
+  }
+}
+
+impl<T, C> From<SkipMap<T, C>> for AsyncSkipMap<T, C> {
+  #[inline]
+  fn from(map: SkipMap<T, C>) -> Self {
+    Self::new(map)
+  }
+}
+
+impl<T, C> AsyncSkipMap<T, C> {
+  /// Wraps the given [`SkipMap`].
+  #[inline]
+  pub const fn new(map: SkipMap<T, C>) -> Self {
+    Self { map }
+  }
+
+  /// Returns the inner [`SkipMap`].
+  #[inline]
+  pub const fn as_inner(&self) -> &SkipMap<T, C> {
+    &self.map
+  }
+
+  /// Consumes the facade and returns the inner [`SkipMap`].
+  #[inline]
+  pub fn into_inner(self) -> SkipMap<T, C> {
+    self.map
+  }
+}
+
+impl<T, C> AsyncSkipMap<T, C>
+where
+  T: Trailer + Send + Sync + 'static,
+  C: Comparator + Clone + Send + Sync + 'static,
+{
+  /// Inserts the entries in the blocking thread pool, returns how many entries are inserted.
+  ///
+  /// The entries are inserted in order, if an error occurs, the entries before the failed one
+  /// are kept in the map.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use skl::{future::AsyncSkipMap, SkipMap};
+  ///
+  /// # tokio::runtime::Runtime::new().unwrap().block_on(async {
+  /// let map = AsyncSkipMap::new(SkipMap::new().unwrap());
+  /// let entries = (0..10u8).map(|i| (0, vec![i], vec![i])).collect::<Vec<_>>();
+  /// assert_eq!(map.bulk_insert(entries).await.unwrap(), 10);
+  /// assert_eq!(map.as_inner().len(), 10);
+  /// # });
+  /// ```
+  pub async fn bulk_insert(&self, entries: Vec<(T, Vec<u8>, Vec<u8>)>) -> Result<usize, Error> {
+    let mut inserted = 0;
+    let mut entries = entries.into_iter();
+
+    loop {
+      let chunk = entries.by_ref().take(CHECKPOINT).collect::<Vec<_>>();
+      if chunk.is_empty() {
+        return Ok(inserted);
+      }
+
+      let map = self.map.clone();
+      inserted += blocking(move || {
+        let mut inserted = 0;
+        for (trailer, key, value) in chunk {
+          map.insert(trailer, &key, &value)?;
+          inserted += 1;
+        }
+        Ok::<_, Error>(inserted)
+      })
+      .await?;
+
+      tokio::task::yield_now().await;
+    }
+  }
+
+  /// Copies the latest version (less or equal to `version`) of all entries in this map to `dst`
+  /// in the blocking thread pool, returns how many entries are copied.
+  ///
+  /// This is useful when compacting the map into a new ARENA.
+  pub async fn copy_into<D>(&self, version: u64, dst: &AsyncSkipMap<T, D>) -> Result<usize, Error>
+  where
+    D: Comparator + Clone + Send + Sync + 'static,
+  {
+    let mut copied = 0;
+    let mut last: Option<Vec<u8>> = None;
+
+    loop {
+      let src = self.map.clone();
+      let dst = dst.map.clone();
+      let (n, next) = blocking(move || {
+        let lower = match &last {
+          Some(last) => Bound::Excluded(last.as_slice()),
+          None => Bound::Unbounded,
+        };
+
+        let mut n = 0;
+        for ent in src.range(version, (lower, Bound::Unbounded)) {
+          dst.insert(*ent.trailer(), ent.key(), ent.value())?;
+          n += 1;
+          if n == CHECKPOINT {
+            return Ok::<_, Error>((n, Some(ent.key().to_vec())));
+          }
+        }
+        Ok((n, None))
+      })
+      .await?;
+
+      copied += n;
+      match next {
+        Some(next) => last = Some(next),
+        None => return Ok(copied),
+      }
+
+      tokio::task::yield_now().await;
+    }
+  }
+
+  /// Flushes outstanding memory map modifications to disk in the blocking thread pool.
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
+  pub async fn flush(&self) -> std::io::Result<()> {
+    let map = self.map.clone();
+    blocking(move || map.flush()).await
+  }
+}
+
+/// Runs `f` on the blocking thread pool, and propagates the panic if `f` panics.
+async fn blocking<F, R>(f: F) -> R
+where
+  F: FnOnce() -> R + Send + 'static,
+  R: Send + 'static,
+{
+  match tokio::task::spawn_blocking(f).await {
+    Ok(r) => r,
+    Err(e) => std::panic::resume_unwind(e.into_panic()),
+  }
+}
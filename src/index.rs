@@ -0,0 +1,230 @@
+use std::vec::Vec;
+
+use super::{
+  map::{EntryRef, Error, Iter},
+  Ascend, Comparator, Options, SkipMap, Trailer,
+};
+
+use core::ops::Bound;
+
+/// Derives the secondary key from the key and the value of a primary entry,
+/// `None` means the entry is not indexed.
+pub type DeriveKey = fn(key: &[u8], value: &[u8]) -> Option<Vec<u8>>;
+
+/// A [`SkipMap`] with an automatically maintained secondary index, which maps the
+/// derived keys to the primary keys.
+///
+/// Every write through the [`IndexedMap`] also updates the index, the index entries are
+/// written before the primary entry. The index is a second [`SkipMap`] with its own ARENA,
+/// created with the same [`Options`] except the capacity, which is split evenly between the
+/// map and the index, so an [`IndexedMap`] takes the same memory as a [`SkipMap`] with the
+/// [`Options`]. The writes fail with the ARENA error when either of them is full. A lookup by the
+/// derived key re-derives the key from the primary entry, so the index entries which are
+/// stale, e.g. left by a concurrent writer of the same primary key, are never yielded.
+///
+/// # Example
+///
+/// ```rust
+/// use skl::{index::IndexedMap, Options};
+///
+/// // index the users by the domain of their email
+/// let l = IndexedMap::with_options(Options::new(), |_, email| {
+///   let at = email.iter().position(|b| *b == b'@')?;
+///   Some(email[at + 1..].to_vec())
+/// })
+/// .unwrap();
+/// l.insert(0, b"alice", b"alice@foo.com").unwrap();
+/// l.insert(0, b"bob", b"bob@bar.com").unwrap();
+/// l.insert(0, b"carol", b"carol@foo.com").unwrap();
+///
+/// let users = l
+///   .get_by_index(0, b"foo.com")
+///   .map(|ent| ent.key().to_vec())
+///   .collect::<Vec<_>>();
+/// assert_eq!(users, [b"alice".to_vec(), b"carol".to_vec()]);
+///
+/// // moving bob to another domain updates the index
+/// l.insert(1, b"bob", b"bob@foo.com").unwrap();
+/// assert_eq!(l.get_by_index(1, b"foo.com").count(), 3);
+/// assert_eq!(l.get_by_index(1, b"bar.com").count(), 0);
+/// assert_eq!(l.get_by_index(0, b"bar.com").count(), 1);
+/// ```
+#[derive(Debug)]
+pub struct IndexedMap<T = u64, C = Ascend> {
+  primary: SkipMap<T, C>,
+  index: SkipMap<T>,
+  derive: DeriveKey,
+}
+
+impl<T> IndexedMap<T> {
+  /// Creates a new map and its index with the [`Options`], each of them gets half of the
+  /// capacity.
+  #[inline]
+  pub fn with_options(opts: Options, derive: DeriveKey) -> Result<Self, Error> {
+    Self::with_options_and_comparator(opts, Ascend, derive)
+  }
+}
+
+impl<T, C> IndexedMap<T, C> {
+  /// Like [`IndexedMap::with_options`], but with a custom [`Comparator`] for the primary
+  /// keys. The index is always ordered by [`Ascend`].
+  pub fn with_options_and_comparator(
+    opts: Options,
+    cmp: C,
+    derive: DeriveKey,
+  ) -> Result<Self, Error> {
+    let index_capacity = opts.capacity() / 2;
+    Ok(Self {
+      primary: SkipMap::with_options_and_comparator(
+        opts.with_capacity(opts.capacity() - index_capacity),
+        cmp,
+      )?,
+      index: SkipMap::with_options(opts.with_capacity(index_capacity))?,
+      derive,
+    })
+  }
+
+  /// Returns the primary map, the writes must go through the [`IndexedMap`] to keep the
+  /// index in sync.
+  #[inline]
+  pub const fn primary(&self) -> &SkipMap<T, C> {
+    &self.primary
+  }
+
+  /// Returns the index, which maps the derived keys to the primary keys, e.g. to check how
+  /// much of its ARENA is used. The writes must go through the [`IndexedMap`].
+  #[inline]
+  pub const fn index(&self) -> &SkipMap<T> {
+    &self.index
+  }
+
+  /// Returns the function which derives the secondary keys.
+  #[inline]
+  pub const fn derive(&self) -> DeriveKey {
+    self.derive
+  }
+}
+
+/// Returns the prefix of the index keys of the derived key, the length is encoded first, so
+/// a derived key is never the prefix of another one.
+fn index_prefix(derived: &[u8]) -> Vec<u8> {
+  let mut buf = Vec::with_capacity(4 + derived.len());
+  buf.extend_from_slice(&(derived.len() as u32).to_be_bytes());
+  buf.extend_from_slice(derived);
+  buf
+}
+
+fn index_key(derived: &[u8], primary: &[u8]) -> Vec<u8> {
+  let mut buf = index_prefix(derived);
+  buf.extend_from_slice(primary);
+  buf
+}
+
+impl<T: Trailer, C: Comparator> IndexedMap<T, C> {
+  /// Upserts a key-value pair, and moves the index entry of the key to the newly derived
+  /// key.
+  pub fn insert(&self, trailer: T, key: &[u8], value: &[u8]) -> Result<(), Error> {
+    let derived = (self.derive)(key, value);
+    let old = self.derived_of(trailer.version(), key);
+    if old.is_some() && old != derived {
+      self.remove_index_entry(trailer, old.as_deref(), key)?;
+    }
+    if let Some(derived) = derived {
+      self.index.insert(trailer, &index_key(&derived, key), &[])?;
+    }
+    self.primary.insert(trailer, key, value).map(|_| ())
+  }
+
+  /// Removes the key and its index entry.
+  pub fn remove(&self, trailer: T, key: &[u8]) -> Result<(), Error> {
+    let old = self.derived_of(trailer.version(), key);
+    self.remove_index_entry(trailer, old.as_deref(), key)?;
+    self
+      .primary
+      .compare_remove(
+        trailer,
+        key,
+        core::sync::atomic::Ordering::AcqRel,
+        core::sync::atomic::Ordering::Relaxed,
+      )
+      .map(|_| ())
+  }
+
+  /// Returns the primary entries (with the version less or equal to `version`) whose
+  /// derived key is `derived`, in the order of the primary keys in [`Ascend`].
+  pub fn get_by_index<'a>(&'a self, version: u64, derived: &[u8]) -> IndexLookup<'a, T, C> {
+    let prefix = index_prefix(derived);
+    let mut iter = self.index.iter(version);
+    let pending = iter.seek_lower_bound(Bound::Included(prefix.as_slice()));
+    IndexLookup {
+      map: self,
+      iter,
+      pending,
+      version,
+      prefix,
+    }
+  }
+
+  fn derived_of(&self, version: u64, key: &[u8]) -> Option<Vec<u8>> {
+    self
+      .primary
+      .get(version, key)
+      .and_then(|ent| (self.derive)(ent.key(), ent.value()))
+  }
+
+  fn remove_index_entry(
+    &self,
+    trailer: T,
+    derived: Option<&[u8]>,
+    key: &[u8],
+  ) -> Result<(), Error> {
+    match derived {
+      Some(derived) => self
+        .index
+        .compare_remove(
+          trailer,
+          &index_key(derived, key),
+          core::sync::atomic::Ordering::AcqRel,
+          core::sync::atomic::Ordering::Relaxed,
+        )
+        .map(|_| ()),
+      None => Ok(()),
+    }
+  }
+}
+
+/// An iterator over the primary entries of a derived key, returned by
+/// [`IndexedMap::get_by_index`].
+pub struct IndexLookup<'a, T, C> {
+  map: &'a IndexedMap<T, C>,
+  iter: Iter<'a, T, Ascend>,
+  pending: Option<EntryRef<'a, T>>,
+  version: u64,
+  prefix: Vec<u8>,
+}
+
+impl<'a, T: Trailer, C: Comparator> Iterator for IndexLookup<'a, T, C> {
+  type Item = EntryRef<'a, T>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    loop {
+      let ent = match self.pending.take() {
+        Some(ent) => ent,
+        None => self.iter.next()?,
+      };
+      let key = ent.0.key;
+      if !key.starts_with(&self.prefix) {
+        return None;
+      }
+
+      // skip the stale index entries
+      let primary_key = &key[self.prefix.len()..];
+      if let Some(primary) = self.map.primary.get(self.version, primary_key) {
+        let derived = (self.map.derive)(primary.key(), primary.value());
+        if derived.as_deref() == Some(&self.prefix[4..]) {
+          return Some(primary);
+        }
+      }
+    }
+  }
+}
@@ -30,8 +30,78 @@ pub use options::{MmapOptions, OpenOptions};
 mod types;
 pub use types::*;
 
+mod backoff;
+
+/// A stable memtable abstraction for LSM-Tree frameworks.
+pub mod memtable;
+pub use memtable::MemTable;
+
+/// Typed keys and values for the [`SkipMap`].
+pub mod typed;
+
+/// A compatibility layer exposing the [`BTreeMap`](std::collections::BTreeMap)-like APIs, and
+/// the `crossbeam-skiplist`-like APIs with the `compat-crossbeam` feature.
+pub mod compat;
+
+/// Exports the range scans as Arrow record batches.
+#[cfg(feature = "arrow")]
+#[cfg_attr(docsrs, doc(cfg(feature = "arrow")))]
+pub mod arrow;
+
+/// NDJSON dump and import of the entries.
+#[cfg(all(feature = "std", feature = "serde"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "std", feature = "serde"))))]
+pub mod ndjson;
+
+/// Badger-compatible value encoding.
+pub mod badger;
+
+/// An adapter for RocksDB-style C comparators.
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+pub mod rocksdb;
+
+/// A block-based table writer, to flush a [`SkipMap`] as the memtable stage of an LSM engine.
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+pub mod flush;
+
+/// A [`SkipMap`] with an automatically maintained secondary index.
+pub mod index;
+
+/// Time-partitioned [`SkipMap`]s with whole-partition retention.
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+pub mod partition;
+
+/// Memory-bounded [`SkipMap`]s with quotas and eviction policies.
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+pub mod quota;
+
+/// Pluggable memory backends of the [`SkipMap`].
+pub mod backend;
+
+pub mod arena;
+
+/// An async facade of the [`SkipMap`] for the [`tokio`](https://docs.rs/tokio) runtime.
+#[cfg(feature = "tokio")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+pub mod future;
+
+#[cfg(feature = "ffi")]
+#[cfg_attr(docsrs, doc(cfg(feature = "ffi")))]
+pub mod ffi;
+
+#[cfg(feature = "testing")]
+#[cfg_attr(docsrs, doc(cfg(feature = "testing")))]
+pub mod testing;
+
 pub use either;
-pub use map::{AllVersionsIter, SkipMap};
+#[cfg(feature = "raw")]
+#[cfg_attr(docsrs, doc(cfg(feature = "raw")))]
+pub use map::raw;
+pub use map::{layout, AllVersionsIter, Error, MergeIterator, SkipMap};
 pub use rarena_allocator::{Arena, Error as ArenaError};
 pub use ux2::{u27, u5};
 
@@ -84,6 +154,16 @@ const PROBABILITIES: [u32; MAX_HEIGHT] = {
   probabilities
 };
 
+/// Returns the smallest height `h` such that `(1/p)^h >= n`, i.e. roughly `log_{1/p}(n)`,
+/// which is the height that a skiplist with `n` entries needs for logarithmic search.
+const fn expected_height(n: u64) -> u8 {
+  let mut h = 1;
+  while h < MAX_HEIGHT && (PROBABILITIES[h] as u64) * n > u32::MAX as u64 {
+    h += 1;
+  }
+  h as u8
+}
+
 /// Comparator is used for key-value database developers to define their own key comparison logic.
 /// e.g. some key-value database developers may want to alpabetically comparation
 pub trait Comparator: core::fmt::Debug {
@@ -91,10 +171,27 @@ pub trait Comparator: core::fmt::Debug {
   fn compare(&self, a: &[u8], b: &[u8]) -> cmp::Ordering;
 
   /// Returns if a is contained in range.
+  ///
+  /// The keys contained in a range must be contiguous in the order of
+  /// [`compare`](Comparator::compare), i.e. if two keys are contained, all the keys between
+  /// them are contained too. The range iterators rely on it: they seek to the first key in the
+  /// range by the tower descent, and stop at the first key after the range, so the keys which
+  /// are contained but out of the contiguous run are not yielded.
   fn contains<'a, Q>(&self, range: &impl RangeBounds<Q>, key: &'a [u8]) -> bool
   where
     &'a [u8]: PartialOrd<Q>,
     Q: ?Sized + PartialOrd<&'a [u8]>;
+
+  /// Returns if the key starts with the prefix, used by [`SkipMap::prefix`](map::SkipMap::prefix).
+  ///
+  /// The keys with the prefix must be contiguous, and not less than the prefix itself, in the
+  /// order of the comparator.
+  ///
+  /// Default is the byte prefix check.
+  #[inline]
+  fn has_prefix(&self, key: &[u8], prefix: &[u8]) -> bool {
+    key.starts_with(prefix)
+  }
 }
 
 impl<C: Comparator> Comparator for std::sync::Arc<C> {
@@ -111,6 +208,11 @@ impl<C: Comparator> Comparator for std::sync::Arc<C> {
   {
     (**self).contains(range, key)
   }
+
+  #[inline]
+  fn has_prefix(&self, key: &[u8], prefix: &[u8]) -> bool {
+    (**self).has_prefix(key, prefix)
+  }
 }
 
 impl<C: Comparator> Comparator for std::rc::Rc<C> {
@@ -127,6 +229,11 @@ impl<C: Comparator> Comparator for std::rc::Rc<C> {
   {
     (**self).contains(range, key)
   }
+
+  #[inline]
+  fn has_prefix(&self, key: &[u8], prefix: &[u8]) -> bool {
+    (**self).has_prefix(key, prefix)
+  }
 }
 
 impl<C: Comparator> Comparator for std::boxed::Box<C> {
@@ -143,6 +250,11 @@ impl<C: Comparator> Comparator for std::boxed::Box<C> {
   {
     (**self).contains(range, key)
   }
+
+  #[inline]
+  fn has_prefix(&self, key: &[u8], prefix: &[u8]) -> bool {
+    (**self).has_prefix(key, prefix)
+  }
 }
 
 /// Ascend is a comparator that compares byte slices in ascending order.
@@ -185,6 +297,241 @@ impl Comparator for Descend {
   }
 }
 
+/// A collation function which transforms a key into its memcmp-ordered form (collation key).
+///
+/// The transformation must be order-preserving, i.e. for any two keys `a` and `b`,
+/// the ordering of `normalize(a)` and `normalize(b)` compared byte by byte must be the same as
+/// the ordering of `a` and `b` defined by the application.
+pub trait KeyNormalizer: core::fmt::Debug {
+  /// Returns the length of the normalized form of the key.
+  fn normalized_len(&self, key: &[u8]) -> usize;
+
+  /// Writes the normalized form of the key to the buffer.
+  ///
+  /// The buffer has exactly [`normalized_len`](KeyNormalizer::normalized_len) bytes of capacity.
+  fn normalize(&self, key: &[u8], buf: &mut VacantBuffer<'_>) -> Result<(), TooLarge>;
+}
+
+impl<N: KeyNormalizer> KeyNormalizer for std::sync::Arc<N> {
+  #[inline]
+  fn normalized_len(&self, key: &[u8]) -> usize {
+    (**self).normalized_len(key)
+  }
+
+  #[inline]
+  fn normalize(&self, key: &[u8], buf: &mut VacantBuffer<'_>) -> Result<(), TooLarge> {
+    (**self).normalize(key, buf)
+  }
+}
+
+/// Normalized is a comparator for the map whose keys are stored already transformed
+/// by a [`KeyNormalizer`] into the memcmp-ordered form, so every comparison during the descent
+/// is a plain byte compare, no matter how expensive the original collation is.
+///
+/// Keys should be inserted and looked up by the `*_normalized` methods on the
+/// [`SkipMap`], e.g. [`SkipMap::insert_normalized`] and [`SkipMap::get_normalized`],
+/// and the keys yielded by the map are the normalized forms.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Normalized<N> {
+  normalizer: N,
+}
+
+impl<N> Normalized<N> {
+  /// Creates a new comparator with the given normalizer.
+  #[inline]
+  pub const fn new(normalizer: N) -> Self {
+    Self { normalizer }
+  }
+
+  /// Returns the normalizer.
+  #[inline]
+  pub const fn normalizer(&self) -> &N {
+    &self.normalizer
+  }
+}
+
+impl<N: KeyNormalizer> Normalized<N> {
+  /// Returns the normalized form of the key, which can be used as the bound of
+  /// [`SkipMap::range`] or [`SkipMap::lower_bound`] and friends.
+  ///
+  /// Returns the error of the normalizer, e.g. [`TooLarge`] if it writes more bytes than
+  /// its [`normalized_len`](KeyNormalizer::normalized_len).
+  pub fn normalize(&self, key: &[u8]) -> Result<std::vec::Vec<u8>, TooLarge> {
+    let len = self.normalizer.normalized_len(key);
+    let mut buf = std::vec![0; len];
+    let mut vb = VacantBuffer::new(len, 0, &mut buf);
+    self.normalizer.normalize(key, &mut vb)?;
+    let written = vb.len();
+    buf.truncate(written);
+    Ok(buf)
+  }
+}
+
+impl<N: KeyNormalizer> Comparator for Normalized<N> {
+  #[inline]
+  fn compare(&self, a: &[u8], b: &[u8]) -> cmp::Ordering {
+    a.cmp(b)
+  }
+
+  #[inline]
+  fn contains<'a, Q>(&self, range: &impl RangeBounds<Q>, key: &'a [u8]) -> bool
+  where
+    &'a [u8]: PartialOrd<Q>,
+    Q: ?Sized + PartialOrd<&'a [u8]>,
+  {
+    range.contains(&key)
+  }
+}
+
+/// Scored is a comparator for the sorted-set mode of the map, which orders the entries by
+/// an 8-byte score first, and then by the member (like a Redis ZSET).
+///
+/// The score is stored in big-endian as the first 8 bytes of the key, so the keys are the
+/// composites of the score and the member, see [`Scored::key`] and [`Scored::split`].
+/// Entries should be inserted and looked up by the `*_scored` and `*_by_score` methods on the
+/// [`SkipMap`], e.g. [`SkipMap::insert_scored`] and [`SkipMap::range_by_score`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Scored;
+
+impl Scored {
+  /// The size of the score prefix of the keys.
+  pub const SCORE_SIZE: usize = core::mem::size_of::<u64>();
+
+  /// Returns the composite key of the score and the member.
+  pub fn key(score: u64, member: &[u8]) -> std::vec::Vec<u8> {
+    let mut key = std::vec::Vec::with_capacity(Self::SCORE_SIZE + member.len());
+    key.extend_from_slice(&score.to_be_bytes());
+    key.extend_from_slice(member);
+    key
+  }
+
+  /// Splits the composite key into the score and the member, returns `None` if the key is
+  /// shorter than [`Scored::SCORE_SIZE`], e.g. it is not inserted by
+  /// [`SkipMap::insert_scored`].
+  #[inline]
+  pub fn split(key: &[u8]) -> Option<(u64, &[u8])> {
+    if key.len() < Self::SCORE_SIZE {
+      return None;
+    }
+
+    let (score, member) = key.split_at(Self::SCORE_SIZE);
+    let mut buf = [0; Self::SCORE_SIZE];
+    buf.copy_from_slice(score);
+    Some((u64::from_be_bytes(buf), member))
+  }
+}
+
+impl Comparator for Scored {
+  #[inline]
+  fn compare(&self, a: &[u8], b: &[u8]) -> cmp::Ordering {
+    // the big-endian score prefix makes the byte order the same as the (score, member) order
+    a.cmp(b)
+  }
+
+  #[inline]
+  fn contains<'a, Q>(&self, range: &impl RangeBounds<Q>, key: &'a [u8]) -> bool
+  where
+    &'a [u8]: PartialOrd<Q>,
+    Q: ?Sized + PartialOrd<&'a [u8]>,
+  {
+    range.contains(&key)
+  }
+}
+
+/// Multi is a comparator for the multimap mode of the map, where a key can be inserted
+/// several times, and the duplicates are ordered by the insertion sequence.
+///
+/// The sequence is stored in big-endian as the last 8 bytes of the key, so the keys are the
+/// composites of the key and the sequence, see [`Multi::key`] and [`Multi::split`]. The
+/// sequence is assigned by the comparator, which is shared by the clones of the map, and is
+/// seeded from the largest sequence in the map on the first [`SkipMap::insert_dup`].
+/// Entries should be inserted and looked up by the `*_dup` methods on the [`SkipMap`],
+/// e.g. [`SkipMap::insert_dup`] and [`SkipMap::get_all`], the other iterators yield every
+/// duplicate with its composite key.
+#[derive(Clone, Debug, Default)]
+pub struct Multi {
+  seq: std::sync::Arc<MultiSeq>,
+}
+
+#[derive(Debug, Default)]
+struct MultiSeq {
+  next: sync::AtomicU64,
+  seeded: sync::AtomicBool,
+}
+
+impl Multi {
+  /// The size of the sequence suffix of the keys.
+  pub const SEQ_SIZE: usize = core::mem::size_of::<u64>();
+
+  /// Creates a new comparator, the sequences start from `0`.
+  #[inline]
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Returns the composite key of the key and the sequence.
+  pub fn key(key: &[u8], seq: u64) -> std::vec::Vec<u8> {
+    let mut buf = std::vec::Vec::with_capacity(key.len() + Self::SEQ_SIZE);
+    buf.extend_from_slice(key);
+    buf.extend_from_slice(&seq.to_be_bytes());
+    buf
+  }
+
+  /// Splits the composite key into the key and the sequence, returns `None` if the key is
+  /// shorter than [`Multi::SEQ_SIZE`], e.g. it is not inserted by [`SkipMap::insert_dup`].
+  #[inline]
+  pub fn split(key: &[u8]) -> Option<(&[u8], u64)> {
+    let at = key.len().checked_sub(Self::SEQ_SIZE)?;
+    let (key, seq) = key.split_at(at);
+    let mut buf = [0; Self::SEQ_SIZE];
+    buf.copy_from_slice(seq);
+    Some((key, u64::from_be_bytes(buf)))
+  }
+
+  /// Returns the next sequence.
+  #[inline]
+  pub(crate) fn next_seq(&self) -> u64 {
+    self.seq.next.fetch_add(1, sync::Ordering::Relaxed)
+  }
+
+  /// Returns `true` if the sequence is seeded, see [`Multi::seed`].
+  #[inline]
+  pub(crate) fn is_seeded(&self) -> bool {
+    self.seq.seeded.load(sync::Ordering::Acquire)
+  }
+
+  /// Makes the later sequences at least `next`.
+  #[inline]
+  pub(crate) fn seed(&self, next: u64) {
+    self.seq.next.fetch_max(next, sync::Ordering::Relaxed);
+    self.seq.seeded.store(true, sync::Ordering::Release);
+  }
+}
+
+impl Comparator for Multi {
+  #[inline]
+  fn compare(&self, a: &[u8], b: &[u8]) -> cmp::Ordering {
+    // the keys shorter than the sequence are treated as keys without a sequence
+    let split = |k: &[u8]| {
+      k.len()
+        .checked_sub(Self::SEQ_SIZE)
+        .map_or((k, &[][..]), |at| k.split_at(at))
+    };
+    let (ak, aseq) = split(a);
+    let (bk, bseq) = split(b);
+    ak.cmp(bk).then_with(|| aseq.cmp(bseq))
+  }
+
+  #[inline]
+  fn contains<'a, Q>(&self, range: &impl RangeBounds<Q>, key: &'a [u8]) -> bool
+  where
+    &'a [u8]: PartialOrd<Q>,
+    Q: ?Sized + PartialOrd<&'a [u8]>,
+  {
+    range.contains(&key)
+  }
+}
+
 /// A trait for extra information that can be stored with entry in the skiplist.
 ///
 /// # Safety
@@ -212,9 +559,5 @@ unsafe impl Trailer for () {
 }
 
 mod sync {
-  #[cfg(not(feature = "loom"))]
   pub(crate) use core::sync::atomic::*;
-
-  #[cfg(feature = "loom")]
-  pub(crate) use loom::sync::atomic::*;
 }
@@ -7,12 +7,12 @@ use core::{
   ptr::{self, NonNull},
 };
 
-use std::boxed::Box;
+use std::{boxed::Box, sync::Arc};
 
-use crate::{Key, Trailer, VacantBuffer};
+use crate::{backoff::Backoff, Key, Trailer, VacantBuffer};
 
 #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
-use error::{bad_magic_version, bad_version, invalid_data};
+use error::{bad_magic_version, bad_version, invalid_data, out_of_memory};
 
 use super::{sync::*, Arena, Ascend, Comparator, *};
 
@@ -26,6 +26,65 @@ mod entry;
 pub use entry::*;
 mod iterator;
 pub use iterator::*;
+mod compaction;
+pub use compaction::*;
+mod access;
+mod arena_stats;
+pub use arena_stats::*;
+mod auxiliary;
+mod diff;
+mod fingerprint;
+mod frozen;
+pub use frozen::*;
+mod huge_pages;
+mod inline_key;
+pub use diff::*;
+mod expiry;
+pub use expiry::*;
+mod histogram;
+pub use histogram::*;
+#[cfg(feature = "perf-stats")]
+mod latency;
+#[cfg(feature = "perf-stats")]
+#[cfg_attr(docsrs, doc(cfg(feature = "perf-stats")))]
+pub use latency::*;
+#[cfg(feature = "perf-stats")]
+mod metrics;
+#[cfg(feature = "perf-stats")]
+#[cfg_attr(docsrs, doc(cfg(feature = "perf-stats")))]
+pub use metrics::*;
+mod merge;
+pub use merge::*;
+mod cow;
+pub use cow::*;
+mod snapshot;
+pub use snapshot::*;
+mod single_writer;
+pub use single_writer::*;
+mod hint;
+pub use hint::*;
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+mod segmented;
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+pub use segmented::*;
+mod scored;
+pub use scored::*;
+mod multi;
+pub use multi::*;
+mod inspect;
+pub use inspect::*;
+#[cfg(feature = "track-guards")]
+mod guards;
+pub mod layout;
+#[cfg(feature = "rayon")]
+mod par;
+#[cfg(feature = "raw")]
+#[cfg_attr(docsrs, doc(cfg(feature = "raw")))]
+pub mod raw;
+#[cfg(feature = "serde")]
+mod serialize;
 
 use rarena_allocator::Error as ArenaError;
 
@@ -53,19 +112,71 @@ struct Meta {
   magic_version: u16,
   /// Current height. 1 <= height <= 31. CAS.
   height: AtomicU8,
-  reserved_byte: u8,
+  /// The layout flags of the nodes in the low 4 bits, see [`Meta::layout_flags`], and the
+  /// poison reason in the high 4 bits, see [`Meta::poison_reason`].
+  flags: AtomicU8,
 }
 
 impl Meta {
+  /// The nodes have the access stamp, see [`Options::with_access_stamps`].
+  const ACCESS_STAMPS: u8 = 0b0000_0001;
+  /// The nodes have the key fingerprint, see [`Options::with_key_fingerprints`].
+  const KEY_FINGERPRINTS: u8 = 0b0000_0010;
+  /// The mask of the flags which change the layout of the nodes.
+  const LAYOUT_FLAGS: u8 = 0b0000_1111;
+  /// The shift of the poison reason in the flags.
+  const POISON_SHIFT: u32 = 4;
+
   #[inline]
-  fn new(version: u16) -> Self {
+  fn new(version: u16, flags: u8) -> Self {
     Self {
       max_version: AtomicU64::new(0),
       min_version: AtomicU64::new(0),
       magic_version: version,
       height: AtomicU8::new(1),
       len: AtomicU32::new(0),
-      reserved_byte: 0,
+      flags: AtomicU8::new(flags),
+    }
+  }
+
+  /// Returns the layout flags of the nodes allocated with the options, which are persisted in
+  /// the meta, so a file backed map cannot be reopened with a different layout.
+  #[inline]
+  const fn layout_flags_of(opts: &Options) -> u8 {
+    let mut flags = 0;
+    if opts.access_stamps() {
+      flags |= Self::ACCESS_STAMPS;
+    }
+    if opts.key_fingerprints() {
+      flags |= Self::KEY_FINGERPRINTS;
+    }
+    flags
+  }
+
+  #[inline]
+  fn layout_flags(&self) -> u8 {
+    self.flags.load(Ordering::Acquire) & Self::LAYOUT_FLAGS
+  }
+
+  /// Returns the persisted poison reason, `0` if the map is not poisoned.
+  #[inline]
+  fn poison_reason(&self) -> u8 {
+    self.flags.load(Ordering::Acquire) >> Self::POISON_SHIFT
+  }
+
+  /// Persists the poison reason, `0` clears it.
+  #[inline]
+  fn set_poison_reason(&self, reason: u8) {
+    let mut current = self.flags.load(Ordering::Acquire);
+    loop {
+      let new = (current & Self::LAYOUT_FLAGS) | (reason << Self::POISON_SHIFT);
+      match self
+        .flags
+        .compare_exchange_weak(current, new, Ordering::AcqRel, Ordering::Acquire)
+      {
+        Ok(_) => break,
+        Err(v) => current = v,
+      }
     }
   }
 
@@ -107,6 +218,8 @@ impl Meta {
         return;
       }
 
+      // `SeqCst` like the link CAS of the insert path, see `SkipMap::update`, which updates the
+      // versions as a part of the publication of the node.
       match self.max_version.compare_exchange_weak(
         current,
         version,
@@ -127,6 +240,7 @@ impl Meta {
         return;
       }
 
+      // `SeqCst` like the link CAS of the insert path, see `SkipMap::update`.
       match self.min_version.compare_exchange_weak(
         current,
         version,
@@ -173,6 +287,26 @@ impl AtomicValuePointer {
     )
   }
 
+  #[inline]
+  fn compare_exchange(
+    &self,
+    current: (u32, u32),
+    new: (u32, u32),
+    success: Ordering,
+    failure: Ordering,
+  ) -> Result<(u32, u32), (u32, u32)> {
+    self
+      .0
+      .compare_exchange(
+        encode_value_pointer(current.0, current.1),
+        encode_value_pointer(new.0, new.1),
+        success,
+        failure,
+      )
+      .map(decode_value_pointer)
+      .map_err(decode_value_pointer)
+  }
+
   #[inline]
   fn compare_remove(&self, success: Ordering, failure: Ordering) -> Result<(u32, u32), (u32, u32)> {
     let old = self.0.load(Ordering::Acquire);
@@ -236,6 +370,11 @@ impl<T> NodePtr<T> {
   #[inline]
   unsafe fn tower(&self, arena: &Arena, idx: usize) -> &Link {
     let tower_ptr_offset = self.offset as usize + Node::<T>::SIZE + idx * Link::SIZE;
+    debug_assert!(
+      idx < super::MAX_HEIGHT,
+      "tower index {idx} is out of bounds"
+    );
+    check_bounds(arena, tower_ptr_offset, Link::SIZE);
     let tower_ptr = arena.get_pointer(tower_ptr_offset);
     &*tower_ptr.cast()
   }
@@ -243,6 +382,11 @@ impl<T> NodePtr<T> {
   #[inline]
   unsafe fn write_tower(&self, arena: &Arena, idx: usize, prev_offset: u32, next_offset: u32) {
     let tower_ptr_offset = self.offset as usize + Node::<T>::SIZE + idx * Link::SIZE;
+    debug_assert!(
+      idx < super::MAX_HEIGHT,
+      "tower index {idx} is out of bounds"
+    );
+    check_bounds(arena, tower_ptr_offset, Link::SIZE);
     let tower_ptr: *mut Link = arena.get_pointer_mut(tower_ptr_offset).cast();
     *tower_ptr = Link::new(next_offset, prev_offset);
   }
@@ -389,13 +533,42 @@ impl<T> Node<T> {
     arena: &'a Arena,
     trailer: T,
     value_size: u32,
+    value_align: u32,
+    f: &impl Fn(&mut VacantBuffer<'a>) -> Result<(), E>,
+  ) -> Result<Pointer, Either<E, Error>> {
+    let (trailer_offset, allocated) =
+      self.alloc_value(arena, trailer, value_size, value_align, f)?;
+    self.value.swap(trailer_offset, value_size);
+    Ok(allocated)
+  }
+
+  /// Allocates and writes the trailer and the value, without publishing them to the node.
+  ///
+  /// Returns the offset of the trailer, and the allocated bytes.
+  fn alloc_value<'a, E>(
+    &self,
+    arena: &'a Arena,
+    trailer: T,
+    value_size: u32,
+    value_align: u32,
     f: &impl Fn(&mut VacantBuffer<'a>) -> Result<(), E>,
-  ) -> Result<(), Either<E, Error>> {
+  ) -> Result<(u32, Pointer), Either<E, Error>> {
+    let padding_cap = value_padding_capacity::<T>(value_align);
+    // The ARENA tracks the offsets in u32, see `check_node_size`.
+    let size = value_size as u64
+      + padding_cap as u64
+      + mem::size_of::<T>() as u64
+      + mem::align_of::<T>() as u64;
+    if size > u32::MAX as u64 || size > arena.capacity() as u64 {
+      return Err(Either::Right(Error::ValueTooLarge(size)));
+    }
+
     let mut bytes = arena
-      .alloc_aligned_bytes::<T>(value_size)
+      .alloc_aligned_bytes::<T>(value_size + padding_cap)
       .map_err(|e| Either::Right(e.into()))?;
-    let trailer_ptr = bytes.as_mut_ptr().cast::<T>();
-    let trailer_offset = bytes.offset();
+    let padding = value_padding::<T>(bytes.offset(), value_align);
+    let trailer_ptr = unsafe { bytes.as_mut_ptr().add(padding).cast::<T>() };
+    let trailer_offset = bytes.offset() + padding;
     let value_offset = trailer_offset + mem::size_of::<T>();
 
     let mut oval = VacantBuffer::new(value_size as usize, value_offset as u32, unsafe {
@@ -411,6 +584,10 @@ impl<T> Node<T> {
       discard += remaining;
     }
 
+    let allocated = Pointer::new(
+      bytes.offset() as u32,
+      (value_offset + oval.len() - bytes.offset()) as u32,
+    );
     bytes.detach();
     unsafe {
       trailer_ptr.write(trailer);
@@ -420,9 +597,7 @@ impl<T> Node<T> {
       arena.increase_discarded(discard as u32);
     }
 
-    self.value.swap(trailer_offset as u32, value_size);
-
-    Ok(())
+    Ok((trailer_offset as u32, allocated))
   }
 
   #[inline]
@@ -445,7 +620,9 @@ impl<T> Node<T> {
   /// ## Safety
   ///
   /// - The caller must ensure that the node is allocated by the arena.
-  const unsafe fn get_key<'a, 'b: 'a>(&'a self, arena: &'b Arena) -> &'b [u8] {
+  #[inline]
+  unsafe fn get_key<'a, 'b: 'a>(&'a self, arena: &'b Arena) -> &'b [u8] {
+    check_bounds(arena, self.key_offset as usize, self.key_size() as usize);
     arena.get_bytes(self.key_offset as usize, self.key_size() as usize)
   }
 
@@ -460,7 +637,9 @@ impl<T> Node<T> {
       return None;
     }
     let align_offset = Self::align_offset(offset);
-    Some(arena.get_bytes(align_offset as usize + mem::size_of::<T>(), len as usize))
+    let value_offset = align_offset as usize + mem::size_of::<T>();
+    check_bounds(arena, value_offset, len as usize);
+    Some(arena.get_bytes(value_offset, len as usize))
   }
 
   /// ## Safety
@@ -477,7 +656,9 @@ impl<T> Node<T> {
       return None;
     }
     let align_offset = Self::align_offset(offset);
-    Some(arena.get_bytes(align_offset as usize + mem::size_of::<T>(), len as usize))
+    let value_offset = align_offset as usize + mem::size_of::<T>();
+    check_bounds(arena, value_offset, len as usize);
+    Some(arena.get_bytes(value_offset, len as usize))
   }
 
   #[inline]
@@ -491,6 +672,7 @@ impl<T: Copy> Node<T> {
   #[inline]
   unsafe fn get_trailer<'a, 'b: 'a>(&'a self, arena: &'b Arena) -> T {
     let (offset, _) = self.value.load(Ordering::Acquire);
+    check_bounds(arena, offset as usize, mem::size_of::<T>());
     *arena.get_aligned_pointer(offset as usize)
   }
 
@@ -499,6 +681,7 @@ impl<T: Copy> Node<T> {
   /// - The caller must ensure that the node is allocated by the arena.
   #[inline]
   unsafe fn get_trailer_by_offset<'a, 'b: 'a>(&'a self, arena: &'b Arena, offset: u32) -> T {
+    check_bounds(arena, offset as usize, mem::size_of::<T>());
     *arena.get_aligned_pointer::<T>(offset as usize)
   }
 
@@ -508,6 +691,22 @@ impl<T: Copy> Node<T> {
   #[inline]
   unsafe fn get_value_and_trailer<'a, 'b: 'a>(&'a self, arena: &'b Arena) -> (T, Option<&'b [u8]>) {
     let (offset, len) = self.value.load(Ordering::Acquire);
+    Self::get_value_and_trailer_by_pointer(arena, offset, len)
+  }
+
+  /// Like [`get_value_and_trailer`](Node::get_value_and_trailer), but with the value pointer
+  /// loaded by the caller.
+  ///
+  /// ## Safety
+  ///
+  /// - The caller must ensure that the value pointer is loaded from a node allocated by the arena.
+  #[inline]
+  unsafe fn get_value_and_trailer_by_pointer(
+    arena: &Arena,
+    offset: u32,
+    len: u32,
+  ) -> (T, Option<&[u8]>) {
+    check_bounds(arena, offset as usize, mem::size_of::<T>());
     let ptr = arena.get_aligned_pointer(offset as usize);
     #[cfg(not(feature = "unaligned"))]
     let trailer = *ptr;
@@ -517,6 +716,7 @@ impl<T: Copy> Node<T> {
     }
 
     let value_offset = arena.offset(ptr as _) + mem::size_of::<T>();
+    check_bounds(arena, value_offset, len as usize);
     (trailer, Some(arena.get_bytes(value_offset, len as usize)))
   }
 }
@@ -531,14 +731,37 @@ impl<T: Copy> Node<T> {
 pub struct SkipMap<T = u64, C = Ascend> {
   arena: Arena,
   meta: NonNull<Meta>,
+  /// The owner of the meta if it lives on the heap, shared by all clones, so the last clone
+  /// frees it whatever the order the clones are dropped in.
+  heap_meta: Option<Arc<HeapMeta>>,
   head: NodePtr<T>,
   tail: NodePtr<T>,
   data_offset: u32,
   opts: Options,
+  /// The poison reason shared by all clones, `0` means the map is not poisoned.
+  poison: Arc<AtomicU8>,
+  /// Whether the map is frozen, shared by all clones.
+  frozen: Arc<AtomicBool>,
+  /// How many times the link CAS in the insert path has been retried, shared by all clones.
+  retries: Arc<AtomicU64>,
+  /// The size histograms shared by all clones, `None` if not enabled.
+  histograms: Option<Arc<SizeHistograms>>,
+  /// The ARENA utilization counters shared by all clones, `None` if not enabled.
+  arena_stats: Option<Arc<AtomicArenaStats>>,
+  /// The access clock shared by all clones, `None` if the access stamps are not enabled.
+  access_clock: Option<Arc<AtomicU32>>,
+  /// The latency histograms shared by all clones.
+  #[cfg(feature = "perf-stats")]
+  latencies: Arc<LatencyHistograms>,
+  /// The metrics counters shared by all clones.
+  #[cfg(feature = "perf-stats")]
+  metrics: Arc<AtomicMetrics>,
   /// If set to true by tests, then extra delays are added to make it easier to
   /// detect unusual race conditions.
   #[cfg(all(test, feature = "std"))]
   yield_now: bool,
+  /// Set by [`SingleWriterMap`], whose writer is the only one to link the nodes.
+  single_writer: bool,
 
   cmp: C,
 }
@@ -552,12 +775,24 @@ impl<T, C: Clone> Clone for SkipMap<T, C> {
     Self {
       arena: self.arena.clone(),
       meta: self.meta,
+      heap_meta: self.heap_meta.clone(),
       head: self.head,
       tail: self.tail,
       data_offset: self.data_offset,
       opts: self.opts,
+      poison: self.poison.clone(),
+      frozen: self.frozen.clone(),
+      retries: self.retries.clone(),
+      histograms: self.histograms.clone(),
+      arena_stats: self.arena_stats.clone(),
+      access_clock: self.access_clock.clone(),
+      #[cfg(feature = "perf-stats")]
+      latencies: self.latencies.clone(),
+      #[cfg(feature = "perf-stats")]
+      metrics: self.metrics.clone(),
       #[cfg(all(test, feature = "std"))]
       yield_now: self.yield_now,
+      single_writer: self.single_writer,
       cmp: self.cmp.clone(),
     }
   }
@@ -565,23 +800,68 @@ impl<T, C: Clone> Clone for SkipMap<T, C> {
 
 impl<T, C> Drop for SkipMap<T, C> {
   fn drop(&mut self) {
-    if self.arena.refs() == 1 && !self.opts.unify() {
-      unsafe {
-        let _ = Box::from_raw(self.meta.as_ptr());
-      }
+    #[cfg(feature = "track-guards")]
+    guards::report_on_drop(&self.arena);
+  }
+}
+
+/// The meta allocated on the heap, freed when dropped.
+#[derive(Debug)]
+struct HeapMeta(NonNull<Meta>);
+
+// Safety: the meta only contains atomics and plain integers.
+unsafe impl Send for HeapMeta {}
+unsafe impl Sync for HeapMeta {}
+
+impl HeapMeta {
+  #[inline]
+  fn new(magic_version: u16, flags: u8) -> Self {
+    // Safety: the pointer is returned by `Box::into_raw`.
+    Self(unsafe {
+      NonNull::new_unchecked(Box::into_raw(Box::new(Meta::new(magic_version, flags))))
+    })
+  }
+}
+
+impl Drop for HeapMeta {
+  fn drop(&mut self) {
+    // Safety: the pointer is allocated by `HeapMeta::new`, and only freed here.
+    unsafe {
+      let _ = Box::from_raw(self.0.as_ptr());
     }
   }
 }
 
 impl<T, C> SkipMap<T, C> {
   fn new_in(arena: Arena, cmp: C, opts: Options) -> Result<Self, Error> {
+    if u8::from(opts.max_height()) == 0 {
+      return Err(Error::InvalidHeight(0));
+    }
+
+    // `Options::with_value_alignment` stores `0` if the alignment cannot be rounded up.
+    if opts.value_alignment() == 0 {
+      return Err(Error::InvalidAlignment(0));
+    }
+
     let data_offset = Self::check_capacity(&arena, opts.max_height().into())?;
 
     if arena.read_only() {
       let (meta, head, tail) = Self::get_pointers(&arena);
+      // Safety: the meta is within the capacity checked above.
+      if unsafe { meta.as_ref() }.layout_flags() != Meta::layout_flags_of(&opts) {
+        return Err(Error::LayoutMismatch);
+      }
+      // The offset of the tail node is read from the ARENA, so it is untrusted.
+      let node_end = (tail.offset as usize)
+        .checked_add(Node::<T>::size(opts.max_height().into()) + mem::size_of::<T>());
+      if tail.offset <= head.offset || node_end.map_or(true, |end| end > arena.capacity()) {
+        return Err(Error::Corrupted(PoisonReason::OutOfBounds));
+      }
+
       return Ok(Self::construct(
         arena,
         meta,
+        None,
         head,
         tail,
         data_offset,
@@ -590,19 +870,22 @@ impl<T, C> SkipMap<T, C> {
       ));
     }
 
-    let meta = if opts.unify() {
-      Self::allocate_meta(&arena, opts.magic_version())?
+    if opts.huge_pages() {
+      // the advice is best effort, the map works the same without the huge pages.
+      let _ = huge_pages::advise_huge_pages(&arena);
+    }
+
+    let (meta, heap_meta) = if opts.unify() {
+      (
+        Self::allocate_meta(&arena, opts.magic_version(), Meta::layout_flags_of(&opts))?,
+        None,
+      )
     } else {
-      unsafe {
-        NonNull::new_unchecked(Box::into_raw(Box::new(Meta {
-          max_version: AtomicU64::new(0),
-          min_version: AtomicU64::new(0),
-          height: AtomicU8::new(1),
-          len: AtomicU32::new(0),
-          magic_version: opts.magic_version(),
-          reserved_byte: 0,
-        })))
-      }
+      let heap_meta = Arc::new(HeapMeta::new(
+        opts.magic_version(),
+        Meta::layout_flags_of(&opts),
+      ));
+      (heap_meta.0, Some(heap_meta))
     };
 
     let max_height: u8 = opts.max_height().into();
@@ -624,6 +907,7 @@ impl<T, C> SkipMap<T, C> {
     Ok(Self::construct(
       arena,
       meta,
+      heap_meta,
       head,
       tail,
       data_offset,
@@ -681,32 +965,51 @@ impl<T, C> SkipMap<T, C> {
     key_size: u32,
     kf: impl FnOnce(&mut VacantBuffer<'a>) -> Result<(), E>,
     value_size: u32,
+    value_align: u32,
     vf: impl FnOnce(&mut VacantBuffer<'a>) -> Result<(), E>,
   ) -> Result<(NodePtr<T>, Deallocator), Either<E, Error>> {
     self
-      .check_node_size(height, key_size, value_size)
+      .check_node_size(height, key_size, value_size, value_align)
       .map_err(Either::Right)?;
 
     unsafe {
       let mut node = self
         .arena
-        .alloc_aligned_bytes::<Node<T>>(height * Link::SIZE as u32)
+        .alloc_aligned_bytes::<Node<T>>(
+          height * Link::SIZE as u32 + self.node_suffix_size() + self.inline_key_size(key_size),
+        )
         .map_err(|e| Either::Right(e.into()))?;
       let node_ptr = node.as_mut_ptr().cast::<Node<T>>();
       let node_offset = node.offset();
+      self.init_access_stamp(node_ptr, height);
 
-      let mut key = self
-        .arena
-        .alloc_bytes(key_size)
-        .map_err(|e| Either::Right(e.into()))?;
-      let key_offset = key.offset();
-      let key_cap = key.capacity();
+      // the inline key lives at the end of the node, so it is freed together with the node.
+      let mut key = if self.is_inline_key(key_size) {
+        None
+      } else {
+        Some(
+          self
+            .arena
+            .alloc_bytes(key_size)
+            .map_err(|e| Either::Right(e.into()))?,
+        )
+      };
+      let (key_offset, key_cap) = match &key {
+        Some(key) => (key.offset(), key.capacity()),
+        None => (
+          self.inline_key_offset(node_offset as u32, height) as usize,
+          key_size as usize,
+        ),
+      };
       let mut trailer_and_value = self
         .arena
-        .alloc_aligned_bytes::<T>(value_size)
+        .alloc_aligned_bytes::<T>(
+          value_size.saturating_add(value_padding_capacity::<T>(value_align)),
+        )
         .map_err(|e| Either::Right(e.into()))?;
-      let trailer_offset = trailer_and_value.offset();
-      let trailer_ptr = trailer_and_value.as_mut_ptr().cast::<T>();
+      let padding = value_padding::<T>(trailer_and_value.offset(), value_align);
+      let trailer_offset = trailer_and_value.offset() + padding;
+      let trailer_ptr = trailer_and_value.as_mut_ptr().add(padding).cast::<T>();
       trailer_ptr.write(trailer);
 
       let value_offset = (trailer_offset + mem::size_of::<T>()) as u32;
@@ -716,14 +1019,26 @@ impl<T, C> SkipMap<T, C> {
       node_ref.value = AtomicValuePointer::new(trailer_offset as u32, value_size);
       node_ref.key_offset = key_offset as u32;
       node_ref.key_size_and_height = encode_key_size_and_height(key_cap as u32, height as u8);
-      key.detach();
-      let (_, key_deallocate_info) = self
-        .fill_vacant_key(key_cap as u32, key_offset as u32, kf)
-        .map_err(Either::Left)?;
+      let key_deallocate_info = match key.as_mut() {
+        Some(key) => {
+          key.detach();
+          let (_, info) = self
+            .fill_vacant_key(key_cap as u32, key_offset as u32, kf)
+            .map_err(Either::Left)?;
+          Some(info)
+        }
+        None => {
+          self
+            .fill_inline_key(key_cap as u32, key_offset as u32, kf)
+            .map_err(Either::Left)?;
+          None
+        }
+      };
+      self.init_fingerprint(node_ptr);
       trailer_and_value.detach();
       let (_, value_deallocate_info) = self
         .fill_vacant_value(
-          trailer_offset as u32,
+          trailer_and_value.offset() as u32,
           trailer_and_value.capacity() as u32,
           value_size,
           value_offset,
@@ -735,7 +1050,7 @@ impl<T, C> SkipMap<T, C> {
         NodePtr::new(node_ptr as _, node_offset as u32),
         Deallocator {
           node: Some(Pointer::new(node_offset as u32, node.capacity() as u32)),
-          key: Some(key_deallocate_info),
+          key: key_deallocate_info,
           value: Some(value_deallocate_info),
         },
       ))
@@ -752,16 +1067,17 @@ impl<T, C> SkipMap<T, C> {
     value_size: u32,
   ) -> Result<(NodePtr<T>, Deallocator), Either<E, Error>> {
     self
-      .check_node_size(height, key_size, value_size)
+      .check_node_size(height, key_size, value_size, 0)
       .map_err(Either::Right)?;
 
     unsafe {
       let mut node = self
         .arena
-        .alloc_aligned_bytes::<Node<T>>(height * Link::SIZE as u32)
+        .alloc_aligned_bytes::<Node<T>>(height * Link::SIZE as u32 + self.node_suffix_size())
         .map_err(|e| Either::Right(e.into()))?;
       let node_ptr = node.as_mut_ptr().cast::<Node<T>>();
       let node_offset = node.offset();
+      self.init_access_stamp(node_ptr, height);
 
       let mut trailer_ref = self
         .arena
@@ -775,6 +1091,7 @@ impl<T, C> SkipMap<T, C> {
       node_ref.value = AtomicValuePointer::new(trailer_offset as u32, value_size);
       node_ref.key_offset = key_offset;
       node_ref.key_size_and_height = encode_key_size_and_height(key_size, height as u8);
+      self.init_fingerprint(node_ptr);
 
       trailer_ref.detach();
       node.detach();
@@ -802,23 +1119,38 @@ impl<T, C> SkipMap<T, C> {
     value_size: u32,
   ) -> Result<(NodePtr<T>, Deallocator), Either<E, Error>> {
     self
-      .check_node_size(height, key_size, value_size)
+      .check_node_size(height, key_size, value_size, 0)
       .map_err(Either::Right)?;
 
     unsafe {
       let mut node = self
         .arena
-        .alloc_aligned_bytes::<Node<T>>(height * Link::SIZE as u32)
+        .alloc_aligned_bytes::<Node<T>>(
+          height * Link::SIZE as u32 + self.node_suffix_size() + self.inline_key_size(key_size),
+        )
         .map_err(|e| Either::Right(e.into()))?;
       let node_ptr = node.as_mut_ptr().cast::<Node<T>>();
       let node_offset = node.offset();
+      self.init_access_stamp(node_ptr, height);
 
-      let mut key = self
-        .arena
-        .alloc_bytes(key_size)
-        .map_err(|e| Either::Right(e.into()))?;
-      let key_offset = key.offset();
-      let key_cap = key.capacity();
+      // the inline key lives at the end of the node, so it is freed together with the node.
+      let mut key = if self.is_inline_key(key_size) {
+        None
+      } else {
+        Some(
+          self
+            .arena
+            .alloc_bytes(key_size)
+            .map_err(|e| Either::Right(e.into()))?,
+        )
+      };
+      let (key_offset, key_cap) = match &key {
+        Some(key) => (key.offset(), key.capacity()),
+        None => (
+          self.inline_key_offset(node_offset as u32, height) as usize,
+          key_size as usize,
+        ),
+      };
 
       let mut trailer_ref = self
         .arena
@@ -833,10 +1165,22 @@ impl<T, C> SkipMap<T, C> {
       node_ref.key_offset = key_offset as u32;
       node_ref.key_size_and_height = encode_key_size_and_height(key_cap as u32, height as u8);
 
-      key.detach();
-      let (_, key_deallocate_info) = self
-        .fill_vacant_key(key_cap as u32, key_offset as u32, kf)
-        .map_err(Either::Left)?;
+      let key_deallocate_info = match key.as_mut() {
+        Some(key) => {
+          key.detach();
+          let (_, info) = self
+            .fill_vacant_key(key_cap as u32, key_offset as u32, kf)
+            .map_err(Either::Left)?;
+          Some(info)
+        }
+        None => {
+          self
+            .fill_inline_key(key_cap as u32, key_offset as u32, kf)
+            .map_err(Either::Left)?;
+          None
+        }
+      };
+      self.init_fingerprint(node_ptr);
 
       trailer_ref.detach();
       node.detach();
@@ -845,7 +1189,7 @@ impl<T, C> SkipMap<T, C> {
         NodePtr::new(node_ptr as _, node_offset as u32),
         Deallocator {
           node: Some(Pointer::new(node_offset as u32, node.capacity() as u32)),
-          key: Some(key_deallocate_info),
+          key: key_deallocate_info,
           value: Some(Pointer::new(
             trailer_offset as u32,
             mem::size_of::<T>() as u32,
@@ -863,26 +1207,31 @@ impl<T, C> SkipMap<T, C> {
     key_size: u32,
     key_offset: u32,
     value_size: u32,
+    value_align: u32,
     vf: impl FnOnce(&mut VacantBuffer<'a>) -> Result<(), E>,
   ) -> Result<(NodePtr<T>, Deallocator), Either<E, Error>> {
     self
-      .check_node_size(height, key_size, value_size)
+      .check_node_size(height, key_size, value_size, value_align)
       .map_err(Either::Right)?;
 
     unsafe {
       let mut node = self
         .arena
-        .alloc_aligned_bytes::<Node<T>>(height * Link::SIZE as u32)
+        .alloc_aligned_bytes::<Node<T>>(height * Link::SIZE as u32 + self.node_suffix_size())
         .map_err(|e| Either::Right(e.into()))?;
       let node_ptr = node.as_mut_ptr().cast::<Node<T>>();
       let node_offset = node.offset();
+      self.init_access_stamp(node_ptr, height);
 
       let mut trailer_and_value = self
         .arena
-        .alloc_aligned_bytes::<T>(value_size)
+        .alloc_aligned_bytes::<T>(
+          value_size.saturating_add(value_padding_capacity::<T>(value_align)),
+        )
         .map_err(|e| Either::Right(e.into()))?;
-      let trailer_offset = trailer_and_value.offset();
-      let trailer_ptr = trailer_and_value.as_mut_ptr().cast::<T>();
+      let padding = value_padding::<T>(trailer_and_value.offset(), value_align);
+      let trailer_offset = trailer_and_value.offset() + padding;
+      let trailer_ptr = trailer_and_value.as_mut_ptr().add(padding).cast::<T>();
       trailer_ptr.write(trailer);
       let value_offset = (trailer_offset + mem::size_of::<T>()) as u32;
 
@@ -891,11 +1240,12 @@ impl<T, C> SkipMap<T, C> {
       node_ref.value = AtomicValuePointer::new(trailer_offset as u32, value_size);
       node_ref.key_offset = key_offset;
       node_ref.key_size_and_height = encode_key_size_and_height(key_size, height as u8);
+      self.init_fingerprint(node_ptr);
 
       trailer_and_value.detach();
       let (_, value_deallocate_info) = self
         .fill_vacant_value(
-          trailer_offset as u32,
+          trailer_and_value.offset() as u32,
           trailer_and_value.capacity() as u32,
           value_size,
           value_offset,
@@ -944,20 +1294,17 @@ impl<T, C> SkipMap<T, C> {
   }
 
   #[inline]
-  fn allocate_meta(arena: &Arena, magic_version: u16) -> Result<NonNull<Meta>, ArenaError> {
+  fn allocate_meta(
+    arena: &Arena,
+    magic_version: u16,
+    flags: u8,
+  ) -> Result<NonNull<Meta>, ArenaError> {
     // Safety: meta does not need to be dropped, and it is recoverable.
     unsafe {
       let mut meta = arena.alloc::<Meta>()?;
       meta.detach();
 
-      meta.write(Meta {
-        max_version: AtomicU64::new(0),
-        min_version: AtomicU64::new(0),
-        height: AtomicU8::new(1),
-        len: AtomicU32::new(0),
-        magic_version,
-        reserved_byte: 0,
-      });
+      meta.write(Meta::new(magic_version, flags));
       Ok(meta.as_mut_ptr())
     }
   }
@@ -1050,11 +1397,35 @@ impl<T, C> SkipMap<T, C> {
     }
   }
 
+  /// Returns the [`Options`] with the maximum height and the node layout of the read-only
+  /// ARENA: the height of the head node, which is always a full node, and the layout flags of
+  /// the meta. The maximum key size is not stored, it only limits the writes, which a read-only
+  /// map rejects.
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  fn stored_options(arena: &Arena, opts: Options) -> Result<Options, Error> {
+    Self::check_capacity(arena, 0)?;
+    let (meta, head, _) = Self::get_pointers(arena);
+    // Safety: the meta and the header of the head node are within the capacity checked above.
+    let (flags, height) = unsafe { (meta.as_ref().layout_flags(), head.as_ref().height()) };
+    Ok(
+      opts
+        .with_max_height(u5::new(height))
+        .with_access_stamps(flags & Meta::ACCESS_STAMPS != 0)
+        .with_key_fingerprints(flags & Meta::KEY_FINGERPRINTS != 0),
+    )
+  }
+
   #[inline]
-  fn check_node_size(&self, height: u32, key_size: u32, mut value_size: u32) -> Result<(), Error> {
+  fn check_node_size(
+    &self,
+    height: u32,
+    key_size: u32,
+    mut value_size: u32,
+    value_align: u32,
+  ) -> Result<(), Error> {
     let max_height: u32 = self.opts.max_height().into();
     if height < 1 || height > max_height {
-      panic!("height cannot be less than one or greater than the max height");
+      return Err(Error::InvalidHeight(height as u8));
     }
 
     let max_key_size: u32 = self.opts.max_key_size().into();
@@ -1073,8 +1444,19 @@ impl<T, C> SkipMap<T, C> {
       return Err(Error::ValueTooLarge(value_size as u64));
     }
 
-    let entry_size = (value_size as u64 + key_size as u64) + Node::<T>::size(height as u8) as u64;
-    if entry_size > u32::MAX as u64 {
+    // Includes the worst case paddings, computed in u64, so it cannot overflow.
+    let entry_size = Node::<T>::size(height as u8) as u64
+      + self.node_suffix_size() as u64
+      + mem::align_of::<Node<T>>() as u64
+      + key_size as u64
+      + mem::size_of::<T>() as u64
+      + mem::align_of::<T>() as u64
+      + value_size as u64
+      + value_padding_capacity::<T>(value_align) as u64;
+
+    // The ARENA tracks the offsets in u32, reject the entries which can never fit before
+    // the allocator sees them, so the offsets cannot wrap around.
+    if entry_size > u32::MAX as u64 || entry_size > self.arena.capacity() as u64 {
       return Err(Error::EntryTooLarge(entry_size));
     }
 
@@ -1082,9 +1464,11 @@ impl<T, C> SkipMap<T, C> {
   }
 
   #[inline]
+  #[allow(clippy::too_many_arguments)]
   fn construct(
     arena: Arena,
     meta: NonNull<Meta>,
+    heap_meta: Option<Arc<HeapMeta>>,
     head: NodePtr<T>,
     tail: NodePtr<T>,
     data_offset: u32,
@@ -1094,16 +1478,58 @@ impl<T, C> SkipMap<T, C> {
     Self {
       arena,
       meta,
+      heap_meta,
       head,
       tail,
       data_offset,
       opts,
+      // Safety: the meta is allocated or checked by the callers, the reason is persisted by a
+      // previous process if the map is reopened.
+      poison: Arc::new(AtomicU8::new(unsafe { meta.as_ref() }.poison_reason())),
+      frozen: Arc::new(AtomicBool::new(false)),
+      retries: Arc::new(AtomicU64::new(0)),
+      histograms: if opts.size_histograms() {
+        Some(Arc::new(SizeHistograms::new()))
+      } else {
+        None
+      },
+      arena_stats: if opts.arena_stats() {
+        Some(Arc::new(AtomicArenaStats::new()))
+      } else {
+        None
+      },
+      access_clock: if opts.access_stamps() {
+        Some(Arc::new(AtomicU32::new(0)))
+      } else {
+        None
+      },
+      #[cfg(feature = "perf-stats")]
+      latencies: Arc::new(LatencyHistograms::new()),
+      #[cfg(feature = "perf-stats")]
+      metrics: Arc::new(AtomicMetrics::new()),
       #[cfg(all(test, feature = "std"))]
       yield_now: false,
+      single_writer: false,
       cmp,
     }
   }
 
+  /// Returns the maximum height a newly generated tower can have.
+  ///
+  /// If [`Options::expected_entries`] is set, the height is capped at roughly
+  /// `log_{1/p}(max(expected_entries, len))`.
+  #[inline]
+  fn max_random_height(&self) -> u8 {
+    let max_height: u8 = self.opts.max_height().into();
+    match self.opts.expected_entries() {
+      0 => max_height,
+      expected => {
+        let n = expected.max(self.meta().len());
+        super::expected_height(n as u64).min(max_height)
+      }
+    }
+  }
+
   #[inline]
   const fn meta(&self) -> &Meta {
     // Safety: the pointer is well aligned and initialized.
@@ -1117,9 +1543,10 @@ impl<T: Trailer, C> SkipMap<T, C> {
     key: &Key<'a, 'b>,
     trailer: T,
     value_size: u32,
+    value_align: u32,
     f: &impl Fn(&mut VacantBuffer<'a>) -> Result<(), E>,
   ) -> Result<(NodePtr<T>, u32, Deallocator), Either<E, Error>> {
-    let height = super::random_height(self.opts.max_height().into());
+    let height = super::random_height(self.max_random_height());
     let (nd, deallocator) = match key {
       Key::Occupied(key) => self.allocate_entry_node(
         height,
@@ -1130,13 +1557,20 @@ impl<T: Trailer, C> SkipMap<T, C> {
           Ok(())
         },
         value_size,
+        value_align,
+        f,
+      )?,
+      Key::Vacant(key) => self.allocate_value_node(
+        height,
+        trailer,
+        key.len() as u32,
+        key.offset,
+        value_size,
+        value_align,
         f,
       )?,
-      Key::Vacant(key) => {
-        self.allocate_value_node(height, trailer, key.len() as u32, key.offset, value_size, f)?
-      }
       Key::Pointer { offset, len, .. } => {
-        self.allocate_value_node(height, trailer, *len, *offset, value_size, f)?
+        self.allocate_value_node(height, trailer, *len, *offset, value_size, value_align, f)?
       }
       Key::Remove(key) => self.allocate_key_node(
         height,
@@ -1156,7 +1590,8 @@ impl<T: Trailer, C> SkipMap<T, C> {
       }
     };
 
-    // Try to increase self.height via CAS.
+    // Try to increase self.height via CAS. `SeqCst` like the link CAS of the insert path, see
+    // `SkipMap::update`: the height bounds the levels the searches and the splices start from.
     let mut list_height = self.height();
     while height as u8 > list_height {
       match self.meta().height.compare_exchange_weak(
@@ -1185,8 +1620,7 @@ impl<T: Trailer, C: Comparator> SkipMap<T, C> {
     }
 
     let offset = nd.prev_offset(&self.arena, height);
-    let ptr = self.arena.get_pointer(offset as usize);
-    NodePtr::new(ptr as _, offset)
+    self.node_at(offset, height, self.head)
   }
 
   /// ## Safety
@@ -1198,10 +1632,105 @@ impl<T: Trailer, C: Comparator> SkipMap<T, C> {
       return NodePtr::NULL;
     }
     let offset = nptr.next_offset(&self.arena, height);
+    self.node_at(offset, height, self.tail)
+  }
+
+  /// Like [`get_next`](SkipMap::get_next), but without the null check, used by the tower descent.
+  ///
+  /// Every level of the head node and of every linked node ends at the tail sentinel,
+  /// so the next node of a linked node is never null.
+  ///
+  /// ## Safety
+  ///
+  /// - The caller must ensure that the node is allocated by the arena.
+  /// - The caller must ensure that the node is not null and is linked at the given height.
+  #[inline]
+  unsafe fn get_next_unchecked(&self, nptr: NodePtr<T>, height: usize) -> NodePtr<T> {
+    let offset = nptr.next_offset(&self.arena, height);
+    self.node_at(offset, height, self.tail)
+  }
+
+  /// Returns the node at the offset read from a link at the level, `sentinel` ends the walk in
+  /// the direction of the link (the tail going forward, the head going backward).
+  ///
+  /// With the `untrusted` feature the node is checked like [`verify`](SkipMap::verify) does
+  /// before it is returned, if its header, tower, key or value is out of the allocated part of
+  /// the ARENA, the map is poisoned and the walk ends at the sentinel, so a malformed ARENA
+  /// is reported by [`check_poison`](SkipMap::check_poison) instead of a panic.
+  ///
+  /// ## Safety
+  ///
+  /// - The offset must be read from a link of a node allocated by the arena.
+  #[inline]
+  unsafe fn node_at(&self, offset: u32, level: usize, sentinel: NodePtr<T>) -> NodePtr<T> {
+    #[cfg(feature = "untrusted")]
+    if offset != self.tail.offset
+      && offset != self.head.offset
+      && !self.node_in_bounds(offset, level)
+    {
+      self.poison(PoisonReason::OutOfBounds);
+      return sentinel;
+    }
+    #[cfg(not(feature = "untrusted"))]
+    let _ = (level, sentinel);
+
     let ptr = self.arena.get_pointer(offset as usize);
     NodePtr::new(ptr as _, offset)
   }
 
+  /// Returns `true` if the node at the offset, linked at the level, is well aligned, its height
+  /// is valid, and its header, tower, key and value are in the allocated part of the ARENA.
+  #[cfg(feature = "untrusted")]
+  unsafe fn node_in_bounds(&self, offset: u32, level: usize) -> bool {
+    let allocated = self.arena.allocated() as u64;
+    if (offset as usize) < self.arena.data_offset()
+      || offset as usize % mem::align_of::<Node<T>>() != 0
+      || offset as u64 + Node::<T>::SIZE as u64 > allocated
+    {
+      return false;
+    }
+
+    let node = &*self.arena.get_pointer(offset as usize).cast::<Node<T>>();
+    let height = node.height();
+    let max_height: u8 = self.opts.max_height().into();
+    if height == 0 || height > max_height || (height as usize) <= level {
+      return false;
+    }
+
+    let (trailer_offset, value_size) = node.value.load(Ordering::Acquire);
+    let value_size = if value_size == REMOVE { 0 } else { value_size };
+    let tower_end = offset as u64 + Node::<T>::size(height) as u64;
+    let key_end = node.key_offset as u64 + node.key_size() as u64;
+    let value_end = trailer_offset as u64 + mem::size_of::<T>() as u64 + value_size as u64;
+    tower_end <= allocated && key_end <= allocated && value_end <= allocated
+  }
+
+  /// Returns the last node whose key is `before` the target, starting from the head by the
+  /// tower descent, or the head if the key of the first node is not `before` the target.
+  ///
+  /// `before` must be monotone in the order of the map: `true` for a prefix of the nodes.
+  ///
+  /// ## Safety
+  ///
+  /// - The caller must ensure that the head node is allocated by the arena.
+  unsafe fn find_last_before<'a>(&'a self, before: impl Fn(&'a [u8]) -> bool) -> NodePtr<T> {
+    let mut x = self.head;
+    let mut level = self.height() as usize - 1;
+
+    loop {
+      let next = self.get_next_unchecked(x, level);
+      if next.ptr != self.tail.ptr && before(next.as_ref().get_key(&self.arena)) {
+        x = next;
+        continue;
+      }
+
+      if level == 0 {
+        return x;
+      }
+      level -= 1;
+    }
+  }
+
   /// Returns the first entry in the map.
   fn first_in(&self, version: u64) -> Option<NodePtr<T>> {
     // Safety: head node was definitely allocated by self.arena
@@ -1220,8 +1749,7 @@ impl<T: Trailer, C: Comparator> SkipMap<T, C> {
 
   /// Returns the last entry in the map.
   fn last_in(&self, version: u64) -> Option<NodePtr<T>> {
-    // Safety: tail node was definitely allocated by self.arena
-    let nd = unsafe { self.get_prev(self.tail, 0) };
+    let nd = self.last_node();
 
     if nd.is_null() || nd.ptr == self.head.ptr {
       return None;
@@ -1234,6 +1762,27 @@ impl<T: Trailer, C: Comparator> SkipMap<T, C> {
     }
   }
 
+  /// Returns the last node of the base level, the head if the map is empty.
+  ///
+  /// It descends from the top level following the next links until the tail, rather than
+  /// following the previous link of the tail, which lags behind the concurrent inserts.
+  fn last_node(&self) -> NodePtr<T> {
+    let mut nd = self.head;
+    let mut level = self.height() as usize - 1;
+    loop {
+      // Safety: the nodes are reached at the level, so their towers are at least that high.
+      let next = unsafe { self.get_next(nd, level) };
+      if next.is_null() || next.ptr == self.tail.ptr {
+        if level == 0 {
+          return nd;
+        }
+        level -= 1;
+      } else {
+        nd = next;
+      }
+    }
+  }
+
   /// Returns the entry greater or equal to the given key, if it exists.
   ///
   /// e.g.
@@ -1335,7 +1884,8 @@ impl<T: Trailer, C: Comparator> SkipMap<T, C> {
 
       let prev_node = prev.as_ref();
       let prev_key = prev_node.get_key(&self.arena);
-      if self.cmp.compare(prev_key, curr_key) == cmp::Ordering::Less {
+      // the keys are ordered, so the different keys must be less
+      if !self.node_keys_eq(prev, prev_key, curr, curr_key) {
         return Some(curr);
       }
 
@@ -1377,7 +1927,8 @@ impl<T: Trailer, C: Comparator> SkipMap<T, C> {
       let next_node = next.as_ref();
       let next_key = next_node.get_key(&self.arena);
       let version_cmp = next_node.get_trailer(&self.arena).version().cmp(&version);
-      if self.cmp.compare(next_key, curr_key) == cmp::Ordering::Greater {
+      // the keys are ordered, so the different keys must be greater
+      if !self.node_keys_eq(next, next_key, curr, curr_key) {
         if let cmp::Ordering::Less | cmp::Ordering::Equal = version_cmp {
           return Some(curr);
         }
@@ -1411,21 +1962,47 @@ impl<T: Trailer, C: Comparator> SkipMap<T, C> {
     less: bool,
     allow_equal: bool,
   ) -> (Option<NodePtr<T>>, bool) {
+    let tail = self.tail.offset;
     let mut x = self.head;
     let mut level = self.height() as usize - 1;
+    #[cfg(feature = "perf-stats")]
+    let mut probe = self.probe();
 
-    loop {
+    // The upper levels only narrow down the predecessor of the key, so they are walked by a
+    // dedicated loop whose only decision is to move right or to descend. What to return is
+    // only resolved on the base level, except an allowed equal key, which ends the search.
+    while level > 0 {
       // Assume x.key < key.
-      let next = self.get_next(x, level);
-      if next.is_null() || next.ptr == self.tail.ptr {
-        // x.key < key < END OF LIST
-        if level > 0 {
-          // Can descend further to iterate closer to the end.
-          level -= 1;
-          continue;
+      let next = self.get_next_unchecked(x, level);
+      if next.offset != tail {
+        let next_node = next.as_ref();
+        #[cfg(feature = "perf-stats")]
+        probe.compared();
+        let cmp = self
+          .cmp
+          .compare(key, next_node.get_key(&self.arena))
+          .then_with(|| next_node.get_trailer(&self.arena).version().cmp(&version));
+
+        match cmp {
+          // x.key < next.key < key. We can continue to move right.
+          cmp::Ordering::Greater => {
+            x = next;
+            continue;
+          }
+          cmp::Ordering::Equal if allow_equal => return (Some(next), true),
+          // key <= next.key, the node is found closer on the levels below.
+          _ => {}
         }
+      }
 
-        // level == 0. Can't descend further. Let's return something that makes sense.
+      level -= 1;
+    }
+
+    loop {
+      // Assume x.key < key.
+      let next = self.get_next_unchecked(x, 0);
+      if next.offset == tail {
+        // x.key < key < END OF LIST. Let's return something that makes sense.
         if !less {
           return (None, false);
         }
@@ -1440,6 +2017,8 @@ impl<T: Trailer, C: Comparator> SkipMap<T, C> {
 
       let next_node = next.as_ref();
       let next_key = next_node.get_key(&self.arena);
+      #[cfg(feature = "perf-stats")]
+      probe.compared();
       let cmp = self
         .cmp
         .compare(key, next_key)
@@ -1458,27 +2037,15 @@ impl<T: Trailer, C: Comparator> SkipMap<T, C> {
           }
 
           if !less {
-            // We want >, so go to base level to grab the next bigger node.
+            // We want >, so grab the next bigger node.
             return (Some(self.get_next(next, 0)), false);
           }
 
-          // We want <. If not base level, we should go closer in the next level.
-          if level > 0 {
-            level -= 1;
-            continue;
-          }
-
-          // On base level, Return x.
+          // We want <, return x.
           return (Some(x), false);
         }
         // In other words, x.key < key < next.
         cmp::Ordering::Less => {
-          if level > 0 {
-            level -= 1;
-            continue;
-          }
-
-          // On base level. Need to return something.
           if !less {
             return (Some(next), false);
           }
@@ -1572,12 +2139,13 @@ impl<T: Trailer, C: Comparator> SkipMap<T, C> {
     level: usize,
     start: NodePtr<T>,
   ) -> FindResult<T> {
+    let tail = self.tail.offset;
     let mut prev = start;
 
     loop {
       // Assume prev.key < key.
-      let next = self.get_next(prev, level);
-      if next.ptr == self.tail.ptr {
+      let next = self.get_next_unchecked(prev, level);
+      if next.offset == tail {
         // Tail node, so done.
         return FindResult {
           splice: Splice { prev, next },
@@ -1661,6 +2229,60 @@ impl<T: Trailer, C: Comparator> SkipMap<T, C> {
     }
   }
 
+  pub(crate) fn get_in(&self, version: u64, key: &[u8]) -> Option<EntryRef<'_, T>> {
+    #[cfg(feature = "perf-stats")]
+    let _timer = self.timer(latency::Op::Get);
+
+    // the links of a poisoned map may be corrupted, see `SkipMap::poison`
+    if self.is_poisoned() {
+      return None;
+    }
+
+    unsafe {
+      let (n, eq) = self.find_near(version, key, false, true); // findLessOrEqual.
+                                                               // the search poisons the map on a corrupted link of an untrusted ARENA
+      if self.is_poisoned() {
+        return None;
+      }
+
+      let n = n?;
+      let node = n.as_ref();
+      let node_key = node.get_key(&self.arena);
+      let (trailer, value) = node.get_value_and_trailer(&self.arena);
+      if eq {
+        return value.map(|val| {
+          self.record_access(n);
+          EntryRef(VersionedEntryRef {
+            arena: &self.arena,
+            key: node_key,
+            trailer,
+            value: Some(val),
+            ptr: n,
+          })
+        });
+      }
+
+      if !matches!(self.cmp.compare(key, node_key), cmp::Ordering::Equal) {
+        return None;
+      }
+
+      if trailer.version() > version {
+        return None;
+      }
+
+      value.map(|val| {
+        self.record_access(n);
+        EntryRef(VersionedEntryRef {
+          arena: &self.arena,
+          key: node_key,
+          trailer,
+          value: Some(val),
+          ptr: n,
+        })
+      })
+    }
+  }
+
   fn fetch_vacant_key<'a, 'b: 'a, E>(
     &'a self,
     key_size: u32,
@@ -1692,18 +2314,45 @@ impl<T: Trailer, C: Comparator> SkipMap<T, C> {
       .map(|_| vk)
   }
 
+  /// Inserts or updates the entry.
+  ///
+  /// All user-provided code (the comparator, the key and value builders) may panic, so
+  /// the node is fully initialized by [`new_node`](SkipMap::new_node) before it becomes
+  /// reachable, and linking it into the base level is the publication point. A panic
+  /// before that only leaks the allocated bytes in the ARENA, a panic after that (when
+  /// the comparator is called again to link the upper levels) leaves a fully initialized
+  /// node which is only reachable from the lower levels, which is still a valid skiplist.
+  ///
+  /// Without `upsert`, returns `Either::Left(None)` if the node is inserted, and
+  /// `Either::Left(Some(old))` if the key with the version already exists, even if `old` is
+  /// removed, so the callers can tell the two apart.
   #[allow(clippy::too_many_arguments)]
   fn update<'a, 'b: 'a, E>(
     &'a self,
     trailer: T,
     key: Key<'a, 'b>,
     value_size: u32,
+    value_align: u32,
     f: impl Fn(&mut VacantBuffer<'a>) -> Result<(), E>,
     success: Ordering,
     failure: Ordering,
     ins: &mut Inserter<T>,
     upsert: bool,
   ) -> Result<UpdateOk<'a, 'b, T>, Either<E, Error>> {
+    #[cfg(feature = "perf-stats")]
+    let _timer = self.timer(latency::Op::Insert);
+
+    if let Some(reason) = self.poison_reason() {
+      return Err(Either::Right(Error::Corrupted(reason)));
+    }
+
+    // All the writes go through here (or `swap_value`), so a map frozen through another clone
+    // rejects them even if the caller did not check.
+    if self.read_only() {
+      key.on_fail(&self.arena);
+      return Err(Either::Right(Error::read_only()));
+    }
+
     let version = trailer.version();
 
     // Safety: a fresh new Inserter, so safe here
@@ -1717,20 +2366,31 @@ impl<T: Trailer, C: Comparator> SkipMap<T, C> {
 
         if upsert {
           return self.upsert(
-            old, node_ptr, &key, trailer, value_size, &f, success, failure,
+            old,
+            node_ptr,
+            &key,
+            trailer,
+            value_size,
+            value_align,
+            &f,
+            success,
+            failure,
           );
         }
 
-        return Ok(Either::Left(if old.is_removed() {
-          None
-        } else {
-          Some(old)
-        }));
+        return Ok(Either::Left(Some(old)));
       }
 
       found_key
     };
 
+    // the search ends at the tail on a corrupted link of an untrusted ARENA, see
+    // `SkipMap::node_at`, so the splice must not be linked.
+    if let Some(reason) = self.poison_reason() {
+      key.on_fail(&self.arena);
+      return Err(Either::Right(Error::Corrupted(reason)));
+    }
+
     #[cfg(all(test, feature = "std"))]
     if self.yield_now {
       // Add delay to make it easier to test race between this thread
@@ -1758,16 +2418,23 @@ impl<T: Trailer, C: Comparator> SkipMap<T, C> {
       }
     };
 
-    let (nd, height, mut deallocator) =
-      self.new_node(&k, trailer, value_size, &f).map_err(|e| {
+    let (nd, height, mut deallocator) = self
+      .new_node(&k, trailer, value_size, value_align, &f)
+      .map_err(|e| {
         k.on_fail(&self.arena);
         e
       })?;
+    // Safety: the node is not linked yet, so its value cannot be replaced.
+    let (_, value_len) = unsafe { nd.as_ref() }.value.load(Ordering::Relaxed);
 
     // We always insert from the base level and up. After you add a node in base
     // level, we cannot create a node in the level above because it would have
     // discovered the node in the base level.
     let mut invalid_data_splice = false;
+    let mut backoff = Backoff::new(
+      self.opts.backoff_spin_limit(),
+      self.opts.backoff_yield_limit(),
+    );
 
     for i in 0..(height as usize) {
       let mut prev = ins.spl[i].prev;
@@ -1777,7 +2444,8 @@ impl<T: Trailer, C: Comparator> SkipMap<T, C> {
         // New node increased the height of the skiplist, so assume that the
         // new level has not yet been populated.
         if !next.is_null() {
-          panic!("next is expected to be nil, since prev is nil");
+          // next is expected to be nil, since prev is nil.
+          return Err(Either::Right(self.corrupted(PoisonReason::BrokenPrevLink)));
         }
 
         prev = self.head;
@@ -1796,6 +2464,35 @@ impl<T: Trailer, C: Comparator> SkipMap<T, C> {
       // 2. CAS prevNextOffset to repoint from next to nd.
       // 3. CAS nextPrevOffset to repoint from prev to nd.
       unsafe {
+        if self.single_writer {
+          // No other writer can change the splice, so the links are stored without CAS, the
+          // `Release` stores still publish the node to the readers.
+          nd.write_tower(&self.arena, i, prev.offset, next.offset);
+          prev
+            .tower(&self.arena, i)
+            .next_offset
+            .store(nd.offset, Ordering::Release);
+          next
+            .tower(&self.arena, i)
+            .prev_offset
+            .store(nd.offset, Ordering::Release);
+
+          if i == 0 {
+            self.record_node_sizes(nd);
+            self.record_node_allocation(&deallocator, value_len);
+            #[cfg(feature = "perf-stats")]
+            self.record_insert(nd);
+            self.meta().increase_len();
+            self.meta().update_max_version(version);
+            self.meta().update_min_version(version);
+          }
+          continue;
+        }
+
+        // The link CAS and the helping CAS of the previous link stay `SeqCst`. The protocol is
+        // the one of Pebble's arenaskl, whose correctness argument assumes sequentially
+        // consistent atomics (Go's), and a weaker ordering cannot be model checked here, as
+        // the links are words of the ARENA cast to atomics, which loom cannot instrument.
         loop {
           let prev_offset = prev.offset;
           let next_offset = next.offset;
@@ -1858,6 +2555,19 @@ impl<T: Trailer, C: Comparator> SkipMap<T, C> {
                 Ordering::Acquire,
               );
 
+              // The node is published once it is linked into the base level, update the
+              // meta now, so it stays consistent even if the comparator panics when
+              // linking the upper levels.
+              if i == 0 {
+                self.record_node_sizes(nd);
+                self.record_node_allocation(&deallocator, value_len);
+                #[cfg(feature = "perf-stats")]
+                self.record_insert(nd);
+                self.meta().increase_len();
+                self.meta().update_max_version(version);
+                self.meta().update_min_version(version);
+              }
+
               break;
             }
             Err(_) => {
@@ -1866,9 +2576,19 @@ impl<T: Trailer, C: Comparator> SkipMap<T, C> {
               // because it is unlikely that lots of nodes are inserted between prev
               // and next.
               let fr = self.find_splice_for_level(trailer.version(), k.as_ref(), i, prev);
+              if let Some(reason) = self.poison_reason() {
+                // the node is published once it is linked into the base level
+                if i == 0 {
+                  k.on_fail(&self.arena);
+                  deallocator.dealloc(&self.arena);
+                }
+                return Err(Either::Right(Error::Corrupted(reason)));
+              }
+
               if fr.found {
                 if i != 0 {
-                  panic!("how can another thread have inserted a node at a non-base level?");
+                  // Another thread cannot insert the same entry at a non-base level.
+                  return Err(Either::Right(self.corrupted(PoisonReason::Unordered)));
                 }
 
                 let node_ptr = fr
@@ -1880,18 +2600,26 @@ impl<T: Trailer, C: Comparator> SkipMap<T, C> {
 
                 if upsert {
                   deallocator.dealloc(&self.arena);
-                  return self.upsert(old, node_ptr, &k, trailer, value_size, &f, success, failure);
+                  return self.upsert(
+                    old,
+                    node_ptr,
+                    &k,
+                    trailer,
+                    value_size,
+                    value_align,
+                    &f,
+                    success,
+                    failure,
+                  );
                 }
 
                 deallocator.dealloc(&self.arena);
-                return Ok(Either::Left(if old.is_removed() {
-                  None
-                } else {
-                  Some(old)
-                }));
+                return Ok(Either::Left(Some(old)));
               }
 
-              if let Some(p) = fr.found_key {
+              // The node is not reachable until it is linked into the base level, after
+              // that its key must not be changed anymore.
+              if let (0, Some(p)) = (i, fr.found_key) {
                 k.on_fail(&self.arena);
                 let node = nd.as_mut();
                 node.key_offset = p.offset;
@@ -1907,6 +2635,10 @@ impl<T: Trailer, C: Comparator> SkipMap<T, C> {
               invalid_data_splice = true;
               prev = fr.splice.prev;
               next = fr.splice.next;
+
+              // A statistic only, it does not order any other memory access.
+              self.retries.fetch_add(1, Ordering::Relaxed);
+              backoff.snooze();
             }
           }
         }
@@ -1925,13 +2657,90 @@ impl<T: Trailer, C: Comparator> SkipMap<T, C> {
         ins.spl[i].prev = nd;
       }
     }
-    self.meta().increase_len();
-    self.meta().update_max_version(version);
-    self.meta().update_min_version(version);
 
     Ok(Either::Left(None))
   }
 
+  /// Swaps the value of the node by CAS, to the value returned by `f` for the current value
+  /// (`None` if it is removed), until the swap succeeds or `f` returns `None`.
+  ///
+  /// Returns `Ok(previous)` if the value is swapped, and `Err(current)` if `f` returns `None`,
+  /// the entries are `None` if they are removed.
+  ///
+  /// ## Safety
+  ///
+  /// - The node must be allocated by the ARENA of this map.
+  unsafe fn swap_value<'a, R: AsRef<[u8]>>(
+    &'a self,
+    node_ptr: NodePtr<T>,
+    trailer: T,
+    success: Ordering,
+    failure: Ordering,
+    mut f: impl FnMut(Option<&'a [u8]>) -> Option<R>,
+  ) -> Result<Result<Option<EntryRef<'a, T>>, Option<EntryRef<'a, T>>>, Error> {
+    if self.read_only() {
+      return Err(Error::read_only());
+    }
+
+    let node = node_ptr.as_ref();
+    let mut pointer = node.value.load(Ordering::Acquire);
+    loop {
+      let (old_trailer, old) =
+        Node::<T>::get_value_and_trailer_by_pointer(&self.arena, pointer.0, pointer.1);
+      let current = old.map(|value| {
+        EntryRef(VersionedEntryRef {
+          arena: &self.arena,
+          key: node.get_key(&self.arena),
+          trailer: old_trailer,
+          value: Some(value),
+          ptr: node_ptr,
+        })
+      });
+
+      let new = match f(old) {
+        Some(new) => new,
+        None => return Ok(Err(current)),
+      };
+      let new = new.as_ref();
+      let copy = |buf: &mut VacantBuffer| {
+        let _ = buf.write(new);
+        Ok::<_, Infallible>(())
+      };
+      let (trailer_offset, allocated) = node
+        .alloc_value(
+          &self.arena,
+          trailer,
+          new.len() as u32,
+          self.opts.value_alignment(),
+          &copy,
+        )
+        .map_err(|e| e.expect_right("must be map::Error"))?;
+
+      match node.value.compare_exchange(
+        pointer,
+        (trailer_offset, new.len() as u32),
+        success,
+        failure,
+      ) {
+        Ok(_) => {
+          self.record_value_size(node_ptr);
+          self.record_value_allocation(&allocated, new.len() as u32);
+          return Ok(Ok(current));
+        }
+        Err(actual) => {
+          // the new value is never published, so it can be reclaimed.
+          Deallocator {
+            node: None,
+            key: None,
+            value: Some(allocated),
+          }
+          .dealloc(&self.arena);
+          pointer = actual;
+        }
+      }
+    }
+  }
+
   #[allow(clippy::too_many_arguments)]
   unsafe fn upsert<'a, 'b: 'a, E>(
     &'a self,
@@ -1940,6 +2749,7 @@ impl<T: Trailer, C: Comparator> SkipMap<T, C> {
     key: &Key<'a, 'b>,
     trailer: T,
     value_size: u32,
+    value_align: u32,
     f: &impl Fn(&mut VacantBuffer<'a>) -> Result<(), E>,
     success: Ordering,
     failure: Ordering,
@@ -1947,8 +2757,12 @@ impl<T: Trailer, C: Comparator> SkipMap<T, C> {
     match key {
       Key::Occupied(_) | Key::Vacant(_) | Key::Pointer { .. } => node_ptr
         .as_ref()
-        .set_value(&self.arena, trailer, value_size, f)
-        .map(|_| Either::Left(if old.is_removed() { None } else { Some(old) })),
+        .set_value(&self.arena, trailer, value_size, value_align, f)
+        .map(|allocated| {
+          self.record_value_size(node_ptr);
+          self.record_value_allocation(&allocated, value_size);
+          Either::Left(if old.is_removed() { None } else { Some(old) })
+        }),
       Key::Remove(_) | Key::RemoveVacant(_) | Key::RemovePointer { .. } => {
         let node = node_ptr.as_ref();
         let key = node.get_key(&self.arena);
@@ -2056,6 +2870,59 @@ struct FindResult<T> {
   curr: Option<NodePtr<T>>,
 }
 
+/// Returns how many extra bytes must be allocated after a trailer `T`,
+/// so that the value following the trailer can be aligned to `value_align`.
+#[inline]
+const fn value_padding_capacity<T>(value_align: u32) -> u32 {
+  let trailer_align = mem::align_of::<T>() as u32;
+  if value_align > trailer_align {
+    value_align - trailer_align
+  } else {
+    0
+  }
+}
+
+/// Returns how many bytes the trailer `T` must be moved forward from `offset`,
+/// so that the value following the trailer is aligned to `value_align`.
+///
+/// `offset` must be aligned to `T`, and `value_align` must be a power of two.
+#[inline]
+const fn value_padding<T>(offset: usize, value_align: u32) -> usize {
+  let value_align = if value_align == 0 {
+    1
+  } else {
+    value_align as usize
+  };
+  let value_offset = offset + mem::size_of::<T>();
+  ((value_offset + value_align - 1) & !(value_align - 1)) - value_offset
+}
+
+/// Checks that the region `offset..offset + len` stays within the allocated part of the ARENA,
+/// so encoding and offset bugs are caught early instead of reading adjacent entries or the
+/// unallocated memory.
+///
+/// Only enabled in debug builds, or always with the `untrusted` feature, which treats all the
+/// offsets and sizes read from the ARENA as untrusted. The nodes of a malformed file poison the
+/// map when they are reached by a link, see `SkipMap::node_at`, so this is the last resort
+/// which panics instead of reading out of bounds.
+#[inline]
+#[cfg_attr(any(debug_assertions, feature = "untrusted"), track_caller)]
+fn check_bounds(arena: &Arena, offset: usize, len: usize) {
+  if cfg!(any(debug_assertions, feature = "untrusted")) {
+    let allocated = arena.allocated();
+    assert!(
+      offset
+        .checked_add(len)
+        .map_or(false, |end| end <= allocated),
+      "region {}..{}+{} is out of the bounds of the ARENA (allocated: {})",
+      offset,
+      offset,
+      len,
+      allocated
+    );
+  }
+}
+
 #[inline]
 const fn encode_value_pointer(offset: u32, val_size: u32) -> u64 {
   (val_size as u64) << 32 | offset as u64
@@ -0,0 +1,139 @@
+use super::*;
+
+/// The size of the access stamp appended after the tower of a node.
+const ACCESS_STAMP_SIZE: u32 = mem::size_of::<u32>() as u32;
+
+impl<T, C> SkipMap<T, C> {
+  /// Returns the size of the access stamp of the nodes, `0` if not enabled.
+  #[inline]
+  pub(super) const fn access_stamp_size(&self) -> u32 {
+    if self.opts.access_stamps() {
+      ACCESS_STAMP_SIZE
+    } else {
+      0
+    }
+  }
+
+  /// ## Safety
+  ///
+  /// - The node must be allocated by the ARENA with the access stamp, and is not the head or
+  ///   the tail.
+  #[inline]
+  unsafe fn access_stamp<'a>(nd: *const Node<T>, height: u32) -> &'a AtomicU32 {
+    // the stamp is 4-byte aligned, since the node and the links are.
+    &*nd
+      .cast::<u8>()
+      .add(Node::<T>::SIZE + height as usize * Link::SIZE)
+      .cast::<AtomicU32>()
+  }
+
+  /// Stamps a newly allocated node with the current access clock, the memory may be reused
+  /// from the freelist, so it must be overwritten.
+  #[inline]
+  pub(super) fn init_access_stamp(&self, nd: *mut Node<T>, height: u32) {
+    if let Some(clock) = &self.access_clock {
+      // Safety: the node is just allocated with the access stamp.
+      unsafe {
+        nd.cast::<u8>()
+          .add(Node::<T>::SIZE + height as usize * Link::SIZE)
+          .cast::<u32>()
+          .write(clock.load(Ordering::Relaxed));
+      }
+    }
+  }
+
+  /// Records an access of the node, the read-only maps are not stamped.
+  #[inline]
+  pub(super) fn record_access(&self, nd: NodePtr<T>) {
+    if self.arena.read_only() {
+      return;
+    }
+
+    if let Some(clock) = &self.access_clock {
+      // Safety: the node is allocated by the ARENA, and the access stamps are enabled.
+      unsafe {
+        let height = nd.as_ref().height() as u32;
+        Self::access_stamp(nd.ptr, height).store(clock.load(Ordering::Relaxed), Ordering::Relaxed);
+      }
+    }
+  }
+
+  /// Sets the access clock, which stamps the nodes on the later writes and reads,
+  /// e.g. to the seconds since the start of the process, ticked by a timer. The clock is
+  /// shared by all the clones of the map, and is not persisted.
+  ///
+  /// Does nothing if [`Options::with_access_stamps`] is not enabled.
+  #[inline]
+  pub fn set_access_clock(&self, now: u32) {
+    if let Some(clock) = &self.access_clock {
+      clock.store(now, Ordering::Relaxed);
+    }
+  }
+
+  /// Returns the access clock, `None` if [`Options::with_access_stamps`] is not enabled.
+  #[inline]
+  pub fn access_clock(&self) -> Option<u32> {
+    self
+      .access_clock
+      .as_ref()
+      .map(|clock| clock.load(Ordering::Relaxed))
+  }
+
+  /// Returns the access clock when the entry was last written or read by
+  /// [`get`](SkipMap::get), `None` if [`Options::with_access_stamps`] is not enabled.
+  ///
+  /// The iterators do not update the stamps, so the scans do not pollute the recency.
+  /// Returns `None` if the entry does not come from this map (or one of its clones).
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use skl::{Options, SkipMap};
+  ///
+  /// let l = SkipMap::with_options(Options::new().with_access_stamps(true)).unwrap();
+  /// l.insert(0, b"a", b"a").unwrap();
+  /// l.insert(0, b"b", b"b").unwrap();
+  ///
+  /// l.set_access_clock(10);
+  /// l.get(0, b"a").unwrap();
+  ///
+  /// // the least recently used entry
+  /// let lru = l.iter(0).min_by_key(|ent| l.last_access(ent)).unwrap();
+  /// assert_eq!(lru.key(), b"b");
+  /// assert_eq!(l.last_access(&l.first(0).unwrap()), Some(10));
+  /// ```
+  pub fn last_access(&self, ent: &EntryRef<'_, T>) -> Option<u32> {
+    self.access_clock.as_ref()?;
+
+    let nd = ent.0.ptr;
+    if !self.is_entry_node(nd) {
+      return None;
+    }
+
+    // Safety: the node is an entry node of this ARENA, and the access stamps are enabled.
+    unsafe {
+      let height = nd.as_ref().height() as u32;
+      Some(Self::access_stamp(nd.ptr, height).load(Ordering::Relaxed))
+    }
+  }
+
+  /// Returns `true` if the node is an entry node allocated by the ARENA of this map, i.e. it is
+  /// after the tail, and the node with its tower and suffix is in the allocated part.
+  fn is_entry_node(&self, nd: NodePtr<T>) -> bool {
+    let offset = nd.offset as usize;
+    let allocated = self.arena.allocated();
+    if nd.offset <= self.tail.offset || offset + Node::<T>::SIZE > allocated {
+      return false;
+    }
+
+    // Safety: the offset is in the allocated part of the ARENA. The pointer of a node of
+    // another ARENA never equals the pointer at the same offset of this ARENA.
+    if unsafe { self.arena.get_pointer(offset) } != nd.ptr as *const u8 {
+      return false;
+    }
+
+    // Safety: the node header is in the allocated part of this ARENA.
+    let height = unsafe { nd.as_ref() }.height() as usize;
+    offset + Node::<T>::SIZE + height * Link::SIZE + self.node_suffix_size() as usize <= allocated
+  }
+}
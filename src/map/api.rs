@@ -2,6 +2,7 @@ use rarena_allocator::ArenaOptions;
 use ux2::u27;
 
 use super::*;
+use crate::backend::{Heap, StorageBackend};
 
 impl<T> SkipMap<T> {
   /// Create a new skipmap with default options.
@@ -34,6 +35,36 @@ impl<T> SkipMap<T> {
     Self::with_options_and_comparator(opts, Ascend)
   }
 
+  /// Like [`SkipMap::new`], but returns an error of kind
+  /// [`OutOfMemory`](std::io::ErrorKind::OutOfMemory) instead of aborting the process when the
+  /// memory for the ARENA cannot be allocated, see
+  /// [`SkipMap::try_with_options_and_comparator`].
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
+  #[inline]
+  pub fn try_new() -> std::io::Result<Self> {
+    Self::try_with_options(Options::new())
+  }
+
+  /// Like [`SkipMap::with_options`], but returns an error of kind
+  /// [`OutOfMemory`](std::io::ErrorKind::OutOfMemory) instead of aborting the process when the
+  /// memory for the ARENA cannot be allocated, see
+  /// [`SkipMap::try_with_options_and_comparator`].
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
+  #[inline]
+  pub fn try_with_options(opts: Options) -> std::io::Result<Self> {
+    Self::try_with_options_and_comparator(opts, Ascend)
+  }
+
+  /// Like [`SkipMap::with_options`], but the ARENA is created by the [`StorageBackend`].
+  ///
+  /// See [`StorageBackend`] for more details.
+  #[inline]
+  pub fn with_backend<B: StorageBackend>(backend: B, opts: Options) -> Result<Self, B::Error> {
+    Self::with_backend_and_comparator(backend, opts, Ascend)
+  }
+
   /// Create a new memory map file backed with default options.
   ///
   /// **Note:** The capacity stands for how many memory mmaped,
@@ -76,6 +107,101 @@ impl<T> SkipMap<T> {
     Self::map_with_comparator(path, open_options, mmap_options, Ascend, magic_version)
   }
 
+  /// Like [`SkipMap::map`], but with [`Options`], the magic version is taken from the [`Options`].
+  ///
+  /// See [`SkipMap::map_with_options_and_comparator`] for the validation of the ARENA.
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
+  pub fn map_with_options<P: AsRef<std::path::Path>>(
+    path: P,
+    opts: Options,
+    open_options: OpenOptions,
+    mmap_options: MmapOptions,
+  ) -> std::io::Result<Self>
+  where
+    T: Trailer,
+  {
+    Self::map_with_options_and_comparator(path, opts, open_options, mmap_options, Ascend)
+  }
+
+  /// Creates (or truncates) the file with `capacity` bytes, and mmaps it to create a skipmap
+  /// with default options, a shortcut of [`SkipMap::map_mut`] for a persistent memtable.
+  ///
+  /// The nodes, keys and values are written into the mapped file, [`flush`](SkipMap::flush)
+  /// it and reopen the file with [`SkipMap::map_read_only`].
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use skl::SkipMap;
+  ///
+  /// let dir = tempfile::tempdir().unwrap();
+  /// let path = dir.path().join("memtable");
+  /// {
+  ///   let l = SkipMap::map_mut_with_capacity(&path, 1 << 20).unwrap();
+  ///   l.insert(0, b"hello", b"world").unwrap();
+  ///   l.flush().unwrap();
+  /// }
+  ///
+  /// let l = SkipMap::<u64>::map_read_only(&path).unwrap();
+  /// assert_eq!(l.get(0, b"hello").unwrap().value(), b"world");
+  /// ```
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
+  pub fn map_mut_with_capacity<P: AsRef<std::path::Path>>(
+    path: P,
+    capacity: u32,
+  ) -> std::io::Result<Self> {
+    let open_options = OpenOptions::default()
+      .create(Some(capacity))
+      .read(true)
+      .write(true);
+    Self::map_mut(path, open_options, MmapOptions::default())
+  }
+
+  /// Opens an existing file created with the default magic version, and mmaps it read-only to
+  /// create a skipmap, a shortcut of [`SkipMap::map`].
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
+  pub fn map_read_only<P: AsRef<std::path::Path>>(path: P) -> std::io::Result<Self> {
+    Self::map(
+      path,
+      OpenOptions::default().read(true),
+      MmapOptions::default(),
+      0,
+    )
+  }
+
+  /// Reopens a file written by a (possibly crashed) process, like [`SkipMap::map_read_only`],
+  /// but the file is treated as untrusted, see [`Options::with_validate_on_open`].
+  ///
+  /// The ARENA header at offset 0 (the magic, the format version and the allocated length) and
+  /// the meta of the map (the height, the length and the versions) are read back, so the map is
+  /// ready without rescanning the entries. A file with a different format version or magic
+  /// version, or a malformed skiplist, is rejected with [`std::io::ErrorKind::InvalidData`].
+  ///
+  /// The maximum height and the node layout ([`Options::with_access_stamps`] and
+  /// [`Options::with_key_fingerprints`]) are read from the file as well, so the file may be
+  /// created with any of them.
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
+  pub fn open<P: AsRef<std::path::Path>>(path: P) -> std::io::Result<Self>
+  where
+    T: Trailer,
+  {
+    let arena = Arena::map(
+      path,
+      OpenOptions::default().read(true),
+      MmapOptions::default(),
+      CURRENT_VERSION,
+    )?;
+    let opts = Self::stored_options(&arena, Options::new().with_validate_on_open(true))
+      .map_err(invalid_data)?;
+    let map = Self::from_mapped(arena, opts, Ascend)?;
+    map.verify().map_err(invalid_data)?;
+    Ok(map)
+  }
+
   /// Create a new memory map backed skipmap with default options.
   ///
   /// **What the difference between this method and [`SkipMap::new`]?**
@@ -175,6 +301,14 @@ impl<T, C> SkipMap<T, C> {
     self.meta().height()
   }
 
+  /// Returns the maximum height of the towers of this skiplist, chosen per instance by
+  /// [`Options::with_max_height`], the head and tail towers are allocated with this height,
+  /// and the towers of the entries never exceed it.
+  #[inline]
+  pub fn max_height(&self) -> u8 {
+    self.opts.max_height().into()
+  }
+
   /// Returns the number of remaining bytes can be allocated by the arena.
   #[inline]
   pub fn remaining(&self) -> usize {
@@ -182,6 +316,11 @@ impl<T, C> SkipMap<T, C> {
   }
 
   /// Returns the number of bytes that have allocated from the arena.
+  ///
+  /// This is the high watermark of the ARENA, read in O(1), so it is the memory usage a
+  /// memtable flush policy should trigger on, rather than [`len`](SkipMap::len). Use
+  /// [`memory_report`](SkipMap::memory_report) for the breakdown into the node headers, the
+  /// towers, the paddings and the keys and values.
   #[inline]
   pub fn allocated(&self) -> usize {
     self.arena.allocated()
@@ -194,6 +333,27 @@ impl<T, C> SkipMap<T, C> {
   }
 
   /// Returns the number of entries in the skipmap.
+  ///
+  /// The count is maintained on every insertion, so it is O(1), e.g. to decide when to flush
+  /// a memtable without iterating, together with [`allocated`](SkipMap::allocated). Every
+  /// version of a key is an entry, and so is a removed one, as the nodes are never unlinked,
+  /// so the count never decreases; use [`iter`](SkipMap::iter) to count the live keys at a
+  /// version.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use skl::SkipMap;
+  ///
+  /// let l = SkipMap::new().unwrap();
+  /// assert!(l.is_empty());
+  ///
+  /// l.insert(0, b"a", b"a0").unwrap();
+  /// l.insert(1, b"a", b"a1").unwrap();
+  /// l.get_or_remove(2, b"a").unwrap();
+  /// assert_eq!(l.len(), 3);
+  /// assert_eq!(l.iter(2).count(), 0);
+  /// ```
   #[inline]
   pub fn len(&self) -> usize {
     self.meta().len() as usize
@@ -217,6 +377,13 @@ impl<T, C> SkipMap<T, C> {
     self.arena.discarded()
   }
 
+  /// Returns how many times the CAS operations in the insert path have been retried
+  /// because of contention with other writers.
+  #[inline]
+  pub fn retries(&self) -> u64 {
+    self.retries.load(Ordering::Relaxed)
+  }
+
   /// Returns the maximum version of all entries in the map.
   #[inline]
   pub fn max_version(&self) -> u64 {
@@ -244,14 +411,75 @@ impl<T, C> SkipMap<T, C> {
   /// Like [`SkipMap::new`], but with [`Options`] and a custom [`Comparator`].
   #[inline]
   pub fn with_options_and_comparator(opts: Options, cmp: C) -> Result<Self, Error> {
+    Self::with_backend_and_comparator(Heap, opts, cmp)
+  }
+
+  /// Like [`SkipMap::with_options_and_comparator`], but returns an error of kind
+  /// [`OutOfMemory`](std::io::ErrorKind::OutOfMemory), which wraps [`Error::OutOfMemory`],
+  /// instead of aborting the process when the memory for the ARENA cannot be allocated, so
+  /// services can degrade (e.g. use a smaller memtable) when memory is tight. The other
+  /// failures keep their own kinds, e.g. the invalid options are reported as
+  /// [`InvalidData`](std::io::ErrorKind::InvalidData) wrapping the [`Error`].
+  ///
+  /// The global allocator aborts the process when it fails, so the ARENA is mapped by an
+  /// anonymous memory map instead, like [`SkipMap::map_anon`], whose failure is returned.
+  ///
+  /// **Note:** the operating system may still overcommit the memory, so the process can be
+  /// killed later when the pages are touched.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use skl::{Ascend, Options, SkipMap};
+  /// use std::io::ErrorKind;
+  ///
+  /// let opts = Options::new().with_capacity(1 << 20);
+  /// let l = match SkipMap::<u64>::try_with_options_and_comparator(opts, Ascend) {
+  ///   Ok(l) => l,
+  ///   // degrade to a smaller memtable
+  ///   Err(e) if e.kind() == ErrorKind::OutOfMemory => {
+  ///     SkipMap::<u64>::try_with_options(Options::new().with_capacity(1024)).unwrap()
+  ///   }
+  ///   Err(e) => panic!("{e}"),
+  /// };
+  /// l.insert(0, b"a", b"a").unwrap();
+  /// ```
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
+  pub fn try_with_options_and_comparator(opts: Options, cmp: C) -> std::io::Result<Self> {
+    let arena_opts = ArenaOptions::new()
+      .with_capacity(opts.capacity())
+      .with_maximum_alignment((Node::<T>::ALIGN as usize).max(opts.value_alignment() as usize))
+      .with_unify(opts.unify())
+      .with_magic_version(CURRENT_VERSION)
+      .with_freelist(opts.freelist());
+    let arena = Arena::map_anon(arena_opts, MmapOptions::default().len(opts.capacity()))
+      .map_err(|e| out_of_memory(e, opts.capacity()))?;
+    Self::new_in(arena, cmp, opts).map_err(invalid_data)
+  }
+
+  /// Like [`SkipMap::with_backend`], but with a custom [`Comparator`].
+  ///
+  /// The meta is always stored in the ARENA if the backend is
+  /// [`persistent`](StorageBackend::persistent).
+  pub fn with_backend_and_comparator<B: StorageBackend>(
+    backend: B,
+    opts: Options,
+    cmp: C,
+  ) -> Result<Self, B::Error> {
+    let opts = if backend.persistent() {
+      opts.with_unify(true)
+    } else {
+      opts
+    };
     let arena_opts = ArenaOptions::new()
       .with_capacity(opts.capacity())
-      .with_maximum_alignment(Node::<T>::ALIGN as usize)
+      .with_maximum_alignment((Node::<T>::ALIGN as usize).max(opts.value_alignment() as usize))
       .with_unify(opts.unify())
       .with_magic_version(CURRENT_VERSION)
       .with_freelist(opts.freelist());
-    let arena = Arena::new(arena_opts);
-    Self::new_in(arena, cmp, opts)
+    let arena = backend.create_arena(arena_opts)?;
+    Self::new_in(arena, cmp, opts).map_err(Into::into)
   }
 
   /// Like [`SkipMap::map_mut`], but with a custom [`Comparator`].
@@ -278,7 +506,7 @@ impl<T, C> SkipMap<T, C> {
     mmap_options: MmapOptions,
     cmp: C,
   ) -> std::io::Result<Self> {
-    let alignment = Node::<T>::ALIGN as usize;
+    let alignment = (Node::<T>::ALIGN as usize).max(opts.value_alignment() as usize);
     let arena_opts = ArenaOptions::new()
       .with_maximum_alignment(alignment)
       .with_magic_version(CURRENT_VERSION)
@@ -308,24 +536,67 @@ impl<T, C> SkipMap<T, C> {
     cmp: C,
     magic_version: u16,
   ) -> std::io::Result<Self> {
-    let arena = Arena::map(path, open_options, mmap_options, CURRENT_VERSION)?;
-    Self::new_in(
-      arena,
+    Self::map_in(
+      path,
+      Options::new().with_magic_version(magic_version),
+      open_options,
+      mmap_options,
       cmp,
-      Options::new()
-        .with_unify(true)
-        .with_magic_version(magic_version),
     )
-    .map_err(invalid_data)
-    .and_then(|map| {
-      if map.magic_version() != magic_version {
-        Err(bad_magic_version())
-      } else if map.version() != CURRENT_VERSION {
-        Err(bad_version())
-      } else {
-        Ok(map)
-      }
-    })
+  }
+
+  /// Like [`SkipMap::map`], but with [`Options`] and a custom [`Comparator`], the magic version
+  /// is taken from the [`Options`].
+  ///
+  /// If [`Options::with_validate_on_open`] is set, the ARENA is treated as untrusted and
+  /// [`SkipMap::verify`] is run before returning, a malformed file is rejected with
+  /// [`std::io::ErrorKind::InvalidData`].
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
+  pub fn map_with_options_and_comparator<P: AsRef<std::path::Path>>(
+    path: P,
+    opts: Options,
+    open_options: OpenOptions,
+    mmap_options: MmapOptions,
+    cmp: C,
+  ) -> std::io::Result<Self>
+  where
+    T: Trailer,
+    C: Comparator,
+  {
+    let map = Self::map_in(path, opts, open_options, mmap_options, cmp)?;
+    if opts.validate_on_open() {
+      map.verify().map_err(invalid_data)?;
+    }
+    Ok(map)
+  }
+
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  fn map_in<P: AsRef<std::path::Path>>(
+    path: P,
+    opts: Options,
+    open_options: OpenOptions,
+    mmap_options: MmapOptions,
+    cmp: C,
+  ) -> std::io::Result<Self> {
+    let arena = Arena::map(path, open_options, mmap_options, CURRENT_VERSION)?;
+    Self::from_mapped(arena, opts, cmp)
+  }
+
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  fn from_mapped(arena: Arena, opts: Options, cmp: C) -> std::io::Result<Self> {
+    let magic_version = opts.magic_version();
+    Self::new_in(arena, cmp, opts.with_unify(true))
+      .map_err(invalid_data)
+      .and_then(|map| {
+        if map.magic_version() != magic_version {
+          Err(bad_magic_version())
+        } else if map.version() != CURRENT_VERSION {
+          Err(bad_version())
+        } else {
+          Ok(map)
+        }
+      })
   }
 
   /// Like [`SkipMap::map_anon`], but with a custom [`Comparator`].
@@ -345,7 +616,7 @@ impl<T, C> SkipMap<T, C> {
     mmap_options: MmapOptions,
     cmp: C,
   ) -> std::io::Result<Self> {
-    let alignment = Node::<T>::ALIGN as usize;
+    let alignment = (Node::<T>::ALIGN as usize).max(opts.value_alignment() as usize);
     let arena_opts = ArenaOptions::new()
       .with_maximum_alignment(alignment)
       .with_unify(opts.unify())
@@ -379,16 +650,26 @@ impl<T, C> SkipMap<T, C> {
     self.arena.clear()?;
 
     let meta = if self.opts.unify() {
-      Self::allocate_meta(&self.arena, self.meta().magic_version())?
+      Self::allocate_meta(
+        &self.arena,
+        self.meta().magic_version(),
+        Meta::layout_flags_of(&self.opts),
+      )?
     } else {
-      unsafe {
-        let magic_version = self.meta().magic_version();
-        let _ = Box::from_raw(self.meta.as_ptr());
-        NonNull::new_unchecked(Box::into_raw(Box::new(Meta::new(magic_version))))
-      }
+      // the old meta is freed with the last clone which still refers to it.
+      let heap_meta = Arc::new(HeapMeta::new(
+        self.meta().magic_version(),
+        Meta::layout_flags_of(&self.opts),
+      ));
+      let meta = heap_meta.0;
+      self.heap_meta = Some(heap_meta);
+      meta
     };
 
     self.meta = meta;
+    if self.arena_stats.is_some() {
+      self.arena_stats = Some(Arc::new(AtomicArenaStats::new()));
+    }
 
     let max_height: u8 = self.opts.max_height().into();
     let head = Self::allocate_full_node(&self.arena, max_height)?;
@@ -411,6 +692,41 @@ impl<T, C> SkipMap<T, C> {
     Ok(())
   }
 
+  /// Clears the map to empty and rewinds the ARENA to its start, so a memtable can be reused
+  /// after it is flushed, without allocating a new ARENA.
+  ///
+  /// Unlike [`clear`](SkipMap::clear), this is safe: no entry can be borrowed while the map
+  /// is borrowed mutably, and the map must be the only handle of the ARENA, i.e. it has no
+  /// clones and no [`allocator`](SkipMap::allocator) handles, otherwise [`Error::Shared`] is
+  /// returned and the map is left untouched.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use skl::{Error, SkipMap};
+  ///
+  /// let mut l = SkipMap::new().unwrap();
+  /// l.insert(0, b"hello", b"world").unwrap();
+  /// let allocated = l.allocated();
+  ///
+  /// let clone = l.clone();
+  /// assert_eq!(l.reset(), Err(Error::Shared(2)));
+  /// drop(clone);
+  ///
+  /// l.reset().unwrap();
+  /// assert!(l.is_empty());
+  /// assert!(l.allocated() < allocated);
+  /// ```
+  pub fn reset(&mut self) -> Result<(), Error> {
+    let refs = self.arena.refs();
+    if refs != 1 {
+      return Err(Error::Shared(refs));
+    }
+
+    // Safety: the map is the only handle of the ARENA, and no entry borrows the map.
+    unsafe { self.clear() }
+  }
+
   /// Flushes outstanding memory map modifications to disk.
   ///
   /// When this method returns with a non-error result,
@@ -453,10 +769,142 @@ impl<T: Trailer, C: Comparator> SkipMap<T, C> {
     key: &'b [u8],
     value: &'b [u8],
   ) -> Result<Option<EntryRef<'a, T>>, Error> {
-    if self.arena.read_only() {
+    if self.read_only() {
+      return Err(Error::read_only());
+    }
+
+    let copy = |buf: &mut VacantBuffer| {
+      let _ = buf.write(value);
+      Ok(())
+    };
+    let val_len = value.len() as u32;
+
+    self
+      .update::<Infallible>(
+        trailer,
+        Key::Occupied(key),
+        val_len,
+        self.opts.value_alignment(),
+        copy,
+        Ordering::Relaxed,
+        Ordering::Relaxed,
+        &mut Inserter::default(),
+        true,
+      )
+      .map(|old| {
+        old.expect_left("insert must get InsertOk").and_then(|old| {
+          if old.is_removed() {
+            None
+          } else {
+            Some(EntryRef(old))
+          }
+        })
+      })
+      .map_err(|e| e.expect_right("must be map::Error"))
+  }
+
+  /// Bulk loads the items, which are sorted by key (and by version descending for the same
+  /// key), e.g. the entries of an SSTable, returns the number of the loaded items.
+  ///
+  /// The splice of the previous insert is cached and revalidated for the next one, instead of
+  /// descending the tower from the head, so loading sorted items which are appended at the
+  /// tail costs nearly O(1) per item, rather than O(log n). The result is the same as
+  /// inserting the items one by one with [`insert`](SkipMap::insert), so unsorted items are
+  /// still loaded correctly, but slower.
+  ///
+  /// Returns the first error, the items loaded before it are kept.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use skl::SkipMap;
+  ///
+  /// let l = SkipMap::new().unwrap();
+  /// let loaded = l
+  ///   .load_sorted((0..1000u32).map(|i| (0, i.to_be_bytes(), i.to_le_bytes())))
+  ///   .unwrap();
+  /// assert_eq!(loaded, 1000);
+  /// assert_eq!(l.get(0, &42u32.to_be_bytes()).unwrap().value(), 42u32.to_le_bytes());
+  /// ```
+  pub fn load_sorted<K, V>(
+    &self,
+    sorted_items: impl IntoIterator<Item = (T, K, V)>,
+  ) -> Result<usize, Error>
+  where
+    K: AsRef<[u8]>,
+    V: AsRef<[u8]>,
+  {
+    if self.read_only() {
+      return Err(Error::read_only());
+    }
+
+    let mut ins = Inserter::default();
+    let mut loaded = 0;
+    for (trailer, key, value) in sorted_items {
+      let value = value.as_ref();
+      self
+        .update::<Infallible>(
+          trailer,
+          Key::Occupied(key.as_ref()),
+          value.len() as u32,
+          self.opts.value_alignment(),
+          |buf: &mut VacantBuffer| {
+            let _ = buf.write(value);
+            Ok(())
+          },
+          Ordering::Relaxed,
+          Ordering::Relaxed,
+          &mut ins,
+          true,
+        )
+        .map_err(|e| e.expect_right("must be map::Error"))?;
+      loaded += 1;
+    }
+    Ok(loaded)
+  }
+
+  /// Like [`insert`](SkipMap::insert), but the value will be stored at an offset aligned to `align`,
+  /// so that it can be safely reinterpreted as a type whose alignment is `align`.
+  ///
+  /// `align` will be rounded up to the next power of two. If `align` is smaller than
+  /// [`Options::value_alignment`], the latter one will be used.
+  ///
+  /// The alignment is relative to the start of the ARENA, and the ARENA itself is only aligned
+  /// to the larger one of the node alignment and [`Options::value_alignment`], so a larger
+  /// `align` cannot be guaranteed and is rejected with [`Error::InvalidAlignment`], configure
+  /// [`Options::with_value_alignment`] instead.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use skl::SkipMap;
+  ///
+  /// let l = SkipMap::new().unwrap();
+  ///
+  /// l.insert_aligned(1, b"alice", &42u64.to_le_bytes(), 8).unwrap();
+  ///
+  /// let ent = l.get(1, b"alice").unwrap();
+  /// assert_eq!(ent.value().as_ptr() as usize % 8, 0);
+  /// ```
+  pub fn insert_aligned<'a, 'b: 'a>(
+    &'a self,
+    trailer: T,
+    key: &'b [u8],
+    value: &'b [u8],
+    align: u32,
+  ) -> Result<Option<EntryRef<'a, T>>, Error> {
+    if self.read_only() {
       return Err(Error::read_only());
     }
 
+    let align = match align.checked_next_power_of_two() {
+      Some(align) => align.max(self.opts.value_alignment()),
+      None => return Err(Error::InvalidAlignment(align as u64)),
+    };
+    if align as usize > (Node::<T>::ALIGN as usize).max(self.opts.value_alignment() as usize) {
+      return Err(Error::InvalidAlignment(align as u64));
+    }
+
     let copy = |buf: &mut VacantBuffer| {
       let _ = buf.write(value);
       Ok(())
@@ -468,6 +916,7 @@ impl<T: Trailer, C: Comparator> SkipMap<T, C> {
         trailer,
         Key::Occupied(key),
         val_len,
+        align,
         copy,
         Ordering::Relaxed,
         Ordering::Relaxed,
@@ -538,7 +987,7 @@ impl<T: Trailer, C: Comparator> SkipMap<T, C> {
     value_size: u32,
     f: impl Fn(&mut VacantBuffer<'a>) -> Result<(), E>,
   ) -> Result<Option<EntryRef<'a, T>>, Either<E, Error>> {
-    if self.arena.read_only() {
+    if self.read_only() {
       return Err(Either::Right(Error::read_only()));
     }
 
@@ -547,6 +996,7 @@ impl<T: Trailer, C: Comparator> SkipMap<T, C> {
         trailer,
         Key::Occupied(key),
         value_size,
+        self.opts.value_alignment(),
         f,
         Ordering::Relaxed,
         Ordering::Relaxed,
@@ -570,13 +1020,32 @@ impl<T: Trailer, C: Comparator> SkipMap<T, C> {
   ///
   /// - Returns `Ok(None)` if the key was successfully get_or_inserted.
   /// - Returns `Ok(Some(_))` if the key with the given version already exists.
+  ///
+  /// The lookup and the insertion share a single tower descent, and the new node is spliced
+  /// by CAS, so when several threads race to insert the same key and version, exactly one of
+  /// them inserts its value and the others get the winner's entry, there is no window between
+  /// a [`get`](SkipMap::get) and an [`insert`](SkipMap::insert) to guard.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use skl::SkipMap;
+  ///
+  /// let l = SkipMap::new().unwrap();
+  /// assert!(l.get_or_insert(0, b"a", b"a1").unwrap().is_none());
+  ///
+  /// // the existing entry is returned and kept
+  /// let ent = l.get_or_insert(0, b"a", b"a2").unwrap().unwrap();
+  /// assert_eq!(ent.value(), b"a1");
+  /// assert_eq!(l.get(0, b"a").unwrap().value(), b"a1");
+  /// ```
   pub fn get_or_insert<'a, 'b: 'a>(
     &'a self,
     trailer: T,
     key: &'b [u8],
     value: &'b [u8],
   ) -> Result<Option<EntryRef<'a, T>>, Error> {
-    if self.arena.read_only() {
+    if self.read_only() {
       return Err(Error::read_only());
     }
 
@@ -591,6 +1060,7 @@ impl<T: Trailer, C: Comparator> SkipMap<T, C> {
         trailer,
         Key::Occupied(key),
         val_len,
+        self.opts.value_alignment(),
         copy,
         Ordering::Relaxed,
         Ordering::Relaxed,
@@ -662,7 +1132,7 @@ impl<T: Trailer, C: Comparator> SkipMap<T, C> {
     value_size: u32,
     f: impl Fn(&mut VacantBuffer<'a>) -> Result<(), E>,
   ) -> Result<Option<EntryRef<'a, T>>, Either<E, Error>> {
-    if self.arena.read_only() {
+    if self.read_only() {
       return Err(Either::Right(Error::read_only()));
     }
 
@@ -671,6 +1141,7 @@ impl<T: Trailer, C: Comparator> SkipMap<T, C> {
         trailer,
         Key::Occupied(key),
         value_size,
+        self.opts.value_alignment(),
         f,
         Ordering::Relaxed,
         Ordering::Relaxed,
@@ -751,6 +1222,7 @@ impl<T: Trailer, C: Comparator> SkipMap<T, C> {
         trailer,
         Key::Vacant(vk),
         val_size,
+        self.opts.value_alignment(),
         val,
         Ordering::Relaxed,
         Ordering::Relaxed,
@@ -829,6 +1301,7 @@ impl<T: Trailer, C: Comparator> SkipMap<T, C> {
         trailer,
         Key::Vacant(vk),
         val_size,
+        self.opts.value_alignment(),
         val,
         Ordering::Relaxed,
         Ordering::Relaxed,
@@ -866,6 +1339,7 @@ impl<T: Trailer, C: Comparator> SkipMap<T, C> {
         trailer,
         Key::Remove(key),
         0,
+        self.opts.value_alignment(),
         noop::<Infallible>,
         success,
         failure,
@@ -894,6 +1368,192 @@ impl<T: Trailer, C: Comparator> SkipMap<T, C> {
       .map_err(|e| e.expect_right("must be map::Error"))
   }
 
+  /// Removes the key, by inserting a removed entry (a tombstone) with the given version, which
+  /// shadows the older versions of the key.
+  ///
+  /// [`get`](SkipMap::get) and the iterators treat the removed entries as absent, they are
+  /// still yielded by [`iter_all_versions`](SkipMap::iter_all_versions), e.g. for compaction.
+  /// Returns the removed entry if it was alive, a shortcut of
+  /// [`compare_remove`](SkipMap::compare_remove).
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use skl::SkipMap;
+  ///
+  /// let l = SkipMap::new().unwrap();
+  /// l.insert(0, b"hello", b"world").unwrap();
+  /// l.remove(1, b"hello").unwrap();
+  ///
+  /// assert!(l.get(1, b"hello").is_none());
+  /// assert_eq!(l.get(0, b"hello").unwrap().value(), b"world");
+  /// assert!(l.iter_all_versions(1).next().unwrap().is_removed());
+  /// ```
+  #[inline]
+  pub fn remove<'a, 'b: 'a>(
+    &'a self,
+    trailer: T,
+    key: &'b [u8],
+  ) -> Result<Option<EntryRef<'a, T>>, Error> {
+    self.compare_remove(trailer, key, Ordering::AcqRel, Ordering::Relaxed)
+  }
+
+  /// Swaps the value of the entry with the key and the version of the trailer to `new`, only if
+  /// its current value is still `current`, for the optimistic read-modify-write without locks.
+  ///
+  /// The new value is written into the ARENA, and the value pointer of the node is swapped by a
+  /// CAS operation, the old value is not overwritten, so the readers of the old value are not
+  /// affected.
+  ///
+  /// - Returns `Ok(Ok(()))` if the value is swapped.
+  /// - Returns `Ok(Err(current))` if the current value is not `current`, `None` if the entry
+  ///   does not exist or is removed, use [`get_or_insert`](SkipMap::get_or_insert) to create it.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use skl::SkipMap;
+  /// use core::sync::atomic::Ordering;
+  ///
+  /// let l = SkipMap::new().unwrap();
+  /// l.insert(0, b"counter", &1u64.to_be_bytes()).unwrap();
+  ///
+  /// // increments the counter
+  /// loop {
+  ///   let current = l.get(0, b"counter").unwrap().value();
+  ///   let next = (u64::from_be_bytes(current.try_into().unwrap()) + 1).to_be_bytes();
+  ///   match l.compare_and_swap(0, b"counter", current, &next, Ordering::AcqRel, Ordering::Acquire) {
+  ///     Ok(Ok(())) => break,
+  ///     Ok(Err(_)) => continue,
+  ///     Err(e) => panic!("{}", e),
+  ///   }
+  /// }
+  /// assert_eq!(l.get(0, b"counter").unwrap().value(), 2u64.to_be_bytes());
+  ///
+  /// let stale = 1u64.to_be_bytes();
+  /// let res = l.compare_and_swap(0, b"counter", &stale, &[], Ordering::AcqRel, Ordering::Acquire);
+  /// assert_eq!(res.unwrap().unwrap_err().unwrap().value(), 2u64.to_be_bytes());
+  /// ```
+  pub fn compare_and_swap<'a, 'b: 'a>(
+    &'a self,
+    trailer: T,
+    key: &'b [u8],
+    current: &[u8],
+    new: &[u8],
+    success: Ordering,
+    failure: Ordering,
+  ) -> Result<Result<(), Option<EntryRef<'a, T>>>, Error> {
+    if self.read_only() {
+      return Err(Error::read_only());
+    }
+
+    if let Some(reason) = self.poison_reason() {
+      return Err(Error::Corrupted(reason));
+    }
+
+    unsafe {
+      let node_ptr = match self.find_near(trailer.version(), key, false, true) {
+        (Some(n), true) => n,
+        _ => return Ok(Err(None)),
+      };
+
+      self
+        .swap_value(node_ptr, trailer, success, failure, |old| {
+          if old == Some(current) {
+            Some(new)
+          } else {
+            None
+          }
+        })
+        .map(|res| res.map(|_| ()))
+    }
+  }
+
+  /// Updates the value of the entry with the key and the version of the trailer to the value
+  /// returned by `f` for the current value (`None` if the entry does not exist or is removed),
+  /// like [`AtomicU64::fetch_update`](core::sync::atomic::AtomicU64::fetch_update).
+  ///
+  /// The new value is written into the ARENA and swapped in by CAS, and `f` is called again
+  /// with the newer value if another thread updates the entry in the meantime, so it may be
+  /// called more than once. The entry is inserted if it does not exist.
+  ///
+  /// - Returns `Ok(Ok(previous))` if the value is updated, `previous` is `None` if the entry
+  ///   did not exist or was removed.
+  /// - Returns `Ok(Err(current))` if `f` returns `None`, and the entry is not updated.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use skl::SkipMap;
+  /// use core::sync::atomic::Ordering;
+  ///
+  /// let l = SkipMap::new().unwrap();
+  ///
+  /// let incr = |old: Option<&[u8]>| {
+  ///   let n = old.map_or(0, |v| u64::from_be_bytes(v.try_into().unwrap()));
+  ///   Some((n + 1).to_be_bytes())
+  /// };
+  /// for _ in 0..3 {
+  ///   l.fetch_update(0, b"counter", Ordering::AcqRel, Ordering::Acquire, incr)
+  ///     .unwrap()
+  ///     .unwrap();
+  /// }
+  /// assert_eq!(l.get(0, b"counter").unwrap().value(), 3u64.to_be_bytes());
+  /// ```
+  pub fn fetch_update<'a, 'b: 'a, R: AsRef<[u8]>>(
+    &'a self,
+    trailer: T,
+    key: &'b [u8],
+    success: Ordering,
+    failure: Ordering,
+    mut f: impl FnMut(Option<&[u8]>) -> Option<R>,
+  ) -> Result<Result<Option<EntryRef<'a, T>>, Option<EntryRef<'a, T>>>, Error> {
+    if self.read_only() {
+      return Err(Error::read_only());
+    }
+
+    if let Some(reason) = self.poison_reason() {
+      return Err(Error::Corrupted(reason));
+    }
+
+    loop {
+      unsafe {
+        if let (Some(node_ptr), true) = self.find_near(trailer.version(), key, false, true) {
+          return self.swap_value(node_ptr, trailer, success, failure, &mut f);
+        }
+      }
+
+      let new = match f(None) {
+        Some(new) => new,
+        None => return Ok(Err(None)),
+      };
+      let new = new.as_ref();
+      let copy = |buf: &mut VacantBuffer| {
+        let _ = buf.write(new);
+        Ok(())
+      };
+
+      let inserted = self
+        .update::<Infallible>(
+          trailer,
+          Key::Occupied(key),
+          new.len() as u32,
+          self.opts.value_alignment(),
+          copy,
+          Ordering::Relaxed,
+          Ordering::Relaxed,
+          &mut Inserter::default(),
+          false,
+        )
+        .map_err(|e| e.expect_right("must be map::Error"))?;
+
+      // another thread inserted the key first, updates its value instead.
+      if let Either::Left(None) = inserted {
+        return Ok(Ok(None));
+      }
+    }
+  }
+
   /// Gets or removes the key-value pair if it exists.
   /// Unlike [`compare_remove`](SkipMap::compare_remove), this method will not remove the value if the key with the given version already exists.
   ///
@@ -909,6 +1569,7 @@ impl<T: Trailer, C: Comparator> SkipMap<T, C> {
         trailer,
         Key::Remove(key),
         0,
+        self.opts.value_alignment(),
         noop::<Infallible>,
         Ordering::Relaxed,
         Ordering::Relaxed,
@@ -988,6 +1649,7 @@ impl<T: Trailer, C: Comparator> SkipMap<T, C> {
         trailer,
         key,
         0,
+        self.opts.value_alignment(),
         noop::<Infallible>,
         Ordering::Relaxed,
         Ordering::Relaxed,
@@ -1026,47 +1688,48 @@ impl<T: Trailer, C: Comparator> SkipMap<T, C> {
     self.iter(version).seek_upper_bound(Bound::Unbounded)
   }
 
-  /// Returns the value associated with the given key, if it exists.
-  pub fn get<'a, 'b: 'a>(&'a self, version: u64, key: &'b [u8]) -> Option<EntryRef<'a, T>> {
-    unsafe {
-      let (n, eq) = self.find_near(version, key, false, true); // findLessOrEqual.
-
-      let n = n?;
-      let node = n.as_ref();
-      let node_key = node.get_key(&self.arena);
-      let (trailer, value) = node.get_value_and_trailer(&self.arena);
-      if eq {
-        return value.map(|val| {
-          EntryRef(VersionedEntryRef {
-            arena: &self.arena,
-            key: node_key,
-            trailer,
-            value: Some(val),
-            ptr: n,
-          })
-        });
-      }
-
-      if !matches!(self.cmp.compare(key, node_key), cmp::Ordering::Equal) {
-        return None;
-      }
-
-      if trailer.version() > version {
-        return None;
-      }
+  /// Removes the first entry visible at the version of the trailer, by inserting a removed
+  /// entry with the trailer, see [`remove`](SkipMap::remove), returns the removed entry.
+  ///
+  /// The concurrent pops with the same version can return the same entry, so a queue should
+  /// have a single consumer per version.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use skl::SkipMap;
+  ///
+  /// let l = SkipMap::new().unwrap();
+  /// l.insert(0, b"a", b"a").unwrap();
+  /// l.insert(0, b"b", b"b").unwrap();
+  ///
+  /// assert_eq!(l.pop_first(1).unwrap().unwrap().key(), b"a");
+  /// assert_eq!(l.pop_last(1).unwrap().unwrap().key(), b"b");
+  /// assert!(l.pop_first(1).unwrap().is_none());
+  /// // the older version is still visible
+  /// assert_eq!(l.first(0).unwrap().key(), b"a");
+  /// ```
+  pub fn pop_first(&self, trailer: T) -> Result<Option<EntryRef<'_, T>>, Error> {
+    match self.first(trailer.version()) {
+      Some(ent) => self.remove(trailer, ent.key()).map(|_| Some(ent)),
+      None => Ok(None),
+    }
+  }
 
-      value.map(|val| {
-        EntryRef(VersionedEntryRef {
-          arena: &self.arena,
-          key: node_key,
-          trailer,
-          value: Some(val),
-          ptr: n,
-        })
-      })
+  /// Removes the last entry visible at the version of the trailer, see
+  /// [`pop_first`](SkipMap::pop_first).
+  pub fn pop_last(&self, trailer: T) -> Result<Option<EntryRef<'_, T>>, Error> {
+    match self.last(trailer.version()) {
+      Some(ent) => self.remove(trailer, ent.key()).map(|_| Some(ent)),
+      None => Ok(None),
     }
   }
 
+  /// Returns the value associated with the given key, if it exists.
+  pub fn get<'a, 'b: 'a>(&'a self, version: u64, key: &'b [u8]) -> Option<EntryRef<'a, T>> {
+    self.get_in(version, key)
+  }
+
   /// Returns an `EntryRef` pointing to the highest element whose key is below the given bound.
   /// If no such element is found then `None` is returned.
   pub fn upper_bound<'a, 'b: 'a>(
@@ -1093,6 +1756,38 @@ impl<T: Trailer, C: Comparator> SkipMap<T, C> {
     iterator::Iter::new(version, self)
   }
 
+  /// Returns an iterator over the latest version (less or equal to the given version) of the
+  /// entries whose keys start with the prefix, checked by [`Comparator::has_prefix`].
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use skl::SkipMap;
+  ///
+  /// let l = SkipMap::new().unwrap();
+  /// for k in [&b"user/1"[..], b"user/2", b"users", b"video/1"] {
+  ///   l.insert(0, k, b"").unwrap();
+  /// }
+  ///
+  /// assert_eq!(l.prefix(0, b"user/").count(), 2);
+  /// assert_eq!(l.prefix(0, b"user").count(), 3);
+  /// ```
+  #[inline]
+  pub fn prefix<'a, 'b: 'a>(
+    &'a self,
+    version: u64,
+    prefix: &'b [u8],
+  ) -> iterator::Prefix<'a, T, C> {
+    iterator::Prefix::new(version, self, prefix)
+  }
+
+  /// Returns a new [`Cursor`](iterator::Cursor) over the latest version of all entries in the map less or equal to the given version,
+  /// the cursor is not positioned until it is seeked.
+  #[inline]
+  pub const fn cursor(&self, version: u64) -> iterator::Cursor<T, C> {
+    iterator::Cursor::new(version, self)
+  }
+
   /// Returns a new iterator, this iterator will yield all versions for all entries in the map less or equal to the given version.
   #[inline]
   pub const fn iter_all_versions(&self, version: u64) -> iterator::AllVersionsIter<T, C> {
@@ -1124,4 +1819,166 @@ impl<T: Trailer, C: Comparator> SkipMap<T, C> {
   {
     iterator::AllVersionsIter::range(version, self, range, true)
   }
+
+  /// Returns an iterator over all versions (less or equal to the given version) of the key,
+  /// from the newest to the oldest, including the removed ones.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use skl::SkipMap;
+  ///
+  /// let l = SkipMap::new().unwrap();
+  /// l.insert(1, b"k", b"v1").unwrap();
+  /// l.remove(2, b"k").unwrap();
+  /// l.insert(3, b"k", b"v3").unwrap();
+  ///
+  /// let versions = l.get_versions(2, b"k").map(|ent| ent.version()).collect::<Vec<_>>();
+  /// assert_eq!(versions, [2, 1]);
+  /// assert!(l.get_versions(3, b"k").nth(1).unwrap().is_removed());
+  /// ```
+  #[inline]
+  pub fn get_versions<'a, 'b: 'a>(
+    &'a self,
+    version: u64,
+    key: &'b [u8],
+  ) -> iterator::AllVersionsIter<'a, T, C, &'b [u8], core::ops::RangeInclusive<&'b [u8]>> {
+    self.range_all_versions(version, key..=key)
+  }
+}
+
+impl<T: Trailer, N: KeyNormalizer> SkipMap<T, Normalized<N>> {
+  /// Upserts a new key-value pair, the key will be stored in the normalized form produced by the
+  /// [`KeyNormalizer`].
+  ///
+  /// See [`insert`](SkipMap::insert) for more details.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use skl::{KeyNormalizer, Normalized, SkipMap, TooLarge, VacantBuffer};
+  ///
+  /// /// Case-insensitive collation.
+  /// #[derive(Debug)]
+  /// struct CaseInsensitive;
+  ///
+  /// impl KeyNormalizer for CaseInsensitive {
+  ///   fn normalized_len(&self, key: &[u8]) -> usize {
+  ///     key.len()
+  ///   }
+  ///
+  ///   fn normalize(&self, key: &[u8], buf: &mut VacantBuffer<'_>) -> Result<(), TooLarge> {
+  ///     for b in key {
+  ///       buf.write(&[b.to_ascii_lowercase()])?;
+  ///     }
+  ///     Ok(())
+  ///   }
+  /// }
+  ///
+  /// let l = SkipMap::<u64, _>::with_comparator(Normalized::new(CaseInsensitive)).unwrap();
+  /// l.insert_normalized(0, b"Alice", b"alice").unwrap();
+  ///
+  /// let ent = l.get_normalized(0, b"ALICE").unwrap().unwrap();
+  /// assert_eq!(ent.key(), b"alice");
+  /// assert_eq!(ent.value(), b"alice");
+  /// ```
+  pub fn insert_normalized<'a, 'b: 'a>(
+    &'a self,
+    trailer: T,
+    key: &'b [u8],
+    value: &'b [u8],
+  ) -> Result<Option<EntryRef<'a, T>>, Either<TooLarge, Error>> {
+    if self.read_only() {
+      return Err(Either::Right(Error::read_only()));
+    }
+
+    let normalizer = self.cmp.normalizer();
+    let vk = self.fetch_vacant_key(normalizer.normalized_len(key) as u32, |buf| {
+      normalizer.normalize(key, buf)
+    })?;
+
+    self
+      .update(
+        trailer,
+        Key::Vacant(vk),
+        value.len() as u32,
+        self.opts.value_alignment(),
+        |buf: &mut VacantBuffer| buf.write(value),
+        Ordering::Relaxed,
+        Ordering::Relaxed,
+        &mut Inserter::default(),
+        true,
+      )
+      .map(|old| {
+        old.expect_left("insert must get InsertOk").and_then(|old| {
+          if old.is_removed() {
+            None
+          } else {
+            Some(EntryRef(old))
+          }
+        })
+      })
+  }
+
+  /// Inserts a new key-value pair if it does not yet exist, the key will be stored in the normalized form produced by the
+  /// [`KeyNormalizer`].
+  ///
+  /// See [`get_or_insert`](SkipMap::get_or_insert) for more details.
+  pub fn get_or_insert_normalized<'a, 'b: 'a>(
+    &'a self,
+    trailer: T,
+    key: &'b [u8],
+    value: &'b [u8],
+  ) -> Result<Option<EntryRef<'a, T>>, Either<TooLarge, Error>> {
+    if self.read_only() {
+      return Err(Either::Right(Error::read_only()));
+    }
+
+    let normalizer = self.cmp.normalizer();
+    let vk = self.fetch_vacant_key(normalizer.normalized_len(key) as u32, |buf| {
+      normalizer.normalize(key, buf)
+    })?;
+
+    self
+      .update(
+        trailer,
+        Key::Vacant(vk),
+        value.len() as u32,
+        self.opts.value_alignment(),
+        |buf: &mut VacantBuffer| buf.write(value),
+        Ordering::Relaxed,
+        Ordering::Relaxed,
+        &mut Inserter::default(),
+        false,
+      )
+      .map(|old| {
+        old.expect_left("insert must get InsertOk").and_then(|old| {
+          if old.is_removed() {
+            None
+          } else {
+            Some(EntryRef(old))
+          }
+        })
+      })
+  }
+
+  /// Returns the value associated with the given key, if it exists. The key will be normalized
+  /// by the [`KeyNormalizer`] before the lookup, and the error of the normalizer is returned.
+  pub fn get_normalized(
+    &self,
+    version: u64,
+    key: &[u8],
+  ) -> Result<Option<EntryRef<'_, T>>, TooLarge> {
+    self
+      .cmp
+      .normalize(key)
+      .map(|key| self.get_in(version, &key))
+  }
+
+  /// Returns true if the key exists in the map. The key will be normalized
+  /// by the [`KeyNormalizer`] before the lookup, and the error of the normalizer is returned.
+  #[inline]
+  pub fn contains_key_normalized(&self, version: u64, key: &[u8]) -> Result<bool, TooLarge> {
+    self.get_normalized(version, key).map(|ent| ent.is_some())
+  }
 }
@@ -0,0 +1,118 @@
+use super::*;
+
+/// The utilization of the ARENA, returned by [`SkipMap::arena_stats`].
+///
+/// The byte counters are accumulated when the nodes are linked and when the values are
+/// replaced, by this map and its clones, so unlike [`SkipMap::memory_report`], the
+/// overwritten values are still counted, and the alignment paddings are exact.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct ArenaStats {
+  /// The capacity of the ARENA.
+  pub cap: usize,
+  /// The allocated bytes of the ARENA, see [`SkipMap::allocated`].
+  pub allocated: usize,
+  /// The bytes of the nodes: the headers, the towers, the access stamps, the key fingerprints
+  /// and the inline keys.
+  pub node_bytes: u64,
+  /// The bytes of the keys which are not stored inline, the versions sharing a key are
+  /// counted once.
+  pub key_bytes: u64,
+  /// The bytes of the trailers and the values.
+  pub value_bytes: u64,
+  /// The bytes of the alignment paddings of the trailers and the values.
+  pub alignment_waste: u64,
+  /// The bytes of the levels of the head and tail towers above the current height of the map,
+  /// the towers of the other nodes are allocated with their exact height.
+  pub unused_tower_bytes: usize,
+}
+
+/// The counters of the [`ArenaStats`] shared by all the clones of a [`SkipMap`].
+#[derive(Debug)]
+pub(super) struct AtomicArenaStats {
+  node_bytes: AtomicU64,
+  key_bytes: AtomicU64,
+  value_bytes: AtomicU64,
+  alignment_waste: AtomicU64,
+}
+
+impl AtomicArenaStats {
+  pub(super) const fn new() -> Self {
+    Self {
+      node_bytes: AtomicU64::new(0),
+      key_bytes: AtomicU64::new(0),
+      value_bytes: AtomicU64::new(0),
+      alignment_waste: AtomicU64::new(0),
+    }
+  }
+}
+
+impl<T, C> SkipMap<T, C> {
+  /// Records the allocations of a newly linked node, `value_len` is the size of its value.
+  #[inline]
+  pub(super) fn record_node_allocation(&self, deallocator: &Deallocator, value_len: u32) {
+    if let Some(stats) = &self.arena_stats {
+      if let Some(node) = &deallocator.node {
+        stats
+          .node_bytes
+          .fetch_add(node.size as u64, Ordering::Relaxed);
+      }
+      if let Some(key) = &deallocator.key {
+        stats
+          .key_bytes
+          .fetch_add(key.size as u64, Ordering::Relaxed);
+      }
+      if let Some(value) = &deallocator.value {
+        self.record_value_allocation(value, value_len);
+      }
+    }
+  }
+
+  /// Records the allocation of a trailer and a value of `value_len` bytes.
+  #[inline]
+  pub(super) fn record_value_allocation(&self, allocated: &Pointer, value_len: u32) {
+    if let Some(stats) = &self.arena_stats {
+      let value_len = if value_len == REMOVE { 0 } else { value_len };
+      let value = (mem::size_of::<T>() as u32)
+        .saturating_add(value_len)
+        .min(allocated.size);
+      stats.value_bytes.fetch_add(value as u64, Ordering::Relaxed);
+      stats
+        .alignment_waste
+        .fetch_add((allocated.size - value) as u64, Ordering::Relaxed);
+    }
+  }
+
+  /// Returns the utilization of the ARENA, `None` if [`Options::with_arena_stats`] is not
+  /// enabled.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use skl::{Options, SkipMap};
+  ///
+  /// let l = SkipMap::with_options(Options::new().with_arena_stats(true)).unwrap();
+  /// l.insert(0, b"key", b"value").unwrap();
+  /// l.insert(0, b"key", b"new value").unwrap();
+  ///
+  /// let stats = l.arena_stats().unwrap();
+  /// assert_eq!(stats.key_bytes, 3);
+  /// // the trailers are `u64`s
+  /// assert_eq!(stats.value_bytes, 8 + 5 + 8 + 9);
+  /// assert!(stats.node_bytes > 0);
+  /// assert!(stats.allocated <= stats.cap);
+  /// ```
+  pub fn arena_stats(&self) -> Option<ArenaStats> {
+    self.arena_stats.as_ref().map(|stats| {
+      let unused_levels = self.max_height().saturating_sub(self.height()) as usize;
+      ArenaStats {
+        cap: self.capacity(),
+        allocated: self.allocated(),
+        node_bytes: stats.node_bytes.load(Ordering::Relaxed),
+        key_bytes: stats.key_bytes.load(Ordering::Relaxed),
+        value_bytes: stats.value_bytes.load(Ordering::Relaxed),
+        alignment_waste: stats.alignment_waste.load(Ordering::Relaxed),
+        unused_tower_bytes: 2 * unused_levels * Link::SIZE,
+      }
+    })
+  }
+}
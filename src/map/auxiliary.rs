@@ -0,0 +1,82 @@
+use super::*;
+
+impl<T, C> SkipMap<T, C> {
+  /// Reserves `len` zeroed bytes aligned to `align` in the ARENA, for the metadata of the
+  /// embedders (e.g. bloom filters or sequence maps), which lives and is flushed together with
+  /// the entries of the map, and persists in the file of a file backed map.
+  ///
+  /// Returns the offset of the bytes in the ARENA, which should be stored by the embedder, e.g.
+  /// as the value of a well-known key, to find the bytes again with [`aux`](SkipMap::aux)
+  /// after the map is reopened. The bytes are never reclaimed, until the map is
+  /// [`clear`](SkipMap::clear)ed.
+  ///
+  /// `align` will be rounded up to the next power of two, [`Error::InvalidAlignment`] is
+  /// returned if it is `0` or larger than `2^31`.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use skl::SkipMap;
+  ///
+  /// let l = SkipMap::new().unwrap();
+  /// let (offset, buf) = l.reserve_aux(8, 8).unwrap();
+  /// buf.copy_from_slice(&42u64.to_le_bytes());
+  /// l.insert(0, b"__aux", &offset.to_le_bytes()).unwrap();
+  ///
+  /// let offset = u32::from_le_bytes(l.get(0, b"__aux").unwrap().value().try_into().unwrap());
+  /// // Safety: the bytes are reserved by `reserve_aux`, and no mutable reference is alive.
+  /// let buf = unsafe { l.aux(offset, 8) };
+  /// assert_eq!(u64::from_le_bytes(buf.try_into().unwrap()), 42);
+  /// ```
+  pub fn reserve_aux(&self, len: u32, align: u32) -> Result<(u32, &mut [u8]), Error> {
+    if self.read_only() {
+      return Err(Error::read_only());
+    }
+
+    if align == 0 || align > 1 << 31 {
+      return Err(Error::InvalidAlignment(align as u64));
+    }
+
+    let align = align.next_power_of_two();
+    let size = len
+      .checked_add(align - 1)
+      .ok_or(Error::EntryTooLarge(len as u64 + align as u64 - 1))?;
+    let mut buf = self.arena.alloc_bytes(size)?;
+    buf.detach();
+    let offset = buf.offset();
+
+    // Safety: the bytes are just allocated, and detached from the ARENA.
+    unsafe {
+      let addr = self.arena.get_bytes_mut(offset, size as usize).as_ptr() as usize;
+      let padding = (align as usize - addr % align as usize) % align as usize;
+      let aux = self.arena.get_bytes_mut(offset + padding, len as usize);
+      // the memory may be reused from the freelist
+      aux.fill(0);
+      Ok(((offset + padding) as u32, aux))
+    }
+  }
+
+  /// Returns the auxiliary bytes reserved by [`reserve_aux`](SkipMap::reserve_aux) at the
+  /// offset.
+  ///
+  /// # Safety
+  ///
+  /// - `offset` and `len` must be returned by a [`reserve_aux`](SkipMap::reserve_aux) of this
+  ///   map (or of the map stored in the same file), or be within such bytes.
+  /// - No mutable reference to the bytes may be alive while the returned slice is used.
+  pub unsafe fn aux(&self, offset: u32, len: u32) -> &[u8] {
+    self.arena.get_bytes(offset as usize, len as usize)
+  }
+
+  /// Returns the auxiliary bytes reserved by [`reserve_aux`](SkipMap::reserve_aux) at the
+  /// offset, mutably.
+  ///
+  /// # Safety
+  ///
+  /// - `offset` and `len` must be returned by a [`reserve_aux`](SkipMap::reserve_aux) of this
+  ///   map, or be within such bytes.
+  /// - No other reference to the bytes may be alive while the returned slice is used.
+  pub unsafe fn aux_mut(&self, offset: u32, len: u32) -> &mut [u8] {
+    self.arena.get_bytes_mut(offset as usize, len as usize)
+  }
+}
@@ -0,0 +1,136 @@
+use super::*;
+
+/// Describes which entries a [`CompactionIter`] can drop.
+#[derive(Debug, Clone, Copy)]
+pub struct CompactionOptions<T> {
+  watermark: u64,
+  now: u64,
+  ttl: Option<fn(&T) -> Option<u64>>,
+}
+
+impl<T> CompactionOptions<T> {
+  /// Creates the options with the GC watermark, which is the version of the oldest
+  /// snapshot still in use, and the current time.
+  #[inline]
+  pub const fn new(watermark: u64, now: u64) -> Self {
+    Self {
+      watermark,
+      now,
+      ttl: None,
+    }
+  }
+
+  /// Set the function to extract the expiration time from the trailer, an entry expires
+  /// when the expiration time is less or equal to the current time.
+  ///
+  /// The default value is `None`, which means the entries never expire.
+  #[inline]
+  pub const fn with_ttl(mut self, ttl: fn(&T) -> Option<u64>) -> Self {
+    self.ttl = Some(ttl);
+    self
+  }
+
+  /// Returns the GC watermark.
+  #[inline]
+  pub const fn watermark(&self) -> u64 {
+    self.watermark
+  }
+
+  /// Returns the current time.
+  #[inline]
+  pub const fn now(&self) -> u64 {
+    self.now
+  }
+
+  #[inline]
+  fn is_expired(&self, trailer: &T) -> bool {
+    match self.ttl {
+      Some(ttl) => ttl(trailer).map_or(false, |expires_at| expires_at <= self.now),
+      None => false,
+    }
+  }
+}
+
+/// An iterator over the entries which must survive a flush or a compaction,
+/// returned by [`SkipMap::compaction_iter`].
+pub struct CompactionIter<'a, T, C> {
+  map: &'a SkipMap<T, C>,
+  iter: AllVersionsIter<'a, T, C>,
+  opts: CompactionOptions<T>,
+  key: Option<&'a [u8]>,
+  /// Whether the newest version at or below the watermark of the current key is handled,
+  /// so all the older versions are shadowed.
+  shadowed: bool,
+}
+
+impl<'a, T: Trailer, C: Comparator> Iterator for CompactionIter<'a, T, C> {
+  type Item = VersionedEntryRef<'a, T>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    loop {
+      let ent = self.iter.next()?;
+      let same_key = self.key.map_or(false, |key| {
+        self.map.cmp.compare(key, ent.key) == cmp::Ordering::Equal
+      });
+      if !same_key {
+        self.key = Some(ent.key);
+        self.shadowed = false;
+      }
+
+      if self.shadowed {
+        continue;
+      }
+
+      // the snapshots newer than the watermark can still read this version
+      if ent.version() > self.opts.watermark {
+        return Some(ent);
+      }
+
+      // the newest version visible to all the snapshots, the older ones are shadowed
+      self.shadowed = true;
+      if ent.is_removed() || self.opts.is_expired(&ent.trailer) {
+        continue;
+      }
+      return Some(ent);
+    }
+  }
+}
+
+impl<T: Trailer, C: Comparator> SkipMap<T, C> {
+  /// Returns an iterator over the entries which must be kept when flushing or compacting
+  /// the map, in key order and from the newest version to the oldest for the same key.
+  ///
+  /// - All the versions newer than the watermark are kept, including the removed and the
+  ///   expired ones, since the snapshots newer than the watermark can still read them.
+  /// - For the versions at or below the watermark, only the newest one is kept, the older
+  ///   ones are shadowed. It is elided as well if it is removed or expired.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use core::sync::atomic::Ordering;
+  /// use skl::{map::CompactionOptions, SkipMap};
+  ///
+  /// let l = SkipMap::new().unwrap();
+  /// l.insert(1, b"a", b"a1").unwrap();
+  /// l.insert(2, b"a", b"a2").unwrap();
+  /// l.insert(3, b"a", b"a3").unwrap();
+  /// l.insert(1, b"b", b"b1").unwrap();
+  /// l.compare_remove(2, b"b", Ordering::AcqRel, Ordering::Relaxed).unwrap();
+  ///
+  /// let kept = l
+  ///   .compaction_iter(CompactionOptions::new(2, 0))
+  ///   .map(|ent| (ent.key().to_vec(), ent.version()))
+  ///   .collect::<Vec<_>>();
+  /// assert_eq!(kept, [(b"a".to_vec(), 3), (b"a".to_vec(), 2)]);
+  /// ```
+  pub fn compaction_iter(&self, opts: CompactionOptions<T>) -> CompactionIter<'_, T, C> {
+    CompactionIter {
+      map: self,
+      iter: self.iter_all_versions(u64::MAX),
+      opts,
+      key: None,
+      shadowed: false,
+    }
+  }
+}
@@ -0,0 +1,173 @@
+use super::*;
+
+/// A copy-on-write fork of a [`SkipMap`], returned by [`SkipMap::cow_clone`].
+///
+/// The fork shares the ARENA of the base map and never writes to it, the writes go to a
+/// private overlay map, and the reads merge the overlay with the base. Forking is O(1) and
+/// does not copy any entry, so it is cheap to fork for speculative or branching workloads,
+/// and to drop the fork to discard its writes.
+///
+/// The base is read at the versions less or equal to the [`fork_version`](CowMap::fork_version),
+/// so the later writes to the base through the other handles are not visible in the fork, as
+/// long as they use greater versions. Like [`MergeIterator`], the newest version of a key wins,
+/// and the overlay wins the ties, so the writes to the fork should use versions greater or
+/// equal to the fork version to shadow the base.
+///
+/// # Example
+///
+/// ```rust
+/// use skl::SkipMap;
+///
+/// let base = SkipMap::new().unwrap();
+/// base.insert(1, b"a", b"a1").unwrap();
+/// base.insert(1, b"b", b"b1").unwrap();
+///
+/// let fork = base.cow_clone().unwrap();
+/// fork.insert(2, b"a", b"a2").unwrap();
+/// fork.remove(2, b"b").unwrap();
+/// fork.insert(2, b"c", b"c2").unwrap();
+///
+/// assert_eq!(fork.get(2, b"a").unwrap().value(), b"a2");
+/// assert!(fork.get(2, b"b").is_none());
+/// assert_eq!(fork.iter(2).count(), 2);
+///
+/// // the base is not affected
+/// assert_eq!(base.get(2, b"a").unwrap().value(), b"a1");
+/// assert!(base.get(2, b"c").is_none());
+/// ```
+#[derive(Debug)]
+pub struct CowMap<T = u64, C = Ascend> {
+  base: SkipMap<T, C>,
+  overlay: SkipMap<T, C>,
+  fork_version: u64,
+}
+
+impl<T, C> CowMap<T, C> {
+  /// Returns the base map.
+  #[inline]
+  pub const fn base(&self) -> &SkipMap<T, C> {
+    &self.base
+  }
+
+  /// Returns the overlay map, which contains the writes of the fork.
+  #[inline]
+  pub const fn overlay(&self) -> &SkipMap<T, C> {
+    &self.overlay
+  }
+
+  /// Returns the maximum version of the base map when the fork was created.
+  #[inline]
+  pub const fn fork_version(&self) -> u64 {
+    self.fork_version
+  }
+}
+
+impl<T: Trailer, C: Comparator> CowMap<T, C> {
+  /// Upserts a key-value pair into the overlay.
+  ///
+  /// See [`SkipMap::insert`] for more details.
+  pub fn insert<'a, 'b: 'a>(
+    &'a self,
+    trailer: T,
+    key: &'b [u8],
+    value: &'b [u8],
+  ) -> Result<(), Error> {
+    self.overlay.insert(trailer, key, value).map(|_| ())
+  }
+
+  /// Removes the key by writing a removed entry into the overlay, which shadows the entry of
+  /// the base.
+  pub fn remove<'a, 'b: 'a>(&'a self, trailer: T, key: &'b [u8]) -> Result<(), Error> {
+    self.overlay.remove(trailer, key).map(|_| ())
+  }
+
+  /// Returns the newest entry of the key (with the version less or equal to `version`) in
+  /// the overlay or the base.
+  pub fn get<'a, 'b: 'a>(&'a self, version: u64, key: &'b [u8]) -> Option<EntryRef<'a, T>> {
+    let base_version = version.min(self.fork_version);
+    let ent = match (
+      self.overlay.newest_in(version, key),
+      self.base.newest_in(base_version, key),
+    ) {
+      (Some(o), Some(b)) if b.version() > o.version() => b,
+      (Some(o), _) => o,
+      (None, Some(b)) => b,
+      (None, None) => return None,
+    };
+
+    if ent.is_removed() {
+      None
+    } else {
+      Some(EntryRef(ent))
+    }
+  }
+
+  /// Returns true if the key exists in the overlay or the base.
+  #[inline]
+  pub fn contains_key<'a, 'b: 'a>(&'a self, version: u64, key: &'b [u8]) -> bool {
+    self.get(version, key).is_some()
+  }
+
+  /// Returns an iterator over the newest version (less or equal to `version`) of the live
+  /// entries of the overlay and the base, in key order.
+  #[inline]
+  pub fn iter(&self, version: u64) -> MergeIterator<'_, T, C> {
+    MergeIterator::with_versions([
+      (&self.overlay, version),
+      (&self.base, version.min(self.fork_version)),
+    ])
+  }
+}
+
+impl<T: Trailer, C: Comparator + Clone> SkipMap<T, C> {
+  /// Forks the map into a logically independent [`CowMap`], which shares the ARENA of this
+  /// map read-only, and writes to a private overlay created with the [`Options`] of this map.
+  ///
+  /// See [`CowMap`] for more details.
+  #[inline]
+  pub fn cow_clone(&self) -> Result<CowMap<T, C>, Error> {
+    self.cow_clone_with_options(self.opts)
+  }
+
+  /// Like [`cow_clone`](SkipMap::cow_clone), but the overlay is created with the given
+  /// [`Options`], e.g. with a smaller capacity.
+  pub fn cow_clone_with_options(&self, opts: Options) -> Result<CowMap<T, C>, Error> {
+    Ok(CowMap {
+      overlay: SkipMap::with_options_and_comparator(opts, self.cmp.clone())?,
+      fork_version: self.max_version(),
+      base: self.clone(),
+    })
+  }
+}
+
+impl<T: Trailer, C: Comparator> SkipMap<T, C> {
+  /// Returns the newest version (less or equal to `version`) of the key, including the
+  /// removed one.
+  pub(super) fn newest_in(&self, version: u64, key: &[u8]) -> Option<VersionedEntryRef<'_, T>> {
+    unsafe {
+      let (n, eq) = self.find_near(version, key, false, true); // findLessOrEqual.
+
+      let n = n?;
+      let node = n.as_ref();
+      let node_key = node.get_key(&self.arena);
+      let (trailer, value) = node.get_value_and_trailer(&self.arena);
+      if !eq {
+        if !matches!(self.cmp.compare(key, node_key), cmp::Ordering::Equal) {
+          return None;
+        }
+
+        if trailer.version() > version {
+          return None;
+        }
+      }
+
+      Some(VersionedEntryRef {
+        arena: &self.arena,
+        key: node_key,
+        trailer,
+        value,
+        ptr: n,
+      })
+    }
+  }
+}
@@ -0,0 +1,121 @@
+use core::iter::Fuse;
+
+use super::*;
+
+/// A change of a key between two versions of the [`SkipMap`], yielded by [`SkipMap::diff`].
+#[derive(Debug, Clone, Copy)]
+pub enum DiffEntry<'a, T> {
+  /// The key does not exist at the `from` version, but exists at the `to` version.
+  Inserted(EntryRef<'a, T>),
+  /// The key exists at both versions, but was written again in between.
+  Updated {
+    /// The entry at the `from` version.
+    old: EntryRef<'a, T>,
+    /// The entry at the `to` version.
+    new: EntryRef<'a, T>,
+  },
+  /// The key exists at the `from` version, but is removed at the `to` version,
+  /// contains the entry at the `from` version.
+  Deleted(EntryRef<'a, T>),
+}
+
+impl<'a, T> DiffEntry<'a, T> {
+  /// Returns the key of the change.
+  #[inline]
+  pub const fn key(&self) -> &'a [u8] {
+    match self {
+      Self::Inserted(ent) | Self::Deleted(ent) | Self::Updated { new: ent, .. } => ent.0.key,
+    }
+  }
+}
+
+/// An iterator over the changes between two versions of the [`SkipMap`],
+/// returned by [`SkipMap::diff`].
+pub struct Diff<'a, T, C> {
+  map: &'a SkipMap<T, C>,
+  iter: Fuse<AllVersionsIter<'a, T, C>>,
+  from: u64,
+  pending: Option<VersionedEntryRef<'a, T>>,
+}
+
+impl<'a, T: Trailer, C: Comparator> Iterator for Diff<'a, T, C> {
+  type Item = DiffEntry<'a, T>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    loop {
+      // the versions of a key are yielded from the newest to the oldest,
+      // so the first one is the entry at the `to` version.
+      let new = match self.pending.take() {
+        Some(ent) => ent,
+        None => self.iter.next()?,
+      };
+      let mut old = if new.version() <= self.from {
+        Some(new)
+      } else {
+        None
+      };
+
+      for ent in self.iter.by_ref() {
+        if self.map.cmp.compare(ent.key, new.key) != cmp::Ordering::Equal {
+          self.pending = Some(ent);
+          break;
+        }
+
+        if old.is_none() && ent.version() <= self.from {
+          old = Some(ent);
+        }
+      }
+
+      match old.filter(|ent| !ent.is_removed()) {
+        None if !new.is_removed() => return Some(DiffEntry::Inserted(EntryRef(new))),
+        Some(old) if new.is_removed() => return Some(DiffEntry::Deleted(EntryRef(old))),
+        Some(old) if new.version() > self.from => {
+          return Some(DiffEntry::Updated {
+            old: EntryRef(old),
+            new: EntryRef(new),
+          })
+        }
+        _ => continue,
+      }
+    }
+  }
+}
+
+impl<T: Trailer, C: Comparator> SkipMap<T, C> {
+  /// Returns an iterator over the keys inserted, updated or deleted between the `from` and
+  /// the `to` versions (both are the versions of the snapshots, `from` should be less than
+  /// `to`), in key order, so the incremental backup or cache-sync layers can compute the
+  /// deltas without scanning and comparing the full dumps.
+  ///
+  /// A key which is written again with the same value is still reported as updated.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use core::sync::atomic::Ordering;
+  /// use skl::{map::DiffEntry, SkipMap};
+  ///
+  /// let l = SkipMap::new().unwrap();
+  /// l.insert(1, b"a", b"a1").unwrap();
+  /// l.insert(1, b"b", b"b1").unwrap();
+  /// l.insert(1, b"c", b"c1").unwrap();
+  ///
+  /// l.insert(2, b"a", b"a2").unwrap();
+  /// l.compare_remove(2, b"b", Ordering::AcqRel, Ordering::Relaxed).unwrap();
+  /// l.insert(2, b"d", b"d2").unwrap();
+  ///
+  /// let changes = l.diff(1, 2).collect::<Vec<_>>();
+  /// assert_eq!(changes.len(), 3);
+  /// assert!(matches!(changes[0], DiffEntry::Updated { old, new } if old.value() == b"a1" && new.value() == b"a2"));
+  /// assert!(matches!(changes[1], DiffEntry::Deleted(old) if old.key() == b"b"));
+  /// assert!(matches!(changes[2], DiffEntry::Inserted(new) if new.key() == b"d"));
+  /// ```
+  pub fn diff(&self, from: u64, to: u64) -> Diff<'_, T, C> {
+    Diff {
+      map: self,
+      iter: self.iter_all_versions(to).fuse(),
+      from,
+      pending: None,
+    }
+  }
+}
@@ -28,16 +28,23 @@ impl<'a, T: Clone> Clone for VersionedEntryRef<'a, T> {
 
 impl<'a, T: Copy> Copy for VersionedEntryRef<'a, T> {}
 
+// Safety: the entry only reads the ARENA, which is shared by the threads of the map.
+unsafe impl<'a, T: Send> Send for VersionedEntryRef<'a, T> {}
+unsafe impl<'a, T: Sync> Sync for VersionedEntryRef<'a, T> {}
+
 impl<'a, T> VersionedEntryRef<'a, T> {
-  /// Returns the reference to the key
+  /// Returns the reference to the key, borrowed from the ARENA without copying, so it
+  /// outlives the entry reference.
   #[inline]
-  pub const fn key(&self) -> &[u8] {
+  pub const fn key(&self) -> &'a [u8] {
     self.key
   }
 
   /// Returns the reference to the value, `None` means the entry is removed.
+  ///
+  /// The value is borrowed from the ARENA without copying, so it outlives the entry reference.
   #[inline]
-  pub const fn value(&self) -> Option<&[u8]> {
+  pub const fn value(&self) -> Option<&'a [u8]> {
     self.value
   }
 
@@ -64,6 +71,8 @@ impl<'a, T> VersionedEntryRef<'a, T> {
       arena: self.arena.clone(),
       trailer: self.trailer.clone(),
       ptr: self.ptr,
+      #[cfg(feature = "track-guards")]
+      guard: super::guards::GuardToken::register(self.arena),
     }
   }
 
@@ -107,6 +116,8 @@ pub struct VersionedEntry<T> {
   pub(super) arena: Arena,
   pub(super) trailer: T,
   pub(super) ptr: NodePtr<T>,
+  #[cfg(feature = "track-guards")]
+  guard: super::guards::GuardToken,
 }
 
 impl<T: Clone> Clone for VersionedEntry<T> {
@@ -115,6 +126,8 @@ impl<T: Clone> Clone for VersionedEntry<T> {
       arena: self.arena.clone(),
       trailer: self.trailer.clone(),
       ptr: self.ptr,
+      #[cfg(feature = "track-guards")]
+      guard: self.guard.clone(),
     }
   }
 }
@@ -256,15 +269,29 @@ impl<'a, T: Clone> From<EntryRef<'a, T>> for Entry<T> {
 }
 
 impl<'a, T> EntryRef<'a, T> {
-  /// Returns the reference to the key
+  /// Returns the reference to the key, borrowed from the ARENA without copying, so it
+  /// outlives the entry reference.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use skl::SkipMap;
+  ///
+  /// let l = SkipMap::new().unwrap();
+  /// l.insert(0, b"hello", b"world").unwrap();
+  ///
+  /// let value: &[u8] = l.get(0, b"hello").unwrap().value();
+  /// assert_eq!(value, b"world");
+  /// ```
   #[inline]
-  pub const fn key(&self) -> &[u8] {
+  pub const fn key(&self) -> &'a [u8] {
     self.0.key()
   }
 
-  /// Returns the reference to the value, `None` means the entry is removed.
+  /// Returns the reference to the value, borrowed from the ARENA without copying, so it
+  /// outlives the entry reference.
   #[inline]
-  pub const fn value(&self) -> &[u8] {
+  pub const fn value(&self) -> &'a [u8] {
     match self.0.value() {
       Some(value) => value,
       None => panic!("EntryRef's value cannot be `None`"),
@@ -294,4 +321,13 @@ impl<'a, T> EntryRef<'a, T> {
   {
     self.0.version()
   }
+
+  /// Returns the key and the value which live as long as the map.
+  #[inline]
+  pub(crate) fn into_key_value(self) -> (&'a [u8], &'a [u8]) {
+    match self.0.value {
+      Some(value) => (self.0.key, value),
+      None => panic!("EntryRef's value cannot be `None`"),
+    }
+  }
 }
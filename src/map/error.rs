@@ -1,4 +1,17 @@
-/// Error type for the [`SkipMap`](crate::SkipMap).
+/// Error type for the [`SkipMap`](crate::SkipMap), also re-exported as `skl::Error`.
+///
+/// The writes never panic on a full ARENA or on an oversized entry, the failures are surfaced
+/// as the variants of this error, and the corruption found when reading untrusted data is
+/// reported as [`Error::Corrupted`].
+///
+/// # Example
+///
+/// ```rust
+/// use skl::{u27, Error, Options, SkipMap};
+///
+/// let l = SkipMap::with_options(Options::new().with_max_key_size(u27::new(4))).unwrap();
+/// assert_eq!(l.insert(0, b"hello", b"world").unwrap_err(), Error::KeyTooLarge(5));
+/// ```
 ///
 /// [`SkipMap`]: crate::SkipMap
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -17,6 +30,33 @@ pub enum Error {
 
   /// Arena too small
   ArenaTooSmall,
+
+  /// Indicates that the height of the tower is zero or greater than the max height.
+  InvalidHeight(u8),
+
+  /// Indicates that the memory of the given size cannot be allocated, see `SkipMap::try_new`.
+  OutOfMemory(u64),
+
+  /// Indicates that the write would exceed the quota, and nothing can be evicted.
+  QuotaExceeded,
+
+  /// Indicates that the [`SkipMap`](super::SkipMap) is poisoned because of corruption.
+  Corrupted(super::PoisonReason),
+
+  /// Indicates that the ARENA is shared with the given number of handles, so it cannot be
+  /// reset, see [`SkipMap::reset`](super::SkipMap::reset).
+  Shared(usize),
+
+  /// Indicates that the values cannot be aligned to the given alignment, because it cannot be
+  /// rounded up to a power of two in `u32` (reported as `0` by the options), or it is larger
+  /// than the alignment of the ARENA, or the alignment passed to
+  /// [`SkipMap::reserve_aux`](super::SkipMap::reserve_aux) is `0` or cannot be rounded up to a
+  /// power of two in `u32`.
+  InvalidAlignment(u64),
+
+  /// Indicates that the layout of the nodes recorded in the ARENA (e.g. whether the nodes have
+  /// the access stamps) does not match the [`Options`](crate::Options) used to open it.
+  LayoutMismatch,
 }
 
 impl core::fmt::Display for Error {
@@ -27,6 +67,13 @@ impl core::fmt::Display for Error {
       Self::KeyTooLarge(size) => write!(f, "key size {} is too large", size),
       Self::EntryTooLarge(size) => write!(f, "entry size {size} is too large",),
       Self::ArenaTooSmall => write!(f, "ARENA capacity is too small"),
+      Self::InvalidHeight(height) => write!(f, "height {height} is invalid"),
+      Self::OutOfMemory(size) => write!(f, "failed to allocate {size} bytes"),
+      Self::QuotaExceeded => write!(f, "quota exceeded"),
+      Self::Corrupted(reason) => write!(f, "skipmap is poisoned: {reason}"),
+      Self::Shared(refs) => write!(f, "ARENA is shared with {refs} handles"),
+      Self::InvalidAlignment(align) => write!(f, "alignment {align} is not supported"),
+      Self::LayoutMismatch => write!(f, "node layout does not match the options"),
     }
   }
 }
@@ -34,6 +81,13 @@ impl core::fmt::Display for Error {
 #[cfg(feature = "std")]
 impl std::error::Error for Error {}
 
+#[cfg(feature = "std")]
+impl From<Error> for std::io::Error {
+  fn from(e: Error) -> Self {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, e)
+  }
+}
+
 impl From<rarena_allocator::Error> for Error {
   fn from(e: rarena_allocator::Error) -> Self {
     Self::Arena(e)
@@ -46,6 +100,16 @@ impl Error {
   pub const fn read_only() -> Self {
     Self::Arena(rarena_allocator::Error::ReadOnly)
   }
+
+  /// Returns `true` if the ARENA does not have enough space for the write, so the map (e.g. a
+  /// full memtable) should be rotated. The writes never panic when the ARENA is full.
+  #[inline]
+  pub const fn is_arena_full(&self) -> bool {
+    matches!(
+      self,
+      Self::Arena(rarena_allocator::Error::InsufficientSpace { .. })
+    )
+  }
 }
 
 #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
@@ -53,6 +117,20 @@ pub(super) fn invalid_data<E: std::error::Error + Send + Sync + 'static>(e: E) -
   std::io::Error::new(std::io::ErrorKind::InvalidData, e)
 }
 
+/// Wraps [`Error::OutOfMemory`] if the memory of the given size cannot be allocated, the other
+/// errors are returned as is.
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+pub(super) fn out_of_memory(e: std::io::Error, size: u32) -> std::io::Error {
+  if e.kind() == std::io::ErrorKind::OutOfMemory {
+    std::io::Error::new(
+      std::io::ErrorKind::OutOfMemory,
+      Error::OutOfMemory(size as u64),
+    )
+  } else {
+    e
+  }
+}
+
 #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
 pub(super) fn bad_magic_version() -> std::io::Error {
   std::io::Error::new(std::io::ErrorKind::InvalidData, "bad magic version")
@@ -92,4 +170,33 @@ fn test_fmt() {
     std::format!("{}", Error::Arena(rarena_allocator::Error::ReadOnly)),
     "Arena is read-only"
   );
+  assert_eq!(
+    std::format!("{}", Error::InvalidHeight(0)),
+    "height 0 is invalid"
+  );
+  assert_eq!(std::format!("{}", Error::QuotaExceeded), "quota exceeded");
+  assert_eq!(
+    std::format!("{}", Error::Shared(2)),
+    "ARENA is shared with 2 handles"
+  );
+  assert_eq!(
+    std::format!("{}", Error::InvalidAlignment(64)),
+    "alignment 64 is not supported"
+  );
+  assert_eq!(
+    std::format!("{}", Error::LayoutMismatch),
+    "node layout does not match the options"
+  );
+}
+
+#[cfg(test)]
+#[test]
+fn test_is_arena_full() {
+  assert!(Error::Arena(rarena_allocator::Error::InsufficientSpace {
+    requested: 10,
+    available: 1
+  })
+  .is_arena_full());
+  assert!(!Error::read_only().is_arena_full());
+  assert!(!Error::QuotaExceeded.is_arena_full());
 }
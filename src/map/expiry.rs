@@ -0,0 +1,354 @@
+use std::vec::{IntoIter, Vec};
+
+use super::*;
+
+/// The options of the reads, for [`SkipMap::get_with_options`] and
+/// [`SkipMap::iter_with_options`], to skip the expired entries.
+#[derive(Debug, Clone, Copy)]
+pub struct ReadOptions<T> {
+  now: u64,
+  ttl: Option<fn(&T) -> Option<u64>>,
+}
+
+impl<T> ReadOptions<T> {
+  /// Creates the options with the current time.
+  #[inline]
+  pub const fn new(now: u64) -> Self {
+    Self { now, ttl: None }
+  }
+
+  /// Set the function to extract the expiration time from the trailer, like
+  /// [`CompactionOptions::with_ttl`], the reads skip the entries whose expiration time is less
+  /// or equal to the current time.
+  ///
+  /// The default value is `None`, which means the entries never expire.
+  #[inline]
+  pub const fn with_ttl(mut self, ttl: fn(&T) -> Option<u64>) -> Self {
+    self.ttl = Some(ttl);
+    self
+  }
+
+  /// Returns the current time.
+  #[inline]
+  pub const fn now(&self) -> u64 {
+    self.now
+  }
+
+  /// Returns `true` if the entry with the trailer is expired at the current time.
+  #[inline]
+  pub fn is_expired(&self, trailer: &T) -> bool {
+    match self.ttl {
+      Some(ttl) => ttl(trailer).map_or(false, |expires_at| expires_at <= self.now),
+      None => false,
+    }
+  }
+}
+
+/// An iterator over the entries which are not expired, returned by
+/// [`SkipMap::iter_with_options`] and [`SkipMap::range_with_options`].
+pub struct ReadIter<'a, T, C, Q: ?Sized = &'static [u8], R = core::ops::RangeFull> {
+  iter: iterator::Iter<'a, T, C, Q, R>,
+  opts: ReadOptions<T>,
+}
+
+impl<'a, Q, R, T, C> Iterator for ReadIter<'a, T, C, Q, R>
+where
+  C: Comparator,
+  T: Trailer,
+  &'a [u8]: PartialOrd<Q>,
+  Q: ?Sized + PartialOrd<&'a [u8]>,
+  R: RangeBounds<Q>,
+{
+  type Item = EntryRef<'a, T>;
+
+  #[inline]
+  fn next(&mut self) -> Option<Self::Item> {
+    let opts = self.opts;
+    self.iter.find(|ent| !opts.is_expired(ent.trailer()))
+  }
+}
+
+impl<'a, Q, R, T, C> DoubleEndedIterator for ReadIter<'a, T, C, Q, R>
+where
+  C: Comparator,
+  T: Trailer,
+  &'a [u8]: PartialOrd<Q>,
+  Q: ?Sized + PartialOrd<&'a [u8]>,
+  R: RangeBounds<Q>,
+{
+  #[inline]
+  fn next_back(&mut self) -> Option<Self::Item> {
+    let opts = self.opts;
+    self.iter.rfind(|ent| !opts.is_expired(ent.trailer()))
+  }
+}
+
+/// An iterator over the entries which are expired, in key order, returned by
+/// [`SkipMap::expired`].
+pub struct ExpiredIter<'a, T, C> {
+  iter: iterator::Iter<'a, T, C>,
+  opts: ReadOptions<T>,
+}
+
+impl<'a, T: Trailer, C: Comparator> Iterator for ExpiredIter<'a, T, C> {
+  type Item = EntryRef<'a, T>;
+
+  #[inline]
+  fn next(&mut self) -> Option<Self::Item> {
+    let opts = self.opts;
+    self.iter.find(|ent| opts.is_expired(ent.trailer()))
+  }
+}
+
+impl<'a, T: Trailer, C: Comparator> DoubleEndedIterator for ExpiredIter<'a, T, C> {
+  #[inline]
+  fn next_back(&mut self) -> Option<Self::Item> {
+    let opts = self.opts;
+    self.iter.rfind(|ent| opts.is_expired(ent.trailer()))
+  }
+}
+
+impl<T: Trailer, C: Comparator> SkipMap<T, C> {
+  /// Returns an iterator over the latest version (less or equal to the given version) of the
+  /// entries which are expired at the current time of the [`ReadOptions`], in key order, e.g.
+  /// for a cache to sweep the expired keys and to count the reclaimable bytes.
+  ///
+  /// This is the complement of [`iter_with_options`](SkipMap::iter_with_options), the
+  /// iterator is lazy and scans the entries once, unlike
+  /// [`iter_by_expiry`](SkipMap::iter_by_expiry) which sorts them.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use skl::{map::ReadOptions, SkipMap};
+  ///
+  /// let l = SkipMap::<u64>::new().unwrap();
+  /// l.insert(10, b"a", b"a").unwrap();
+  /// l.insert(20, b"b", b"bb").unwrap();
+  /// l.insert(30, b"c", b"ccc").unwrap();
+  ///
+  /// // use the version as the expiration time
+  /// let opts = ReadOptions::new(25).with_ttl(|t| Some(*t));
+  /// let reclaimable = l
+  ///   .expired(u64::MAX, opts)
+  ///   .map(|ent| ent.key().len() + ent.value().len())
+  ///   .sum::<usize>();
+  /// assert_eq!(reclaimable, 5);
+  /// ```
+  #[inline]
+  pub const fn expired(&self, version: u64, opts: ReadOptions<T>) -> ExpiredIter<'_, T, C> {
+    ExpiredIter {
+      iter: self.iter(version),
+      opts,
+    }
+  }
+
+  /// Like [`get`](SkipMap::get), but returns `None` if the latest version (less or equal to
+  /// the given version) of the key is expired, see [`ReadOptions`].
+  ///
+  /// An expired entry shadows the older versions of the key, so they are not returned either.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use skl::{map::ReadOptions, SkipMap};
+  ///
+  /// #[derive(Debug, Clone, Copy)]
+  /// struct Ttl {
+  ///   version: u64,
+  ///   expires_at: u64,
+  /// }
+  ///
+  /// unsafe impl skl::Trailer for Ttl {
+  ///   fn version(&self) -> u64 {
+  ///     self.version
+  ///   }
+  /// }
+  ///
+  /// let l = SkipMap::<Ttl>::new().unwrap();
+  /// l.insert(Ttl { version: 0, expires_at: 10 }, b"a", b"a").unwrap();
+  /// l.insert(Ttl { version: 0, expires_at: 20 }, b"b", b"b").unwrap();
+  ///
+  /// let opts = ReadOptions::new(15).with_ttl(|t: &Ttl| Some(t.expires_at));
+  /// assert!(l.get_with_options(0, b"a", opts).is_none());
+  /// assert!(l.get_with_options(0, b"b", opts).is_some());
+  /// assert_eq!(l.iter_with_options(0, opts).count(), 1);
+  /// ```
+  pub fn get_with_options<'a, 'b: 'a>(
+    &'a self,
+    version: u64,
+    key: &'b [u8],
+    opts: ReadOptions<T>,
+  ) -> Option<EntryRef<'a, T>> {
+    self
+      .get(version, key)
+      .filter(|ent| !opts.is_expired(ent.trailer()))
+  }
+
+  /// Replaces the trailer of the entry with the key and the version of the trailer, and keeps
+  /// the value, e.g. to refresh the expiration time stored in the trailer.
+  ///
+  /// The trailer is stored next to the value, and `T` may be larger than an atomic word, so
+  /// it is not overwritten in place: the value is copied with the new trailer, and the value
+  /// pointer of the node is swapped by CAS, so the concurrent readers see either the old or
+  /// the new trailer. Returns the previous entry, `None` if the entry does not exist or is
+  /// removed.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use skl::{map::ReadOptions, SkipMap};
+  ///
+  /// #[derive(Debug, Clone, Copy)]
+  /// struct Ttl {
+  ///   version: u64,
+  ///   expires_at: u64,
+  /// }
+  ///
+  /// unsafe impl skl::Trailer for Ttl {
+  ///   fn version(&self) -> u64 {
+  ///     self.version
+  ///   }
+  /// }
+  ///
+  /// let l = SkipMap::<Ttl>::new().unwrap();
+  /// l.insert(Ttl { version: 0, expires_at: 10 }, b"a", b"a").unwrap();
+  ///
+  /// let old = l.touch(Ttl { version: 0, expires_at: 20 }, b"a").unwrap().unwrap();
+  /// assert_eq!(old.trailer().expires_at, 10);
+  ///
+  /// let opts = ReadOptions::new(15).with_ttl(|t: &Ttl| Some(t.expires_at));
+  /// assert_eq!(l.get_with_options(0, b"a", opts).unwrap().value(), b"a");
+  /// ```
+  pub fn touch<'a, 'b: 'a>(
+    &'a self,
+    trailer: T,
+    key: &'b [u8],
+  ) -> Result<Option<EntryRef<'a, T>>, Error> {
+    if self.read_only() {
+      return Err(Error::read_only());
+    }
+
+    if let Some(reason) = self.poison_reason() {
+      return Err(Error::Corrupted(reason));
+    }
+
+    unsafe {
+      let node_ptr = match self.find_near(trailer.version(), key, false, true) {
+        (Some(n), true) => n,
+        _ => return Ok(None),
+      };
+
+      // a removed entry is not revived
+      self
+        .swap_value(
+          node_ptr,
+          trailer,
+          Ordering::AcqRel,
+          Ordering::Acquire,
+          |old| old,
+        )
+        .map(|res| res.unwrap_or(None))
+    }
+  }
+
+  /// Like [`iter`](SkipMap::iter), but skips the expired entries, see [`ReadOptions`].
+  #[inline]
+  pub const fn iter_with_options(&self, version: u64, opts: ReadOptions<T>) -> ReadIter<'_, T, C> {
+    ReadIter {
+      iter: self.iter(version),
+      opts,
+    }
+  }
+
+  /// Like [`range`](SkipMap::range), but skips the expired entries, see [`ReadOptions`].
+  #[inline]
+  pub fn range_with_options<'a, Q, R>(
+    &'a self,
+    version: u64,
+    range: R,
+    opts: ReadOptions<T>,
+  ) -> ReadIter<'a, T, C, Q, R>
+  where
+    &'a [u8]: PartialOrd<Q>,
+    Q: ?Sized + PartialOrd<&'a [u8]>,
+    R: RangeBounds<Q> + 'a,
+  {
+    ReadIter {
+      iter: self.range(version, range),
+      opts,
+    }
+  }
+}
+
+/// An iterator over the live entries ordered by the expiration time,
+/// returned by [`SkipMap::iter_by_expiry`].
+pub struct ExpiryIter<'a, T> {
+  entries: IntoIter<(u64, EntryRef<'a, T>)>,
+}
+
+impl<'a, T> Iterator for ExpiryIter<'a, T> {
+  type Item = (u64, EntryRef<'a, T>);
+
+  #[inline]
+  fn next(&mut self) -> Option<Self::Item> {
+    self.entries.next()
+  }
+
+  #[inline]
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    self.entries.size_hint()
+  }
+}
+
+impl<'a, T> DoubleEndedIterator for ExpiryIter<'a, T> {
+  #[inline]
+  fn next_back(&mut self) -> Option<Self::Item> {
+    self.entries.next_back()
+  }
+}
+
+impl<'a, T> ExactSizeIterator for ExpiryIter<'a, T> {}
+
+impl<T: Trailer, C: Comparator> SkipMap<T, C> {
+  /// Returns an iterator over the latest version (less or equal to the given version) of the
+  /// entries which have an expiration time, ordered by the expiration time (the soonest
+  /// first) and then by key, so the eviction logic can remove the soonest-expiring entries
+  /// under memory pressure.
+  ///
+  /// `ttl` extracts the expiration time from the trailer, like
+  /// [`CompactionOptions::with_ttl`], the entries without an expiration time are not
+  /// yielded. The map does not keep the entries indexed by the expiration time, so this
+  /// method scans and sorts the entries once, which is O(n log n), and the iterator is a
+  /// snapshot which does not observe the later writes.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use skl::SkipMap;
+  ///
+  /// let l = SkipMap::<u64>::new().unwrap();
+  /// l.insert(30, b"a", b"a").unwrap();
+  /// l.insert(10, b"b", b"b").unwrap();
+  /// l.insert(20, b"c", b"c").unwrap();
+  /// l.insert(0, b"d", b"d").unwrap();
+  ///
+  /// // use the version as the expiration time, `0` means the entry never expires
+  /// let keys = l
+  ///   .iter_by_expiry(u64::MAX, |t| if *t == 0 { None } else { Some(*t) })
+  ///   .map(|(expires_at, ent)| (expires_at, ent.key().to_vec()))
+  ///   .collect::<Vec<_>>();
+  /// assert_eq!(keys, [(10, b"b".to_vec()), (20, b"c".to_vec()), (30, b"a".to_vec())]);
+  /// ```
+  pub fn iter_by_expiry(&self, version: u64, ttl: fn(&T) -> Option<u64>) -> ExpiryIter<'_, T> {
+    let mut entries = self
+      .iter(version)
+      .filter_map(|ent| ttl(ent.trailer()).map(|expires_at| (expires_at, ent)))
+      .collect::<Vec<_>>();
+    // stable, so the entries with the same expiration time are still in key order
+    entries.sort_by_key(|(expires_at, _)| *expires_at);
+    ExpiryIter {
+      entries: entries.into_iter(),
+    }
+  }
+}
@@ -0,0 +1,91 @@
+use super::*;
+
+/// The size of the key fingerprint appended after the tower (and the access stamp) of a node.
+const FINGERPRINT_SIZE: u32 = mem::size_of::<u32>() as u32;
+
+/// Returns the 32-bit FNV-1a hash of the key.
+#[inline]
+const fn fingerprint(key: &[u8]) -> u32 {
+  let mut h = 0x811c_9dc5u32;
+  let mut i = 0;
+  while i < key.len() {
+    h ^= key[i] as u32;
+    h = h.wrapping_mul(0x0100_0193);
+    i += 1;
+  }
+  h
+}
+
+impl<T, C> SkipMap<T, C> {
+  /// Returns the size of the key fingerprint of the nodes, `0` if not enabled.
+  #[inline]
+  const fn fingerprint_size(&self) -> u32 {
+    if self.opts.key_fingerprints() {
+      FINGERPRINT_SIZE
+    } else {
+      0
+    }
+  }
+
+  /// Returns the size of the optional fields appended after the tower of the nodes.
+  #[inline]
+  pub(super) const fn node_suffix_size(&self) -> u32 {
+    self.access_stamp_size() + self.fingerprint_size()
+  }
+
+  /// ## Safety
+  ///
+  /// - The node must be allocated by the ARENA with the fingerprint, and is not the head or
+  ///   the tail.
+  #[inline]
+  unsafe fn fingerprint_ptr(&self, nd: *const Node<T>, height: u32) -> *const u32 {
+    // the fingerprint is 4-byte aligned, since the node, the links and the stamp are.
+    nd.cast::<u8>()
+      .add(Node::<T>::SIZE + height as usize * Link::SIZE + self.access_stamp_size() as usize)
+      .cast::<u32>()
+  }
+
+  /// Stores the fingerprint of the key of a newly allocated node, the key must be written
+  /// before, and the node must not be published yet.
+  #[inline]
+  pub(super) fn init_fingerprint(&self, nd: *mut Node<T>) {
+    if self.opts.key_fingerprints() {
+      // Safety: the node is just allocated with the fingerprint, and its key is written.
+      unsafe {
+        let node = &*nd;
+        let fp = fingerprint(node.get_key(&self.arena));
+        (self.fingerprint_ptr(nd, node.height() as u32) as *mut u32).write(fp);
+      }
+    }
+  }
+}
+
+impl<T, C: Comparator> SkipMap<T, C> {
+  /// Returns `true` if the keys of the two nodes are equal.
+  ///
+  /// When the fingerprints are enabled, the keys are only compared if the fingerprints are
+  /// equal, which skips most of the comparisons of the different keys.
+  ///
+  /// ## Safety
+  ///
+  /// - The nodes must be allocated by the ARENA, and the keys must be the keys of the nodes.
+  #[inline]
+  pub(super) unsafe fn node_keys_eq(
+    &self,
+    a: NodePtr<T>,
+    a_key: &[u8],
+    b: NodePtr<T>,
+    b_key: &[u8],
+  ) -> bool {
+    let sentinel = |nd: NodePtr<T>| nd.ptr == self.head.ptr || nd.ptr == self.tail.ptr;
+    if self.opts.key_fingerprints() && !sentinel(a) && !sentinel(b) {
+      let fa = *self.fingerprint_ptr(a.ptr, a.as_ref().height() as u32);
+      let fb = *self.fingerprint_ptr(b.ptr, b.as_ref().height() as u32);
+      if fa != fb {
+        return false;
+      }
+    }
+
+    self.cmp.compare(a_key, b_key) == cmp::Ordering::Equal
+  }
+}
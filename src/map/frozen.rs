@@ -0,0 +1,186 @@
+use super::*;
+
+/// An immutable [`SkipMap`], returned by [`SkipMap::freeze`], e.g. a memtable rotated out of
+/// the write path and waiting to be flushed.
+///
+/// The handle only exposes the reads, and freezing also marks the map read-only, so the writes
+/// through the other clones of the map fail with [`Error::read_only`] from then on. As nothing
+/// writes to the map anymore, the reads never see a partially linked tower, and iterating a
+/// frozen map is stable.
+///
+/// # Example
+///
+/// ```rust
+/// use skl::SkipMap;
+///
+/// let l = SkipMap::new().unwrap();
+/// l.insert(0, b"a", b"a").unwrap();
+///
+/// let writer = l.clone();
+/// let frozen = l.freeze();
+/// assert!(writer.insert(0, b"b", b"b").is_err());
+///
+/// assert_eq!(frozen.get(0, b"a").unwrap().value(), b"a");
+/// assert_eq!(frozen.iter(0).count(), 1);
+/// ```
+#[derive(Debug)]
+pub struct FrozenSkipMap<T = u64, C = Ascend> {
+  map: SkipMap<T, C>,
+}
+
+impl<T, C: Clone> Clone for FrozenSkipMap<T, C> {
+  #[inline]
+  fn clone(&self) -> Self {
+    Self {
+      map: self.map.clone(),
+    }
+  }
+}
+
+impl<T, C> SkipMap<T, C> {
+  /// Marks the map and all of its clones read-only, and returns a handle which only exposes
+  /// the reads, see [`FrozenSkipMap`].
+  #[inline]
+  pub fn freeze(self) -> FrozenSkipMap<T, C> {
+    self.frozen.store(true, Ordering::Release);
+    FrozenSkipMap { map: self }
+  }
+
+  /// Returns `true` if the map is frozen by [`freeze`](SkipMap::freeze), the writes to a
+  /// frozen map fail with [`Error::read_only`].
+  #[inline]
+  pub fn is_frozen(&self) -> bool {
+    self.frozen.load(Ordering::Acquire)
+  }
+
+  /// Returns `true` if the writes must be rejected, because the ARENA is read-only or the map
+  /// is frozen.
+  #[inline]
+  pub(super) fn read_only(&self) -> bool {
+    self.arena.read_only() || self.is_frozen()
+  }
+}
+
+impl<T, C> FrozenSkipMap<T, C> {
+  /// Returns the number of entries in the map, see [`SkipMap::len`].
+  #[inline]
+  pub fn len(&self) -> usize {
+    self.map.len()
+  }
+
+  /// Returns `true` if the map contains no entries.
+  #[inline]
+  pub fn is_empty(&self) -> bool {
+    self.map.is_empty()
+  }
+
+  /// Returns the number of bytes allocated from the ARENA, see [`SkipMap::allocated`].
+  #[inline]
+  pub fn allocated(&self) -> usize {
+    self.map.allocated()
+  }
+
+  /// Returns the capacity of the ARENA.
+  #[inline]
+  pub const fn capacity(&self) -> usize {
+    self.map.capacity()
+  }
+
+  /// Returns the maximum version of all the entries in the map.
+  #[inline]
+  pub fn max_version(&self) -> u64 {
+    self.map.max_version()
+  }
+
+  /// Returns the minimum version of all the entries in the map.
+  #[inline]
+  pub fn min_version(&self) -> u64 {
+    self.map.min_version()
+  }
+
+  /// Returns the comparator of the map.
+  #[inline]
+  pub const fn comparator(&self) -> &C {
+    self.map.comparator()
+  }
+}
+
+impl<T: Trailer, C: Comparator> FrozenSkipMap<T, C> {
+  /// Returns true if the key exists in the map.
+  #[inline]
+  pub fn contains_key<'a, 'b: 'a>(&'a self, version: u64, key: &'b [u8]) -> bool {
+    self.map.contains_key(version, key)
+  }
+
+  /// Returns the first entry in the map.
+  #[inline]
+  pub fn first(&self, version: u64) -> Option<EntryRef<'_, T>> {
+    self.map.first(version)
+  }
+
+  /// Returns the last entry in the map.
+  #[inline]
+  pub fn last(&self, version: u64) -> Option<EntryRef<'_, T>> {
+    self.map.last(version)
+  }
+
+  /// Returns the value associated with the given key, if it exists.
+  #[inline]
+  pub fn get<'a, 'b: 'a>(&'a self, version: u64, key: &'b [u8]) -> Option<EntryRef<'a, T>> {
+    self.map.get(version, key)
+  }
+
+  /// Returns the highest entry whose key is below the given bound, see
+  /// [`SkipMap::upper_bound`].
+  #[inline]
+  pub fn upper_bound<'a, 'b: 'a>(
+    &'a self,
+    version: u64,
+    upper: Bound<&'b [u8]>,
+  ) -> Option<EntryRef<'a, T>> {
+    self.map.upper_bound(version, upper)
+  }
+
+  /// Returns the lowest entry whose key is above the given bound, see
+  /// [`SkipMap::lower_bound`].
+  #[inline]
+  pub fn lower_bound<'a, 'b: 'a>(
+    &'a self,
+    version: u64,
+    lower: Bound<&'b [u8]>,
+  ) -> Option<EntryRef<'a, T>> {
+    self.map.lower_bound(version, lower)
+  }
+
+  /// Returns an iterator over the latest version (less or equal to the given version) of all
+  /// the entries.
+  #[inline]
+  pub const fn iter(&self, version: u64) -> iterator::Iter<T, C> {
+    self.map.iter(version)
+  }
+
+  /// Returns an iterator over all versions (less or equal to the given version) of all the
+  /// entries.
+  #[inline]
+  pub const fn iter_all_versions(&self, version: u64) -> iterator::AllVersionsIter<T, C> {
+    self.map.iter_all_versions(version)
+  }
+
+  /// Returns an iterator over the latest version (less or equal to the given version) of the
+  /// entries within the range.
+  #[inline]
+  pub fn range<'a, Q, R>(&'a self, version: u64, range: R) -> iterator::Iter<'a, T, C, Q, R>
+  where
+    &'a [u8]: PartialOrd<Q>,
+    Q: ?Sized + PartialOrd<&'a [u8]>,
+    R: RangeBounds<Q> + 'a,
+  {
+    self.map.range(version, range)
+  }
+
+  /// Returns a read-only view of the map pinned to the version.
+  #[inline]
+  pub const fn snapshot(&self, version: u64) -> Snapshot<'_, T, C> {
+    self.map.snapshot_at(version)
+  }
+}
@@ -0,0 +1,119 @@
+// `std::backtrace` is newer than the MSRV, this module is only compiled with the debug-only
+// `track-guards` feature.
+#![allow(clippy::incompatible_msrv)]
+
+use core::sync::atomic::{AtomicU64, Ordering};
+use std::{backtrace::Backtrace, collections::BTreeMap, string::String, sync::Mutex, vec::Vec};
+
+use rarena_allocator::Arena;
+
+use super::SkipMap;
+
+/// The live guards of all the ARENAs, keyed by the address of the ARENA,
+/// each guard is keyed by its id, with the backtrace of where it was created.
+static REGISTRY: Mutex<BTreeMap<usize, BTreeMap<u64, Backtrace>>> = Mutex::new(BTreeMap::new());
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+#[inline]
+fn arena_key(arena: &Arena) -> usize {
+  // Safety: the data offset is always in bounds of the ARENA.
+  unsafe { arena.get_pointer(arena.data_offset()) as usize }
+}
+
+#[inline]
+fn registry() -> std::sync::MutexGuard<'static, BTreeMap<usize, BTreeMap<u64, Backtrace>>> {
+  // the registry is only used for debugging, keep going even if a thread panicked
+  REGISTRY.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Registers an owned entry which keeps the ARENA alive, and unregisters it on drop.
+#[derive(Debug)]
+pub(crate) struct GuardToken {
+  arena: usize,
+  id: u64,
+}
+
+impl GuardToken {
+  pub(crate) fn register(arena: &Arena) -> Self {
+    let arena = arena_key(arena);
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    registry()
+      .entry(arena)
+      .or_default()
+      .insert(id, Backtrace::force_capture());
+    Self { arena, id }
+  }
+}
+
+impl Clone for GuardToken {
+  fn clone(&self) -> Self {
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    registry()
+      .entry(self.arena)
+      .or_default()
+      .insert(id, Backtrace::force_capture());
+    Self {
+      arena: self.arena,
+      id,
+    }
+  }
+}
+
+impl Drop for GuardToken {
+  fn drop(&mut self) {
+    let mut registry = registry();
+    if let Some(guards) = registry.get_mut(&self.arena) {
+      guards.remove(&self.id);
+      if guards.is_empty() {
+        registry.remove(&self.arena);
+      }
+    }
+  }
+}
+
+/// Reports the guards which keep the ARENA alive, if the last [`SkipMap`] handle is being dropped.
+pub(super) fn report_on_drop(arena: &Arena) {
+  let registry = registry();
+  let guards = match registry.get(&arena_key(arena)) {
+    Some(guards) => guards,
+    None => return,
+  };
+
+  // the remaining references are all held by the guards
+  if arena.refs() != guards.len() + 1 {
+    return;
+  }
+
+  std::eprintln!(
+    "skl: the SkipMap is dropped, but {} guard(s) are keeping its ARENA alive:",
+    guards.len()
+  );
+  for (id, backtrace) in guards {
+    std::eprintln!("guard #{id} created at:\n{backtrace}");
+  }
+}
+
+impl<T, C> SkipMap<T, C> {
+  /// Returns how many owned entries ([`Entry`](super::Entry) and [`VersionedEntry`](super::VersionedEntry))
+  /// of this map are alive, which keep the ARENA alive after all the [`SkipMap`] handles are dropped.
+  ///
+  /// If the last [`SkipMap`] handle is dropped while some guards are alive, the backtraces of
+  /// where the guards were created are printed to stderr.
+  #[cfg_attr(docsrs, doc(cfg(feature = "track-guards")))]
+  pub fn live_guards(&self) -> usize {
+    registry()
+      .get(&arena_key(&self.arena))
+      .map_or(0, |guards| guards.len())
+  }
+
+  /// Returns the backtraces of where the live guards of this map were created,
+  /// see [`live_guards`](SkipMap::live_guards).
+  #[cfg_attr(docsrs, doc(cfg(feature = "track-guards")))]
+  pub fn live_guard_backtraces(&self) -> Vec<String> {
+    registry()
+      .get(&arena_key(&self.arena))
+      .map(|guards| guards.values().map(|bt| std::format!("{bt}")).collect())
+      .unwrap_or_default()
+  }
+}
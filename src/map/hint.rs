@@ -0,0 +1,106 @@
+use super::*;
+
+/// A cache of the splice of the previous insert, returned by [`SkipMap::hint`], for
+/// [`SkipMap::insert_with_hint`].
+///
+/// The splice is the previous and the next nodes around the inserted key at every level.
+/// The next insert revalidates it cheaply and starts the search from the lowest level which
+/// still brackets the key, instead of descending the tower from the head, so the sequential
+/// or almost monotonic keys, e.g. of the time-series workloads, skip most of the descent.
+///
+/// A hint only caches the splices of the map it is created from (and of its clones), it is
+/// ignored when it is used with another map.
+///
+/// # Example
+///
+/// ```rust
+/// use skl::SkipMap;
+///
+/// let l = SkipMap::new().unwrap();
+/// let mut hint = l.hint();
+/// for ts in 0..100u64 {
+///   l.insert_with_hint(0, &ts.to_be_bytes(), b"sample", &mut hint)
+///     .unwrap();
+/// }
+/// assert_eq!(l.len(), 100);
+/// ```
+pub struct Hint<'a, T> {
+  ins: Inserter<'a, T>,
+  head: NodePtr<T>,
+}
+
+impl<'a, T> core::fmt::Debug for Hint<'a, T> {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    f.debug_struct("Hint")
+      .field("height", &self.ins.height)
+      .finish()
+  }
+}
+
+impl<T: Trailer, C> SkipMap<T, C> {
+  /// Returns an empty [`Hint`] for [`insert_with_hint`](SkipMap::insert_with_hint).
+  #[inline]
+  pub fn hint(&self) -> Hint<'_, T> {
+    Hint {
+      ins: Inserter::default(),
+      head: self.head,
+    }
+  }
+}
+
+impl<T: Trailer, C: Comparator> SkipMap<T, C> {
+  /// Like [`insert`](SkipMap::insert), but the search starts from the splice cached in the
+  /// [`Hint`] by the previous insert, which is updated for the next one.
+  ///
+  /// See [`Hint`] for more details.
+  pub fn insert_with_hint<'a, 'b: 'a>(
+    &'a self,
+    trailer: T,
+    key: &'b [u8],
+    value: &'b [u8],
+    hint: &mut Hint<'_, T>,
+  ) -> Result<Option<EntryRef<'a, T>>, Error> {
+    if self.read_only() {
+      return Err(Error::read_only());
+    }
+
+    // The cached nodes must belong to the ARENA of this map. The hint borrows the map it is
+    // created from, so that ARENA cannot be reused by another map, but it does not borrow
+    // this one, so the splice of another map is never cached in the hint.
+    let mut fresh;
+    let ins = if hint.head.ptr == self.head.ptr {
+      &mut hint.ins
+    } else {
+      fresh = Inserter::default();
+      &mut fresh
+    };
+
+    let copy = |buf: &mut VacantBuffer| {
+      let _ = buf.write(value);
+      Ok(())
+    };
+
+    self
+      .update::<Infallible>(
+        trailer,
+        Key::Occupied(key),
+        value.len() as u32,
+        self.opts.value_alignment(),
+        copy,
+        Ordering::Relaxed,
+        Ordering::Relaxed,
+        ins,
+        true,
+      )
+      .map(|old| {
+        old.expect_left("insert must get InsertOk").and_then(|old| {
+          if old.is_removed() {
+            None
+          } else {
+            Some(EntryRef(old))
+          }
+        })
+      })
+      .map_err(|e| e.expect_right("must be map::Error"))
+  }
+}
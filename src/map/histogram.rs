@@ -0,0 +1,184 @@
+use super::*;
+
+/// The number of buckets of a [`Histogram`].
+///
+/// Bucket `0` counts the empty keys or values, bucket `i` counts the sizes in `2^(i - 1)..2^i`.
+pub const HISTOGRAM_BUCKETS: usize = 33;
+
+/// A histogram of sizes with power-of-two buckets, see [`HISTOGRAM_BUCKETS`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Histogram {
+  buckets: [u64; HISTOGRAM_BUCKETS],
+  sum: u64,
+}
+
+impl Histogram {
+  /// Returns the index of the bucket which counts the size.
+  #[inline]
+  pub const fn bucket_of(size: u32) -> usize {
+    (u32::BITS - size.leading_zeros()) as usize
+  }
+
+  /// Returns the range of the sizes counted by the bucket.
+  #[inline]
+  pub const fn bucket_range(idx: usize) -> core::ops::Range<u64> {
+    if idx == 0 {
+      0..1
+    } else {
+      (1 << (idx - 1))..(1 << idx)
+    }
+  }
+
+  /// Returns the counts of the buckets.
+  #[inline]
+  pub const fn buckets(&self) -> &[u64; HISTOGRAM_BUCKETS] {
+    &self.buckets
+  }
+
+  /// Returns how many sizes are recorded.
+  #[inline]
+  pub fn count(&self) -> u64 {
+    self.buckets.iter().sum()
+  }
+
+  /// Returns the sum of the recorded sizes.
+  #[inline]
+  pub const fn sum(&self) -> u64 {
+    self.sum
+  }
+
+  /// Returns the mean of the recorded sizes, `None` if nothing is recorded.
+  #[inline]
+  pub fn mean(&self) -> Option<u64> {
+    self.sum.checked_div(self.count())
+  }
+
+  /// Returns the exclusive upper bound of the bucket which contains the `percentile`-th
+  /// percentile (`0..=100`) of the recorded sizes, `None` if nothing is recorded.
+  pub fn percentile(&self, percentile: u8) -> Option<u64> {
+    let count = self.count();
+    if count == 0 {
+      return None;
+    }
+
+    let rank = ((count * percentile.min(100) as u64 + 99) / 100).max(1);
+    let mut seen = 0;
+    for (idx, n) in self.buckets.iter().enumerate() {
+      seen += n;
+      if seen >= rank {
+        return Some(Self::bucket_range(idx).end);
+      }
+    }
+    None
+  }
+}
+
+/// The distributions of the key and value sizes, returned by [`SkipMap::size_stats`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SizeStats {
+  /// The sizes of the keys of all the written entries, including the removed ones.
+  pub keys: Histogram,
+  /// The sizes of all the written values.
+  pub values: Histogram,
+}
+
+#[derive(Debug)]
+pub(super) struct AtomicHistogram {
+  buckets: [AtomicU64; HISTOGRAM_BUCKETS],
+  sum: AtomicU64,
+}
+
+impl AtomicHistogram {
+  #[allow(clippy::declare_interior_mutable_const)]
+  const ZERO: AtomicU64 = AtomicU64::new(0);
+
+  pub(super) const fn new() -> Self {
+    Self {
+      buckets: [Self::ZERO; HISTOGRAM_BUCKETS],
+      sum: AtomicU64::new(0),
+    }
+  }
+
+  #[inline]
+  pub(super) fn record(&self, size: u32) {
+    self.buckets[Histogram::bucket_of(size)].fetch_add(1, Ordering::Relaxed);
+    self.sum.fetch_add(size as u64, Ordering::Relaxed);
+  }
+
+  pub(super) fn load(&self) -> Histogram {
+    let mut buckets = [0; HISTOGRAM_BUCKETS];
+    for (dst, src) in buckets.iter_mut().zip(self.buckets.iter()) {
+      *dst = src.load(Ordering::Relaxed);
+    }
+    Histogram {
+      buckets,
+      sum: self.sum.load(Ordering::Relaxed),
+    }
+  }
+}
+
+/// The size histograms shared by all the clones of a [`SkipMap`].
+#[derive(Debug)]
+pub(super) struct SizeHistograms {
+  keys: AtomicHistogram,
+  values: AtomicHistogram,
+}
+
+impl SizeHistograms {
+  pub(super) const fn new() -> Self {
+    Self {
+      keys: AtomicHistogram::new(),
+      values: AtomicHistogram::new(),
+    }
+  }
+}
+
+impl<T, C> SkipMap<T, C> {
+  /// Records the sizes of a newly linked node.
+  #[inline]
+  pub(super) fn record_node_sizes(&self, nd: NodePtr<T>) {
+    if let Some(histograms) = &self.histograms {
+      // Safety: the node is allocated by the ARENA.
+      let node = unsafe { nd.as_ref() };
+      histograms.keys.record(node.key_size());
+      self.record_value_size(nd);
+    }
+  }
+
+  /// Records the size of the value of the node.
+  #[inline]
+  pub(super) fn record_value_size(&self, nd: NodePtr<T>) {
+    if let Some(histograms) = &self.histograms {
+      // Safety: the node is allocated by the ARENA.
+      let (_, len) = unsafe { nd.as_ref() }.value.load(Ordering::Acquire);
+      if len != REMOVE {
+        histograms.values.record(len);
+      }
+    }
+  }
+
+  /// Returns the distributions of the key and value sizes written through this map and its
+  /// clones, `None` if [`Options::with_size_histograms`] is not enabled.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use skl::{Options, SkipMap};
+  ///
+  /// let l = SkipMap::with_options(Options::new().with_size_histograms(true)).unwrap();
+  /// l.insert(0, b"k1", b"v").unwrap();
+  /// l.insert(0, b"k2", &[0; 100]).unwrap();
+  ///
+  /// let stats = l.size_stats().unwrap();
+  /// assert_eq!(stats.keys.count(), 2);
+  /// assert_eq!(stats.values.sum(), 101);
+  /// assert_eq!(stats.values.percentile(50), Some(2));
+  /// assert_eq!(stats.values.percentile(100), Some(128));
+  /// ```
+  pub fn size_stats(&self) -> Option<SizeStats> {
+    self.histograms.as_ref().map(|h| SizeStats {
+      keys: h.keys.load(),
+      values: h.values.load(),
+    })
+  }
+}
@@ -0,0 +1,53 @@
+use super::*;
+
+/// The size of the transparent huge pages of x86-64 and (with 4 KiB base pages) aarch64.
+#[cfg(all(feature = "std", target_os = "linux", not(miri)))]
+const HUGE_PAGE_SIZE: usize = 2 << 20;
+
+#[cfg(all(feature = "std", target_os = "linux", not(miri)))]
+const MADV_HUGEPAGE: std::os::raw::c_int = 14;
+
+#[cfg(all(feature = "std", target_os = "linux", not(miri)))]
+extern "C" {
+  fn madvise(
+    addr: *mut core::ffi::c_void,
+    len: usize,
+    advice: std::os::raw::c_int,
+  ) -> std::os::raw::c_int;
+}
+
+/// Advises the kernel to back the ARENA with transparent huge pages, see
+/// [`Options::with_huge_pages`].
+///
+/// Only the huge pages fully inside the ARENA are advised, returns `false` if there is none,
+/// if the kernel rejects the advice, or on the other platforms.
+#[cfg(all(feature = "std", target_os = "linux", not(miri)))]
+pub(super) fn advise_huge_pages(arena: &Arena) -> bool {
+  // Safety: the range is inside the memory of the ARENA, and the advice does not change the
+  // contents of the memory.
+  unsafe {
+    let start = arena.get_pointer(arena.data_offset()) as usize;
+    let end = start + (arena.capacity() - arena.data_offset());
+    let aligned_start = (start + HUGE_PAGE_SIZE - 1) & !(HUGE_PAGE_SIZE - 1);
+    let aligned_end = end & !(HUGE_PAGE_SIZE - 1);
+    if aligned_end <= aligned_start {
+      return false;
+    }
+
+    madvise(
+      aligned_start as *mut core::ffi::c_void,
+      aligned_end - aligned_start,
+      MADV_HUGEPAGE,
+    ) == 0
+  }
+}
+
+/// Advises the kernel to back the ARENA with transparent huge pages, see
+/// [`Options::with_huge_pages`].
+///
+/// Only the huge pages fully inside the ARENA are advised, returns `false` if there is none,
+/// if the kernel rejects the advice, or on the other platforms.
+#[cfg(not(all(feature = "std", target_os = "linux", not(miri))))]
+pub(super) fn advise_huge_pages(_arena: &Arena) -> bool {
+  false
+}
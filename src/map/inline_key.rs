@@ -0,0 +1,48 @@
+use super::*;
+
+impl<T, C> SkipMap<T, C> {
+  /// Returns `true` if a key of the size is stored inline, at the end of its node.
+  #[inline]
+  pub(super) const fn is_inline_key(&self, key_size: u32) -> bool {
+    let max = self.opts.inline_key_size() as u32;
+    max != 0 && key_size <= max
+  }
+
+  /// Returns the size the node allocates for a key of the size, `0` if the key is stored in a
+  /// separate allocation.
+  #[inline]
+  pub(super) const fn inline_key_size(&self, key_size: u32) -> u32 {
+    if self.is_inline_key(key_size) {
+      key_size
+    } else {
+      0
+    }
+  }
+
+  /// Returns the offset of the inline key of the node, which follows the tower and the
+  /// optional fields.
+  #[inline]
+  pub(super) const fn inline_key_offset(&self, node_offset: u32, height: u32) -> u32 {
+    node_offset + Node::<T>::SIZE as u32 + height * Link::SIZE as u32 + self.node_suffix_size()
+  }
+
+  /// Writes the inline key of a newly allocated node.
+  ///
+  /// Unlike [`fill_vacant_key`](SkipMap::fill_vacant_key), the key is not deallocated if `f`
+  /// fails, it is freed together with the node.
+  ///
+  /// ## Safety
+  ///
+  /// - The key must be allocated by [`inline_key_offset`](SkipMap::inline_key_offset) and
+  ///   [`inline_key_size`](SkipMap::inline_key_size).
+  #[inline]
+  pub(super) unsafe fn fill_inline_key<'a, E>(
+    &'a self,
+    size: u32,
+    offset: u32,
+    f: impl FnOnce(&mut VacantBuffer<'a>) -> Result<(), E>,
+  ) -> Result<(), E> {
+    let buf = self.arena.get_bytes_mut(offset as usize, size as usize);
+    f(&mut VacantBuffer::new(size as usize, offset, buf))
+  }
+}
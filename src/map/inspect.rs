@@ -0,0 +1,641 @@
+use std::vec::Vec;
+
+use super::*;
+
+/// A summary of the [`SkipMap`], returned by [`SkipMap::inspect`].
+///
+/// The [`Display`](core::fmt::Display) implementation prints a human-readable report,
+/// so corrupted files can be triaged without writing custom code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+  /// The version of the [`SkipMap`] format.
+  pub version: u16,
+  /// The magic version set by the application.
+  pub magic_version: u16,
+  /// The offset of the data section in the ARENA.
+  pub data_offset: usize,
+  /// The capacity of the ARENA.
+  pub capacity: usize,
+  /// The allocated bytes of the ARENA.
+  pub allocated: usize,
+  /// The remaining bytes of the ARENA.
+  pub remaining: usize,
+  /// The discarded bytes of the ARENA.
+  pub discarded: u32,
+  /// The height recorded in the header.
+  pub height: u8,
+  /// The number of entries recorded in the header.
+  pub len: usize,
+  /// The maximum version recorded in the header.
+  pub max_version: u64,
+  /// The minimum version recorded in the header.
+  pub min_version: u64,
+  /// The number of nodes linked at each level, index `0` is the bottom level.
+  pub levels: Vec<usize>,
+  /// The number of nodes which are not removed.
+  pub entries: usize,
+  /// The number of nodes which are marked as removed.
+  pub removed: usize,
+  /// The result of [`SkipMap::verify`].
+  pub verify: Result<(), VerifyError>,
+}
+
+impl core::fmt::Display for Report {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    writeln!(f, "version: {}", self.version)?;
+    writeln!(f, "magic version: {}", self.magic_version)?;
+    writeln!(f, "data offset: {}", self.data_offset)?;
+    writeln!(
+      f,
+      "capacity: {} (allocated: {}, remaining: {}, discarded: {})",
+      self.capacity, self.allocated, self.remaining, self.discarded
+    )?;
+    writeln!(f, "height: {}", self.height)?;
+    writeln!(f, "len: {}", self.len)?;
+    writeln!(f, "versions: {}..={}", self.min_version, self.max_version)?;
+    writeln!(
+      f,
+      "nodes: {} (entries: {}, removed: {})",
+      self.entries + self.removed,
+      self.entries,
+      self.removed
+    )?;
+    for (level, nodes) in self.levels.iter().enumerate().rev() {
+      writeln!(f, "level {level}: {nodes} nodes")?;
+    }
+    match &self.verify {
+      Ok(()) => write!(f, "verify: ok"),
+      Err(e) => write!(f, "verify: {e}"),
+    }
+  }
+}
+
+/// An error returned by [`SkipMap::verify`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerifyError {
+  /// The node at `offset` linked at `level`, or its tower, key or value, is out of the bounds
+  /// of the ARENA.
+  OutOfBounds {
+    /// The level of the link.
+    level: usize,
+    /// The offset of the node.
+    offset: u32,
+  },
+  /// The node at `offset` linked at `level` has an invalid height.
+  InvalidHeight {
+    /// The level of the link.
+    level: usize,
+    /// The offset of the node.
+    offset: u32,
+    /// The height of the node.
+    height: u8,
+  },
+  /// The node at `offset` linked at `level` is not ordered after its predecessor.
+  Unordered {
+    /// The level of the link.
+    level: usize,
+    /// The offset of the node.
+    offset: u32,
+  },
+  /// The previous link of the node at `offset` does not point to its predecessor at the bottom level.
+  BrokenPrevLink {
+    /// The offset of the node.
+    offset: u32,
+  },
+  /// The links at `level` do not end at the tail node.
+  Unterminated {
+    /// The level of the link.
+    level: usize,
+  },
+  /// The node at `offset` linked at `level` is not linked at the level below, so the upper
+  /// level is not a subset of the lower one.
+  Unlinked {
+    /// The level of the link.
+    level: usize,
+    /// The offset of the node.
+    offset: u32,
+  },
+}
+
+impl core::fmt::Display for VerifyError {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    match self {
+      Self::OutOfBounds { level, offset } => {
+        write!(
+          f,
+          "node at offset {offset} on level {level} is out of bounds"
+        )
+      }
+      Self::InvalidHeight {
+        level,
+        offset,
+        height,
+      } => write!(
+        f,
+        "node at offset {offset} on level {level} has invalid height {height}"
+      ),
+      Self::Unordered { level, offset } => write!(
+        f,
+        "node at offset {offset} on level {level} is not ordered after its predecessor"
+      ),
+      Self::BrokenPrevLink { offset } => write!(
+        f,
+        "the previous link of node at offset {offset} does not point to its predecessor"
+      ),
+      Self::Unterminated { level } => write!(f, "level {level} does not end at the tail node"),
+      Self::Unlinked { level, offset } => write!(
+        f,
+        "node at offset {offset} on level {level} is not linked on the level below"
+      ),
+    }
+  }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for VerifyError {}
+
+impl VerifyError {
+  /// Returns the poison reason of this error.
+  #[inline]
+  pub const fn poison_reason(&self) -> PoisonReason {
+    match self {
+      Self::OutOfBounds { .. } => PoisonReason::OutOfBounds,
+      Self::InvalidHeight { .. } => PoisonReason::InvalidHeight,
+      Self::Unordered { .. } => PoisonReason::Unordered,
+      Self::BrokenPrevLink { .. } => PoisonReason::BrokenPrevLink,
+      Self::Unterminated { .. } => PoisonReason::Unterminated,
+      Self::Unlinked { .. } => PoisonReason::Unlinked,
+    }
+  }
+}
+
+/// The reason why a [`SkipMap`] is poisoned, see [`SkipMap::poison`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum PoisonReason {
+  /// [`SkipMap::verify`] found a node out of the bounds of the ARENA.
+  OutOfBounds = 1,
+  /// [`SkipMap::verify`] found a node with an invalid height.
+  InvalidHeight = 2,
+  /// [`SkipMap::verify`] found an unordered node.
+  Unordered = 3,
+  /// [`SkipMap::verify`] found a broken previous link.
+  BrokenPrevLink = 4,
+  /// [`SkipMap::verify`] found a level which does not end at the tail node.
+  Unterminated = 5,
+  /// The checksum validation of the application failed.
+  Checksum = 6,
+  /// The application poisoned the map for other reasons.
+  Other = 7,
+  /// [`SkipMap::verify`] found a node which is not linked on the level below.
+  Unlinked = 8,
+}
+
+impl PoisonReason {
+  #[inline]
+  const fn from_u8(v: u8) -> Option<Self> {
+    Some(match v {
+      1 => Self::OutOfBounds,
+      2 => Self::InvalidHeight,
+      3 => Self::Unordered,
+      4 => Self::BrokenPrevLink,
+      5 => Self::Unterminated,
+      6 => Self::Checksum,
+      7 => Self::Other,
+      8 => Self::Unlinked,
+      _ => return None,
+    })
+  }
+}
+
+impl core::fmt::Display for PoisonReason {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    match self {
+      Self::OutOfBounds => write!(f, "node out of bounds"),
+      Self::InvalidHeight => write!(f, "invalid node height"),
+      Self::Unordered => write!(f, "unordered nodes"),
+      Self::BrokenPrevLink => write!(f, "broken previous link"),
+      Self::Unterminated => write!(f, "unterminated level"),
+      Self::Checksum => write!(f, "checksum mismatch"),
+      Self::Other => write!(f, "poisoned by the application"),
+      Self::Unlinked => write!(f, "node missing from the lower level"),
+    }
+  }
+}
+
+impl<T, C> SkipMap<T, C> {
+  /// Marks the map (and all of its clones) as poisoned, so all subsequent write operations return
+  /// [`Error::Corrupted`] rather than continuing to modify possibly corrupted data, and the
+  /// reads ([`get`](SkipMap::get), the bounds and the iterators) return `None` rather than
+  /// walking the possibly corrupted links, use [`check_poison`](SkipMap::check_poison) to tell
+  /// a poisoned map from a missing entry.
+  ///
+  /// Only the first reason is kept. The reason is also stored in the meta unless the ARENA is
+  /// read-only, so a file backed map is still poisoned when it is reopened, until
+  /// [`rebuild_index`](SkipMap::rebuild_index) clears it.
+  #[inline]
+  pub fn poison(&self, reason: PoisonReason) {
+    if self
+      .poison
+      .compare_exchange(0, reason as u8, Ordering::AcqRel, Ordering::Acquire)
+      .is_ok()
+      && !self.arena.read_only()
+    {
+      self.meta().set_poison_reason(reason as u8);
+    }
+  }
+
+  /// Returns `true` if the map is poisoned.
+  #[inline]
+  pub fn is_poisoned(&self) -> bool {
+    self.poison.load(Ordering::Acquire) != 0
+  }
+
+  /// Returns the reason why the map is poisoned, `None` if the map is not poisoned.
+  #[inline]
+  pub fn poison_reason(&self) -> Option<PoisonReason> {
+    PoisonReason::from_u8(self.poison.load(Ordering::Acquire))
+  }
+
+  /// Poisons the map, and returns the [`Error::Corrupted`] with the first reason.
+  #[inline]
+  pub(crate) fn corrupted(&self, reason: PoisonReason) -> Error {
+    self.poison(reason);
+    Error::Corrupted(self.poison_reason().unwrap_or(reason))
+  }
+
+  /// Returns [`Error::Corrupted`] if the map is poisoned, useful to tell a poisoned map from
+  /// the `None` returned by the reads.
+  #[inline]
+  pub fn check_poison(&self) -> Result<(), Error> {
+    match self.poison_reason() {
+      Some(reason) => Err(Error::Corrupted(reason)),
+      None => Ok(()),
+    }
+  }
+}
+
+impl<T: Trailer, C: Comparator> SkipMap<T, C> {
+  /// Returns a summary of the header, the level structure and the entry counts of the map,
+  /// and runs [`verify`](SkipMap::verify).
+  ///
+  /// The map should not be modified concurrently.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use skl::SkipMap;
+  ///
+  /// let l = SkipMap::new().unwrap();
+  /// l.insert(0, b"alice", b"a").unwrap();
+  ///
+  /// let report = l.inspect();
+  /// assert_eq!(report.entries, 1);
+  /// assert!(report.verify.is_ok());
+  /// println!("{report}");
+  /// ```
+  pub fn inspect(&self) -> Report {
+    let verify = self.verify();
+    let mut levels = Vec::new();
+    let mut entries = 0;
+    let mut removed = 0;
+
+    if verify.is_ok() {
+      let tail = self.tail.offset;
+      let max_height: u8 = self.opts.max_height().into();
+      // Safety: the links are verified.
+      unsafe {
+        for level in 0..max_height as usize {
+          let mut nodes = 0;
+          let mut nd = self.get_next(self.head, level);
+          while nd.offset != tail {
+            if level == 0 {
+              if nd.as_ref().get_value(&self.arena).is_some() {
+                entries += 1;
+              } else {
+                removed += 1;
+              }
+            }
+            nodes += 1;
+            nd = self.get_next(nd, level);
+          }
+
+          if nodes == 0 {
+            break;
+          }
+          levels.push(nodes);
+        }
+      }
+    }
+
+    Report {
+      version: self.version(),
+      magic_version: self.magic_version(),
+      data_offset: self.data_offset(),
+      capacity: self.capacity(),
+      allocated: self.allocated(),
+      remaining: self.remaining(),
+      discarded: self.discarded(),
+      height: self.height(),
+      len: self.len(),
+      max_version: self.max_version(),
+      min_version: self.min_version(),
+      levels,
+      entries,
+      removed,
+      verify,
+    }
+  }
+
+  /// Verifies the structure of the map: every level is ordered by the comparator
+  /// (and by version descending for the same key), ends at the tail node, only links
+  /// the nodes which are tall enough and are linked on the level below, and the previous links
+  /// of the bottom level are consistent. The towers, keys and values of the nodes must be in
+  /// the bounds of the ARENA.
+  ///
+  /// Every offset is checked before it is dereferenced, so the map can be verified after it is
+  /// loaded from an untrusted file, or after each step of a fuzzing run.
+  ///
+  /// The map should not be modified concurrently.
+  ///
+  /// If [`Options::poison_on_corruption`] is set, the map will be [`poison`](SkipMap::poison)ed when
+  /// an inconsistency is found.
+  pub fn verify(&self) -> Result<(), VerifyError> {
+    let res = self.verify_in();
+    if let Err(e) = &res {
+      if self.opts.poison_on_corruption() {
+        self.poison(e.poison_reason());
+      }
+    }
+    res
+  }
+
+  /// Re-links all the nodes of the map from scratch, a recovery path for the persisted
+  /// ARENAs whose links are broken, e.g. after a crash in the middle of a link update, instead
+  /// of discarding the memtable. Returns the number of the re-linked nodes.
+  ///
+  /// None of the links is trusted: the allocated part of the ARENA is scanned by offset for
+  /// the node records, every candidate is checked like [`verify`](SkipMap::verify) does (the
+  /// height, and the tower, key and value in the bounds), and the key and value of an accepted
+  /// node are skipped by the scan. The nodes are then sorted by the comparator (and by version
+  /// descending for the same key), only the node at the lowest offset is kept for the same key
+  /// and version, all the levels and the previous links are rebuilt from them, the header
+  /// (len, height and versions) is recomputed, and the poison is cleared.
+  ///
+  /// The scan also recovers the records which were written but never linked, e.g. a node whose
+  /// insert failed in the value builder, or whose memory was discarded but not reused.
+  ///
+  /// The map must not be accessed concurrently while it is rebuilt, so it must have no other
+  /// clones and no [`allocator`](SkipMap::allocator) handles, otherwise [`Error::Shared`] is
+  /// returned and the map is left untouched.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use skl::{Error, SkipMap};
+  ///
+  /// let mut l = SkipMap::new().unwrap();
+  /// for i in 0..10u8 {
+  ///   l.insert(0, &[i], &[i]).unwrap();
+  /// }
+  ///
+  /// let clone = l.clone();
+  /// assert_eq!(l.rebuild_index(), Err(Error::Shared(2)));
+  /// drop(clone);
+  ///
+  /// assert_eq!(l.rebuild_index().unwrap(), 10);
+  /// assert!(l.verify().is_ok());
+  /// assert_eq!(l.get(0, &[5]).unwrap().value(), &[5]);
+  /// ```
+  pub fn rebuild_index(&mut self) -> Result<usize, Error> {
+    let refs = self.arena.refs();
+    if refs != 1 {
+      return Err(Error::Shared(refs));
+    }
+    if self.read_only() {
+      return Err(Error::read_only());
+    }
+
+    let max_height: u8 = self.opts.max_height().into();
+    let tail = self.tail.offset;
+    let node_size = mem::size_of::<Node<T>>() as u64;
+    let node_align = mem::align_of::<Node<T>>() as u64;
+    let align_up = |offset: u64| (offset + node_align - 1) / node_align * node_align;
+    let data_offset = self.arena.data_offset() as u64;
+    let allocated = self.allocated() as u64;
+    let mut nodes: Vec<NodePtr<T>> = Vec::new();
+
+    // Safety: the offsets are checked before being dereferenced.
+    unsafe {
+      // the regions owned by the accepted nodes, ordered by the start, which are not scanned
+      let mut claimed = std::collections::BinaryHeap::new();
+      for nd in [self.head, self.tail] {
+        let (trailer_offset, _) = nd.as_ref().value.load(Ordering::Acquire);
+        let start = trailer_offset as u64;
+        claimed.push(cmp::Reverse((start, start + mem::size_of::<T>() as u64)));
+      }
+
+      let mut offset = align_up(tail as u64 + Node::<T>::size(max_height) as u64);
+      while offset + node_size <= allocated {
+        match claimed.peek() {
+          Some(&cmp::Reverse((_, end))) if end <= offset => {
+            claimed.pop();
+            continue;
+          }
+          Some(&cmp::Reverse((start, end))) if start <= offset => {
+            offset = align_up(end);
+            continue;
+          }
+          _ => {}
+        }
+
+        let nd = NodePtr::new(self.arena.get_pointer(offset as usize) as _, offset as u32);
+        let node = nd.as_ref();
+        let height = node.height();
+        let (trailer_offset, value_size) = node.value.load(Ordering::Acquire);
+        let value_size = if value_size == REMOVE { 0 } else { value_size };
+        let tower_end = offset + Node::<T>::size(height) as u64 + self.node_suffix_size() as u64;
+        let key_start = node.key_offset as u64;
+        let key_end = key_start + node.key_size() as u64;
+        let trailer_start = trailer_offset as u64;
+        let value_end = trailer_start + mem::size_of::<T>() as u64 + value_size as u64;
+        // the key is either shared with an earlier node, inline or allocated after the node,
+        // and the trailer is always allocated after the node.
+        if height == 0
+          || height > max_height
+          || tower_end > allocated
+          || key_start < data_offset
+          || (key_start < tower_end && key_end > offset)
+          || key_end > allocated
+          || trailer_start < tower_end
+          || value_end > allocated
+        {
+          offset += node_align;
+          continue;
+        }
+
+        let node_end = if key_start == tower_end {
+          key_end
+        } else {
+          if key_start > offset {
+            claimed.push(cmp::Reverse((key_start, key_end)));
+          }
+          tower_end
+        };
+        claimed.push(cmp::Reverse((trailer_start, value_end)));
+        nodes.push(nd);
+        offset = align_up(node_end);
+      }
+
+      nodes.sort_by(|a, b| {
+        let (a, b) = (a.as_ref(), b.as_ref());
+        self
+          .cmp
+          .compare(a.get_key(&self.arena), b.get_key(&self.arena))
+          .then_with(|| {
+            b.get_trailer(&self.arena)
+              .version()
+              .cmp(&a.get_trailer(&self.arena).version())
+          })
+      });
+      nodes.dedup_by(|a, b| {
+        let (a, b) = (a.as_ref(), b.as_ref());
+        self
+          .cmp
+          .compare(a.get_key(&self.arena), b.get_key(&self.arena))
+          == cmp::Ordering::Equal
+          && a.get_trailer(&self.arena).version() == b.get_trailer(&self.arena).version()
+      });
+
+      for level in 0..max_height as usize {
+        let mut prev = self.head;
+        let mut prev_prev = 0;
+        for nd in nodes
+          .iter()
+          .filter(|nd| nd.as_ref().height() as usize > level)
+        {
+          prev.write_tower(&self.arena, level, prev_prev, nd.offset);
+          prev_prev = prev.offset;
+          prev = *nd;
+        }
+        prev.write_tower(&self.arena, level, prev_prev, tail);
+        self.tail.write_tower(&self.arena, level, prev.offset, 0);
+      }
+
+      let meta = self.meta();
+      let mut height = 1;
+      let mut versions = None;
+      for nd in &nodes {
+        let node = nd.as_ref();
+        height = height.max(node.height());
+        let version = node.get_trailer(&self.arena).version();
+        let (min, max) = versions.unwrap_or((version, version));
+        versions = Some((min.min(version), max.max(version)));
+      }
+      let (min_version, max_version) = versions.unwrap_or((0, 0));
+      meta.len.store(nodes.len() as u32, Ordering::Release);
+      meta.height.store(height, Ordering::Release);
+      meta.min_version.store(min_version, Ordering::Release);
+      meta.max_version.store(max_version, Ordering::Release);
+    }
+
+    self.meta().set_poison_reason(0);
+    self.poison.store(0, Ordering::Release);
+    Ok(nodes.len())
+  }
+
+  fn verify_in(&self) -> Result<(), VerifyError> {
+    let max_height: u8 = self.opts.max_height().into();
+    let tail = self.tail.offset;
+    let node_size = mem::size_of::<Node<T>>();
+
+    for level in 0..max_height as usize {
+      // every linked node is visited at most once on a well-formed level
+      let mut budget = self.capacity() / node_size + 1;
+      let mut prev = self.head;
+      // the position on the level below, which is already verified
+      let mut lower = self.head;
+      // Safety: the offsets are checked before being dereferenced.
+      unsafe {
+        loop {
+          let offset = prev.next_offset(&self.arena, level);
+          if offset == tail {
+            break;
+          }
+
+          if budget == 0 {
+            return Err(VerifyError::Unterminated { level });
+          }
+          budget -= 1;
+
+          if (offset as usize) < self.arena.data_offset()
+            || offset as usize + node_size > self.capacity()
+          {
+            return Err(VerifyError::OutOfBounds { level, offset });
+          }
+
+          let nd = NodePtr::new(self.arena.get_pointer(offset as usize) as _, offset);
+          let node = nd.as_ref();
+          let height = node.height();
+          if height == 0 || height > max_height || (height as usize) <= level {
+            return Err(VerifyError::InvalidHeight {
+              level,
+              offset,
+              height,
+            });
+          }
+
+          // All the offsets and sizes read from the ARENA are untrusted, check the regions
+          // of the tower, key, trailer and value before they are read.
+          let (trailer_offset, value_size) = node.value.load(Ordering::Acquire);
+          let value_size = if value_size == REMOVE { 0 } else { value_size };
+          let tower_end = offset as u64 + Node::<T>::size(height) as u64;
+          let key_end = node.key_offset as u64 + node.key_size() as u64;
+          let value_end = trailer_offset as u64 + mem::size_of::<T>() as u64 + value_size as u64;
+          let cap = self.capacity() as u64;
+          if tower_end > cap || key_end > cap || value_end > cap {
+            return Err(VerifyError::OutOfBounds { level, offset });
+          }
+
+          if prev.offset != self.head.offset {
+            let prev_node = prev.as_ref();
+            let ord = self
+              .cmp
+              .compare(prev_node.get_key(&self.arena), node.get_key(&self.arena))
+              .then_with(|| {
+                node
+                  .get_trailer(&self.arena)
+                  .version()
+                  .cmp(&prev_node.get_trailer(&self.arena).version())
+              });
+            if ord != cmp::Ordering::Less {
+              return Err(VerifyError::Unordered { level, offset });
+            }
+          }
+
+          if level == 0 && nd.prev_offset(&self.arena, 0) != prev.offset {
+            return Err(VerifyError::BrokenPrevLink { offset });
+          }
+
+          // Both levels are ordered, so the node is found on the level below by moving
+          // forward from the previous node.
+          while level > 0 && lower.offset != offset {
+            let next = lower.next_offset(&self.arena, level - 1);
+            if next == tail {
+              return Err(VerifyError::Unlinked { level, offset });
+            }
+            lower = NodePtr::new(self.arena.get_pointer(next as usize) as _, next);
+          }
+
+          prev = nd;
+        }
+
+        if level == 0 && self.tail.prev_offset(&self.arena, 0) != prev.offset {
+          return Err(VerifyError::BrokenPrevLink { offset: tail });
+        }
+      }
+    }
+
+    Ok(())
+  }
+}
@@ -7,3 +7,18 @@ pub use all_versions::*;
 
 mod iter;
 pub use iter::*;
+
+mod cursor;
+pub use cursor::*;
+
+mod prefix;
+pub use prefix::*;
+
+mod into_iter;
+pub use into_iter::*;
+
+#[cfg(feature = "async")]
+mod stream;
+#[cfg(feature = "async")]
+#[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+pub use stream::*;
@@ -2,6 +2,13 @@ use super::*;
 
 /// An iterator over the skipmap. The current state of the iterator can be cloned by
 /// simply value copying the struct.
+///
+/// The iterator has two ends, [`next`](Iterator::next) moves the front forward from the start
+/// of the range, and [`next_back`](DoubleEndedIterator::next_back) moves the back backward
+/// from its end, and the iteration stops once the ends meet, so every entry is yielded once,
+/// from either end. A seek positions both ends at the found entry, from where `next` and
+/// `next_back` move them apart, like a cursor. Once an end is exhausted, it keeps returning
+/// `None` until the iterator is seeked again.
 pub struct AllVersionsIter<'a, T, C, Q: ?Sized = &'static [u8], R = core::ops::RangeFull> {
   pub(super) map: &'a SkipMap<T, C>,
   pub(super) nd: NodePtr<T>,
@@ -9,6 +16,11 @@ pub struct AllVersionsIter<'a, T, C, Q: ?Sized = &'static [u8], R = core::ops::R
   pub(super) range: R,
   pub(super) all_versions: bool,
   pub(super) last: Option<VersionedEntryRef<'a, T>>,
+  /// The position of the back end, `nd` and `last` are the front end.
+  pub(super) back_nd: NodePtr<T>,
+  pub(super) back_last: Option<VersionedEntryRef<'a, T>>,
+  /// Whether the ends are positioned by a seek, they move apart from there and never meet.
+  pub(super) seeked: bool,
   pub(super) _phantom: core::marker::PhantomData<Q>,
 }
 
@@ -20,6 +32,9 @@ impl<'a, R: Clone, Q: Clone, T: Clone, C> Clone for AllVersionsIter<'a, T, C, Q,
       version: self.version,
       range: self.range.clone(),
       last: self.last.clone(),
+      back_nd: self.back_nd,
+      back_last: self.back_last.clone(),
+      seeked: self.seeked,
       all_versions: self.all_versions,
       _phantom: core::marker::PhantomData,
     }
@@ -40,6 +55,9 @@ where
       version,
       range: RangeFull,
       last: None,
+      back_nd: map.head,
+      back_last: None,
+      seeked: false,
       all_versions,
       _phantom: core::marker::PhantomData,
     }
@@ -59,6 +77,9 @@ where
       version,
       range: r,
       last: None,
+      back_nd: map.head,
+      back_last: None,
+      seeked: false,
       all_versions,
       _phantom: core::marker::PhantomData,
     }
@@ -90,71 +111,140 @@ where
   /// Moves the iterator to the highest element whose key is below the given bound.
   /// If no such element is found then `None` is returned.
   pub fn seek_upper_bound(&mut self, upper: Bound<&[u8]>) -> Option<VersionedEntryRef<'a, T>> {
-    match upper {
-      Bound::Included(key) => self.seek_le(key).map(|n| {
-        let ent = VersionedEntryRef::from_node(n, &self.map.arena);
-        self.last = Some(ent);
-        ent
-      }),
-      Bound::Excluded(key) => self.seek_lt(key).map(|n| {
-        let ent = VersionedEntryRef::from_node(n, &self.map.arena);
-        self.last = Some(ent);
-        ent
-      }),
+    #[cfg(feature = "perf-stats")]
+    let _timer = self.map.timer(crate::map::latency::Op::Seek);
+
+    if self.map.is_poisoned() {
+      return None;
+    }
+
+    let ent = match upper {
+      Bound::Included(key) => self
+        .seek_le(key)
+        .map(|n| VersionedEntryRef::from_node(n, &self.map.arena)),
+      Bound::Excluded(key) => self
+        .seek_lt(key)
+        .map(|n| VersionedEntryRef::from_node(n, &self.map.arena)),
       Bound::Unbounded => self.last(),
+    };
+    // the walk poisons the map on a corrupted link of an untrusted ARENA
+    if self.map.is_poisoned() {
+      return None;
     }
+    self.position_at(ent)
   }
 
   /// Moves the iterator to the lowest element whose key is above the given bound.
   /// If no such element is found then `None` is returned.
   pub fn seek_lower_bound(&mut self, lower: Bound<&[u8]>) -> Option<VersionedEntryRef<'a, T>> {
-    match lower {
-      Bound::Included(key) => self.seek_ge(key).map(|n| {
-        let ent = VersionedEntryRef::from_node(n, &self.map.arena);
-        self.last = Some(ent);
-        ent
-      }),
-      Bound::Excluded(key) => self.seek_gt(key).map(|n| {
-        let ent = VersionedEntryRef::from_node(n, &self.map.arena);
-        self.last = Some(ent);
-        ent
-      }),
+    #[cfg(feature = "perf-stats")]
+    let _timer = self.map.timer(crate::map::latency::Op::Seek);
+
+    if self.map.is_poisoned() {
+      return None;
+    }
+
+    let ent = match lower {
+      Bound::Included(key) => self
+        .seek_ge(key)
+        .map(|n| VersionedEntryRef::from_node(n, &self.map.arena)),
+      Bound::Excluded(key) => self
+        .seek_gt(key)
+        .map(|n| VersionedEntryRef::from_node(n, &self.map.arena)),
       Bound::Unbounded => self.first(),
+    };
+    // the walk poisons the map on a corrupted link of an untrusted ARENA
+    if self.map.is_poisoned() {
+      return None;
     }
+    self.position_at(ent)
+  }
+
+  /// Positions both ends at the entry found by a seek, or exhausts them if there is none.
+  fn position_at(
+    &mut self,
+    ent: Option<VersionedEntryRef<'a, T>>,
+  ) -> Option<VersionedEntryRef<'a, T>> {
+    match ent {
+      Some(ent) => {
+        self.last = Some(ent);
+        self.back_nd = self.nd;
+        self.back_last = Some(ent);
+      }
+      None => {
+        self.nd = NodePtr::NULL;
+        self.back_nd = NodePtr::NULL;
+      }
+    }
+    self.seeked = true;
+    ent
   }
 
   /// Advances to the next position. Returns the key and value if the
   /// iterator is pointing at a valid entry, and `None` otherwise.
+  ///
+  /// The null node is the exhausted position, which is never moved from.
   fn next_in(&mut self) -> Option<VersionedEntryRef<T>> {
+    // the links of a poisoned map may be corrupted, see `SkipMap::poison`
+    if self.map.is_poisoned() {
+      return None;
+    }
+
+    if self.nd.ptr == self.map.head.ptr {
+      self.seek_start();
+    }
+
     loop {
       unsafe {
         self.nd = self.map.get_next(self.nd, 0);
 
         if self.nd.is_null() || self.nd.ptr == self.map.tail.ptr {
+          self.nd = NodePtr::NULL;
           return None;
         }
 
         let node = self.nd.as_ref();
+        let nk = node.get_key(&self.map.arena);
+        if self.past_range(nk) {
+          self.nd = NodePtr::NULL;
+          return None;
+        }
+
         let (trailer, value) = node.get_value_and_trailer(&self.map.arena);
         if trailer.version() > self.version {
           continue;
         }
 
+        let (trailer, value) = if self.all_versions {
+          (trailer, value)
+        } else {
+          // the versions of a key are in descending order, walks back to the newest version
+          // which is visible.
+          self.seek_newest_visible(nk);
+          self.nd.as_ref().get_value_and_trailer(&self.map.arena)
+        };
+
         if !self.all_versions && value.is_none() {
           continue;
         }
 
-        let nk = node.get_key(&self.map.arena);
-
         if !self.all_versions {
           if let Some(last) = self.last {
-            if self.map.cmp.compare(last.key, nk) == cmp::Ordering::Equal {
+            if self.map.node_keys_eq(last.ptr, last.key, self.nd, nk) {
               continue;
             }
           }
         }
 
         if self.map.cmp.contains(&self.range, nk) {
+          // the entry has been yielded by the other end, the ends have met.
+          if let (false, Some(other)) = (self.seeked, self.back_last) {
+            if !self.precedes(nk, trailer.version(), other.key, other.version()) {
+              self.nd = NodePtr::NULL;
+              return None;
+            }
+          }
+
           let ent = VersionedEntryRef {
             arena: &self.map.arena,
             key: nk,
@@ -169,38 +259,95 @@ where
     }
   }
 
+  /// Moves the iterator back over the older versions of the key of the current node, to the
+  /// newest version less or equal to the version of the iterator.
+  ///
+  /// ## Safety
+  ///
+  /// - The current node must be linked, and `nk` must be its key.
+  unsafe fn seek_newest_visible(&mut self, nk: &[u8]) {
+    loop {
+      let prev = self.map.get_prev(self.nd, 0);
+      if prev.is_null() || prev.ptr == self.map.head.ptr {
+        return;
+      }
+
+      let node = prev.as_ref();
+      if node.get_trailer(&self.map.arena).version() > self.version
+        || !self
+          .map
+          .node_keys_eq(self.nd, nk, prev, node.get_key(&self.map.arena))
+      {
+        return;
+      }
+      self.nd = prev;
+    }
+  }
+
   /// Advances to the prev position. Returns the key and value if the
   /// iterator is pointing at a valid entry, and `None` otherwise.
   fn prev(&mut self) -> Option<VersionedEntryRef<T>> {
+    if self.map.is_poisoned() {
+      return None;
+    }
+
+    if self.nd.ptr == self.map.head.ptr {
+      self.seek_end();
+    }
+
     loop {
       unsafe {
         self.nd = self.map.get_prev(self.nd, 0);
 
         if self.nd.is_null() || self.nd.ptr == self.map.head.ptr {
+          // the head would start the iteration from the end again, the null node is never
+          // moved from.
+          self.nd = NodePtr::NULL;
           return None;
         }
 
         let node = self.nd.as_ref();
+        let nk = node.get_key(&self.map.arena);
+        if self.past_range(nk) {
+          self.nd = NodePtr::NULL;
+          return None;
+        }
+
         let (trailer, value) = node.get_value_and_trailer(&self.map.arena);
         if trailer.version() > self.version {
           continue;
         }
 
+        let (trailer, value) = if self.all_versions {
+          (trailer, value)
+        } else {
+          // the versions of a key are in descending order, walks back to the newest version
+          // which is visible.
+          self.seek_newest_visible(nk);
+          self.nd.as_ref().get_value_and_trailer(&self.map.arena)
+        };
+
         if !self.all_versions && value.is_none() {
           continue;
         }
 
-        let nk = node.get_key(&self.map.arena);
-
         if !self.all_versions {
           if let Some(last) = self.last {
-            if self.map.cmp.compare(last.key, nk) == cmp::Ordering::Equal {
+            if self.map.node_keys_eq(last.ptr, last.key, self.nd, nk) {
               continue;
             }
           }
         }
 
         if self.map.cmp.contains(&self.range, nk) {
+          // the entry has been yielded by the other end, the ends have met.
+          if let (false, Some(other)) = (self.seeked, self.back_last) {
+            if !self.precedes(other.key, other.version(), nk, trailer.version()) {
+              self.nd = NodePtr::NULL;
+              return None;
+            }
+          }
+
           let ent = VersionedEntryRef {
             arena: &self.map.arena,
             key: nk,
@@ -215,6 +362,91 @@ where
     }
   }
 
+  /// Returns `true` if the entry of the key `a` at version `a_version` comes before the entry
+  /// of the key `b` at version `b_version` in the order of the iteration, the versions of a key
+  /// are ordered from the newest, and are a single entry unless all the versions are yielded.
+  #[inline]
+  fn precedes(&self, a: &[u8], a_version: u64, b: &[u8], b_version: u64) -> bool {
+    match self.map.cmp.compare(a, b) {
+      cmp::Ordering::Less => true,
+      cmp::Ordering::Greater => false,
+      cmp::Ordering::Equal => self.all_versions && a_version > b_version,
+    }
+  }
+
+  /// Moves the back end of the iterator backward, `prev` walks the position in `nd` and `last`
+  /// and stops at the entry in `back_last`, so the ends are swapped around it.
+  fn next_back_in(&mut self) -> Option<VersionedEntryRef<'a, T>> {
+    self.swap_ends();
+    let ent = self.prev().map(|v| {
+      // Safety: the EntryRef holds a reference to the map, so it is always valid.
+      unsafe { core::mem::transmute::<VersionedEntryRef<'_, T>, VersionedEntryRef<'a, T>>(v) }
+    });
+    self.swap_ends();
+    ent
+  }
+
+  /// Moves the front end of the iterator backward, for the cursors which move a single
+  /// position in both directions, unlike `next_back`, which moves the back end.
+  pub(super) fn step_back(&mut self) -> Option<VersionedEntryRef<'a, T>> {
+    self.prev().map(|v| {
+      // Safety: the EntryRef holds a reference to the map, so it is always valid.
+      unsafe { core::mem::transmute::<VersionedEntryRef<'_, T>, VersionedEntryRef<'a, T>>(v) }
+    })
+  }
+
+  #[inline]
+  fn swap_ends(&mut self) {
+    mem::swap(&mut self.nd, &mut self.back_nd);
+    mem::swap(&mut self.last, &mut self.back_last);
+  }
+
+  /// Moves the iterator from the head to the last node before the start bound of the range,
+  /// by the tower descent, instead of scanning the nodes before the range one by one.
+  fn seek_start(&mut self) {
+    let start = match self.range.start_bound() {
+      Bound::Unbounded => return,
+      start => (start, Bound::Unbounded),
+    };
+
+    let map = self.map;
+    // Safety: the head node is allocated by the map's arena.
+    self.nd = unsafe { map.find_last_before(|nk| !map.cmp.contains(&start, nk)) };
+  }
+
+  /// Moves the iterator from the head to the node after the end bound of the range, by the
+  /// tower descent, so the reverse iteration starts from the end of the range.
+  fn seek_end(&mut self) {
+    let map = self.map;
+    let end = match self.range.end_bound() {
+      Bound::Unbounded => {
+        self.nd = map.tail;
+        return;
+      }
+      end => (Bound::Unbounded, end),
+    };
+
+    // Safety: the head node is allocated by the map's arena, and the found node is linked.
+    unsafe {
+      let nd = map.find_last_before(|nk| map.cmp.contains(&end, nk));
+      // the first node is after the end bound, which is the case of an empty range, or of a
+      // map ordered against the range, scans back from the tail.
+      self.nd = if nd.ptr == map.head.ptr {
+        map.tail
+      } else {
+        map.get_next_unchecked(nd, 0)
+      };
+    }
+  }
+
+  /// Returns `true` if the key is out of the range after an entry in the range has been
+  /// yielded, the keys in the range are contiguous, so the rest of the nodes are out of the
+  /// range too.
+  #[inline]
+  fn past_range(&self, nk: &'a [u8]) -> bool {
+    self.last.is_some() && !self.map.cmp.contains(&self.range, nk)
+  }
+
   /// Moves the iterator to the first entry whose key is greater than or
   /// equal to the given key. Returns the key and value if the iterator is
   /// pointing at a valid entry, and `None` otherwise.
@@ -475,24 +707,40 @@ where
     })
   }
 
+  /// The entries inserted concurrently may or may not be yielded, so the number of the rest of
+  /// the entries is only bounded once the map is read-only, by the number of its nodes.
+  #[inline]
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    // the ends of a seeked iterator move apart, so both must be exhausted.
+    let exhausted = if self.seeked {
+      self.nd.is_null() && self.back_nd.is_null()
+    } else {
+      self.nd.is_null() || self.back_nd.is_null()
+    };
+    if exhausted {
+      (0, Some(0))
+    } else if self.map.read_only() {
+      (0, Some(self.map.len()))
+    } else {
+      (0, None)
+    }
+  }
+
   #[inline]
   fn last(mut self) -> Option<Self::Item>
   where
     Self: Sized,
   {
-    self.seek_upper_bound(Bound::Unbounded).map(|e| {
-      // Safety: the EntryRef holds a reference to the map, so it is always valid.
-      unsafe { core::mem::transmute(e) }
-    })
+    self.next_back_in()
   }
 
   #[inline]
-  fn max(self) -> Option<Self::Item>
+  fn max(mut self) -> Option<Self::Item>
   where
     Self: Sized,
     Self::Item: Ord,
   {
-    self.last()
+    self.next_back_in()
   }
 
   #[inline]
@@ -501,13 +749,20 @@ where
     Self: Sized,
     Self::Item: Ord,
   {
-    self.first().map(|e| {
-      // Safety: the EntryRef holds a reference to the map, so it is always valid.
-      unsafe { core::mem::transmute(e) }
-    })
+    self.next()
   }
 }
 
+impl<'a, Q, R, T, C> core::iter::FusedIterator for AllVersionsIter<'a, T, C, Q, R>
+where
+  C: Comparator,
+  T: Trailer,
+  &'a [u8]: PartialOrd<Q>,
+  Q: ?Sized + PartialOrd<&'a [u8]>,
+  R: RangeBounds<Q>,
+{
+}
+
 impl<'a, Q, R, T, C> DoubleEndedIterator for AllVersionsIter<'a, T, C, Q, R>
 where
   C: Comparator,
@@ -516,10 +771,8 @@ where
   Q: ?Sized + PartialOrd<&'a [u8]>,
   R: RangeBounds<Q>,
 {
+  #[inline]
   fn next_back(&mut self) -> Option<Self::Item> {
-    self.prev().map(|v| {
-      // Safety: the EntryRef holds a reference to the map, so it is always valid.
-      unsafe { core::mem::transmute(v) }
-    })
+    self.next_back_in()
   }
 }
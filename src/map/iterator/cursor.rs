@@ -0,0 +1,147 @@
+use super::*;
+
+/// A database-style cursor over the newest versions (less or equal to the version of the
+/// cursor) of the entries of the skipmap, returned by [`SkipMap::cursor`].
+///
+/// The cursor is positioned once by a seek, and then moves in either direction from the
+/// current node, without searching from the head again. A cursor which is not positioned
+/// at an entry is invalid, and must be positioned by a seek before it moves again.
+///
+/// # Example
+///
+/// ```rust
+/// use skl::SkipMap;
+///
+/// let l = SkipMap::new().unwrap();
+/// for k in [b"a", b"b", b"c", b"d"] {
+///   l.insert(0, k, k).unwrap();
+/// }
+///
+/// let mut cursor = l.cursor(0);
+/// assert!(cursor.seek(b"bb"));
+/// assert_eq!(cursor.key(), Some(&b"c"[..]));
+/// assert_eq!(cursor.prev().unwrap().key(), b"b");
+/// assert_eq!(cursor.prev().unwrap().key(), b"a");
+/// assert!(cursor.prev().is_none());
+/// assert!(!cursor.valid());
+///
+/// assert!(cursor.seek_for_prev(b"bb"));
+/// assert_eq!(cursor.value(), Some(&b"b"[..]));
+/// assert_eq!(cursor.next().unwrap().key(), b"c");
+/// ```
+pub struct Cursor<'a, T, C> {
+  map: &'a SkipMap<T, C>,
+  version: u64,
+  iter: Iter<'a, T, C>,
+  current: Option<EntryRef<'a, T>>,
+}
+
+impl<'a, T, C> Cursor<'a, T, C>
+where
+  C: Comparator,
+{
+  #[inline]
+  pub(crate) const fn new(version: u64, map: &'a SkipMap<T, C>) -> Self {
+    Self {
+      map,
+      version,
+      iter: Iter::new(version, map),
+      current: None,
+    }
+  }
+}
+
+impl<'a, T, C> Cursor<'a, T, C> {
+  /// Returns the version of the cursor.
+  #[inline]
+  pub const fn version(&self) -> u64 {
+    self.version
+  }
+
+  /// Returns `true` if the cursor is positioned at an entry.
+  #[inline]
+  pub const fn valid(&self) -> bool {
+    self.current.is_some()
+  }
+
+  /// Returns the entry at the current position of the cursor.
+  #[inline]
+  pub const fn entry(&self) -> Option<&EntryRef<'a, T>> {
+    self.current.as_ref()
+  }
+
+  /// Returns the key of the entry at the current position of the cursor.
+  #[inline]
+  pub fn key(&self) -> Option<&[u8]> {
+    self.current.as_ref().map(|ent| ent.key())
+  }
+
+  /// Returns the value of the entry at the current position of the cursor.
+  #[inline]
+  pub fn value(&self) -> Option<&[u8]> {
+    self.current.as_ref().map(|ent| ent.value())
+  }
+}
+
+impl<'a, T, C> Cursor<'a, T, C>
+where
+  C: Comparator,
+  T: Trailer,
+{
+  /// Positions the cursor at the first entry whose key is greater than or equal to the key.
+  ///
+  /// Returns `true` if the cursor is valid.
+  pub fn seek(&mut self, key: &[u8]) -> bool {
+    self.current = self.iter.seek_lower_bound(Bound::Included(key));
+    self.valid()
+  }
+
+  /// Positions the cursor at the last entry whose key is less than or equal to the key.
+  ///
+  /// Returns `true` if the cursor is valid.
+  pub fn seek_for_prev(&mut self, key: &[u8]) -> bool {
+    self.current = self.iter.seek_upper_bound(Bound::Included(key));
+    self.valid()
+  }
+
+  /// Positions the cursor at the first entry.
+  ///
+  /// Returns `true` if the cursor is valid.
+  pub fn seek_to_first(&mut self) -> bool {
+    self.iter = Iter::new(self.version, self.map);
+    self.current = self.iter.next();
+    self.valid()
+  }
+
+  /// Positions the cursor at the last entry.
+  ///
+  /// Returns `true` if the cursor is valid.
+  pub fn seek_to_last(&mut self) -> bool {
+    self.iter = Iter::new(self.version, self.map);
+    self.current = self.iter.step_back();
+    self.valid()
+  }
+
+  /// Moves the cursor to the next entry, and returns it.
+  ///
+  /// Returns `None` and invalidates the cursor if there is no next entry, or if the cursor
+  /// is not valid.
+  #[allow(clippy::should_implement_trait)]
+  pub fn next(&mut self) -> Option<EntryRef<'a, T>> {
+    if self.current.is_some() {
+      self.current = self.iter.next();
+    }
+    self.current.clone()
+  }
+
+  /// Moves the cursor to the previous entry, and returns it.
+  ///
+  /// Returns `None` and invalidates the cursor if there is no previous entry, or if the cursor
+  /// is not valid.
+  pub fn prev(&mut self) -> Option<EntryRef<'a, T>> {
+    if self.current.is_some() {
+      self.current = self.iter.step_back();
+    }
+    self.current.clone()
+  }
+}
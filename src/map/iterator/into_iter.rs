@@ -0,0 +1,120 @@
+use super::*;
+
+/// An owning iterator over the latest version (less or equal to the version of the iterator)
+/// of the entries of the skipmap, returned by the [`IntoIterator`] implementation of
+/// [`SkipMap`] and by [`SkipMap::into_iter_at`].
+///
+/// The iterator owns the map, and yields the owned [`Entry`]s, which keep the ARENA alive on
+/// their own, so a frozen memtable can be handed to a background thread as a plain iterator.
+///
+/// # Example
+///
+/// ```rust
+/// use skl::SkipMap;
+///
+/// let l = SkipMap::new().unwrap();
+/// l.insert(0, b"a", b"a").unwrap();
+/// l.insert(0, b"b", b"b").unwrap();
+///
+/// let entries = l.into_iter();
+/// let flusher = std::thread::spawn(move || {
+///   entries.map(|ent| ent.key().to_vec()).collect::<Vec<_>>()
+/// });
+/// assert_eq!(flusher.join().unwrap(), [b"a", b"b"]);
+/// ```
+pub struct IntoIter<T, C> {
+  map: SkipMap<T, C>,
+  version: u64,
+  nd: NodePtr<T>,
+  last: Option<NodePtr<T>>,
+  back_nd: NodePtr<T>,
+  back_last: Option<NodePtr<T>>,
+}
+
+// Safety: the iterator owns the map, and only reads the nodes.
+unsafe impl<T: Send, C: Comparator + Send> Send for IntoIter<T, C> {}
+unsafe impl<T: Sync, C: Comparator + Sync> Sync for IntoIter<T, C> {}
+
+impl<T, C> IntoIter<T, C> {
+  #[inline]
+  pub(crate) fn new(version: u64, map: SkipMap<T, C>) -> Self {
+    Self {
+      nd: map.head,
+      back_nd: map.head,
+      map,
+      version,
+      last: None,
+      back_last: None,
+    }
+  }
+
+  /// Returns the version of the iterator.
+  #[inline]
+  pub const fn version(&self) -> u64 {
+    self.version
+  }
+}
+
+impl<T: Trailer, C: Comparator> IntoIter<T, C> {
+  /// Moves the front end forward or the back end backward by a borrowing iterator, which is
+  /// rebuilt from the positions of this iterator on every step.
+  fn step(&mut self, back: bool) -> Option<Entry<T>> {
+    let map = &self.map;
+    let mut iter = AllVersionsIter::new(self.version, map, false);
+    iter.nd = self.nd;
+    iter.last = self
+      .last
+      .map(|nd| VersionedEntryRef::from_node(nd, &map.arena));
+    iter.back_nd = self.back_nd;
+    iter.back_last = self
+      .back_last
+      .map(|nd| VersionedEntryRef::from_node(nd, &map.arena));
+
+    let ent = if back { iter.next_back() } else { iter.next() };
+    let ent = ent.map(|ent| EntryRef(ent).to_owned());
+    self.nd = iter.nd;
+    self.last = iter.last.map(|ent| ent.ptr);
+    self.back_nd = iter.back_nd;
+    self.back_last = iter.back_last.map(|ent| ent.ptr);
+    ent
+  }
+}
+
+impl<T: Trailer, C: Comparator> Iterator for IntoIter<T, C> {
+  type Item = Entry<T>;
+
+  #[inline]
+  fn next(&mut self) -> Option<Self::Item> {
+    self.step(false)
+  }
+}
+
+impl<T: Trailer, C: Comparator> DoubleEndedIterator for IntoIter<T, C> {
+  #[inline]
+  fn next_back(&mut self) -> Option<Self::Item> {
+    self.step(true)
+  }
+}
+
+impl<T: Trailer, C: Comparator> core::iter::FusedIterator for IntoIter<T, C> {}
+
+impl<T: Trailer, C: Comparator> IntoIterator for SkipMap<T, C> {
+  type Item = Entry<T>;
+  type IntoIter = IntoIter<T, C>;
+
+  /// Returns an owning iterator over the latest version of all the entries, see
+  /// [`SkipMap::into_iter_at`] to pin the version.
+  #[inline]
+  fn into_iter(self) -> Self::IntoIter {
+    IntoIter::new(u64::MAX, self)
+  }
+}
+
+impl<T, C> SkipMap<T, C> {
+  /// Returns an owning iterator over the latest version (less or equal to the given version)
+  /// of all the entries, see [`IntoIter`].
+  #[inline]
+  pub fn into_iter_at(self, version: u64) -> IntoIter<T, C> {
+    IntoIter::new(version, self)
+  }
+}
@@ -2,6 +2,10 @@ use super::*;
 
 /// An iterator over the skipmap. The current state of the iterator can be cloned by
 /// simply value copying the struct.
+///
+/// Like [`AllVersionsIter`], the iterator has a front end moved by [`next`](Iterator::next)
+/// and a back end moved by [`next_back`](DoubleEndedIterator::next_back), which stop once they
+/// meet.
 pub struct Iter<'a, T, C, Q: ?Sized = &'static [u8], R = core::ops::RangeFull>(
   AllVersionsIter<'a, T, C, Q, R>,
 );
@@ -70,6 +74,12 @@ where
   pub fn seek_lower_bound(&mut self, lower: Bound<&[u8]>) -> Option<EntryRef<'a, T>> {
     self.0.seek_lower_bound(lower).map(EntryRef)
   }
+
+  /// Moves the front end of the iterator backward, see [`Cursor::prev`].
+  #[inline]
+  pub(super) fn step_back(&mut self) -> Option<EntryRef<'a, T>> {
+    self.0.step_back().map(EntryRef)
+  }
 }
 
 impl<'a, Q, R, T, C> Iterator for Iter<'a, T, C, Q, R>
@@ -87,6 +97,11 @@ where
     self.0.next().map(EntryRef)
   }
 
+  #[inline]
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    self.0.size_hint()
+  }
+
   #[inline]
   fn last(self) -> Option<Self::Item>
   where
@@ -96,6 +111,16 @@ where
   }
 }
 
+impl<'a, Q, R, T, C> core::iter::FusedIterator for Iter<'a, T, C, Q, R>
+where
+  C: Comparator,
+  T: Trailer,
+  &'a [u8]: PartialOrd<Q>,
+  Q: ?Sized + PartialOrd<&'a [u8]>,
+  R: RangeBounds<Q>,
+{
+}
+
 impl<'a, Q, R, T, C> DoubleEndedIterator for Iter<'a, T, C, Q, R>
 where
   C: Comparator,
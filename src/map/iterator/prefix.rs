@@ -0,0 +1,87 @@
+use super::*;
+
+/// An iterator over the latest version of the entries whose keys start with a prefix, in the
+/// order of the comparator, returned by [`SkipMap::prefix`].
+pub struct Prefix<'a, T, C> {
+  map: &'a SkipMap<T, C>,
+  iter: Iter<'a, T, C>,
+  pending: Option<EntryRef<'a, T>>,
+  prefix: &'a [u8],
+  done: bool,
+}
+
+impl<'a, T, C> Prefix<'a, T, C>
+where
+  C: Comparator,
+  T: Trailer,
+{
+  #[inline]
+  pub(crate) fn new(version: u64, map: &'a SkipMap<T, C>, prefix: &'a [u8]) -> Self {
+    let mut iter = Iter::new(version, map);
+    let pending = iter.seek_lower_bound(Bound::Included(prefix));
+    Self {
+      map,
+      iter,
+      done: pending.is_none(),
+      pending,
+      prefix,
+    }
+  }
+}
+
+impl<'a, T, C> Prefix<'a, T, C> {
+  /// Returns the prefix of the iterator.
+  #[inline]
+  pub const fn prefix(&self) -> &'a [u8] {
+    self.prefix
+  }
+}
+
+impl<'a, T, C> Iterator for Prefix<'a, T, C>
+where
+  C: Comparator,
+  T: Trailer,
+{
+  type Item = EntryRef<'a, T>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    if self.done {
+      return None;
+    }
+
+    let ent = match self.pending.take() {
+      Some(ent) => ent,
+      None => match self.iter.next() {
+        Some(ent) => ent,
+        None => {
+          self.done = true;
+          return None;
+        }
+      },
+    };
+
+    if self.map.cmp.has_prefix(ent.key(), self.prefix) {
+      Some(ent)
+    } else {
+      // the keys with the prefix are contiguous, so the rest of the keys are out of the prefix.
+      self.done = true;
+      None
+    }
+  }
+
+  #[inline]
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    if self.done {
+      (0, Some(0))
+    } else {
+      (self.pending.is_some() as usize, None)
+    }
+  }
+}
+
+impl<'a, T, C> core::iter::FusedIterator for Prefix<'a, T, C>
+where
+  C: Comparator,
+  T: Trailer,
+{
+}
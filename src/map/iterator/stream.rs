@@ -0,0 +1,132 @@
+use core::{
+  pin::Pin,
+  task::{Context, Poll},
+};
+
+use futures_core::Stream;
+
+use super::*;
+
+/// How many entries are yielded by a [`RangeStream`] before it yields back to the executor.
+const YIELD_EVERY: usize = 1024;
+
+/// A [`Stream`] over the latest version of the entries in a range, returned by
+/// [`SkipMap::stream`].
+///
+/// The entries are read in place, a poll never blocks, so the stream would never return
+/// [`Poll::Pending`] by itself, and a large scan would hold the executor thread until the end.
+/// Instead, the stream wakes the task and returns [`Poll::Pending`] every `1024` entries (see
+/// [`yield_every`](RangeStream::yield_every)), so the other tasks get a chance to run.
+///
+/// The stream does not depend on any runtime.
+pub struct RangeStream<'a, T, C, Q: ?Sized = &'static [u8], R = core::ops::RangeFull> {
+  iter: Iter<'a, T, C, Q, R>,
+  yield_every: usize,
+  yielded: usize,
+}
+
+// The fields are never pinned, the stream is polled through `&mut Self`.
+impl<'a, T, C, Q: ?Sized, R> Unpin for RangeStream<'a, T, C, Q, R> {}
+
+impl<'a, T, C, Q: ?Sized, R> RangeStream<'a, T, C, Q, R> {
+  #[inline]
+  pub(crate) const fn new(iter: Iter<'a, T, C, Q, R>) -> Self {
+    Self {
+      iter,
+      yield_every: YIELD_EVERY,
+      yielded: 0,
+    }
+  }
+
+  /// Sets how many entries are yielded before the stream yields back to the executor,
+  /// `0` means never.
+  ///
+  /// Default is `1024`.
+  #[inline]
+  pub const fn yield_every(mut self, n: usize) -> Self {
+    self.yield_every = n;
+    self
+  }
+
+  /// Returns the bounds of the stream.
+  #[inline]
+  pub const fn bounds(&self) -> &R {
+    self.iter.bounds()
+  }
+}
+
+impl<'a, Q, R, T, C> Stream for RangeStream<'a, T, C, Q, R>
+where
+  C: Comparator,
+  T: Trailer,
+  &'a [u8]: PartialOrd<Q>,
+  Q: ?Sized + PartialOrd<&'a [u8]>,
+  R: RangeBounds<Q>,
+{
+  type Item = EntryRef<'a, T>;
+
+  fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+    let this = self.get_mut();
+    if this.yield_every != 0 && this.yielded == this.yield_every {
+      this.yielded = 0;
+      cx.waker().wake_by_ref();
+      return Poll::Pending;
+    }
+
+    this.yielded += 1;
+    Poll::Ready(this.iter.next())
+  }
+}
+
+impl<T, C> SkipMap<T, C> {
+  /// Returns a [`Stream`] over the latest version of the entries in the range, less or equal
+  /// to the given version, which yields back to the executor every `1024` entries, so an async
+  /// server can scan a large range without starving the other tasks.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use core::{
+  ///   pin::Pin,
+  ///   task::{Context, Poll, RawWaker, RawWakerVTable, Waker},
+  /// };
+  /// use futures_core::Stream;
+  /// use skl::SkipMap;
+  ///
+  /// fn noop_raw_waker() -> RawWaker {
+  ///   fn clone(_: *const ()) -> RawWaker {
+  ///     noop_raw_waker()
+  ///   }
+  ///   fn noop(_: *const ()) {}
+  ///   static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+  ///   RawWaker::new(core::ptr::null(), &VTABLE)
+  /// }
+  ///
+  /// let map = SkipMap::new().unwrap();
+  /// for i in 0..10u8 {
+  ///   map.insert(0, &[i], &[i]).unwrap();
+  /// }
+  ///
+  /// let waker = unsafe { Waker::from_raw(noop_raw_waker()) };
+  /// let mut cx = Context::from_waker(&waker);
+  /// let mut stream = map.stream(0, &[2u8][..]..).yield_every(4);
+  /// let (mut ready, mut pending) = (0, 0);
+  /// loop {
+  ///   match Pin::new(&mut stream).poll_next(&mut cx) {
+  ///     Poll::Ready(Some(_)) => ready += 1,
+  ///     Poll::Ready(None) => break,
+  ///     Poll::Pending => pending += 1,
+  ///   }
+  /// }
+  /// assert_eq!((ready, pending), (8, 2));
+  /// ```
+  #[inline]
+  pub fn stream<'a, Q, R>(&'a self, version: u64, range: R) -> RangeStream<'a, T, C, Q, R>
+  where
+    &'a [u8]: PartialOrd<Q>,
+    Q: ?Sized + PartialOrd<&'a [u8]>,
+    R: RangeBounds<Q> + 'a,
+  {
+    RangeStream::new(Iter::range(version, self, range))
+  }
+}
@@ -0,0 +1,225 @@
+use std::time::Instant;
+
+use super::*;
+
+/// The number of the linear sub-buckets of every power of two of a [`LatencyHistogram`], as
+/// the number of bits.
+const SUB_BUCKET_BITS: u32 = 4;
+
+const SUB_BUCKETS: usize = 1 << SUB_BUCKET_BITS;
+
+/// The number of buckets of a [`LatencyHistogram`].
+///
+/// The latencies below `16` nanoseconds have a bucket each, every power of two above is split
+/// into `16` linear sub-buckets, so bucket `i` counts the latencies in
+/// [`LatencyHistogram::bucket_range(i)`](LatencyHistogram::bucket_range).
+pub const LATENCY_BUCKETS: usize = (u32::BITS - SUB_BUCKET_BITS + 1) as usize * SUB_BUCKETS;
+
+/// A histogram of latencies in nanoseconds with log-linear buckets, see [`LATENCY_BUCKETS`],
+/// like an HDR histogram with one significant hex digit: the relative error of the recorded
+/// latencies and the percentiles is at most `1/16` (6.25%) at any magnitude.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LatencyHistogram {
+  buckets: [u64; LATENCY_BUCKETS],
+  sum: u64,
+}
+
+impl LatencyHistogram {
+  /// Returns the index of the bucket which counts the latency.
+  #[inline]
+  pub const fn bucket_of(nanos: u32) -> usize {
+    if nanos < SUB_BUCKETS as u32 {
+      return nanos as usize;
+    }
+
+    let exp = u32::BITS - 1 - nanos.leading_zeros();
+    let sub = (nanos >> (exp - SUB_BUCKET_BITS)) as usize & (SUB_BUCKETS - 1);
+    (((exp - SUB_BUCKET_BITS + 1) as usize) << SUB_BUCKET_BITS) | sub
+  }
+
+  /// Returns the range of the latencies counted by the bucket.
+  #[inline]
+  pub const fn bucket_range(idx: usize) -> core::ops::Range<u64> {
+    if idx < SUB_BUCKETS {
+      return idx as u64..idx as u64 + 1;
+    }
+
+    let shift = (idx >> SUB_BUCKET_BITS) - 1;
+    let start = ((SUB_BUCKETS + (idx & (SUB_BUCKETS - 1))) as u64) << shift;
+    start..start + (1 << shift)
+  }
+
+  /// Returns the counts of the buckets.
+  #[inline]
+  pub const fn buckets(&self) -> &[u64; LATENCY_BUCKETS] {
+    &self.buckets
+  }
+
+  /// Returns how many latencies are recorded.
+  #[inline]
+  pub fn count(&self) -> u64 {
+    self.buckets.iter().sum()
+  }
+
+  /// Returns the sum of the recorded latencies.
+  #[inline]
+  pub const fn sum(&self) -> u64 {
+    self.sum
+  }
+
+  /// Returns the mean of the recorded latencies, `None` if nothing is recorded.
+  #[inline]
+  pub fn mean(&self) -> Option<u64> {
+    self.sum.checked_div(self.count())
+  }
+
+  /// Returns the exclusive upper bound of the bucket which contains the `percentile`-th
+  /// percentile (`0..=100`) of the recorded latencies, `None` if nothing is recorded.
+  pub fn percentile(&self, percentile: u8) -> Option<u64> {
+    let count = self.count();
+    if count == 0 {
+      return None;
+    }
+
+    let rank = ((count * percentile.min(100) as u64 + 99) / 100).max(1);
+    let mut seen = 0;
+    for (idx, n) in self.buckets.iter().enumerate() {
+      seen += n;
+      if seen >= rank {
+        return Some(Self::bucket_range(idx).end);
+      }
+    }
+    None
+  }
+}
+
+/// The latency distributions of the operations, in nanoseconds, returned by
+/// [`SkipMap::latency_stats`].
+///
+/// The latencies are recorded into the log-linear buckets of [`LatencyHistogram`], and
+/// saturate at `u32::MAX` nanoseconds (about 4.3 seconds).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LatencyStats {
+  /// The latencies of the writes, including the inserts and the removals.
+  pub insert: LatencyHistogram,
+  /// The latencies of the point lookups.
+  pub get: LatencyHistogram,
+  /// The latencies of the seeks of the iterators.
+  pub seek: LatencyHistogram,
+}
+
+/// The kind of a timed operation.
+#[derive(Debug, Clone, Copy)]
+pub(super) enum Op {
+  Insert,
+  Get,
+  Seek,
+}
+
+#[derive(Debug)]
+struct AtomicLatencyHistogram {
+  buckets: [AtomicU64; LATENCY_BUCKETS],
+  sum: AtomicU64,
+}
+
+impl AtomicLatencyHistogram {
+  #[allow(clippy::declare_interior_mutable_const)]
+  const ZERO: AtomicU64 = AtomicU64::new(0);
+
+  const fn new() -> Self {
+    Self {
+      buckets: [Self::ZERO; LATENCY_BUCKETS],
+      sum: AtomicU64::new(0),
+    }
+  }
+
+  #[inline]
+  fn record(&self, nanos: u32) {
+    self.buckets[LatencyHistogram::bucket_of(nanos)].fetch_add(1, Ordering::Relaxed);
+    self.sum.fetch_add(nanos as u64, Ordering::Relaxed);
+  }
+
+  fn load(&self) -> LatencyHistogram {
+    let mut buckets = [0; LATENCY_BUCKETS];
+    for (dst, src) in buckets.iter_mut().zip(self.buckets.iter()) {
+      *dst = src.load(Ordering::Relaxed);
+    }
+    LatencyHistogram {
+      buckets,
+      sum: self.sum.load(Ordering::Relaxed),
+    }
+  }
+}
+
+/// The latency histograms shared by all the clones of a [`SkipMap`].
+#[derive(Debug)]
+pub(super) struct LatencyHistograms {
+  insert: AtomicLatencyHistogram,
+  get: AtomicLatencyHistogram,
+  seek: AtomicLatencyHistogram,
+}
+
+impl LatencyHistograms {
+  pub(super) const fn new() -> Self {
+    Self {
+      insert: AtomicLatencyHistogram::new(),
+      get: AtomicLatencyHistogram::new(),
+      seek: AtomicLatencyHistogram::new(),
+    }
+  }
+}
+
+/// Records the time elapsed since its creation when dropped.
+pub(super) struct Timer<'a> {
+  histogram: &'a AtomicLatencyHistogram,
+  start: Instant,
+}
+
+impl Drop for Timer<'_> {
+  #[inline]
+  fn drop(&mut self) {
+    let elapsed = self.start.elapsed().as_nanos();
+    self.histogram.record(elapsed.min(u32::MAX as u128) as u32);
+  }
+}
+
+impl<T, C> SkipMap<T, C> {
+  /// Starts timing an operation.
+  #[inline]
+  pub(super) fn timer(&self, op: Op) -> Timer<'_> {
+    let histogram = match op {
+      Op::Insert => &self.latencies.insert,
+      Op::Get => &self.latencies.get,
+      Op::Seek => &self.latencies.seek,
+    };
+    Timer {
+      histogram,
+      start: Instant::now(),
+    }
+  }
+
+  /// Returns the latency distributions of the operations on this map and its clones.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use skl::SkipMap;
+  ///
+  /// let l = SkipMap::new().unwrap();
+  /// l.insert(0, b"a", b"a").unwrap();
+  /// l.get(0, b"a").unwrap();
+  /// l.get(0, b"b");
+  ///
+  /// let stats = l.latency_stats();
+  /// assert_eq!(stats.insert.count(), 1);
+  /// assert_eq!(stats.get.count(), 2);
+  /// assert!(stats.get.percentile(99).is_some());
+  /// ```
+  pub fn latency_stats(&self) -> LatencyStats {
+    LatencyStats {
+      insert: self.latencies.insert.load(),
+      get: self.latencies.get.load(),
+      seek: self.latencies.seek.load(),
+    }
+  }
+}
@@ -0,0 +1,283 @@
+//! The layout of the nodes and of the header in the ARENA, for external debuggers,
+//! pretty-printers and recovery tools which need to parse the ARENA directly.
+//!
+//! **Semi-stable:** the layout only changes together with [`VERSION`], tools should
+//! check [`SkipMap::version`](super::SkipMap::version) before parsing the ARENA.
+//!
+//! All integers are stored in the native endianness.
+//!
+//! # Header
+//!
+//! When the meta is stored in the ARENA (see [`Options::with_unify`](crate::Options::with_unify)),
+//! it is stored at the first [`META_ALIGN`] aligned offset after the data offset of the ARENA,
+//! otherwise it lives on the heap. The head node is stored at the first [`NODE_ALIGN`]
+//! aligned offset after the meta (or after the data offset of the ARENA), with the full
+//! tower, followed by its trailer. The tail node is stored at the first [`NODE_ALIGN`] aligned
+//! offset after the trailer of the head node, with the full tower, followed by its trailer.
+//!
+//! The flags of the meta record the optional fields of the nodes below, see
+//! [`META_FLAG_ACCESS_STAMPS`] and [`META_FLAG_KEY_FINGERPRINTS`].
+//!
+//! # Node
+//!
+//! ```text
+//! +----------------------+----------------+-------------------------+------------------------------------+
+//! | value pointer: u64   | key offset: u32| key size and height: u32| tower: [next: u32, prev: u32; h]   |
+//! +----------------------+----------------+-------------------------+------------------------------------+
+//! ```
+//!
+//! - The value pointer encodes the offset of the trailer in the low 32 bits, and the size of the value
+//!   in the high 32 bits, see [`decode_value_pointer`]. The value bytes follow the trailer directly.
+//!   The size is [`REMOVED`] if the entry is removed.
+//! - The key size and height encodes the size of the key in the high 27 bits, and the height of the tower
+//!   in the low 5 bits, see [`decode_key_size_and_height`].
+//! - The tower is stored right after the node, one [`LINK_SIZE`] link per level,
+//!   see [`tower_offset`].
+//! - If [`Options::with_access_stamps`](crate::Options::with_access_stamps) is enabled, a `u32`
+//!   access stamp follows the tower of every node except the head and the tail.
+//! - If [`Options::with_key_fingerprints`](crate::Options::with_key_fingerprints) is enabled, a
+//!   `u32` FNV-1a hash of the key follows the tower (and the access stamp) of every node except
+//!   the head and the tail.
+//! - If [`Options::with_inline_key_size`](crate::Options::with_inline_key_size) is set, the keys
+//!   up to that size follow the tower (and the optional fields above) of their node, and the key
+//!   offset points there, otherwise the key is stored in a separate allocation.
+
+use super::{Link, Meta, Node, NodePtr, Ordering, SkipMap, Trailer};
+
+/// The version of the layout, same as [`SkipMap::version`](super::SkipMap::version).
+pub const VERSION: u16 = super::CURRENT_VERSION;
+
+/// The value size of the removed entries.
+pub const REMOVED: u32 = super::REMOVE;
+
+/// The size of the meta.
+pub const META_SIZE: usize = core::mem::size_of::<Meta>();
+/// The alignment of the meta.
+pub const META_ALIGN: usize = core::mem::align_of::<Meta>();
+/// The offset of the maximum version (`u64`) in the meta.
+pub const META_MAX_VERSION_OFFSET: usize = 0;
+/// The offset of the minimum version (`u64`) in the meta.
+pub const META_MIN_VERSION_OFFSET: usize = 8;
+/// The offset of the number of entries (`u32`) in the meta.
+pub const META_LEN_OFFSET: usize = 16;
+/// The offset of the magic version (`u16`) in the meta.
+pub const META_MAGIC_VERSION_OFFSET: usize = 20;
+/// The offset of the height (`u8`) in the meta.
+pub const META_HEIGHT_OFFSET: usize = 22;
+/// The offset of the flags (`u8`) in the meta, the low 4 bits are the `META_FLAG_*` flags, and
+/// the high 4 bits are the [`PoisonReason`](super::PoisonReason) (`0` if not poisoned).
+pub const META_FLAGS_OFFSET: usize = 23;
+/// The flag set if the nodes have the access stamp.
+pub const META_FLAG_ACCESS_STAMPS: u8 = Meta::ACCESS_STAMPS;
+/// The flag set if the nodes have the key fingerprint.
+pub const META_FLAG_KEY_FINGERPRINTS: u8 = Meta::KEY_FINGERPRINTS;
+
+/// The size of the node, excluding the tower.
+pub const NODE_SIZE: usize = core::mem::size_of::<Node<()>>();
+/// The alignment of the node.
+pub const NODE_ALIGN: usize = core::mem::align_of::<Node<()>>();
+/// The offset of the value pointer (`u64`) in the node.
+pub const NODE_VALUE_POINTER_OFFSET: usize = 0;
+/// The offset of the key offset (`u32`) in the node.
+pub const NODE_KEY_OFFSET_OFFSET: usize = 8;
+/// The offset of the key size and height (`u32`) in the node.
+pub const NODE_KEY_SIZE_AND_HEIGHT_OFFSET: usize = 12;
+
+/// The size of a link in the tower, which is also the stride of the tower.
+pub const LINK_SIZE: usize = core::mem::size_of::<Link>();
+/// The offset of the next node offset (`u32`) in the link.
+pub const LINK_NEXT_OFFSET: usize = 0;
+/// The offset of the previous node offset (`u32`) in the link.
+pub const LINK_PREV_OFFSET: usize = 4;
+
+/// Returns the offset of the link at the given level of the node at `node_offset`.
+#[inline]
+pub const fn tower_offset(node_offset: usize, level: usize) -> usize {
+  node_offset + NODE_SIZE + level * LINK_SIZE
+}
+
+/// Decodes the value pointer of the node, returns the trailer offset and the value size.
+#[inline]
+pub const fn decode_value_pointer(value: u64) -> (u32, u32) {
+  super::decode_value_pointer(value)
+}
+
+/// Decodes the key size and height of the node, returns the key size and the height.
+#[inline]
+pub const fn decode_key_size_and_height(size: u32) -> (u32, u8) {
+  super::decode_key_size_and_height(size)
+}
+
+/// The offsets of the header of a [`SkipMap`], returned by [`SkipMap::header`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Header {
+  /// The offset of the meta, `None` if the meta lives on the heap.
+  pub meta_offset: Option<usize>,
+  /// The offset of the head node.
+  pub head_offset: usize,
+  /// The offset of the tail node.
+  pub tail_offset: usize,
+}
+
+impl<T, C> SkipMap<T, C> {
+  /// Returns the offsets of the header in the ARENA, see [`layout`](super::layout) for more details.
+  pub fn header(&self) -> Header {
+    let meta_offset = if self.opts.unify() {
+      Some(self.arena.offset(self.meta.as_ptr() as _))
+    } else {
+      None
+    };
+
+    Header {
+      meta_offset,
+      head_offset: self.head.offset as usize,
+      tail_offset: self.tail.offset as usize,
+    }
+  }
+}
+
+/// The memory footprint of an entry or of a whole [`SkipMap`], split into the payload and the
+/// overhead of the structure, see [`entry_overhead`] and [`SkipMap::memory_report`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Footprint {
+  /// The bytes of the node headers, the trailers, the access stamps, the key fingerprints and
+  /// the meta.
+  pub headers: usize,
+  /// The bytes of the towers.
+  pub towers: usize,
+  /// The bytes of the alignment paddings, and for a whole map, also the bytes which are not
+  /// reachable anymore, e.g. the overwritten values and the discarded bytes.
+  pub padding: usize,
+  /// The bytes of the keys and the values.
+  pub payload: usize,
+}
+
+impl Footprint {
+  /// Returns the total bytes.
+  #[inline]
+  pub const fn total(&self) -> usize {
+    self.headers + self.towers + self.padding + self.payload
+  }
+
+  /// Returns the bytes which are not the payload.
+  #[inline]
+  pub const fn overhead(&self) -> usize {
+    self.headers + self.towers + self.padding
+  }
+
+  /// Returns the memory amplification, i.e. the total bytes per payload byte, `None` if there
+  /// is no payload.
+  #[inline]
+  pub fn amplification(&self) -> Option<f64> {
+    if self.payload == 0 {
+      None
+    } else {
+      Some(self.total() as f64 / self.payload as f64)
+    }
+  }
+}
+
+/// Returns the worst-case footprint of an entry with a tower of `height` levels, where
+/// `timestamped` is whether the entry carries a `u64` trailer (e.g. the version of
+/// `SkipMap<u64>`), `false` for `SkipMap<()>`.
+///
+/// The paddings depend on the offsets the entry is allocated at, so the worst case is
+/// returned. The access stamps, the key fingerprints and the value alignment are not included.
+///
+/// # Example
+///
+/// ```rust
+/// use skl::layout::{entry_overhead, LINK_SIZE, NODE_SIZE};
+///
+/// let fp = entry_overhead(1, 16, 100, true);
+/// assert_eq!(fp.payload, 116);
+/// assert_eq!(fp.towers, LINK_SIZE);
+/// assert_eq!(fp.headers, NODE_SIZE + 8);
+/// ```
+pub const fn entry_overhead(
+  height: u8,
+  key_len: u32,
+  val_len: u32,
+  timestamped: bool,
+) -> Footprint {
+  let (trailer_size, trailer_padding) = if timestamped {
+    (
+      core::mem::size_of::<u64>(),
+      core::mem::align_of::<u64>() - 1,
+    )
+  } else {
+    (0, 0)
+  };
+
+  Footprint {
+    headers: NODE_SIZE + trailer_size,
+    towers: height as usize * LINK_SIZE,
+    padding: NODE_ALIGN - 1 + trailer_padding,
+    payload: key_len as usize + val_len as usize,
+  }
+}
+
+impl<T: Trailer, C> SkipMap<T, C> {
+  /// Returns the footprint of all the bytes allocated by the map, including all the versions,
+  /// the removed entries, the head and the tail, so the memory amplification of the
+  /// structure can be quantified and tuned, e.g. by [`Options::with_max_height`](crate::Options::with_max_height).
+  ///
+  /// This walks all the nodes, so it is O(n).
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use skl::SkipMap;
+  ///
+  /// let l = SkipMap::new().unwrap();
+  /// l.insert(0, b"key", b"value").unwrap();
+  ///
+  /// let report = l.memory_report();
+  /// assert_eq!(report.payload, 8);
+  /// assert!(report.overhead() > 0);
+  /// assert!(report.total() <= l.allocated());
+  /// ```
+  pub fn memory_report(&self) -> Footprint {
+    let trailer_size = core::mem::size_of::<T>();
+    let mut fp = Footprint::default();
+    if self.opts.unify() {
+      fp.headers += META_SIZE;
+    }
+
+    let mut add_node = |nd: NodePtr<T>, stamp: usize| {
+      // Safety: the nodes are allocated by the ARENA.
+      let node = unsafe { nd.as_ref() };
+      fp.headers += NODE_SIZE + trailer_size + stamp;
+      fp.towers += node.height() as usize * LINK_SIZE;
+    };
+    add_node(self.head, 0);
+    add_node(self.tail, 0);
+
+    let stamp = self.node_suffix_size() as usize;
+    let mut payload = 0;
+    let mut last_key_offset = None;
+    // Safety: the nodes are allocated by the ARENA.
+    unsafe {
+      let mut nd = self.get_next(self.head, 0);
+      while !nd.is_null() && nd.ptr != self.tail.ptr {
+        add_node(nd, stamp);
+        let node = nd.as_ref();
+        // the versions of a key may share the key bytes
+        if last_key_offset != Some(node.key_offset) {
+          payload += node.key_size() as usize;
+          last_key_offset = Some(node.key_offset);
+        }
+        let (_, len) = node.value.load(Ordering::Acquire);
+        if len != REMOVED {
+          payload += len as usize;
+        }
+        nd = self.get_next(nd, 0);
+      }
+    }
+
+    fp.payload = payload;
+    let used = self.allocated().saturating_sub(self.data_offset as usize);
+    fp.padding = used.saturating_sub(fp.headers + fp.towers + fp.payload);
+    fp
+  }
+}
@@ -0,0 +1,196 @@
+use std::vec::Vec;
+
+use super::*;
+
+/// Yields the newest version (less or equal to the version of the iterator) of every key
+/// of one map, including the removed ones, so they can shadow the older generations.
+struct Source<'a, T, C> {
+  map: &'a SkipMap<T, C>,
+  iter: AllVersionsIter<'a, T, C>,
+  head: Option<VersionedEntryRef<'a, T>>,
+  exhausted: bool,
+}
+
+impl<'a, T: Trailer, C: Comparator> Source<'a, T, C> {
+  fn new(map: &'a SkipMap<T, C>, version: u64) -> Self {
+    let mut iter = map.iter_all_versions(version);
+    let head = iter.next();
+    Self {
+      map,
+      iter,
+      exhausted: head.is_none(),
+      head,
+    }
+  }
+
+  /// Moves to the next key.
+  fn advance(&mut self) {
+    let prev = match self.head.take() {
+      Some(prev) => prev,
+      None => return,
+    };
+
+    if self.exhausted {
+      return;
+    }
+
+    for ent in self.iter.by_ref() {
+      if self.map.cmp.compare(ent.key, prev.key) != cmp::Ordering::Equal {
+        self.head = Some(ent);
+        return;
+      }
+    }
+    self.exhausted = true;
+  }
+}
+
+/// A k-way merging iterator over several [`SkipMap`]s (e.g. the active and the immutable
+/// generations of a memtable), yields the entries in key order, de-duplicated by key with
+/// the newest version winning.
+///
+/// When the same key has the same version in several maps, the map passed earlier wins,
+/// so the maps should be passed from the newest generation to the oldest. A removed entry
+/// shadows the older versions of the key in the other maps, and is not yielded.
+///
+/// All the maps must be ordered by the same comparator.
+///
+/// The iterator is also re-exported at the crate root, as `skl::MergeIterator`.
+///
+/// # Example
+///
+/// ```rust
+/// use core::sync::atomic::Ordering;
+/// use skl::{map::MergeIterator, SkipMap};
+///
+/// let active = SkipMap::new().unwrap();
+/// let immutable = SkipMap::new().unwrap();
+///
+/// immutable.insert(1, b"a", b"a1").unwrap();
+/// immutable.insert(1, b"b", b"b1").unwrap();
+/// immutable.insert(1, b"c", b"c1").unwrap();
+/// active.insert(2, b"a", b"a2").unwrap();
+/// active.compare_remove(2, b"b", Ordering::AcqRel, Ordering::Relaxed).unwrap();
+///
+/// let merged = MergeIterator::new(2, [&active, &immutable])
+///   .map(|ent| (ent.key().to_vec(), ent.value().to_vec()))
+///   .collect::<Vec<_>>();
+/// assert_eq!(merged, [(b"a".to_vec(), b"a2".to_vec()), (b"c".to_vec(), b"c1".to_vec())]);
+/// ```
+pub struct MergeIterator<'a, T, C> {
+  sources: Vec<Source<'a, T, C>>,
+  /// A binary min-heap of the indexes of the sources which have a head.
+  heap: Vec<usize>,
+}
+
+impl<'a, T: Trailer, C: Comparator> MergeIterator<'a, T, C> {
+  /// Creates a merging iterator over the newest versions (less or equal to `version`)
+  /// of the entries of the maps, the maps should be passed from the newest generation to
+  /// the oldest.
+  pub fn new(version: u64, maps: impl IntoIterator<Item = &'a SkipMap<T, C>>) -> Self {
+    Self::with_versions(maps.into_iter().map(|map| (map, version)))
+  }
+
+  /// Like [`MergeIterator::new`], but each map is read at its own version.
+  pub(super) fn with_versions(maps: impl IntoIterator<Item = (&'a SkipMap<T, C>, u64)>) -> Self {
+    let sources: Vec<_> = maps
+      .into_iter()
+      .map(|(map, version)| Source::new(map, version))
+      .collect();
+    let mut this = Self {
+      heap: Vec::with_capacity(sources.len()),
+      sources,
+    };
+    for idx in 0..this.sources.len() {
+      this.push(idx);
+    }
+    this
+  }
+
+  /// Returns `true` if the head of the source `a` should be yielded before the head of `b`.
+  fn less(&self, a: usize, b: usize) -> bool {
+    match (&self.sources[a].head, &self.sources[b].head) {
+      (Some(x), Some(y)) => match self.sources[a].map.cmp.compare(x.key, y.key) {
+        cmp::Ordering::Equal => (y.version(), a) < (x.version(), b),
+        ord => ord == cmp::Ordering::Less,
+      },
+      (x, _) => x.is_some(),
+    }
+  }
+
+  fn push(&mut self, idx: usize) {
+    if self.sources[idx].head.is_none() {
+      return;
+    }
+
+    self.heap.push(idx);
+    let mut pos = self.heap.len() - 1;
+    while pos > 0 {
+      let parent = (pos - 1) / 2;
+      if !self.less(self.heap[pos], self.heap[parent]) {
+        break;
+      }
+      self.heap.swap(pos, parent);
+      pos = parent;
+    }
+  }
+
+  fn pop(&mut self) -> Option<usize> {
+    if self.heap.is_empty() {
+      return None;
+    }
+
+    let top = self.heap.swap_remove(0);
+    let len = self.heap.len();
+    let mut pos = 0;
+    loop {
+      let (left, right) = (2 * pos + 1, 2 * pos + 2);
+      let mut min = pos;
+      if left < len && self.less(self.heap[left], self.heap[min]) {
+        min = left;
+      }
+      if right < len && self.less(self.heap[right], self.heap[min]) {
+        min = right;
+      }
+      if min == pos {
+        break;
+      }
+      self.heap.swap(pos, min);
+      pos = min;
+    }
+    Some(top)
+  }
+}
+
+impl<'a, T: Trailer, C: Comparator> Iterator for MergeIterator<'a, T, C> {
+  type Item = EntryRef<'a, T>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    loop {
+      let idx = self.pop()?;
+      let winner = self.sources[idx].head?;
+      self.sources[idx].advance();
+      self.push(idx);
+
+      // skip the older versions of the same key in the other sources
+      while let Some(&top) = self.heap.first() {
+        let same_key = match &self.sources[top].head {
+          Some(ent) => {
+            self.sources[top].map.cmp.compare(ent.key, winner.key) == cmp::Ordering::Equal
+          }
+          None => false,
+        };
+        if !same_key {
+          break;
+        }
+
+        self.pop();
+        self.sources[top].advance();
+        self.push(top);
+      }
+
+      if !winner.is_removed() {
+        return Some(EntryRef(winner));
+      }
+    }
+  }
+}
@@ -0,0 +1,125 @@
+use super::*;
+
+/// The operation counters of a map, returned by [`SkipMap::metrics`], e.g. to be exported to
+/// a dashboard.
+///
+/// The counters are shared by all the clones of the map, and count since the map is created,
+/// [`clear`](SkipMap::clear) does not reset them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Metrics {
+  /// How many nodes are linked by the writes, including the removed entries.
+  pub inserts: u64,
+  /// How many times the link CAS in the insert path has been retried, see
+  /// [`SkipMap::retries`].
+  pub retries: u64,
+  /// The distribution of the key comparisons per search, of the point lookups and of the
+  /// seeks of the iterators.
+  pub comparisons: Histogram,
+  /// The heights of the linked nodes, `heights[h - 1]` counts the nodes of height `h`.
+  pub heights: [u64; MAX_HEIGHT],
+  /// The bytes allocated in the ARENA, see [`SkipMap::allocated`].
+  pub arena_allocated: usize,
+  /// The capacity of the ARENA in bytes, see [`SkipMap::capacity`].
+  pub arena_capacity: usize,
+}
+
+/// The metrics counters shared by all the clones of a [`SkipMap`].
+#[derive(Debug)]
+pub(super) struct AtomicMetrics {
+  inserts: AtomicU64,
+  comparisons: AtomicHistogram,
+  heights: [AtomicU64; MAX_HEIGHT],
+}
+
+impl AtomicMetrics {
+  #[allow(clippy::declare_interior_mutable_const)]
+  const ZERO: AtomicU64 = AtomicU64::new(0);
+
+  pub(super) const fn new() -> Self {
+    Self {
+      inserts: AtomicU64::new(0),
+      comparisons: AtomicHistogram::new(),
+      heights: [Self::ZERO; MAX_HEIGHT],
+    }
+  }
+}
+
+/// Counts the key comparisons of a search, and records them when dropped.
+pub(super) struct Probe<'a> {
+  histogram: &'a AtomicHistogram,
+  comparisons: u32,
+}
+
+impl Probe<'_> {
+  /// Counts a key comparison.
+  #[inline]
+  pub(super) fn compared(&mut self) {
+    self.comparisons = self.comparisons.saturating_add(1);
+  }
+}
+
+impl Drop for Probe<'_> {
+  #[inline]
+  fn drop(&mut self) {
+    self.histogram.record(self.comparisons);
+  }
+}
+
+impl<T, C> SkipMap<T, C> {
+  /// Starts counting the key comparisons of a search.
+  #[inline]
+  pub(super) fn probe(&self) -> Probe<'_> {
+    Probe {
+      histogram: &self.metrics.comparisons,
+      comparisons: 0,
+    }
+  }
+
+  /// Records a node linked into the base level.
+  #[inline]
+  pub(super) fn record_insert(&self, nd: NodePtr<T>) {
+    // Safety: the node is allocated by the ARENA.
+    let height = unsafe { nd.as_ref() }.height() as usize;
+    self.metrics.inserts.fetch_add(1, Ordering::Relaxed);
+    if let Some(count) = height
+      .checked_sub(1)
+      .and_then(|h| self.metrics.heights.get(h))
+    {
+      count.fetch_add(1, Ordering::Relaxed);
+    }
+  }
+
+  /// Returns the operation counters of this map and its clones.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use skl::SkipMap;
+  ///
+  /// let l = SkipMap::new().unwrap();
+  /// for i in 0..100u32 {
+  ///   l.insert(0, &i.to_be_bytes(), b"v").unwrap();
+  /// }
+  /// l.get(0, &42u32.to_be_bytes()).unwrap();
+  ///
+  /// let metrics = l.metrics();
+  /// assert_eq!(metrics.inserts, 100);
+  /// assert_eq!(metrics.heights.iter().sum::<u64>(), 100);
+  /// assert_eq!(metrics.comparisons.count(), 1);
+  /// assert!(metrics.arena_allocated <= metrics.arena_capacity);
+  /// ```
+  pub fn metrics(&self) -> Metrics {
+    let mut heights = [0; MAX_HEIGHT];
+    for (dst, src) in heights.iter_mut().zip(self.metrics.heights.iter()) {
+      *dst = src.load(Ordering::Relaxed);
+    }
+    Metrics {
+      inserts: self.metrics.inserts.load(Ordering::Relaxed),
+      retries: self.retries(),
+      comparisons: self.metrics.comparisons.load(),
+      heights,
+      arena_allocated: self.allocated(),
+      arena_capacity: self.capacity(),
+    }
+  }
+}
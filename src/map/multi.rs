@@ -0,0 +1,175 @@
+use super::*;
+
+/// An iterator over the duplicates of a key in the multimap mode, in the insertion order,
+/// returned by [`SkipMap::get_all`].
+pub struct Duplicates<'a, T> {
+  iter: iterator::Iter<'a, T, Multi>,
+  pending: Option<EntryRef<'a, T>>,
+  key: std::vec::Vec<u8>,
+  done: bool,
+}
+
+impl<'a, T: Trailer> Iterator for Duplicates<'a, T> {
+  type Item = EntryRef<'a, T>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    while !self.done {
+      let ent = match self.pending.take() {
+        Some(ent) => ent,
+        None => self.iter.next()?,
+      };
+
+      // the keys without a sequence sort before the duplicates of the same key, so such a key
+      // is never in the middle of the duplicates.
+      match Multi::split(ent.0.key) {
+        Some((key, _)) if key == self.key.as_slice() => {}
+        _ => {
+          self.done = true;
+          break;
+        }
+      }
+
+      if !ent.0.is_removed() {
+        return Some(ent);
+      }
+    }
+    None
+  }
+}
+
+impl<T: Trailer> SkipMap<T, Multi> {
+  /// Appends a duplicate of the key, the existing duplicates are never overwritten, and the
+  /// duplicates are ordered by the insertion sequence, see [`Multi`].
+  ///
+  /// Returns the sequence of the duplicate, which identifies it for
+  /// [`remove_dup`](SkipMap::remove_dup).
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use skl::{Multi, SkipMap};
+  ///
+  /// let l = SkipMap::<u64, _>::with_comparator(Multi::new()).unwrap();
+  /// l.insert_dup(0, b"rust", b"doc3").unwrap();
+  /// let seq = l.insert_dup(0, b"rust", b"doc1").unwrap();
+  /// l.insert_dup(0, b"go", b"doc2").unwrap();
+  /// l.insert_dup(0, b"rust", b"doc2").unwrap();
+  ///
+  /// let docs = l
+  ///   .get_all(0, b"rust")
+  ///   .map(|ent| ent.value().to_vec())
+  ///   .collect::<Vec<_>>();
+  /// assert_eq!(docs, [b"doc3".to_vec(), b"doc1".to_vec(), b"doc2".to_vec()]);
+  ///
+  /// l.remove_dup(1, b"rust", seq).unwrap();
+  /// assert_eq!(l.get_all(1, b"rust").count(), 2);
+  /// ```
+  pub fn insert_dup(&self, trailer: T, key: &[u8], value: &[u8]) -> Result<u64, Error> {
+    if self.read_only() {
+      return Err(Error::read_only());
+    }
+
+    self.seed_seq();
+    let seq = self.cmp.next_seq();
+    let vk = self.fetch_vacant_dup_key(key, seq)?;
+    self
+      .update(
+        trailer,
+        Key::Vacant(vk),
+        value.len() as u32,
+        self.opts.value_alignment(),
+        |buf: &mut VacantBuffer| buf.write(value),
+        Ordering::Relaxed,
+        Ordering::Relaxed,
+        &mut Inserter::default(),
+        true,
+      )
+      .map(|_| seq)
+      .map_err(|e| e.expect_right("must be map::Error"))
+  }
+
+  /// Removes the duplicate of the key with the sequence.
+  ///
+  /// See [`compare_remove`](SkipMap::compare_remove) for more details and the returned entry.
+  pub fn remove_dup<'a>(
+    &'a self,
+    trailer: T,
+    key: &[u8],
+    seq: u64,
+  ) -> Result<Option<EntryRef<'a, T>>, Error> {
+    if self.read_only() {
+      return Err(Error::read_only());
+    }
+
+    let vk = self.fetch_vacant_dup_key(key, seq)?;
+    self
+      .update(
+        trailer,
+        Key::RemoveVacant(vk),
+        0,
+        self.opts.value_alignment(),
+        noop::<Infallible>,
+        Ordering::AcqRel,
+        Ordering::Relaxed,
+        &mut Inserter::default(),
+        true,
+      )
+      .map(|res| {
+        let old = match res {
+          Either::Left(old) => old,
+          Either::Right(Ok(old)) | Either::Right(Err(old)) => Some(old),
+        };
+        old.filter(|old| !old.is_removed()).map(EntryRef)
+      })
+      .map_err(|e| e.expect_right("must be map::Error"))
+  }
+
+  /// Returns the duplicate of the key with the sequence, if it exists.
+  pub fn get_dup(&self, version: u64, key: &[u8], seq: u64) -> Option<EntryRef<'_, T>> {
+    self.get_in(version, &Multi::key(key, seq))
+  }
+
+  /// Returns an iterator over the duplicates of the key (with the version less or equal to
+  /// `version`), in the insertion order.
+  ///
+  /// Use [`Multi::split`] to get the sequences from the keys of the entries.
+  pub fn get_all(&self, version: u64, key: &[u8]) -> Duplicates<'_, T> {
+    let mut iter = self.iter(version);
+    let start = Multi::key(key, 0);
+    let pending = iter.seek_lower_bound(Bound::Included(start.as_slice()));
+    Duplicates {
+      iter,
+      done: pending.is_none(),
+      pending,
+      key: key.to_vec(),
+    }
+  }
+
+  /// Seeds the sequence of the comparator from the largest sequence in the map on the first
+  /// write, so the comparator of a map which already has duplicates (e.g. written by another
+  /// process) never assigns a sequence which sorts before them.
+  fn seed_seq(&self) {
+    if self.cmp.is_seeded() {
+      return;
+    }
+
+    let next = self
+      .iter_all_versions(u64::MAX)
+      .filter_map(|ent| Multi::split(ent.key()).map(|(_, seq)| seq.saturating_add(1)))
+      .max()
+      .unwrap_or(0);
+    self.cmp.seed(next);
+  }
+
+  fn fetch_vacant_dup_key<'a>(&'a self, key: &[u8], seq: u64) -> Result<VacantBuffer<'a>, Error> {
+    self
+      .fetch_vacant_key((key.len() + Multi::SEQ_SIZE) as u32, |buf| {
+        buf.write(key)?;
+        buf.write(&seq.to_be_bytes())
+      })
+      .map_err(|e| match e {
+        Either::Left(_) => Error::KeyTooLarge(key.len() as u64),
+        Either::Right(e) => e,
+      })
+  }
+}
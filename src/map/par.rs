@@ -0,0 +1,282 @@
+use core::ops::Bound;
+
+use rayon::{
+  iter::{IntoParallelIterator, ParallelExtend, ParallelIterator},
+  slice::{ParallelSlice, ParallelSliceMut},
+};
+use std::vec::Vec;
+
+use super::*;
+
+/// Bulk parallel ingestion of unsorted data.
+///
+/// The incoming items are sorted by key in parallel and partitioned into contiguous key ranges,
+/// one range per rayon worker, and each worker inserts its range in order with
+/// [`SkipMap::load_sorted`], so the splice cache is reused across neighbouring keys.
+///
+/// # Panics
+///
+/// **Panics if an item cannot be inserted**, e.g. the ARENA does not have enough space, as
+/// [`ParallelExtend`] cannot return an error. Use [`SkipMap::try_par_extend`] to get the error
+/// instead.
+///
+/// # Example
+///
+/// ```rust
+/// use rayon::iter::{IntoParallelIterator, ParallelExtend, ParallelIterator};
+/// use skl::SkipMap;
+///
+/// let mut l = SkipMap::new().unwrap();
+/// l.par_extend((0..1000u32).into_par_iter().map(|i| (0, i.to_be_bytes(), i.to_le_bytes())));
+/// assert_eq!(l.len(), 1000);
+/// ```
+impl<T, C, K, V> ParallelExtend<(T, K, V)> for SkipMap<T, C>
+where
+  T: Trailer + Send + Sync,
+  C: Comparator + Sync,
+  K: AsRef<[u8]> + Send + Sync,
+  V: AsRef<[u8]> + Send + Sync,
+{
+  fn par_extend<I>(&mut self, par_iter: I)
+  where
+    I: IntoParallelIterator<Item = (T, K, V)>,
+  {
+    if let Err(e) = self.try_par_extend(par_iter) {
+      panic!("failed to insert the item: {e}");
+    }
+  }
+}
+
+impl<T, C> SkipMap<T, C>
+where
+  T: Trailer + Send + Sync,
+  C: Comparator + Sync,
+{
+  /// Like [`par_extend`](ParallelExtend::par_extend), but returns the first error instead of
+  /// panicking, the items inserted before it are kept.
+  ///
+  /// The items are sorted by key in parallel and partitioned into contiguous key ranges, one
+  /// range per rayon worker, and each worker inserts its range in order with
+  /// [`load_sorted`](SkipMap::load_sorted).
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rayon::iter::{IntoParallelIterator, ParallelIterator};
+  /// use skl::{Options, SkipMap};
+  ///
+  /// let l = SkipMap::with_options(Options::new().with_capacity(1 << 20)).unwrap();
+  /// l.try_par_extend((0..1000u32).into_par_iter().map(|i| (0, i.to_be_bytes(), i.to_le_bytes())))
+  ///   .unwrap();
+  /// assert_eq!(l.len(), 1000);
+  ///
+  /// let small = SkipMap::with_options(Options::new().with_capacity(1024)).unwrap();
+  /// let err = small
+  ///   .try_par_extend((0..1000u32).into_par_iter().map(|i| (0, i.to_be_bytes(), i.to_le_bytes())))
+  ///   .unwrap_err();
+  /// assert!(err.is_arena_full());
+  /// ```
+  pub fn try_par_extend<I, K, V>(&self, par_iter: I) -> Result<(), Error>
+  where
+    I: IntoParallelIterator<Item = (T, K, V)>,
+    K: AsRef<[u8]> + Send + Sync,
+    V: AsRef<[u8]> + Send + Sync,
+  {
+    if self.read_only() {
+      return Err(Error::read_only());
+    }
+
+    let mut items: Vec<(T, K, V)> = par_iter.into_par_iter().collect();
+    if items.is_empty() {
+      return Ok(());
+    }
+
+    items.par_sort_unstable_by(|a, b| {
+      self
+        .cmp
+        .compare(a.1.as_ref(), b.1.as_ref())
+        .then_with(|| b.0.version().cmp(&a.0.version()))
+    });
+
+    let chunk_size = (items.len() / rayon::current_num_threads()).max(1);
+    items
+      .par_chunks(chunk_size)
+      .try_for_each(|chunk| self.insert_sorted_chunk(chunk))
+  }
+
+  /// Inserts the items, which are sorted by key (and by version descending for the same key),
+  /// in `threads` parallel parts, for fast warm-up loads of large datasets.
+  ///
+  /// The input is partitioned into `threads` contiguous key ranges, and each worker loads its
+  /// range with [`load_sorted`](SkipMap::load_sorted), so the searches are hinted by the previous
+  /// insert, and the workers only contend at the range boundaries. The result is the same as
+  /// inserting the items one by one, unsorted input is still inserted correctly, but slower.
+  ///
+  /// The workers run in the current rayon thread pool, the global one unless the call is made
+  /// inside [`ThreadPool::install`](rayon::ThreadPool::install), so the loads do not spawn
+  /// threads. Returns the first error, the items inserted before it are kept.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use skl::{Options, SkipMap};
+  ///
+  /// let l = SkipMap::with_options(Options::new().with_capacity(1 << 20)).unwrap();
+  /// let items = (0..1000u32)
+  ///   .map(|i| (0, i.to_be_bytes(), i.to_le_bytes()))
+  ///   .collect::<Vec<_>>();
+  /// l.par_bulk_insert(&items, 4).unwrap();
+  /// assert_eq!(l.len(), 1000);
+  /// ```
+  pub fn par_bulk_insert<K, V>(
+    &self,
+    sorted_items: &[(T, K, V)],
+    threads: usize,
+  ) -> Result<(), Error>
+  where
+    K: AsRef<[u8]> + Sync,
+    V: AsRef<[u8]> + Sync,
+  {
+    if self.read_only() {
+      return Err(Error::read_only());
+    }
+    if sorted_items.is_empty() {
+      return Ok(());
+    }
+
+    let threads = threads.max(1);
+    let chunk_size = (sorted_items.len() + threads - 1) / threads;
+    sorted_items
+      .par_chunks(chunk_size)
+      .try_for_each(|chunk| self.insert_sorted_chunk(chunk))
+  }
+
+  /// Returns a parallel iterator over the latest versions of the entries less or equal to the
+  /// given version, like [`iter`](SkipMap::iter), but unordered.
+  ///
+  /// The key space is partitioned with the keys of the highest level of the towers which has
+  /// at least [`rayon::current_num_threads`] nodes, so the split points cost a walk of a few
+  /// nodes instead of a scan, and the ranges between them are about the same size, as the
+  /// heights are random. Each range is scanned with [`range`](SkipMap::range) by one worker,
+  /// and its bounds are ordered by the comparator of the map, not bytewise.
+  ///
+  /// The entries inserted during the iteration may or may not be yielded, the same as
+  /// [`iter`](SkipMap::iter).
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rayon::iter::ParallelIterator;
+  /// use skl::SkipMap;
+  ///
+  /// let l = SkipMap::new().unwrap();
+  /// for i in 0..1000u32 {
+  ///   l.insert(0, &i.to_be_bytes(), &i.to_le_bytes()).unwrap();
+  /// }
+  /// assert_eq!(l.par_iter(0).count(), 1000);
+  /// ```
+  pub fn par_iter(&self, version: u64) -> impl ParallelIterator<Item = EntryRef<'_, T>> + '_ {
+    let splits = self.split_keys(rayon::current_num_threads());
+    let mut ranges = Vec::with_capacity(splits.len() + 1);
+    let mut lower = Bound::Unbounded;
+    for key in splits {
+      let key = SplitKey {
+        cmp: &self.cmp,
+        key,
+      };
+      ranges.push((lower, Bound::Excluded(key)));
+      lower = Bound::Included(key);
+    }
+    ranges.push((lower, Bound::Unbounded));
+
+    ranges
+      .into_par_iter()
+      .flat_map_iter(move |r| self.range(version, r))
+  }
+
+  /// Returns the distinct keys of the highest level which has at least `n` nodes, or of the
+  /// base level if the map is smaller.
+  fn split_keys(&self, n: usize) -> Vec<&[u8]> {
+    let mut level = self.height() as usize - 1;
+    loop {
+      let mut keys: Vec<&[u8]> = Vec::new();
+      // Safety: the nodes are reached at the level, so their towers are at least that high.
+      unsafe {
+        let mut nd = self.get_next(self.head, level);
+        while !nd.is_null() && nd.ptr != self.tail.ptr {
+          let key = nd.as_ref().get_key(&self.arena);
+          // The versions of a key are neighbours, keep only the first one.
+          if keys.last().map_or(true, |last| {
+            self.cmp.compare(last, key) != cmp::Ordering::Equal
+          }) {
+            keys.push(key);
+          }
+          nd = self.get_next(nd, level);
+        }
+      }
+
+      if keys.len() >= n || level == 0 {
+        return keys;
+      }
+      level -= 1;
+    }
+  }
+
+  fn insert_sorted_chunk<K, V>(&self, chunk: &[(T, K, V)]) -> Result<(), Error>
+  where
+    K: AsRef<[u8]>,
+    V: AsRef<[u8]>,
+  {
+    self
+      .load_sorted(
+        chunk
+          .iter()
+          .map(|(trailer, key, value)| (*trailer, key, value)),
+      )
+      .map(|_| ())
+  }
+}
+
+/// A split key of [`SkipMap::par_iter`], ordered by the comparator of the map, so the ranges
+/// between the split keys are contiguous for any comparator.
+struct SplitKey<'a, C> {
+  cmp: &'a C,
+  key: &'a [u8],
+}
+
+impl<C> Clone for SplitKey<'_, C> {
+  #[inline]
+  fn clone(&self) -> Self {
+    *self
+  }
+}
+
+impl<C> Copy for SplitKey<'_, C> {}
+
+impl<C: Comparator> PartialEq<&[u8]> for SplitKey<'_, C> {
+  #[inline]
+  fn eq(&self, other: &&[u8]) -> bool {
+    self.cmp.compare(self.key, other) == cmp::Ordering::Equal
+  }
+}
+
+impl<C: Comparator> PartialOrd<&[u8]> for SplitKey<'_, C> {
+  #[inline]
+  fn partial_cmp(&self, other: &&[u8]) -> Option<cmp::Ordering> {
+    Some(self.cmp.compare(self.key, other))
+  }
+}
+
+impl<C: Comparator> PartialEq<SplitKey<'_, C>> for &[u8] {
+  #[inline]
+  fn eq(&self, other: &SplitKey<'_, C>) -> bool {
+    other.cmp.compare(self, other.key) == cmp::Ordering::Equal
+  }
+}
+
+impl<C: Comparator> PartialOrd<SplitKey<'_, C>> for &[u8] {
+  #[inline]
+  fn partial_cmp(&self, other: &SplitKey<'_, C>) -> Option<cmp::Ordering> {
+    Some(other.cmp.compare(self, other.key))
+  }
+}
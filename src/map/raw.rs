@@ -0,0 +1,228 @@
+//! Unsafe raw traversal of a [`SkipMap`], for experts who want to prototype alternative
+//! algorithms (e.g. custom unlink schemes) on top of the ARENA without forking the crate.
+//!
+//! The nodes are identified by their offsets in the ARENA, see [`layout`](super::layout)
+//! for how they are stored.
+//!
+//! # Invariants
+//!
+//! All the methods of [`Raw`] are `unsafe`, the caller must uphold the following invariants,
+//! otherwise the behavior is undefined:
+//!
+//! - A [`RawNode`] must be the head, the tail, or a node returned by [`Raw::next`] or [`Raw::prev`]
+//!   of the same map, or an offset the caller knows points to a node of the same map.
+//! - `level` must be less than the height of the node, see [`Raw::height`]. The head and the tail
+//!   nodes have the full height ([`Options::max_height`](crate::Options::max_height)).
+//! - The key, value and trailer of the head and the tail nodes must not be read.
+//! - Every level must stay a sorted, doubly linked list from the head to the tail, which
+//!   the rest of the map relies on. The next links are the source of truth, the previous links
+//!   may lag behind while a node is being linked, see [`Raw::cas_next`].
+//! - The nodes are never freed, an unlinked node can still be read by the concurrent readers.
+
+use core::sync::atomic::Ordering;
+
+use super::{NodePtr, SkipMap, Trailer};
+
+/// A node of the [`SkipMap`], identified by its offset in the ARENA.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct RawNode(u32);
+
+impl RawNode {
+  /// Creates a raw node from the offset of the node in the ARENA.
+  #[inline]
+  pub const fn from_offset(offset: u32) -> Self {
+    Self(offset)
+  }
+
+  /// Returns the offset of the node in the ARENA.
+  #[inline]
+  pub const fn offset(&self) -> u32 {
+    self.0
+  }
+}
+
+/// A raw view of the [`SkipMap`], returned by [`SkipMap::raw`], see the [module](self)
+/// documentation for the invariants.
+#[derive(Debug, Clone, Copy)]
+pub struct Raw<'a, T, C> {
+  map: &'a SkipMap<T, C>,
+}
+
+impl<T, C> SkipMap<T, C> {
+  /// Returns the raw view of the map.
+  #[cfg_attr(docsrs, doc(cfg(feature = "raw")))]
+  #[inline]
+  pub const fn raw(&self) -> Raw<'_, T, C> {
+    Raw { map: self }
+  }
+}
+
+impl<'a, T, C> Raw<'a, T, C> {
+  /// Returns the map.
+  #[inline]
+  pub const fn map(&self) -> &'a SkipMap<T, C> {
+    self.map
+  }
+
+  /// Returns the head node.
+  #[inline]
+  pub const fn head(&self) -> RawNode {
+    RawNode(self.map.head.offset)
+  }
+
+  /// Returns the tail node.
+  #[inline]
+  pub const fn tail(&self) -> RawNode {
+    RawNode(self.map.tail.offset)
+  }
+
+  #[inline]
+  unsafe fn ptr(&self, node: RawNode) -> NodePtr<T> {
+    NodePtr::new(self.map.arena.get_pointer(node.0 as usize) as _, node.0)
+  }
+
+  /// Returns the height of the node.
+  ///
+  /// # Safety
+  ///
+  /// See the [module](self) documentation.
+  #[inline]
+  pub unsafe fn height(&self, node: RawNode) -> u8 {
+    self.ptr(node).as_ref().height()
+  }
+
+  /// Returns the next node of the node at the given level.
+  ///
+  /// # Safety
+  ///
+  /// See the [module](self) documentation.
+  #[inline]
+  pub unsafe fn next(&self, node: RawNode, level: usize) -> RawNode {
+    RawNode(self.ptr(node).next_offset(&self.map.arena, level))
+  }
+
+  /// Returns the previous node of the node at the given level.
+  ///
+  /// # Safety
+  ///
+  /// See the [module](self) documentation.
+  #[inline]
+  pub unsafe fn prev(&self, node: RawNode, level: usize) -> RawNode {
+    RawNode(self.ptr(node).prev_offset(&self.map.arena, level))
+  }
+
+  /// Repoints the next link of the node at the given level from `current` to `new`,
+  /// returns the actual next node on failure.
+  ///
+  /// # Safety
+  ///
+  /// See the [module](self) documentation, `new` must be fully initialized and its
+  /// links at `level` must be written (see [`write_links`](Raw::write_links)) before
+  /// it is published by this method.
+  #[inline]
+  pub unsafe fn cas_next(
+    &self,
+    node: RawNode,
+    level: usize,
+    current: RawNode,
+    new: RawNode,
+    success: Ordering,
+    failure: Ordering,
+  ) -> Result<(), RawNode> {
+    self
+      .ptr(node)
+      .cas_next_offset(&self.map.arena, level, current.0, new.0, success, failure)
+      .map(|_| ())
+      .map_err(RawNode)
+  }
+
+  /// Repoints the previous link of the node at the given level from `current` to `new`,
+  /// returns the actual previous node on failure.
+  ///
+  /// # Safety
+  ///
+  /// See the [module](self) documentation.
+  #[inline]
+  pub unsafe fn cas_prev(
+    &self,
+    node: RawNode,
+    level: usize,
+    current: RawNode,
+    new: RawNode,
+    success: Ordering,
+    failure: Ordering,
+  ) -> Result<(), RawNode> {
+    self
+      .ptr(node)
+      .cas_prev_offset(&self.map.arena, level, current.0, new.0, success, failure)
+      .map(|_| ())
+      .map_err(RawNode)
+  }
+
+  /// Writes the links of the node at the given level non-atomically.
+  ///
+  /// # Safety
+  ///
+  /// See the [module](self) documentation, the node must not be reachable at `level` yet.
+  #[inline]
+  pub unsafe fn write_links(&self, node: RawNode, level: usize, prev: RawNode, next: RawNode) {
+    self
+      .ptr(node)
+      .write_tower(&self.map.arena, level, prev.0, next.0)
+  }
+
+  /// Returns the key of the node.
+  ///
+  /// # Safety
+  ///
+  /// See the [module](self) documentation.
+  #[inline]
+  pub unsafe fn key(&self, node: RawNode) -> &'a [u8] {
+    self.ptr(node).as_ref().get_key(&self.map.arena)
+  }
+
+  /// Returns the value of the node, `None` means the entry is removed.
+  ///
+  /// # Safety
+  ///
+  /// See the [module](self) documentation.
+  #[inline]
+  pub unsafe fn value(&self, node: RawNode) -> Option<&'a [u8]> {
+    self.ptr(node).as_ref().get_value(&self.map.arena)
+  }
+
+  /// Returns `len` bytes of the ARENA starting at `offset`.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `offset..offset + len` is not within the allocated part of the ARENA, also in the
+  /// release builds.
+  ///
+  /// # Safety
+  ///
+  /// See the [module](self) documentation.
+  #[inline]
+  #[track_caller]
+  pub unsafe fn bytes(&self, offset: usize, len: usize) -> &'a [u8] {
+    let allocated = self.map.arena.allocated();
+    assert!(
+      offset
+        .checked_add(len)
+        .map_or(false, |end| end <= allocated),
+      "region {offset}..{offset}+{len} is out of the allocated part of the ARENA ({allocated})"
+    );
+    self.map.arena.get_bytes(offset, len)
+  }
+}
+
+impl<'a, T: Trailer, C> Raw<'a, T, C> {
+  /// Returns the trailer of the node.
+  ///
+  /// # Safety
+  ///
+  /// See the [module](self) documentation.
+  #[inline]
+  pub unsafe fn trailer(&self, node: RawNode) -> T {
+    self.ptr(node).as_ref().get_trailer(&self.map.arena)
+  }
+}
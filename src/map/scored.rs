@@ -0,0 +1,194 @@
+use super::*;
+
+/// An iterator over the entries of the sorted-set mode whose scores are in a range,
+/// returned by [`SkipMap::range_by_score`].
+pub struct ScoreRange<'a, T> {
+  iter: iterator::Iter<'a, T, Scored>,
+  pending: Option<EntryRef<'a, T>>,
+  end: Bound<u64>,
+  done: bool,
+}
+
+impl<'a, T: Trailer> Iterator for ScoreRange<'a, T> {
+  type Item = EntryRef<'a, T>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    while !self.done {
+      let ent = match self.pending.take() {
+        Some(ent) => ent,
+        None => self.iter.next()?,
+      };
+
+      // the keys without a score are not members, they sort among the members by their bytes
+      let score = match Scored::split(ent.0.key) {
+        Some((score, _)) => score,
+        None => continue,
+      };
+      let in_range = match self.end {
+        Bound::Included(end) => score <= end,
+        Bound::Excluded(end) => score < end,
+        Bound::Unbounded => true,
+      };
+      if !in_range {
+        self.done = true;
+        break;
+      }
+
+      if !ent.0.is_removed() {
+        return Some(ent);
+      }
+    }
+    None
+  }
+}
+
+impl<T: Trailer> SkipMap<T, Scored> {
+  /// Upserts a member with the score, the key will be stored as the composite of the score
+  /// and the member, see [`Scored`].
+  ///
+  /// A member can be stored with several scores, to change the score of a member, remove
+  /// the old one by [`remove_scored`](SkipMap::remove_scored) first.
+  ///
+  /// See [`insert`](SkipMap::insert) for more details.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use skl::{Scored, SkipMap};
+  ///
+  /// let l = SkipMap::<u64, _>::with_comparator(Scored).unwrap();
+  /// l.insert_scored(0, 30, b"carol", b"c").unwrap();
+  /// l.insert_scored(0, 10, b"alice", b"a").unwrap();
+  /// l.insert_scored(0, 20, b"bob", b"b").unwrap();
+  ///
+  /// let members = l
+  ///   .range_by_score(0, 15..)
+  ///   .map(|ent| Scored::split(ent.key()).unwrap().1.to_vec())
+  ///   .collect::<Vec<_>>();
+  /// assert_eq!(members, [b"bob".to_vec(), b"carol".to_vec()]);
+  /// assert_eq!(l.rank_by_score(0, 20), 1);
+  /// ```
+  pub fn insert_scored<'a, 'b: 'a>(
+    &'a self,
+    trailer: T,
+    score: u64,
+    member: &'b [u8],
+    value: &'b [u8],
+  ) -> Result<Option<EntryRef<'a, T>>, Error> {
+    if self.read_only() {
+      return Err(Error::read_only());
+    }
+
+    let vk = self.fetch_vacant_scored_key(score, member)?;
+    self
+      .update(
+        trailer,
+        Key::Vacant(vk),
+        value.len() as u32,
+        self.opts.value_alignment(),
+        |buf: &mut VacantBuffer| buf.write(value),
+        Ordering::Relaxed,
+        Ordering::Relaxed,
+        &mut Inserter::default(),
+        true,
+      )
+      .map(|old| {
+        old.expect_left("insert must get InsertOk").and_then(|old| {
+          if old.is_removed() {
+            None
+          } else {
+            Some(EntryRef(old))
+          }
+        })
+      })
+      .map_err(|e| e.expect_right("must be map::Error"))
+  }
+
+  /// Removes the member with the score.
+  ///
+  /// See [`compare_remove`](SkipMap::compare_remove) for more details and the returned entry.
+  pub fn remove_scored<'a, 'b: 'a>(
+    &'a self,
+    trailer: T,
+    score: u64,
+    member: &'b [u8],
+  ) -> Result<Option<EntryRef<'a, T>>, Error> {
+    if self.read_only() {
+      return Err(Error::read_only());
+    }
+
+    let vk = self.fetch_vacant_scored_key(score, member)?;
+    self
+      .update(
+        trailer,
+        Key::RemoveVacant(vk),
+        0,
+        self.opts.value_alignment(),
+        noop::<Infallible>,
+        Ordering::AcqRel,
+        Ordering::Relaxed,
+        &mut Inserter::default(),
+        true,
+      )
+      .map(|res| {
+        let old = match res {
+          Either::Left(old) => old,
+          Either::Right(Ok(old)) | Either::Right(Err(old)) => Some(old),
+        };
+        old.filter(|old| !old.is_removed()).map(EntryRef)
+      })
+      .map_err(|e| e.expect_right("must be map::Error"))
+  }
+
+  /// Returns the entry of the member with the score, if it exists.
+  pub fn get_scored(&self, version: u64, score: u64, member: &[u8]) -> Option<EntryRef<'_, T>> {
+    self.get_in(version, &Scored::key(score, member))
+  }
+
+  /// Returns an iterator over the entries whose scores are in the range, ordered by the
+  /// score and then by the member. The keys shorter than [`Scored::SCORE_SIZE`] are skipped.
+  ///
+  /// Use [`Scored::split`] to get the score and the member from the keys of the entries.
+  pub fn range_by_score(&self, version: u64, range: impl RangeBounds<u64>) -> ScoreRange<'_, T> {
+    let mut iter = self.iter(version);
+    let start = match range.start_bound() {
+      Bound::Included(&score) => Some(Scored::key(score, &[])),
+      Bound::Excluded(&score) => score.checked_add(1).map(|score| Scored::key(score, &[])),
+      Bound::Unbounded => Some(std::vec::Vec::new()),
+    };
+    let pending = start
+      .as_ref()
+      .and_then(|start| iter.seek_lower_bound(Bound::Included(start.as_slice())));
+
+    ScoreRange {
+      iter,
+      done: pending.is_none(),
+      pending,
+      end: range.end_bound().cloned(),
+    }
+  }
+
+  /// Returns the rank (0-based) the score would take in the map, i.e. how many members
+  /// have a score less than the given one.
+  ///
+  /// The skiplist does not track the subtree sizes, so this is O(rank).
+  pub fn rank_by_score(&self, version: u64, score: u64) -> usize {
+    self.range_by_score(version, ..score).count()
+  }
+
+  fn fetch_vacant_scored_key<'a>(
+    &'a self,
+    score: u64,
+    member: &[u8],
+  ) -> Result<VacantBuffer<'a>, Error> {
+    self
+      .fetch_vacant_key((Scored::SCORE_SIZE + member.len()) as u32, |buf| {
+        buf.write(&score.to_be_bytes())?;
+        buf.write(member)
+      })
+      .map_err(|e| match e {
+        Either::Left(_) => Error::KeyTooLarge(member.len() as u64),
+        Either::Right(e) => e,
+      })
+  }
+}
@@ -0,0 +1,154 @@
+use std::{sync::RwLock, vec::Vec};
+
+use super::*;
+
+/// A [`SkipMap`] which grows on demand, by chaining new segments (maps with their own ARENA)
+/// when the active one is full, instead of provisioning the whole capacity up front.
+///
+/// The ARENA itself has a fixed capacity, so the growth happens at the map level: the writes go
+/// to the newest segment, each new segment doubles the capacity of the previous one, and the
+/// existing segments are never moved, so the entries and their references stay valid. The
+/// reads merge the segments, the newest version of a key wins, and the newer segment wins
+/// the ties, like [`MergeIterator`].
+///
+/// # Example
+///
+/// ```rust
+/// use skl::{map::SegmentedMap, Options};
+///
+/// let l = SegmentedMap::with_options(Options::new().with_capacity(1024)).unwrap();
+/// for i in 0..100u32 {
+///   l.insert(0, &i.to_be_bytes(), &[0; 64]).unwrap();
+/// }
+/// assert!(l.num_segments() > 1);
+/// assert!(l.get(0, &42u32.to_be_bytes()).is_some());
+///
+/// let segments = l.segments();
+/// assert_eq!(skl::map::MergeIterator::new(0, &segments).count(), 100);
+/// ```
+#[derive(Debug)]
+pub struct SegmentedMap<T = u64, C = Ascend> {
+  opts: Options,
+  cmp: C,
+  /// The segments, from the oldest to the newest.
+  segments: RwLock<Vec<SkipMap<T, C>>>,
+}
+
+impl<T> SegmentedMap<T> {
+  /// Creates a new map, the first segment is created with the [`Options`].
+  #[inline]
+  pub fn with_options(opts: Options) -> Result<Self, Error> {
+    Self::with_options_and_comparator(opts, Ascend)
+  }
+}
+
+impl<T, C: Clone> SegmentedMap<T, C> {
+  /// Like [`SegmentedMap::with_options`], but with a custom [`Comparator`].
+  pub fn with_options_and_comparator(opts: Options, cmp: C) -> Result<Self, Error> {
+    let first = SkipMap::with_options_and_comparator(opts, cmp.clone())?;
+    Ok(Self {
+      opts,
+      cmp,
+      segments: RwLock::new(std::vec![first]),
+    })
+  }
+
+  /// Returns the number of the segments.
+  pub fn num_segments(&self) -> usize {
+    self
+      .segments
+      .read()
+      .unwrap_or_else(|e| e.into_inner())
+      .len()
+  }
+
+  /// Returns the segments, from the newest to the oldest, e.g. to be merged by
+  /// [`MergeIterator`].
+  pub fn segments(&self) -> Vec<SkipMap<T, C>> {
+    self
+      .segments
+      .read()
+      .unwrap_or_else(|e| e.into_inner())
+      .iter()
+      .rev()
+      .cloned()
+      .collect()
+  }
+
+  /// Returns the newest segment and the number of the segments.
+  fn active(&self) -> (SkipMap<T, C>, usize) {
+    let segments = self.segments.read().unwrap_or_else(|e| e.into_inner());
+    (segments[segments.len() - 1].clone(), segments.len())
+  }
+
+  /// Chains a new segment with at least twice the capacity of the newest one and of
+  /// `at_least`, unless another writer already did it after the `seen`-th one.
+  fn grow(&self, seen: usize, at_least: usize) -> Result<(), Error> {
+    let mut segments = self.segments.write().unwrap_or_else(|e| e.into_inner());
+    if segments.len() != seen {
+      return Ok(());
+    }
+
+    let capacity = segments[seen - 1]
+      .capacity()
+      .max(at_least)
+      .saturating_mul(2)
+      .min(u32::MAX as usize);
+    let opts = self.opts.with_capacity(capacity as u32);
+    segments.push(SkipMap::with_options_and_comparator(
+      opts,
+      self.cmp.clone(),
+    )?);
+    Ok(())
+  }
+}
+
+impl<T: Trailer, C: Comparator + Clone> SegmentedMap<T, C> {
+  /// Upserts a key-value pair into the newest segment, a new segment is chained if the newest
+  /// one is full.
+  ///
+  /// An entry larger than the newest segment gets a segment large enough for it, up to the
+  /// maximum capacity of the ARENA.
+  pub fn insert(&self, trailer: T, key: &[u8], value: &[u8]) -> Result<(), Error> {
+    self.write(|map| map.insert(trailer, key, value).map(|_| ()))
+  }
+
+  /// Removes the key, by writing a removed entry into the newest segment, which shadows the
+  /// entries of the older segments.
+  pub fn remove(&self, trailer: T, key: &[u8]) -> Result<(), Error> {
+    self.write(|map| map.remove(trailer, key).map(|_| ()))
+  }
+
+  /// Returns the newest entry of the key (with the version less or equal to `version`) in all
+  /// the segments.
+  pub fn get(&self, version: u64, key: &[u8]) -> Option<Entry<T>> {
+    let segments = self.segments.read().unwrap_or_else(|e| e.into_inner());
+    let mut newest: Option<VersionedEntryRef<'_, T>> = None;
+    for map in segments.iter().rev() {
+      if let Some(ent) = map.newest_in(version, key) {
+        if newest.map_or(true, |n| ent.version() > n.version()) {
+          newest = Some(ent);
+        }
+      }
+    }
+
+    newest
+      .filter(|ent| !ent.is_removed())
+      .map(|ent| EntryRef(ent).to_owned())
+  }
+
+  fn write(&self, f: impl Fn(&SkipMap<T, C>) -> Result<(), Error>) -> Result<(), Error> {
+    let mut grown = false;
+    loop {
+      let (map, seen) = self.active();
+      let at_least = match f(&map) {
+        Err(Error::EntryTooLarge(size)) if !grown && size <= u32::MAX as u64 => size as usize,
+        // another writer may have filled the new segment
+        Err(e) if e.is_arena_full() && !(grown && map.is_empty()) => 0,
+        res => return res,
+      };
+      self.grow(seen, at_least)?;
+      grown = true;
+    }
+  }
+}
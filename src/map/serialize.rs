@@ -0,0 +1,194 @@
+use core::{fmt, marker::PhantomData};
+use std::vec::Vec;
+
+use ::serde::{
+  de::{self, Deserialize, Deserializer, SeqAccess, Visitor},
+  ser::{Serialize, SerializeSeq, SerializeTuple, Serializer},
+};
+
+use super::*;
+
+/// The ARENA overhead of the head, the tail and the meta, which is added to the capacity of
+/// the deserialized map.
+const BASE_CAPACITY: usize = 4096;
+
+/// A byte slice serialized with [`Serializer::serialize_bytes`], instead of a sequence of `u8`.
+struct Bytes<'a>(&'a [u8]);
+
+impl<'a> Serialize for Bytes<'a> {
+  #[inline]
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_bytes(self.0)
+  }
+}
+
+/// The owned counterpart of [`Bytes`], accepts both the bytes and the sequences of `u8`.
+struct ByteBuf(Vec<u8>);
+
+impl<'de> Deserialize<'de> for ByteBuf {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    struct ByteBufVisitor;
+
+    impl<'de> Visitor<'de> for ByteBufVisitor {
+      type Value = ByteBuf;
+
+      fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a byte array")
+      }
+
+      fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+        Ok(ByteBuf(v.to_vec()))
+      }
+
+      fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<Self::Value, E> {
+        Ok(ByteBuf(v))
+      }
+
+      fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut buf = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(b) = seq.next_element()? {
+          buf.push(b);
+        }
+        Ok(ByteBuf(buf))
+      }
+    }
+
+    deserializer.deserialize_byte_buf(ByteBufVisitor)
+  }
+}
+
+/// One entry of the serialized map, `value` is `None` for the removed entries.
+struct Record<'a, T> {
+  key: &'a [u8],
+  trailer: &'a T,
+  value: Option<&'a [u8]>,
+}
+
+impl<'a, T: Serialize> Serialize for Record<'a, T> {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    let mut tuple = serializer.serialize_tuple(3)?;
+    tuple.serialize_element(&Bytes(self.key))?;
+    tuple.serialize_element(self.trailer)?;
+    tuple.serialize_element(&self.value.map(Bytes))?;
+    tuple.end()
+  }
+}
+
+/// Serializes the map as an ordered sequence of `(key, trailer, value)` entries, all versions
+/// of the keys included, in the order of [`iter_all_versions`](SkipMap::iter_all_versions). The
+/// trailer carries the version and the TTL of the entry, the value is `None` for the removed
+/// entries.
+///
+/// The entries inserted during the serialization are not serialized.
+///
+/// # Example
+///
+/// ```rust
+/// use skl::SkipMap;
+///
+/// let l = SkipMap::new().unwrap();
+/// l.insert(0, b"a", b"a0").unwrap();
+/// l.insert(1, b"a", b"a1").unwrap();
+/// l.remove(1, b"b").unwrap();
+///
+/// let json = serde_json::to_string(&l).unwrap();
+/// assert_eq!(json, "[[[97],1,[97,49]],[[97],0,[97,48]],[[98],1,null]]");
+///
+/// let l2: SkipMap = serde_json::from_str(&json).unwrap();
+/// assert_eq!(l2.get(0, b"a").unwrap().value(), b"a0");
+/// assert_eq!(l2.get(1, b"a").unwrap().value(), b"a1");
+/// assert!(l2.get(1, b"b").is_none());
+/// ```
+impl<T: Trailer + Serialize, C: Comparator> Serialize for SkipMap<T, C> {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    let entries = self.iter_all_versions(u64::MAX).collect::<Vec<_>>();
+    let mut seq = serializer.serialize_seq(Some(entries.len()))?;
+    for ent in &entries {
+      seq.serialize_element(&Record {
+        key: ent.key(),
+        trailer: ent.trailer(),
+        value: ent.value(),
+      })?;
+    }
+    seq.end()
+  }
+}
+
+/// Deserializes the entries written by the [`Serialize`] implementation, by bulk loading them
+/// into a new ARENA, which is large enough for the entries, with the default [`Options`] and
+/// comparator.
+impl<'de, T, C> Deserialize<'de> for SkipMap<T, C>
+where
+  T: Trailer + Deserialize<'de>,
+  C: Comparator + Default,
+{
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    struct MapVisitor<T, C>(PhantomData<fn() -> (T, C)>);
+
+    impl<'de, T, C> Visitor<'de> for MapVisitor<T, C>
+    where
+      T: Trailer + Deserialize<'de>,
+      C: Comparator + Default,
+    {
+      type Value = SkipMap<T, C>;
+
+      fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a sequence of (key, trailer, value) entries")
+      }
+
+      fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut entries = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some((key, trailer, value)) =
+          seq.next_element::<(ByteBuf, T, Option<ByteBuf>)>()?
+        {
+          entries.push((key.0, trailer, value.map(|v| v.0)));
+        }
+
+        SkipMap::load_entries(entries).map_err(de::Error::custom)
+      }
+    }
+
+    deserializer.deserialize_seq(MapVisitor(PhantomData))
+  }
+}
+
+impl<T: Trailer, C: Comparator + Default> SkipMap<T, C> {
+  /// Creates a map whose ARENA fits the entries, and loads them in order with one splice
+  /// cache, like [`load_sorted`](SkipMap::load_sorted), but with the removed entries.
+  fn load_entries(entries: Vec<(Vec<u8>, T, Option<Vec<u8>>)>) -> Result<Self, Error> {
+    let node_size = Node::<T>::size(MAX_HEIGHT as u8) + mem::size_of::<T>();
+    let cap = entries
+      .iter()
+      .fold(BASE_CAPACITY + 2 * node_size, |cap, (k, _, v)| {
+        // the key and the value may be padded to their alignments
+        cap.saturating_add(node_size + k.len() + v.as_ref().map_or(0, Vec::len) + 16)
+      });
+    let opts = Options::new().with_capacity(cap.min(u32::MAX as usize) as u32);
+    let map = Self::with_options_and_comparator(opts, C::default())?;
+
+    let mut ins = Inserter::default();
+    for (key, trailer, value) in &entries {
+      let (key, value) = match value {
+        Some(value) => (Key::Occupied(key), value.as_slice()),
+        None => (Key::Remove(key), &[][..]),
+      };
+      map
+        .update::<Infallible>(
+          *trailer,
+          key,
+          value.len() as u32,
+          map.opts.value_alignment(),
+          |buf: &mut VacantBuffer| {
+            let _ = buf.write(value);
+            Ok(())
+          },
+          Ordering::Relaxed,
+          Ordering::Relaxed,
+          &mut ins,
+          true,
+        )
+        .map_err(|e| e.expect_right("must be map::Error"))?;
+    }
+    Ok(map)
+  }
+}
@@ -0,0 +1,154 @@
+use super::*;
+
+/// A [`SkipMap`] with exactly one writer and any number of readers, e.g. the memtable of a
+/// write path which is already serialized.
+///
+/// The writes take `&mut self`, so the writer is the only one to link the nodes, and the link
+/// CAS loops of the concurrent writes are replaced by plain `Release` stores. The reads are
+/// still lock-free: the [`ReadHandle`]s can be sent to other threads, and see a node once
+/// its base level is linked, same as the readers of a [`SkipMap`].
+///
+/// # Example
+///
+/// ```rust
+/// use skl::map::SingleWriterMap;
+///
+/// let mut w = SingleWriterMap::new().unwrap();
+/// let r = w.reader();
+///
+/// w.insert(1, b"a", b"a1").unwrap();
+/// w.insert(1, b"b", b"b1").unwrap();
+/// w.remove(2, b"b").unwrap();
+///
+/// let reader = std::thread::spawn(move || {
+///   assert_eq!(r.get(2, b"a").unwrap().value(), b"a1");
+///   assert!(r.get(2, b"b").is_none());
+///   r.snapshot(1).iter().count()
+/// });
+/// assert_eq!(reader.join().unwrap(), 2);
+/// ```
+#[derive(Debug)]
+pub struct SingleWriterMap<T = u64, C = Ascend> {
+  map: SkipMap<T, C>,
+}
+
+/// A read-only handle of a [`SingleWriterMap`], returned by [`SingleWriterMap::reader`].
+#[derive(Debug)]
+pub struct ReadHandle<T = u64, C = Ascend> {
+  map: SkipMap<T, C>,
+}
+
+impl<T, C: Clone> Clone for ReadHandle<T, C> {
+  #[inline]
+  fn clone(&self) -> Self {
+    Self {
+      map: self.map.clone(),
+    }
+  }
+}
+
+impl<T> SingleWriterMap<T> {
+  /// Creates a new map with the default options.
+  #[inline]
+  pub fn new() -> Result<Self, Error> {
+    Self::with_options(Options::new())
+  }
+
+  /// Creates a new map with the given options.
+  #[inline]
+  pub fn with_options(opts: Options) -> Result<Self, Error> {
+    Self::with_options_and_comparator(opts, Ascend)
+  }
+}
+
+impl<T, C> SingleWriterMap<T, C> {
+  /// Like [`SingleWriterMap::with_options`], but with a custom [`Comparator`].
+  #[inline]
+  pub fn with_options_and_comparator(opts: Options, cmp: C) -> Result<Self, Error> {
+    SkipMap::with_options_and_comparator(opts, cmp).map(|mut map| {
+      map.single_writer = true;
+      Self { map }
+    })
+  }
+
+  /// Returns the number of entries in the map, see [`SkipMap::len`].
+  #[inline]
+  pub fn len(&self) -> usize {
+    self.map.len()
+  }
+
+  /// Returns `true` if the map contains no entries.
+  #[inline]
+  pub fn is_empty(&self) -> bool {
+    self.map.is_empty()
+  }
+}
+
+impl<T, C: Clone> SingleWriterMap<T, C> {
+  /// Returns a new read-only handle of the map.
+  #[inline]
+  pub fn reader(&self) -> ReadHandle<T, C> {
+    ReadHandle {
+      map: self.map.clone(),
+    }
+  }
+}
+
+impl<T: Trailer, C: Comparator> SingleWriterMap<T, C> {
+  /// Upserts a key-value pair.
+  ///
+  /// See [`SkipMap::insert`] for more details.
+  #[inline]
+  pub fn insert(&mut self, trailer: T, key: &[u8], value: &[u8]) -> Result<(), Error> {
+    self.map.insert(trailer, key, value).map(|_| ())
+  }
+
+  /// Removes the key by writing a removed entry.
+  ///
+  /// See [`SkipMap::remove`] for more details.
+  #[inline]
+  pub fn remove(&mut self, trailer: T, key: &[u8]) -> Result<(), Error> {
+    self.map.remove(trailer, key).map(|_| ())
+  }
+
+  /// Returns a read-only view of the map pinned to the version.
+  #[inline]
+  pub fn snapshot(&self, version: u64) -> Snapshot<'_, T, C> {
+    self.map.snapshot_at(version)
+  }
+}
+
+impl<T, C> ReadHandle<T, C> {
+  /// Returns the number of entries in the map, see [`SkipMap::len`].
+  #[inline]
+  pub fn len(&self) -> usize {
+    self.map.len()
+  }
+
+  /// Returns `true` if the map contains no entries.
+  #[inline]
+  pub fn is_empty(&self) -> bool {
+    self.map.is_empty()
+  }
+}
+
+impl<T: Trailer, C: Comparator> ReadHandle<T, C> {
+  /// Returns the newest entry of the key (with the version less or equal to `version`).
+  #[inline]
+  pub fn get<'a, 'b: 'a>(&'a self, version: u64, key: &'b [u8]) -> Option<EntryRef<'a, T>> {
+    self.map.get(version, key)
+  }
+
+  /// Returns `true` if the key exists (with the version less or equal to `version`).
+  #[inline]
+  pub fn contains_key<'a, 'b: 'a>(&'a self, version: u64, key: &'b [u8]) -> bool {
+    self.map.contains_key(version, key)
+  }
+
+  /// Returns a read-only view of the map pinned to the version, to iterate or to scan the
+  /// ranges.
+  #[inline]
+  pub fn snapshot(&self, version: u64) -> Snapshot<'_, T, C> {
+    self.map.snapshot_at(version)
+  }
+}
@@ -0,0 +1,105 @@
+use super::*;
+
+/// A read-only view of a [`SkipMap`] pinned to a version, returned by
+/// [`SkipMap::snapshot`].
+///
+/// The snapshot only observes the entries with versions less or equal to its version, so the
+/// writes after the snapshot is taken are not visible to it, as long as the writers use greater
+/// versions, e.g. increasing sequence numbers. Taking a snapshot is O(1), it does not copy or
+/// lock anything.
+///
+/// # Example
+///
+/// ```rust
+/// use skl::SkipMap;
+///
+/// let l = SkipMap::new().unwrap();
+/// l.insert(1, b"a", b"a1").unwrap();
+///
+/// let snapshot = l.snapshot();
+/// l.insert(2, b"a", b"a2").unwrap();
+/// l.insert(2, b"b", b"b2").unwrap();
+///
+/// assert_eq!(snapshot.get(b"a").unwrap().value(), b"a1");
+/// assert!(snapshot.get(b"b").is_none());
+/// assert_eq!(snapshot.iter().count(), 1);
+/// ```
+pub struct Snapshot<'a, T = u64, C = Ascend> {
+  map: &'a SkipMap<T, C>,
+  version: u64,
+}
+
+impl<'a, T, C> Clone for Snapshot<'a, T, C> {
+  #[inline]
+  fn clone(&self) -> Self {
+    *self
+  }
+}
+
+impl<'a, T, C> Copy for Snapshot<'a, T, C> {}
+
+impl<'a, T, C> Snapshot<'a, T, C> {
+  /// Returns the version the snapshot is pinned to.
+  #[inline]
+  pub const fn version(&self) -> u64 {
+    self.version
+  }
+
+  /// Returns the map of the snapshot.
+  #[inline]
+  pub const fn map(&self) -> &'a SkipMap<T, C> {
+    self.map
+  }
+}
+
+impl<'a, T: Trailer, C: Comparator> Snapshot<'a, T, C> {
+  /// Returns the newest entry of the key visible to the snapshot.
+  #[inline]
+  pub fn get<'b: 'a>(&self, key: &'b [u8]) -> Option<EntryRef<'a, T>> {
+    self.map.get(self.version, key)
+  }
+
+  /// Returns `true` if the key is visible to the snapshot.
+  #[inline]
+  pub fn contains_key<'b: 'a>(&self, key: &'b [u8]) -> bool {
+    self.map.contains_key(self.version, key)
+  }
+
+  /// Returns an iterator over the newest versions of the entries visible to the snapshot.
+  #[inline]
+  pub fn iter(&self) -> iterator::Iter<'a, T, C> {
+    self.map.iter(self.version)
+  }
+
+  /// Returns an iterator over the newest versions of the entries in the range visible to the
+  /// snapshot.
+  #[inline]
+  pub fn range<Q, R>(&self, range: R) -> iterator::Iter<'a, T, C, Q, R>
+  where
+    &'a [u8]: PartialOrd<Q>,
+    Q: ?Sized + PartialOrd<&'a [u8]>,
+    R: RangeBounds<Q> + 'a,
+  {
+    self.map.range(self.version, range)
+  }
+
+  /// Returns a [`Cursor`] over the entries visible to the snapshot.
+  #[inline]
+  pub fn cursor(&self) -> Cursor<'a, T, C> {
+    self.map.cursor(self.version)
+  }
+}
+
+impl<T: Trailer, C: Comparator> SkipMap<T, C> {
+  /// Takes a [`Snapshot`] of the map pinned to the current [`max_version`](SkipMap::max_version).
+  #[inline]
+  pub fn snapshot(&self) -> Snapshot<'_, T, C> {
+    self.snapshot_at(self.max_version())
+  }
+
+  /// Takes a [`Snapshot`] of the map pinned to the version.
+  #[inline]
+  pub const fn snapshot_at(&self, version: u64) -> Snapshot<'_, T, C> {
+    Snapshot { map: self, version }
+  }
+}
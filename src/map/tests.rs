@@ -160,6 +160,7 @@ fn full_in(l: impl FnOnce(usize) -> SkipMap) {
         e,
         Error::Arena(ArenaError::InsufficientSpace { .. })
       ));
+      assert!(e.is_arena_full());
       found_arena_full = true;
       break;
     }
@@ -340,6 +341,26 @@ fn test_basic_unify() {
   run(|| basic_in(SkipMap::with_options(UNIFY_TEST_OPTIONS).unwrap()))
 }
 
+#[test]
+fn test_clear_with_clones() {
+  run(|| {
+    let mut l = SkipMap::with_options(TEST_OPTIONS).unwrap();
+    l.insert(0, b"a", b"a").unwrap();
+    let clone = l.clone();
+
+    unsafe {
+      l.clear().unwrap();
+    }
+    assert!(l.is_empty());
+
+    // the clone still refers to the old meta, which is freed with the last clone only
+    assert_eq!(clone.len(), 1);
+    drop(l);
+    assert_eq!(clone.len(), 1);
+    drop(clone);
+  })
+}
+
 #[test]
 #[cfg(feature = "memmap")]
 #[cfg_attr(miri, ignore)]
@@ -1194,9 +1215,9 @@ fn test_basic_large_testcases_map_anon_unify() {
 
 #[cfg(feature = "std")]
 fn test_concurrent_basic_runner(l: Arc<SkipMap>) {
-  #[cfg(not(any(miri, feature = "loom")))]
+  #[cfg(not(miri))]
   const N: usize = 1000;
-  #[cfg(any(miri, feature = "loom"))]
+  #[cfg(miri)]
   const N: usize = 5;
 
   let mut wg = Arc::new(());
@@ -1293,9 +1314,9 @@ fn test_concurrent_basic_map_anon_unify() {
 
 #[cfg(feature = "std")]
 fn test_concurrent_basic_big_values_runner(mut l: Arc<SkipMap>) {
-  #[cfg(not(any(miri, feature = "loom")))]
+  #[cfg(not(miri))]
   const N: usize = 100;
-  #[cfg(any(miri, feature = "loom"))]
+  #[cfg(miri)]
   const N: usize = 5;
 
   for i in 0..N {
@@ -1388,9 +1409,9 @@ fn test_concurrent_basic_big_values_map_anon_unify() {
 
 #[cfg(feature = "std")]
 fn concurrent_one_key(l: Arc<SkipMap>) {
-  #[cfg(not(any(miri, feature = "loom")))]
+  #[cfg(not(miri))]
   const N: usize = 100;
-  #[cfg(any(miri, feature = "loom"))]
+  #[cfg(miri)]
   const N: usize = 5;
 
   let wg = WaitGroup::new();
@@ -3008,3 +3029,4457 @@ fn test_remove2_map_anon_unify() {
     remove2(SkipMap::map_anon_with_options(UNIFY_TEST_OPTIONS, map_options).unwrap());
   })
 }
+
+#[test]
+fn test_expected_height() {
+  assert_eq!(crate::expected_height(0), 1);
+  assert_eq!(crate::expected_height(1), 1);
+  assert_eq!(crate::expected_height(16), 3);
+  assert_eq!(crate::expected_height(1000), 7);
+  assert_eq!(crate::expected_height(u32::MAX as u64), 22);
+}
+
+fn expected_entries(l: SkipMap) {
+  for i in 0..16 {
+    l.insert(0, &key(i), &new_value(i)).unwrap();
+  }
+
+  assert!(l.height() <= 3);
+  assert_eq!(l.len(), 16);
+}
+
+#[test]
+fn test_expected_entries() {
+  run(|| expected_entries(SkipMap::with_options(TEST_OPTIONS.with_expected_entries(16)).unwrap()))
+}
+
+#[test]
+fn test_expected_entries_unify() {
+  run(|| {
+    expected_entries(SkipMap::with_options(UNIFY_TEST_OPTIONS.with_expected_entries(16)).unwrap())
+  })
+}
+
+#[test]
+fn test_no_retries_single_writer() {
+  run(|| {
+    let l = SkipMap::with_options(TEST_OPTIONS).unwrap();
+    for i in 0..100 {
+      l.insert(0, &key(i), &new_value(i)).unwrap();
+    }
+    assert_eq!(l.retries(), 0);
+  })
+}
+
+fn insert_aligned(l: SkipMap) {
+  for i in 0..100 {
+    l.insert_aligned(0, &key(i), &new_value(i), 8).unwrap();
+  }
+
+  for i in 0..100 {
+    let ent = l.get(0, &key(i)).unwrap();
+    assert_eq!(ent.value(), new_value(i));
+    assert_eq!(ent.value().as_ptr() as usize % 8, 0);
+  }
+
+  // update the existing entries, the new values should also be aligned
+  for i in 0..100 {
+    l.insert_aligned(0, &key(i), &make_value(i), 8).unwrap();
+    let ent = l.get(0, &key(i)).unwrap();
+    assert_eq!(ent.value(), make_value(i));
+    assert_eq!(ent.value().as_ptr() as usize % 8, 0);
+  }
+
+  // the ARENA is only aligned to 8, so larger alignments cannot be guaranteed
+  assert!(matches!(
+    l.insert_aligned(0, b"x", b"x", 64),
+    Err(Error::InvalidAlignment(64))
+  ));
+  assert!(matches!(
+    l.insert_aligned(0, b"x", b"x", u32::MAX),
+    Err(Error::InvalidAlignment(_))
+  ));
+  assert!(l.get(0, b"x").is_none());
+}
+
+#[test]
+fn test_insert_aligned() {
+  run(|| insert_aligned(SkipMap::with_options(TEST_OPTIONS).unwrap()))
+}
+
+#[test]
+fn test_insert_aligned_unify() {
+  run(|| insert_aligned(SkipMap::with_options(UNIFY_TEST_OPTIONS).unwrap()))
+}
+
+#[test]
+fn test_invalid_value_alignment() {
+  assert!(matches!(
+    SkipMap::with_options(TEST_OPTIONS.with_value_alignment(u32::MAX)),
+    Err(Error::InvalidAlignment(0))
+  ));
+}
+
+#[test]
+fn test_value_alignment() {
+  run(|| {
+    let l = SkipMap::with_options(TEST_OPTIONS.with_value_alignment(16)).unwrap();
+    for i in 0..100 {
+      l.insert(0, &key(i), &new_value(i)).unwrap();
+    }
+
+    for i in 0..100 {
+      let ent = l.get(0, &key(i)).unwrap();
+      assert_eq!(ent.value(), new_value(i));
+      assert_eq!(ent.value().as_ptr() as usize % 16, 0);
+    }
+  })
+}
+
+#[derive(Debug)]
+struct CaseInsensitive;
+
+impl crate::KeyNormalizer for CaseInsensitive {
+  fn normalized_len(&self, key: &[u8]) -> usize {
+    key.len()
+  }
+
+  fn normalize(&self, key: &[u8], buf: &mut VacantBuffer<'_>) -> Result<(), crate::TooLarge> {
+    for b in key {
+      buf.write(&[b.to_ascii_lowercase()])?;
+    }
+    Ok(())
+  }
+}
+
+fn normalized(l: SkipMap<u64, crate::Normalized<CaseInsensitive>>) {
+  l.insert_normalized(0, b"Bob", b"bob").unwrap();
+  l.insert_normalized(0, b"alice", b"alice").unwrap();
+  l.insert_normalized(0, b"CAROL", b"carol").unwrap();
+  assert_eq!(l.len(), 3);
+
+  let keys = l
+    .iter(0)
+    .map(|ent| ent.key().to_vec())
+    .collect::<std::vec::Vec<_>>();
+  assert_eq!(
+    keys,
+    [b"alice".to_vec(), b"bob".to_vec(), b"carol".to_vec()]
+  );
+
+  let ent = l.get_normalized(0, b"ALICE").unwrap().unwrap();
+  assert_eq!(ent.key(), b"alice");
+  assert_eq!(ent.value(), b"alice");
+  assert!(l.contains_key_normalized(0, b"cArOl").unwrap());
+  assert!(!l.contains_key_normalized(0, b"dave").unwrap());
+
+  let old = l
+    .get_or_insert_normalized(0, b"BOB", b"BOB")
+    .unwrap()
+    .unwrap();
+  assert_eq!(old.value(), b"bob");
+
+  let old = l.insert_normalized(0, b"BOB", b"BOB").unwrap().unwrap();
+  assert_eq!(old.value(), b"bob");
+  assert_eq!(
+    l.get_normalized(0, b"bob").unwrap().unwrap().value(),
+    b"BOB"
+  );
+
+  let lower = l.comparator().normalize(b"BOB").unwrap();
+  let ent = l.lower_bound(0, Bound::Excluded(&lower)).unwrap();
+  assert_eq!(ent.key(), b"carol");
+}
+
+#[test]
+fn test_normalized() {
+  run(|| {
+    normalized(
+      SkipMap::with_options_and_comparator(TEST_OPTIONS, crate::Normalized::new(CaseInsensitive))
+        .unwrap(),
+    )
+  })
+}
+
+#[test]
+fn test_normalized_unify() {
+  run(|| {
+    normalized(
+      SkipMap::with_options_and_comparator(
+        UNIFY_TEST_OPTIONS,
+        crate::Normalized::new(CaseInsensitive),
+      )
+      .unwrap(),
+    )
+  })
+}
+
+/// Writes more bytes than its normalized length.
+#[derive(Debug)]
+struct Overflowing;
+
+impl crate::KeyNormalizer for Overflowing {
+  fn normalized_len(&self, _key: &[u8]) -> usize {
+    1
+  }
+
+  fn normalize(&self, _key: &[u8], buf: &mut VacantBuffer<'_>) -> Result<(), crate::TooLarge> {
+    buf.write(&[0; 64])
+  }
+}
+
+#[test]
+fn test_normalizer_error() {
+  run(|| {
+    let l = SkipMap::<u64, _>::with_options_and_comparator(
+      TEST_OPTIONS,
+      crate::Normalized::new(Overflowing),
+    )
+    .unwrap();
+
+    assert!(matches!(
+      l.insert_normalized(0, b"a", b"a"),
+      Err(Either::Left(_))
+    ));
+    assert!(matches!(
+      l.get_or_insert_normalized(0, b"a", b"a"),
+      Err(Either::Left(_))
+    ));
+    assert!(l.get_normalized(0, b"a").is_err());
+    assert!(l.contains_key_normalized(0, b"a").is_err());
+    assert!(l.comparator().normalize(b"a").is_err());
+    assert!(l.is_empty());
+  })
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test(flavor = "multi_thread")]
+async fn test_future_bulk_insert_and_copy_into() {
+  use crate::future::AsyncSkipMap;
+
+  const N: usize = 3000;
+
+  let src = AsyncSkipMap::new(SkipMap::with_options(BIG_TEST_OPTIONS).unwrap());
+  let entries = (0..N)
+    .map(|i| (0, key(i), new_value(i)))
+    .collect::<std::vec::Vec<_>>();
+  assert_eq!(src.bulk_insert(entries).await.unwrap(), N);
+  assert_eq!(src.as_inner().len(), N);
+
+  let dst = AsyncSkipMap::new(SkipMap::with_options(BIG_TEST_OPTIONS).unwrap());
+  assert_eq!(src.copy_into(0, &dst).await.unwrap(), N);
+
+  let dst = dst.into_inner();
+  assert_eq!(dst.len(), N);
+  for i in 0..N {
+    assert_eq!(dst.get(0, &key(i)).unwrap().value(), new_value(i));
+  }
+}
+
+fn memtable<M: crate::MemTable<Trailer = u64>>(m: M)
+where
+  M::Error: core::fmt::Debug,
+{
+  for i in 0..100 {
+    m.insert(0, &key(i), &new_value(i)).unwrap();
+  }
+
+  assert!(m.approximate_size() > 0);
+  for i in 0..100 {
+    assert_eq!(m.get(0, &key(i)).unwrap().value(), new_value(i));
+  }
+
+  let mut cnt = 0;
+  for (i, ent) in m.iter(0).enumerate() {
+    assert_eq!(ent.key(), key(i));
+    cnt += 1;
+  }
+  assert_eq!(cnt, 100);
+
+  #[cfg(feature = "std")]
+  m.flush().unwrap();
+}
+
+#[test]
+fn test_memtable() {
+  run(|| memtable(SkipMap::with_options(TEST_OPTIONS).unwrap()))
+}
+
+#[test]
+#[cfg(feature = "memmap")]
+fn test_memtable_map_mut() {
+  run(|| {
+    let dir = tempfile::tempdir().unwrap();
+    let p = dir.path().join("test_skipmap_memtable_map_mut");
+    let open_options = OpenOptions::default()
+      .create_new(Some(ARENA_SIZE as u32))
+      .read(true)
+      .write(true);
+    let map_options = MmapOptions::default();
+    memtable(SkipMap::map_mut(p, open_options, map_options).unwrap());
+  })
+}
+
+#[test]
+fn test_compat_ordered_map() {
+  run(|| {
+    let m = crate::compat::OrderedMap::with_options(TEST_OPTIONS).unwrap();
+    for i in (0..100).rev() {
+      assert!(m.insert(&key(i), &new_value(i)).unwrap().is_none());
+    }
+    assert_eq!(m.len(), 100);
+
+    assert_eq!(m.get(&key(1)), Some(new_value(1).as_slice()));
+    assert_eq!(
+      m.get_key_value(&key(2)),
+      Some((key(2).as_slice(), new_value(2).as_slice()))
+    );
+    assert_eq!(m.first_key_value().unwrap().0, key(0));
+    assert_eq!(m.last_key_value().unwrap().0, key(99));
+
+    for (i, (k, v)) in m.iter().enumerate() {
+      assert_eq!(k, key(i));
+      assert_eq!(v, new_value(i));
+    }
+    assert!(m
+      .keys()
+      .rev()
+      .map(|k| k.to_vec())
+      .eq((0..100).rev().map(key)));
+    assert!(m.values().map(|v| v.to_vec()).eq((0..100).map(new_value)));
+
+    let lower = key(10);
+    let upper = key(20);
+    assert_eq!(m.range(lower.as_slice()..upper.as_slice()).count(), 10);
+
+    let old = m.insert(&key(1), &make_value(1)).unwrap().unwrap();
+    assert_eq!(old.value(), new_value(1));
+    assert_eq!(m.get(&key(1)), Some(make_value(1).as_slice()));
+
+    let old = m.remove(&key(1)).unwrap().unwrap();
+    assert_eq!(old.value(), make_value(1));
+    assert!(!m.contains_key(&key(1)));
+    assert!(m.remove(&key(1)).unwrap().is_none());
+    assert_eq!(m.iter().count(), 99);
+  })
+}
+
+#[cfg(feature = "std")]
+unsafe extern "C" fn reverse_bytewise_compare(
+  _state: *mut core::ffi::c_void,
+  a: *const std::os::raw::c_char,
+  alen: usize,
+  b: *const std::os::raw::c_char,
+  blen: usize,
+) -> std::os::raw::c_int {
+  let a = core::slice::from_raw_parts(a as *const u8, alen);
+  let b = core::slice::from_raw_parts(b as *const u8, blen);
+  match b.cmp(a) {
+    cmp::Ordering::Less => -1,
+    cmp::Ordering::Equal => 0,
+    cmp::Ordering::Greater => 1,
+  }
+}
+
+#[cfg(feature = "std")]
+unsafe extern "C" fn reverse_bytewise_name(
+  _state: *mut core::ffi::c_void,
+) -> *const std::os::raw::c_char {
+  b"rocksdb.ReverseBytewiseComparator\0".as_ptr() as _
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn test_rocksdb_comparator() {
+  run(|| {
+    let cmp = unsafe {
+      crate::rocksdb::RocksDbComparator::new(
+        ptr::null_mut(),
+        None,
+        reverse_bytewise_compare,
+        reverse_bytewise_name,
+      )
+    };
+    assert_eq!(cmp.name().to_bytes(), b"rocksdb.ReverseBytewiseComparator");
+
+    let l = SkipMap::with_options_and_comparator(TEST_OPTIONS, Arc::new(cmp)).unwrap();
+    for i in 0..100 {
+      l.insert(0, &key(i), &new_value(i)).unwrap();
+    }
+
+    for (i, ent) in l.iter(0).enumerate() {
+      assert_eq!(ent.key(), key(99 - i));
+    }
+    assert_eq!(l.get(0, &key(50)).unwrap().value(), new_value(50));
+
+    // The bounds are ordered by the comparator, so the range is descending.
+    let cmp = l.comparator();
+    let (upper, lower) = (key(80), key(20));
+    let range = l.range(0, cmp.key(&upper)..cmp.key(&lower));
+    for (i, ent) in range.enumerate() {
+      assert_eq!(ent.key(), key(80 - i));
+    }
+    let range = l.range(0, cmp.key(&upper)..cmp.key(&lower));
+    assert_eq!(range.count(), 60);
+    let range = l.range(0, cmp.key(&upper)..cmp.key(&lower));
+    assert_eq!(range.rev().next().unwrap().key(), key(21));
+  })
+}
+
+#[test]
+fn test_badger_value_struct() {
+  use crate::badger::ValueStruct;
+
+  run(|| {
+    let l = SkipMap::with_options(TEST_OPTIONS).unwrap();
+    let vs = ValueStruct::new(b"alice")
+      .with_meta(1)
+      .with_user_meta(2)
+      .with_expires_at(300);
+    assert_eq!(vs.encoded_size(), 2 + 2 + 5);
+
+    l.insert_with_value(0, b"k", vs.encoded_size() as u32, |buf| vs.encode(buf))
+      .unwrap();
+
+    let ent = l.get(0, b"k").unwrap();
+    // same as the output of Badger's `ValueStruct.Encode`
+    assert_eq!(ent.value(), b"\x01\x02\xac\x02alice");
+    assert_eq!(ValueStruct::decode(ent.value()), Some(vs));
+
+    let vs = ValueStruct::new(b"").with_expires_at(u64::MAX);
+    assert_eq!(vs.encoded_size(), 2 + 10);
+    l.insert_with_value(0, b"max", vs.encoded_size() as u32, |buf| vs.encode(buf))
+      .unwrap();
+    let ent = l.get(0, b"max").unwrap();
+    assert_eq!(ValueStruct::decode(ent.value()), Some(vs));
+
+    assert_eq!(ValueStruct::decode(b"\x01"), None);
+    assert_eq!(ValueStruct::decode(b"\x01\x02\x80"), None);
+  })
+}
+
+#[test]
+#[cfg(feature = "arrow")]
+fn test_range_to_record_batch() {
+  use crate::arrow::RecordBatchHint;
+  use arrow_array::{Array, BinaryArray, UInt64Array};
+
+  run(|| {
+    let l = SkipMap::with_options(TEST_OPTIONS).unwrap();
+    for i in 0..100 {
+      l.insert(i as u64, &key(i), &new_value(i)).unwrap();
+    }
+
+    let hint = RecordBatchHint::new()
+      .with_batch_size(16)
+      .with_ttl(|v: &u64| if *v % 2 == 0 { Some(*v * 10) } else { None });
+    assert_eq!(hint.schema().fields().len(), 4);
+
+    let lower = key(10);
+    let upper = key(50);
+    let batches = l
+      .range_to_record_batch(100, lower.as_slice()..upper.as_slice(), hint)
+      .collect::<Result<std::vec::Vec<_>, _>>()
+      .unwrap();
+    assert_eq!(
+      batches
+        .iter()
+        .map(|b| b.num_rows())
+        .collect::<std::vec::Vec<_>>(),
+      [16, 16, 8]
+    );
+
+    let mut i = 10;
+    for batch in batches {
+      let keys = batch
+        .column(0)
+        .as_any()
+        .downcast_ref::<BinaryArray>()
+        .unwrap();
+      let values = batch
+        .column(1)
+        .as_any()
+        .downcast_ref::<BinaryArray>()
+        .unwrap();
+      let ttls = batch
+        .column(2)
+        .as_any()
+        .downcast_ref::<UInt64Array>()
+        .unwrap();
+      let seqs = batch
+        .column(3)
+        .as_any()
+        .downcast_ref::<UInt64Array>()
+        .unwrap();
+      for row in 0..batch.num_rows() {
+        assert_eq!(keys.value(row), key(i));
+        assert_eq!(values.value(row), new_value(i));
+        assert_eq!(ttls.is_null(row), i % 2 == 1);
+        assert_eq!(seqs.value(row), i as u64);
+        i += 1;
+      }
+    }
+    assert_eq!(i, 50);
+
+    let hint = RecordBatchHint::new().with_seq(false);
+    let batch = l
+      .range_to_record_batch(100, ..=upper.as_slice(), hint)
+      .next()
+      .unwrap()
+      .unwrap();
+    assert_eq!(batch.num_columns(), 2);
+    assert_eq!(batch.num_rows(), 51);
+  })
+}
+
+#[test]
+#[cfg(all(feature = "std", feature = "serde"))]
+fn test_ndjson_dump_and_load() {
+  run(|| {
+    let l = SkipMap::with_options(TEST_OPTIONS).unwrap();
+    for i in 0..100 {
+      l.insert(1, &key(i), &new_value(i)).unwrap();
+    }
+    for i in 0..10 {
+      l.insert(2, &key(i), &make_value(i)).unwrap();
+    }
+    l.compare_remove(3, &key(50), Ordering::AcqRel, Ordering::Relaxed)
+      .unwrap();
+
+    let mut buf = std::vec::Vec::new();
+    assert_eq!(l.dump_ndjson(3, &mut buf).unwrap(), 111);
+    assert_eq!(buf.iter().filter(|b| **b == b'\n').count(), 111);
+
+    let l2 = SkipMap::with_options(TEST_OPTIONS).unwrap();
+    assert_eq!(l2.load_ndjson(buf.as_slice()).unwrap(), 111);
+    for i in 0..100 {
+      let v1 = l.get(3, &key(i)).map(|ent| ent.value().to_vec());
+      let v2 = l2.get(3, &key(i)).map(|ent| ent.value().to_vec());
+      assert_eq!(v1, v2);
+      assert_eq!(
+        l.get(1, &key(i)).unwrap().value(),
+        l2.get(1, &key(i)).unwrap().value()
+      );
+    }
+    assert!(l2.get(3, &key(50)).is_none());
+
+    let err = l2.load_ndjson(&b"{\"key\":\"!\",\"value\":null,\"trailer\":0}\n"[..]);
+    assert!(matches!(err, Err(crate::ndjson::NdjsonError::Base64(_))));
+    let err = l2.load_ndjson(&b"not json\n"[..]);
+    assert!(matches!(err, Err(crate::ndjson::NdjsonError::Json(_))));
+  })
+}
+
+#[test]
+fn test_verify_out_of_bounds_value() {
+  run(|| {
+    let l = SkipMap::with_options(TEST_OPTIONS).unwrap();
+    for i in 0..10 {
+      l.insert(0, &key(i), &new_value(i)).unwrap();
+    }
+    assert_eq!(l.verify(), Ok(()));
+
+    // point the value of the first node out of the ARENA
+    unsafe {
+      let first = l.get_next(l.head, 0);
+      first.as_ref().value.swap(l.capacity() as u32 - 1, 8);
+      assert_eq!(
+        l.verify(),
+        Err(VerifyError::OutOfBounds {
+          level: 0,
+          offset: first.offset
+        })
+      );
+    }
+  })
+}
+
+fn inspect(l: SkipMap) {
+  let report = l.inspect();
+  assert_eq!(report.entries, 0);
+  assert!(report.levels.is_empty());
+  assert_eq!(report.verify, Ok(()));
+
+  for i in 0..1000 {
+    l.insert(0, &key(i), &new_value(i)).unwrap();
+  }
+  for i in 0..100 {
+    l.insert(1, &key(i), &make_value(i)).unwrap();
+  }
+  for i in 0..10 {
+    l.compare_remove(2, &key(i), Ordering::AcqRel, Ordering::Relaxed)
+      .unwrap();
+  }
+
+  assert_eq!(l.verify(), Ok(()));
+  let report = l.inspect();
+  assert_eq!(report.verify, Ok(()));
+  assert_eq!(report.entries, 1100);
+  assert_eq!(report.removed, 10);
+  assert_eq!(report.levels[0], 1110);
+  assert_eq!(report.levels.len(), l.height() as usize);
+  assert!(report.levels.windows(2).all(|w| w[0] >= w[1]));
+  assert!(format!("{report}").ends_with("verify: ok"));
+}
+
+#[test]
+fn test_inspect() {
+  run(|| inspect(SkipMap::with_options(TEST_OPTIONS).unwrap()))
+}
+
+#[test]
+fn test_inspect_unify() {
+  run(|| inspect(SkipMap::with_options(UNIFY_TEST_OPTIONS).unwrap()))
+}
+
+#[test]
+#[cfg(feature = "rayon")]
+fn test_par_extend() {
+  use rayon::iter::{IntoParallelIterator, ParallelExtend, ParallelIterator};
+
+  run(|| {
+    let mut l = SkipMap::with_options(BIG_TEST_OPTIONS).unwrap();
+    // unsorted input with multiple versions
+    l.par_extend(
+      (0..10000usize)
+        .into_par_iter()
+        .map(|i| ((i / 5000) as u64, key((i * 7919) % 5000), new_value(i))),
+    );
+
+    assert_eq!(l.verify(), Ok(()));
+    assert_eq!(l.iter_all_versions(1).count(), 10000);
+    assert_eq!(l.iter(1).count(), 5000);
+  })
+}
+
+#[test]
+#[cfg(feature = "rayon")]
+fn test_try_par_extend() {
+  use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+  run(|| {
+    let l = SkipMap::with_options(TEST_OPTIONS).unwrap();
+    l.try_par_extend(
+      (0..100usize)
+        .into_par_iter()
+        .map(|i| (0, key(i), new_value(i))),
+    )
+    .unwrap();
+    assert_eq!(l.len(), 100);
+
+    // the ARENA is too small for the items, the error is returned instead of panicking
+    let err = l
+      .try_par_extend(
+        (0..2048usize)
+          .into_par_iter()
+          .map(|i| (1, key(i), std::vec![0u8; 1024])),
+      )
+      .unwrap_err();
+    assert!(err.is_arena_full());
+    assert_eq!(l.verify(), Ok(()));
+
+    let _frozen = l.clone().freeze();
+    assert_eq!(
+      l.try_par_extend(
+        (0..1usize)
+          .into_par_iter()
+          .map(|i| (2, key(i), new_value(i)))
+      )
+      .unwrap_err(),
+      Error::read_only()
+    );
+  })
+}
+
+#[test]
+#[cfg(feature = "rayon")]
+fn test_par_bulk_insert() {
+  run(|| {
+    let l = SkipMap::with_options(BIG_TEST_OPTIONS).unwrap();
+    let items = (0..10000usize)
+      .map(|i| (1 - (i % 2) as u64, key(i / 2), new_value(i)))
+      .collect::<Vec<_>>();
+    l.par_bulk_insert(&items, 4).unwrap();
+    // more threads than items
+    l.par_bulk_insert(&items[..3], 16).unwrap();
+    l.par_bulk_insert(&items[..0], 4).unwrap();
+
+    assert_eq!(l.verify(), Ok(()));
+    assert_eq!(l.iter_all_versions(1).count(), 10000);
+    assert_eq!(l.iter(1).count(), 5000);
+    assert_eq!(
+      l.get(0, &key(42)).unwrap().value(),
+      new_value(85).as_slice()
+    );
+  })
+}
+
+#[test]
+fn test_layout_offsets() {
+  use core::mem::MaybeUninit;
+
+  let node = MaybeUninit::<Node<u64>>::uninit();
+  let base = node.as_ptr() as usize;
+  unsafe {
+    let ptr = node.as_ptr();
+    assert_eq!(
+      ptr::addr_of!((*ptr).value) as usize - base,
+      layout::NODE_VALUE_POINTER_OFFSET
+    );
+    assert_eq!(
+      ptr::addr_of!((*ptr).key_offset) as usize - base,
+      layout::NODE_KEY_OFFSET_OFFSET
+    );
+    assert_eq!(
+      ptr::addr_of!((*ptr).key_size_and_height) as usize - base,
+      layout::NODE_KEY_SIZE_AND_HEIGHT_OFFSET
+    );
+  }
+  assert_eq!(mem::size_of::<Node<u64>>(), layout::NODE_SIZE);
+
+  let meta = MaybeUninit::<Meta>::uninit();
+  let base = meta.as_ptr() as usize;
+  unsafe {
+    let ptr = meta.as_ptr();
+    assert_eq!(
+      ptr::addr_of!((*ptr).max_version) as usize - base,
+      layout::META_MAX_VERSION_OFFSET
+    );
+    assert_eq!(
+      ptr::addr_of!((*ptr).min_version) as usize - base,
+      layout::META_MIN_VERSION_OFFSET
+    );
+    assert_eq!(
+      ptr::addr_of!((*ptr).len) as usize - base,
+      layout::META_LEN_OFFSET
+    );
+    assert_eq!(
+      ptr::addr_of!((*ptr).magic_version) as usize - base,
+      layout::META_MAGIC_VERSION_OFFSET
+    );
+    assert_eq!(
+      ptr::addr_of!((*ptr).height) as usize - base,
+      layout::META_HEIGHT_OFFSET
+    );
+    assert_eq!(
+      ptr::addr_of!((*ptr).flags) as usize - base,
+      layout::META_FLAGS_OFFSET
+    );
+  }
+
+  let link = MaybeUninit::<Link>::uninit();
+  let base = link.as_ptr() as usize;
+  unsafe {
+    let ptr = link.as_ptr();
+    assert_eq!(
+      ptr::addr_of!((*ptr).next_offset) as usize - base,
+      layout::LINK_NEXT_OFFSET
+    );
+    assert_eq!(
+      ptr::addr_of!((*ptr).prev_offset) as usize - base,
+      layout::LINK_PREV_OFFSET
+    );
+  }
+}
+
+fn layout_parse(l: SkipMap) {
+  fn read_u32(arena: &Arena, offset: usize) -> u32 {
+    u32::from_ne_bytes(arena.get_bytes(offset, 4).try_into().unwrap())
+  }
+
+  fn read_u64(arena: &Arena, offset: usize) -> u64 {
+    u64::from_ne_bytes(arena.get_bytes(offset, 8).try_into().unwrap())
+  }
+
+  for i in 0..100 {
+    l.insert(0, &key(i), &new_value(i)).unwrap();
+  }
+
+  let arena = l.allocator();
+  let header = l.header();
+  if let Some(meta) = header.meta_offset {
+    assert_eq!(
+      read_u32(arena, meta + layout::META_LEN_OFFSET) as usize,
+      l.len()
+    );
+  }
+
+  // walk the bottom level by the layout only
+  let mut i = 0;
+  let mut nd = read_u32(
+    arena,
+    layout::tower_offset(header.head_offset, 0) + layout::LINK_NEXT_OFFSET,
+  ) as usize;
+  while nd != header.tail_offset {
+    let key_offset = read_u32(arena, nd + layout::NODE_KEY_OFFSET_OFFSET) as usize;
+    let (key_size, height) = layout::decode_key_size_and_height(read_u32(
+      arena,
+      nd + layout::NODE_KEY_SIZE_AND_HEIGHT_OFFSET,
+    ));
+    assert!(height >= 1);
+    assert_eq!(arena.get_bytes(key_offset, key_size as usize), key(i));
+
+    let (trailer_offset, value_size) =
+      layout::decode_value_pointer(read_u64(arena, nd + layout::NODE_VALUE_POINTER_OFFSET));
+    assert_ne!(value_size, layout::REMOVED);
+    assert_eq!(read_u64(arena, trailer_offset as usize), 0);
+    assert_eq!(
+      arena.get_bytes(trailer_offset as usize + 8, value_size as usize),
+      new_value(i)
+    );
+
+    nd = read_u32(
+      arena,
+      layout::tower_offset(nd, 0) + layout::LINK_NEXT_OFFSET,
+    ) as usize;
+    i += 1;
+  }
+  assert_eq!(i, 100);
+}
+
+#[test]
+fn test_layout_parse() {
+  run(|| layout_parse(SkipMap::with_options(TEST_OPTIONS).unwrap()))
+}
+
+#[test]
+fn test_layout_parse_unify() {
+  run(|| layout_parse(SkipMap::with_options(UNIFY_TEST_OPTIONS).unwrap()))
+}
+
+#[test]
+#[cfg(any(debug_assertions, feature = "untrusted"))]
+#[should_panic(expected = "out of the bounds of the ARENA")]
+fn test_debug_bounds_check_key() {
+  let l = SkipMap::with_options(TEST_OPTIONS).unwrap();
+  let node = Node::<u64> {
+    value: AtomicValuePointer::new(0, 0),
+    key_offset: ARENA_SIZE as u32 - 4,
+    key_size_and_height: encode_key_size_and_height(8, 1),
+    trailer: PhantomData,
+  };
+  unsafe {
+    node.get_key(&l.arena);
+  }
+}
+
+#[test]
+#[cfg(any(debug_assertions, feature = "untrusted"))]
+#[should_panic(expected = "out of the bounds of the ARENA")]
+fn test_debug_bounds_check_unallocated() {
+  let l = SkipMap::with_options(TEST_OPTIONS).unwrap();
+  l.insert(0, b"a", b"a").unwrap();
+  // within the capacity, but after the allocated part
+  let node = Node::<u64> {
+    value: AtomicValuePointer::new(0, 0),
+    key_offset: l.allocated() as u32,
+    key_size_and_height: encode_key_size_and_height(4, 1),
+    trailer: PhantomData,
+  };
+  unsafe {
+    node.get_key(&l.arena);
+  }
+}
+
+#[test]
+#[cfg(any(debug_assertions, feature = "untrusted"))]
+#[should_panic(expected = "out of the bounds of the ARENA")]
+fn test_debug_bounds_check_value() {
+  let l = SkipMap::with_options(TEST_OPTIONS).unwrap();
+  let node = Node::<u64> {
+    value: AtomicValuePointer::new(ARENA_SIZE as u32 - 8, 16),
+    key_offset: 0,
+    key_size_and_height: encode_key_size_and_height(0, 1),
+    trailer: PhantomData,
+  };
+  unsafe {
+    node.get_value(&l.arena);
+  }
+}
+
+#[test]
+fn test_poison() {
+  run(|| {
+    let l = SkipMap::with_options(TEST_OPTIONS).unwrap();
+    l.insert(0, b"a", b"a").unwrap();
+    assert!(!l.is_poisoned());
+    assert_eq!(l.check_poison(), Ok(()));
+
+    let l2 = l.clone();
+    l.poison(PoisonReason::Checksum);
+    // only the first reason is kept
+    l.poison(PoisonReason::Other);
+    assert!(l2.is_poisoned());
+    assert_eq!(l2.poison_reason(), Some(PoisonReason::Checksum));
+    assert_eq!(
+      l2.check_poison(),
+      Err(Error::Corrupted(PoisonReason::Checksum))
+    );
+
+    assert_eq!(
+      l.insert(0, b"b", b"b").unwrap_err(),
+      Error::Corrupted(PoisonReason::Checksum)
+    );
+    assert!(l.get_or_insert(0, b"c", b"c").is_err());
+    assert!(l
+      .compare_remove(0, b"a", Ordering::AcqRel, Ordering::Relaxed)
+      .is_err());
+
+    // the reads do not walk the links of a poisoned map
+    assert!(l.get(0, b"a").is_none());
+    assert!(!l2.contains_key(0, b"a"));
+    assert!(l.first(0).is_none());
+    assert!(l.last(0).is_none());
+    assert!(l.lower_bound(0, Bound::Unbounded).is_none());
+    assert_eq!(l.iter(0).count(), 0);
+    assert_eq!(l.iter(0).rev().count(), 0);
+    assert_eq!(l.iter_all_versions(0).count(), 0);
+  })
+}
+
+#[test]
+#[cfg(feature = "memmap")]
+#[cfg_attr(miri, ignore)]
+fn test_poison_persisted() {
+  run(|| {
+    let dir = tempfile::tempdir().unwrap();
+    let p = dir.path().join("test_skipmap_poison_persisted");
+    {
+      let open_options = OpenOptions::default()
+        .create_new(Some(ARENA_SIZE as u32))
+        .read(true)
+        .write(true);
+      let l = SkipMap::map_mut(&p, open_options, MmapOptions::default()).unwrap();
+      l.insert(0, b"a", b"a").unwrap();
+      l.poison(PoisonReason::Checksum);
+      l.flush().unwrap();
+    }
+
+    let open_options = OpenOptions::default().read(true);
+    let l = SkipMap::<u64>::map(&p, open_options, MmapOptions::default(), 0).unwrap();
+    assert_eq!(l.poison_reason(), Some(PoisonReason::Checksum));
+    assert!(l.get(0, b"a").is_none());
+    assert_eq!(l.iter(0).count(), 0);
+  })
+}
+
+#[test]
+fn test_poison_on_corruption() {
+  run(|| {
+    let l = SkipMap::with_options(TEST_OPTIONS.with_poison_on_corruption(true)).unwrap();
+    for i in 0..10 {
+      l.insert(0, &key(i), &new_value(i)).unwrap();
+    }
+    assert_eq!(l.verify(), Ok(()));
+    assert!(!l.is_poisoned());
+
+    // break the previous link of the first node
+    unsafe {
+      let first = l.get_next(l.head, 0);
+      let second = l.get_next(first, 0);
+      second
+        .tower(&l.arena, 0)
+        .prev_offset
+        .store(l.head.offset, Ordering::Release);
+      assert_eq!(
+        l.verify(),
+        Err(VerifyError::BrokenPrevLink {
+          offset: second.offset
+        })
+      );
+    }
+    assert_eq!(l.poison_reason(), Some(PoisonReason::BrokenPrevLink));
+    assert!(l.insert(0, &key(10), &new_value(10)).is_err());
+  })
+}
+
+#[test]
+#[cfg(feature = "untrusted")]
+fn test_untrusted_links_poison() {
+  run(|| {
+    let l = SkipMap::with_options(TEST_OPTIONS).unwrap();
+    for i in 0..10 {
+      l.insert(0, &key(i), &new_value(i)).unwrap();
+    }
+
+    // point the bottom level link of the last node after the allocated part of the ARENA
+    unsafe {
+      let mut last = l.get_next(l.head, 0);
+      while l.get_next(last, 0).offset != l.tail.offset {
+        last = l.get_next(last, 0);
+      }
+      last
+        .tower(&l.arena, 0)
+        .next_offset
+        .store(l.allocated() as u32 + 64, Ordering::Release);
+    }
+
+    // the seek to the end walks the broken link, and poisons the map instead of panicking
+    assert!(l.last(0).is_none());
+    assert_eq!(
+      l.check_poison(),
+      Err(Error::Corrupted(PoisonReason::OutOfBounds))
+    );
+    assert!(l.get(0, &key(0)).is_none());
+    assert_eq!(l.iter(0).count(), 0);
+    assert_eq!(
+      l.insert(0, &key(10), &new_value(10)).unwrap_err(),
+      Error::Corrupted(PoisonReason::OutOfBounds)
+    );
+  })
+}
+
+#[test]
+fn test_verify_untrusted_offsets() {
+  run(|| {
+    let l = SkipMap::with_options(TEST_OPTIONS).unwrap();
+    for i in 0..10 {
+      l.insert(0, &key(i), &new_value(i)).unwrap();
+    }
+    assert_eq!(l.verify(), Ok(()));
+
+    // point the key of the first node out of the ARENA
+    unsafe {
+      let first = l.get_next(l.head, 0);
+      first.as_mut().key_offset = l.capacity() as u32 - 1;
+      assert_eq!(
+        l.verify(),
+        Err(VerifyError::OutOfBounds {
+          level: 0,
+          offset: first.offset
+        })
+      );
+    }
+  })
+}
+
+#[test]
+fn test_verify_unlinked_tower() {
+  run(|| {
+    let l = SkipMap::with_options(TEST_OPTIONS.with_poison_on_corruption(true)).unwrap();
+    for i in 0..100 {
+      l.insert(0, &key(i), &new_value(i)).unwrap();
+    }
+    assert_eq!(l.verify(), Ok(()));
+
+    // unlink a tall node from the bottom level only, the bottom level stays consistent
+    unsafe {
+      let nd = l.get_next(l.head, 1);
+      assert_ne!(nd.offset, l.tail.offset);
+      let mut prev = l.head;
+      while l.get_next(prev, 0).offset != nd.offset {
+        prev = l.get_next(prev, 0);
+      }
+      let next = l.get_next(nd, 0);
+      prev
+        .tower(&l.arena, 0)
+        .next_offset
+        .store(next.offset, Ordering::Release);
+      next
+        .tower(&l.arena, 0)
+        .prev_offset
+        .store(prev.offset, Ordering::Release);
+
+      assert_eq!(
+        l.verify(),
+        Err(VerifyError::Unlinked {
+          level: 1,
+          offset: nd.offset
+        })
+      );
+    }
+    assert_eq!(l.poison_reason(), Some(PoisonReason::Unlinked));
+  })
+}
+
+#[test]
+#[cfg(feature = "memmap")]
+#[cfg_attr(miri, ignore)]
+fn test_reopen_mmap_validate_on_open() {
+  run(|| {
+    let dir = tempfile::tempdir().unwrap();
+    let p = dir.path().join("reopen_skipmap_validate_on_open");
+    {
+      let open_options = OpenOptions::default()
+        .create(Some(ARENA_SIZE as u32))
+        .read(true)
+        .write(true);
+      let map_options = MmapOptions::default();
+      let l = SkipMap::map_mut(&p, open_options, map_options).unwrap();
+      for i in 0..1000 {
+        l.get_or_insert(0, &key(i), &new_value(i)).unwrap();
+      }
+      l.flush().unwrap();
+    }
+
+    let open_options = OpenOptions::default().read(true);
+    let map_options = MmapOptions::default();
+    let l = SkipMap::<u64>::map_with_options(
+      &p,
+      Options::new().with_validate_on_open(true),
+      open_options,
+      map_options,
+    )
+    .unwrap();
+    assert_eq!(1000, l.len());
+    for i in 0..1000 {
+      assert_eq!(l.get(0, &key(i)).unwrap().value(), new_value(i));
+    }
+  })
+}
+
+#[test]
+#[cfg(feature = "memmap")]
+fn test_try_new() {
+  run(|| {
+    let l = SkipMap::try_with_options(TEST_OPTIONS).unwrap();
+    l.insert(0, b"a", b"a").unwrap();
+    assert_eq!(l.get(0, b"a").unwrap().value(), b"a");
+
+    let l = SkipMap::<u64>::try_new().unwrap();
+    assert!(l.is_empty());
+
+    // the invalid options are not reported as out of memory
+    let opts = TEST_OPTIONS.with_max_height(crate::u5::new(0));
+    let err = SkipMap::<u64>::try_with_options(opts).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+
+    assert_eq!(
+      std::format!("{}", Error::OutOfMemory(10)),
+      "failed to allocate 10 bytes"
+    );
+  })
+}
+
+/// Panics when the countdown reaches zero.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Default)]
+struct PanicComparator(Arc<core::sync::atomic::AtomicUsize>);
+
+#[cfg(feature = "std")]
+impl PanicComparator {
+  fn panic_after(&self, n: usize) {
+    self.0.store(n, Ordering::SeqCst);
+  }
+}
+
+#[cfg(feature = "std")]
+impl Comparator for PanicComparator {
+  fn compare(&self, a: &[u8], b: &[u8]) -> cmp::Ordering {
+    if self.0.fetch_sub(1, Ordering::SeqCst) == 1 {
+      panic!("comparator panicked");
+    }
+    a.cmp(b)
+  }
+
+  fn contains<'a, Q>(&self, range: &impl core::ops::RangeBounds<Q>, key: &'a [u8]) -> bool
+  where
+    &'a [u8]: PartialOrd<Q>,
+    Q: ?Sized + PartialOrd<&'a [u8]>,
+  {
+    range.contains(&key)
+  }
+}
+
+#[cfg(feature = "std")]
+fn panic_safe_insert(l: SkipMap<u64, PanicComparator>) {
+  use std::panic::{catch_unwind, AssertUnwindSafe};
+
+  let cmp = l.comparator().clone();
+  for i in 0..100 {
+    cmp.panic_after(usize::MAX);
+    l.insert(0, &key(i * 2), &new_value(i * 2)).unwrap();
+  }
+
+  let mut panicked = 0;
+  for i in 0..100 {
+    cmp.panic_after(i % 10 + 1);
+    let res = catch_unwind(AssertUnwindSafe(|| {
+      l.insert(0, &key(i * 2 + 1), &new_value(i * 2 + 1)).unwrap();
+    }));
+    panicked += res.is_err() as usize;
+  }
+  assert!(panicked > 0);
+
+  cmp.panic_after(usize::MAX);
+  assert!(l.verify().is_ok());
+  assert_eq!(l.iter(0).count(), l.len());
+
+  // The entries which failed to insert can be inserted again.
+  for i in 0..200 {
+    l.insert(0, &key(i), &new_value(i)).unwrap();
+  }
+  assert_eq!(l.len(), 200);
+  for i in 0..200 {
+    assert_eq!(l.get(0, &key(i)).unwrap().value(), new_value(i));
+  }
+  assert!(l.verify().is_ok());
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn test_panic_safe_insert() {
+  run(|| {
+    panic_safe_insert(
+      SkipMap::with_options_and_comparator(TEST_OPTIONS, PanicComparator::default()).unwrap(),
+    )
+  })
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn test_panic_safe_insert_unify() {
+  run(|| {
+    panic_safe_insert(
+      SkipMap::with_options_and_comparator(UNIFY_TEST_OPTIONS, PanicComparator::default()).unwrap(),
+    )
+  })
+}
+
+#[cfg(feature = "std")]
+fn panic_safe_value_builder(l: SkipMap) {
+  use std::panic::{catch_unwind, AssertUnwindSafe};
+
+  for i in 0..10 {
+    l.insert(0, &key(i), &new_value(i)).unwrap();
+  }
+
+  let res = catch_unwind(AssertUnwindSafe(|| {
+    let _ = l.insert_with_value::<()>(0, &key(100), 5, |_| panic!("value builder panicked"));
+  }));
+  assert!(res.is_err());
+  let res = catch_unwind(AssertUnwindSafe(|| {
+    let _ = l.get_or_insert_with_value::<()>(1, &key(5), 5, |_| panic!("value builder panicked"));
+  }));
+  assert!(res.is_err());
+
+  assert!(l.get(0, &key(100)).is_none());
+  assert_eq!(l.get(1, &key(5)).unwrap().version(), 0);
+  assert_eq!(l.len(), 10);
+  assert!(l.verify().is_ok());
+
+  l.insert(0, &key(100), &new_value(100)).unwrap();
+  assert_eq!(l.get(0, &key(100)).unwrap().value(), new_value(100));
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn test_panic_safe_value_builder() {
+  run(|| panic_safe_value_builder(SkipMap::with_options(TEST_OPTIONS).unwrap()))
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn test_panic_safe_value_builder_unify() {
+  run(|| panic_safe_value_builder(SkipMap::with_options(UNIFY_TEST_OPTIONS).unwrap()))
+}
+
+#[test]
+fn test_invalid_height() {
+  run(|| {
+    let opts = TEST_OPTIONS.with_max_height(crate::u5::new(0));
+    assert_eq!(
+      SkipMap::<u64>::with_options(opts).unwrap_err(),
+      Error::InvalidHeight(0)
+    );
+  })
+}
+
+fn entry_size_overflow(l: SkipMap) {
+  // The sizes would overflow u32 with the node, trailer and paddings added.
+  let err = l
+    .insert_with_value::<()>(0, b"a", u32::MAX - 1, |_| Ok(()))
+    .unwrap_err();
+  assert!(matches!(err, Either::Right(Error::EntryTooLarge(_))));
+
+  let err = l
+    .insert_with_value::<()>(0, b"a", (l.capacity() + 1) as u32, |_| Ok(()))
+    .unwrap_err();
+  assert!(matches!(err, Either::Right(Error::EntryTooLarge(_))));
+
+  l.insert(0, b"a", b"a").unwrap();
+  let err = l
+    .insert_with_value::<()>(0, b"a", u32::MAX - 1, |_| Ok(()))
+    .unwrap_err();
+  assert!(matches!(err, Either::Right(Error::ValueTooLarge(_))));
+
+  assert_eq!(l.get(0, b"a").unwrap().value(), b"a");
+  assert_eq!(l.len(), 1);
+}
+
+#[test]
+fn test_entry_size_overflow() {
+  run(|| entry_size_overflow(SkipMap::with_options(TEST_OPTIONS).unwrap()))
+}
+
+#[test]
+fn test_entry_size_overflow_unify() {
+  run(|| entry_size_overflow(SkipMap::with_options(UNIFY_TEST_OPTIONS).unwrap()))
+}
+
+#[test]
+fn test_small_max_height() {
+  run(|| {
+    let tall = SkipMap::<u64>::with_options(TEST_OPTIONS).unwrap();
+    let small =
+      SkipMap::<u64>::with_options(TEST_OPTIONS.with_max_height(crate::u5::new(4))).unwrap();
+    assert_eq!(
+      tall.allocated() - small.allocated(),
+      2 * 16 * mem::size_of::<Link>()
+    );
+
+    for i in 0..1000 {
+      small.insert(0, &key(i), &new_value(i)).unwrap();
+    }
+    assert_eq!(small.max_height(), 4);
+    assert_eq!(tall.max_height(), 20);
+    assert!(small.height() <= 4);
+    assert_eq!(small.len(), 1000);
+    assert!(small.verify().is_ok());
+  })
+}
+
+#[test]
+#[cfg(feature = "track-guards")]
+fn test_track_guards() {
+  run(|| {
+    let l = SkipMap::with_options(TEST_OPTIONS).unwrap();
+    l.insert(0, b"a", b"a").unwrap();
+    assert_eq!(l.live_guards(), 0);
+
+    let ent = l.get(0, b"a").unwrap().to_owned();
+    let ent2 = ent.clone();
+    assert_eq!(l.live_guards(), 2);
+    assert_eq!(l.live_guard_backtraces().len(), 2);
+
+    drop(ent2);
+    assert_eq!(l.live_guards(), 1);
+
+    // the entry keeps the ARENA alive after the map is dropped
+    drop(l);
+    assert_eq!(ent.value(), b"a");
+    drop(ent);
+  })
+}
+
+#[test]
+#[cfg(feature = "raw")]
+fn test_raw_traversal() {
+  run(|| {
+    let l = SkipMap::with_options(TEST_OPTIONS).unwrap();
+    for i in 0..100 {
+      l.insert(0, &key(i), &new_value(i)).unwrap();
+    }
+    l.compare_remove(1, &key(50), Ordering::AcqRel, Ordering::Relaxed)
+      .unwrap();
+
+    let raw = l.raw();
+    let mut visited = 0;
+    unsafe {
+      let mut node = raw.next(raw.head(), 0);
+      let mut prev = raw.head();
+      while node != raw.tail() {
+        assert_eq!(raw.prev(node, 0), prev);
+        assert!(raw.height(node) >= 1);
+        if raw.trailer(node).version() == 0 {
+          assert_eq!(raw.key(node), key(visited));
+          assert_eq!(raw.value(node).unwrap(), new_value(visited));
+          visited += 1;
+        } else {
+          assert_eq!(raw.key(node), key(50));
+          assert!(raw.value(node).is_none());
+        }
+
+        let header = raw.bytes(node.offset() as usize, layout::NODE_SIZE);
+        let key_offset = u32::from_ne_bytes(
+          header[layout::NODE_KEY_OFFSET_OFFSET..layout::NODE_KEY_OFFSET_OFFSET + 4]
+            .try_into()
+            .unwrap(),
+        );
+        assert_eq!(
+          raw.bytes(key_offset as usize, raw.key(node).len()),
+          raw.key(node)
+        );
+
+        prev = node;
+        node = raw.next(node, 0);
+      }
+      assert_eq!(raw.prev(raw.tail(), 0), prev);
+    }
+    assert_eq!(visited, 100);
+  })
+}
+
+#[test]
+#[cfg(feature = "raw")]
+#[should_panic(expected = "out of the allocated part of the ARENA")]
+fn test_raw_bytes_unallocated() {
+  let l = SkipMap::with_options(TEST_OPTIONS).unwrap();
+  l.insert(0, b"a", b"a").unwrap();
+  unsafe {
+    l.raw().bytes(l.allocated(), 1);
+  }
+}
+
+fn diff(l: SkipMap) {
+  for i in 0..100 {
+    l.insert(1, &key(i), &new_value(i)).unwrap();
+  }
+
+  for i in (0..100).step_by(3) {
+    l.insert(2, &key(i), &make_value(i)).unwrap();
+  }
+  for i in (1..100).step_by(3) {
+    l.compare_remove(3, &key(i), Ordering::AcqRel, Ordering::Relaxed)
+      .unwrap();
+  }
+  for i in 100..110 {
+    l.insert(3, &key(i), &new_value(i)).unwrap();
+  }
+  // inserted and removed between the snapshots
+  l.insert(2, &key(200), &new_value(200)).unwrap();
+  l.compare_remove(3, &key(200), Ordering::AcqRel, Ordering::Relaxed)
+    .unwrap();
+
+  assert_eq!(l.diff(1, 1).count(), 0);
+  assert_eq!(l.diff(3, 1).count(), 0);
+
+  let (mut inserted, mut updated, mut deleted) = (0, 0, 0);
+  for change in l.diff(1, 3) {
+    let i: usize = core::str::from_utf8(change.key()).unwrap().parse().unwrap();
+    match change {
+      DiffEntry::Inserted(new) => {
+        assert!((100..110).contains(&i));
+        assert_eq!(new.value(), new_value(i));
+        inserted += 1;
+      }
+      DiffEntry::Updated { old, new } => {
+        assert_eq!(i % 3, 0);
+        assert_eq!(old.value(), new_value(i));
+        assert_eq!(new.value(), make_value(i));
+        assert_eq!(new.version(), 2);
+        updated += 1;
+      }
+      DiffEntry::Deleted(old) => {
+        assert_eq!(i % 3, 1);
+        assert_eq!(old.value(), new_value(i));
+        deleted += 1;
+      }
+    }
+  }
+  assert_eq!((inserted, updated, deleted), (10, 34, 33));
+
+  // only the changes between the versions 2 and 3
+  assert!(l
+    .diff(2, 3)
+    .all(|change| !matches!(change, DiffEntry::Updated { .. })));
+  assert_eq!(l.diff(2, 3).count(), 10 + 33 + 1);
+}
+
+#[test]
+fn test_diff() {
+  run(|| diff(SkipMap::with_options(TEST_OPTIONS).unwrap()))
+}
+
+#[test]
+fn test_diff_unify() {
+  run(|| diff(SkipMap::with_options(UNIFY_TEST_OPTIONS).unwrap()))
+}
+
+#[test]
+fn test_merge_iterator() {
+  run(|| {
+    let gens = (0..3)
+      .map(|_| SkipMap::with_options(TEST_OPTIONS).unwrap())
+      .collect::<std::vec::Vec<_>>();
+
+    // the oldest generation has all the keys, the newer ones overwrite or remove some of them
+    for i in 0..100 {
+      gens[2].insert(1, &key(i), &new_value(i)).unwrap();
+    }
+    for i in (0..100).step_by(2) {
+      gens[1].insert(2, &key(i), &make_value(i)).unwrap();
+    }
+    for i in (0..100).step_by(5) {
+      gens[0]
+        .compare_remove(3, &key(i), Ordering::AcqRel, Ordering::Relaxed)
+        .unwrap();
+    }
+    for i in 100..110 {
+      gens[0].insert(3, &key(i), &new_value(i)).unwrap();
+    }
+    // the same version in two generations, the newer generation wins
+    gens[0].insert(2, &key(1), b"newest").unwrap();
+
+    let merged = MergeIterator::new(3, gens.iter()).collect::<std::vec::Vec<_>>();
+    assert_eq!(merged.len(), 80 + 10);
+    for w in merged.windows(2) {
+      assert!(w[0].key() < w[1].key());
+    }
+    for ent in &merged {
+      let i: usize = core::str::from_utf8(ent.key()).unwrap().parse().unwrap();
+      assert_ne!(i % 5, 0);
+      if i == 1 {
+        assert_eq!(ent.value(), b"newest");
+      } else if i >= 100 || i % 2 == 1 {
+        assert_eq!(ent.value(), new_value(i));
+      } else {
+        assert_eq!(ent.value(), make_value(i));
+      }
+    }
+
+    // the newer versions are invisible to the older snapshots
+    let merged = MergeIterator::new(1, gens.iter()).count();
+    assert_eq!(merged, 100);
+    assert_eq!(MergeIterator::<u64, Ascend>::new(1, []).count(), 0);
+  })
+}
+
+/// A trailer with an expiration time, only used for testing.
+#[derive(Debug, Clone, Copy)]
+struct TtlTrailer {
+  version: u64,
+  expires_at: u64,
+}
+
+unsafe impl Trailer for TtlTrailer {
+  fn version(&self) -> u64 {
+    self.version
+  }
+}
+
+#[test]
+fn test_compaction_iter() {
+  run(|| {
+    let l = SkipMap::<TtlTrailer>::with_options(TEST_OPTIONS).unwrap();
+    let trailer = |version, expires_at| TtlTrailer {
+      version,
+      expires_at,
+    };
+
+    // a: versions above and below the watermark
+    for v in 1..=5 {
+      l.insert(trailer(v, 0), b"a", &[v as u8]).unwrap();
+    }
+    // b: removed below the watermark
+    l.insert(trailer(1, 0), b"b", b"b").unwrap();
+    l.compare_remove(trailer(2, 0), b"b", Ordering::AcqRel, Ordering::Relaxed)
+      .unwrap();
+    // c: removed above the watermark
+    l.insert(trailer(1, 0), b"c", b"c").unwrap();
+    l.compare_remove(trailer(4, 0), b"c", Ordering::AcqRel, Ordering::Relaxed)
+      .unwrap();
+    // d: expired below the watermark, shadows the older version
+    l.insert(trailer(1, 0), b"d", b"d1").unwrap();
+    l.insert(trailer(2, 10), b"d", b"d2").unwrap();
+    // e: expires in the future
+    l.insert(trailer(1, 100), b"e", b"e").unwrap();
+
+    let opts = CompactionOptions::new(3, 50).with_ttl(|t: &TtlTrailer| {
+      if t.expires_at == 0 {
+        None
+      } else {
+        Some(t.expires_at)
+      }
+    });
+    let kept = l
+      .compaction_iter(opts)
+      .map(|ent| (ent.key().to_vec(), ent.version(), ent.is_removed()))
+      .collect::<std::vec::Vec<_>>();
+    assert_eq!(
+      kept,
+      [
+        (b"a".to_vec(), 5, false),
+        (b"a".to_vec(), 4, false),
+        (b"a".to_vec(), 3, false),
+        (b"c".to_vec(), 4, true),
+        (b"c".to_vec(), 1, false),
+        (b"e".to_vec(), 1, false),
+      ]
+    );
+
+    // without the TTL, the version 2 of d is the newest visible one
+    let kept = l
+      .compaction_iter(CompactionOptions::new(3, 50))
+      .filter(|ent| ent.key() == b"d")
+      .map(|ent| ent.version())
+      .collect::<std::vec::Vec<_>>();
+    assert_eq!(kept, [2]);
+  })
+}
+
+fn size_stats(l: SkipMap) {
+  for i in 0..100 {
+    l.insert(0, &key(i), &std::vec![0; i]).unwrap();
+  }
+  // upsert only writes a new value
+  l.insert(0, &key(0), &[0; 1000]).unwrap();
+  // removal only writes a key
+  l.compare_remove(1, &key(1), Ordering::AcqRel, Ordering::Relaxed)
+    .unwrap();
+
+  let stats = l.size_stats().unwrap();
+  assert_eq!(stats.keys.count(), 101);
+  assert_eq!(stats.keys.sum(), 101 * 5);
+  assert_eq!(stats.keys.buckets()[Histogram::bucket_of(5)], 101);
+
+  assert_eq!(stats.values.count(), 101);
+  assert_eq!(stats.values.sum(), (0..100).sum::<u64>() + 1000);
+  assert_eq!(stats.values.buckets()[0], 1);
+  assert_eq!(stats.values.buckets()[Histogram::bucket_of(1000)], 1);
+  assert_eq!(stats.values.percentile(0), Some(1));
+  assert_eq!(stats.values.percentile(100), Some(1024));
+  assert_eq!(stats.values.mean(), Some(stats.values.sum() / 101));
+
+  // shared by the clones
+  let l2 = l.clone();
+  l2.insert(0, &key(100), b"").unwrap();
+  assert_eq!(l.size_stats().unwrap().keys.count(), 102);
+}
+
+#[test]
+fn test_size_stats() {
+  run(|| {
+    size_stats(SkipMap::with_options(TEST_OPTIONS.with_size_histograms(true)).unwrap());
+    assert!(SkipMap::<u64>::with_options(TEST_OPTIONS)
+      .unwrap()
+      .size_stats()
+      .is_none());
+  })
+}
+
+#[test]
+fn test_size_stats_unify() {
+  run(|| size_stats(SkipMap::with_options(UNIFY_TEST_OPTIONS.with_size_histograms(true)).unwrap()))
+}
+
+#[test]
+fn test_histogram_buckets() {
+  assert_eq!(Histogram::bucket_of(0), 0);
+  assert_eq!(Histogram::bucket_of(1), 1);
+  assert_eq!(Histogram::bucket_of(3), 2);
+  assert_eq!(Histogram::bucket_of(u32::MAX), HISTOGRAM_BUCKETS - 1);
+  for idx in 0..HISTOGRAM_BUCKETS {
+    let range = Histogram::bucket_range(idx);
+    assert_eq!(Histogram::bucket_of(range.start as u32), idx);
+    assert_eq!(Histogram::bucket_of((range.end - 1) as u32), idx);
+  }
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn test_time_partitioned_list() {
+  use crate::partition::TimePartitionedList;
+  use core::num::NonZeroU64;
+
+  run(|| {
+    let list = TimePartitionedList::new(NonZeroU64::new(100).unwrap(), TEST_OPTIONS);
+    assert_eq!(list.partition_start(199), 100);
+    for ts in (0..500).step_by(50) {
+      list
+        .insert(ts, 0, &key(ts as usize), &new_value(ts as usize))
+        .unwrap();
+    }
+    assert_eq!(list.partition_starts(), [0, 100, 200, 300, 400]);
+
+    let ent = list.get(250, 0, &key(250)).unwrap();
+    assert_eq!(ent.value(), new_value(250));
+    assert!(list.get(250, 0, &key(150)).is_none());
+
+    assert_eq!(list.drop_before(99), 0);
+    assert_eq!(list.drop_before(250), 2);
+    assert_eq!(list.partition_starts(), [200, 300, 400]);
+    assert!(list.get(150, 0, &key(150)).is_none());
+    assert!(list.partition(100).is_none());
+    // the owned entry keeps the ARENA alive
+    assert_eq!(list.drop_before(u64::MAX), 3);
+    assert_eq!(list.num_partitions(), 0);
+    assert_eq!(ent.value(), new_value(250));
+  })
+}
+
+fn sorted_set(l: SkipMap<u64, Scored>) {
+  for i in 0..100 {
+    // several members share the same score
+    l.insert_scored(0, (i / 2) as u64, &key(i), &new_value(i))
+      .unwrap();
+  }
+
+  let ents = l.range_by_score(0, 10..=20).collect::<std::vec::Vec<_>>();
+  assert_eq!(ents.len(), 22);
+  for (ent, i) in ents.iter().zip(20..) {
+    let (score, member) = Scored::split(ent.key()).unwrap();
+    assert_eq!(score, (i / 2) as u64);
+    assert_eq!(member, key(i));
+    assert_eq!(ent.value(), new_value(i));
+  }
+  assert_eq!(
+    l.range_by_score(0, (Bound::Excluded(48), Bound::Unbounded))
+      .count(),
+    2
+  );
+  assert_eq!(
+    l.range_by_score(0, (Bound::Excluded(u64::MAX), Bound::Unbounded))
+      .count(),
+    0
+  );
+  assert_eq!(l.range_by_score(0, ..).count(), 100);
+
+  assert_eq!(l.rank_by_score(0, 0), 0);
+  assert_eq!(l.rank_by_score(0, 10), 20);
+  assert_eq!(l.rank_by_score(0, 1000), 100);
+
+  // move a member to another score
+  l.remove_scored(1, 5, &key(10)).unwrap();
+  l.insert_scored(1, 100, &key(10), &new_value(10)).unwrap();
+  assert!(l.get_scored(1, 5, &key(10)).is_none());
+  assert!(l.get_scored(0, 5, &key(10)).is_some());
+  assert_eq!(l.rank_by_score(1, 10), 19);
+  let last = l.range_by_score(1, 50..).collect::<std::vec::Vec<_>>();
+  assert_eq!(last.len(), 1);
+  assert_eq!(
+    Scored::split(last[0].key()),
+    Some((100, key(10).as_slice()))
+  );
+  assert_eq!(l.range_by_score(0, 50..).count(), 0);
+
+  // the keys without a score are skipped
+  l.insert(0, &[0], b"plain").unwrap();
+  l.insert(0, &[0, 0, 0, 0, 0, 0, 0x14], b"plain").unwrap();
+  assert_eq!(Scored::split(&[0]), None);
+  assert_eq!(l.range_by_score(0, ..).count(), 100);
+  assert_eq!(l.rank_by_score(0, 10), 20);
+}
+
+#[test]
+fn test_sorted_set() {
+  run(|| sorted_set(SkipMap::with_options_and_comparator(TEST_OPTIONS, Scored).unwrap()))
+}
+
+#[test]
+fn test_sorted_set_unify() {
+  run(|| sorted_set(SkipMap::with_options_and_comparator(UNIFY_TEST_OPTIONS, Scored).unwrap()))
+}
+
+fn first_byte(_: &[u8], value: &[u8]) -> Option<std::vec::Vec<u8>> {
+  value.first().map(|b| std::vec![*b])
+}
+
+#[test]
+fn test_indexed_map() {
+  use crate::index::IndexedMap;
+
+  run(|| {
+    let l = IndexedMap::with_options(TEST_OPTIONS, first_byte).unwrap();
+    // the capacity is split between the map and the index
+    assert!(l.primary().capacity() < TEST_OPTIONS.capacity() as usize);
+    assert!(l.index().capacity() < TEST_OPTIONS.capacity() as usize);
+    for i in 0..30 {
+      l.insert(0, &key(i), &[b'a' + (i % 3) as u8]).unwrap();
+    }
+    // values without the first byte are not indexed
+    l.insert(0, b"empty", &[]).unwrap();
+
+    let keys = l
+      .get_by_index(0, b"a")
+      .map(|ent| ent.key().to_vec())
+      .collect::<std::vec::Vec<_>>();
+    assert_eq!(
+      keys,
+      (0..30).step_by(3).map(key).collect::<std::vec::Vec<_>>()
+    );
+    assert_eq!(l.get_by_index(0, b"").count(), 0);
+
+    // move every key indexed by `a` to `b`, and remove the ones indexed by `c`
+    for i in 0..30 {
+      match i % 3 {
+        0 => l.insert(1, &key(i), b"b").unwrap(),
+        2 => l.remove(1, &key(i)).unwrap(),
+        _ => {}
+      }
+    }
+    assert_eq!(l.get_by_index(1, b"a").count(), 0);
+    assert_eq!(l.get_by_index(1, b"b").count(), 20);
+    assert_eq!(l.get_by_index(1, b"c").count(), 0);
+    assert_eq!(l.get_by_index(0, b"a").count(), 10);
+    assert_eq!(l.get_by_index(0, b"c").count(), 10);
+  })
+}
+
+#[test]
+fn test_iter_by_expiry() {
+  run(|| {
+    let l = SkipMap::<TtlTrailer>::with_options(TEST_OPTIONS).unwrap();
+    let trailer = |version, expires_at| TtlTrailer {
+      version,
+      expires_at,
+    };
+    let ttl = |t: &TtlTrailer| {
+      if t.expires_at == 0 {
+        None
+      } else {
+        Some(t.expires_at)
+      }
+    };
+
+    for i in 0..100 {
+      l.insert(trailer(1, (100 - i as u64) % 10), &key(i), &new_value(i))
+        .unwrap();
+    }
+    // the latest version decides the expiration time
+    l.insert(trailer(2, 1000), &key(0), &new_value(0)).unwrap();
+    l.compare_remove(trailer(2, 0), &key(1), Ordering::AcqRel, Ordering::Relaxed)
+      .unwrap();
+
+    let ents = l.iter_by_expiry(2, ttl).collect::<std::vec::Vec<_>>();
+    assert_eq!(ents.len(), 90);
+    for w in ents.windows(2) {
+      assert!(w[0].0 <= w[1].0);
+      if w[0].0 == w[1].0 {
+        assert!(w[0].1.key() < w[1].1.key());
+      }
+    }
+    assert_eq!(ents[0].0, 1);
+    assert_eq!(ents[0].1.key(), key(9));
+    let (expires_at, last) = ents.last().unwrap();
+    assert_eq!((*expires_at, last.key()), (1000, key(0).as_slice()));
+
+    assert_eq!(l.iter_by_expiry(1, ttl).len(), 90);
+    assert_eq!(l.iter_by_expiry(0, ttl).len(), 0);
+  })
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn test_bounded_map() {
+  use crate::quota::{BoundedMap, EvictionPolicy, Quota};
+
+  run(|| {
+    // reject
+    let l = BoundedMap::with_options(
+      TEST_OPTIONS,
+      Quota::new().with_max_live_bytes(100),
+      EvictionPolicy::Reject,
+    )
+    .unwrap();
+    for i in 0..10 {
+      l.insert(0, &key(i), &new_value(i)).unwrap();
+    }
+    assert_eq!(l.live_bytes(), 100);
+    assert_eq!(
+      l.insert(0, &key(10), &new_value(10)),
+      Err(Error::QuotaExceeded)
+    );
+    // overwriting with the value of the same size still fits
+    l.insert(1, &key(0), &new_value(100)).unwrap();
+    l.remove(1, &key(1)).unwrap();
+    assert_eq!((l.entries(), l.live_bytes()), (9, 90));
+    l.insert(1, &key(10), &new_value(10)).unwrap();
+    assert_eq!(l.entries(), 10);
+
+    // evict the first key by the callback
+    let l = BoundedMap::with_options(
+      TEST_OPTIONS,
+      Quota::new().with_max_entries(5),
+      EvictionPolicy::EvictWith(|map, version| map.first(version).map(|ent| ent.key().to_vec())),
+    )
+    .unwrap();
+    for i in 0..20 {
+      l.insert(0, &key(i), &new_value(i)).unwrap();
+    }
+    assert_eq!(l.entries(), 5);
+    let keys = l
+      .map()
+      .iter(0)
+      .map(|ent| ent.key().to_vec())
+      .collect::<std::vec::Vec<_>>();
+    assert_eq!(keys, (15..20).map(key).collect::<std::vec::Vec<_>>());
+    // the written key is never evicted for itself
+    let l = BoundedMap::with_options(
+      TEST_OPTIONS,
+      Quota::new().with_max_live_bytes(15),
+      EvictionPolicy::EvictWith(|map, version| map.first(version).map(|ent| ent.key().to_vec())),
+    )
+    .unwrap();
+    l.insert(0, &key(0), &new_value(0)).unwrap();
+    assert_eq!(l.insert(0, &key(0), &make_value(0)), Ok(()));
+    assert_eq!(
+      l.insert(0, &key(0), &big_value(0)),
+      Err(Error::QuotaExceeded)
+    );
+    assert_eq!((l.entries(), l.live_bytes()), (1, 11));
+  })
+}
+
+fn access_stamps(l: SkipMap) {
+  for i in 0..100 {
+    l.set_access_clock(i as u32);
+    l.insert(0, &key(i), &new_value(i)).unwrap();
+  }
+
+  // the clones share the clock
+  let l2 = l.clone();
+  l2.set_access_clock(1000);
+  assert_eq!(l.access_clock(), Some(1000));
+  for i in (0..100).step_by(2) {
+    assert_eq!(l.get(0, &key(i)).unwrap().value(), new_value(i));
+  }
+
+  // the scans do not update the stamps
+  for (i, ent) in l.iter(0).enumerate() {
+    let expected = if i % 2 == 0 { 1000 } else { i as u32 };
+    assert_eq!(l.last_access(&ent), Some(expected));
+  }
+  let lru = l.iter(0).min_by_key(|ent| l.last_access(ent)).unwrap();
+  assert_eq!(lru.key(), key(1));
+
+  // the entries of another map have no stamp in this map
+  let other = SkipMap::<u64>::with_options(TEST_OPTIONS).unwrap();
+  other.insert(0, &key(0), &new_value(0)).unwrap();
+  assert_eq!(l.last_access(&other.get(0, &key(0)).unwrap()), None);
+}
+
+#[test]
+fn test_access_stamps() {
+  run(|| {
+    access_stamps(SkipMap::with_options(TEST_OPTIONS.with_access_stamps(true)).unwrap());
+
+    let l = SkipMap::<u64>::with_options(TEST_OPTIONS).unwrap();
+    l.insert(0, b"a", b"a").unwrap();
+    l.set_access_clock(1);
+    assert!(l.access_clock().is_none());
+    assert!(l.last_access(&l.get(0, b"a").unwrap()).is_none());
+  })
+}
+
+#[test]
+fn test_access_stamps_unify() {
+  run(|| access_stamps(SkipMap::with_options(UNIFY_TEST_OPTIONS.with_access_stamps(true)).unwrap()))
+}
+
+fn memory_report(opts: Options) {
+  let l = SkipMap::with_options(opts).unwrap();
+  let stamp = if opts.access_stamps() { 4 } else { 0 };
+  let meta = if opts.unify() { layout::META_SIZE } else { 0 };
+  let max_height: u8 = opts.max_height().into();
+
+  for i in 0..100 {
+    l.insert(0, &key(i), &new_value(i)).unwrap();
+  }
+  let report = l.memory_report();
+  assert_eq!(report.payload, 1000);
+  assert_eq!(
+    report.headers,
+    meta + 102 * (layout::NODE_SIZE + 8) + 100 * stamp
+  );
+  assert!(report.towers >= (100 + 2 * max_height as usize) * layout::LINK_SIZE);
+  assert!(report.total() <= l.allocated());
+  assert!(report.amplification().unwrap() > 1.0);
+
+  l.compare_remove(0, &key(0), Ordering::AcqRel, Ordering::Relaxed)
+    .unwrap();
+  assert_eq!(l.memory_report().payload, 995);
+}
+
+#[test]
+fn test_memory_report() {
+  run(|| {
+    memory_report(TEST_OPTIONS);
+    memory_report(TEST_OPTIONS.with_access_stamps(true));
+    assert_eq!(
+      SkipMap::<u64>::with_options(TEST_OPTIONS)
+        .unwrap()
+        .memory_report()
+        .amplification(),
+      None
+    );
+  })
+}
+
+#[test]
+fn test_memory_report_unify() {
+  run(|| memory_report(UNIFY_TEST_OPTIONS))
+}
+
+#[test]
+fn test_entry_overhead() {
+  let fp = layout::entry_overhead(3, 10, 20, true);
+  assert_eq!(fp.payload, 30);
+  assert_eq!(fp.towers, 3 * layout::LINK_SIZE);
+  assert_eq!(fp.headers, layout::NODE_SIZE + 8);
+  assert_eq!(fp.overhead() + fp.payload, fp.total());
+
+  let fp = layout::entry_overhead(3, 10, 20, false);
+  assert_eq!(fp.headers, layout::NODE_SIZE);
+  assert_eq!(fp.padding, layout::NODE_ALIGN - 1);
+}
+
+fn rebuild_index(mut l: SkipMap) {
+  for i in 0..1000 {
+    l.insert(0, &key(i), &new_value(i)).unwrap();
+  }
+  let height = l.height();
+
+  // break all the previous links and the upper levels
+  let mut nodes = std::vec::Vec::new();
+  unsafe {
+    let mut nd = l.get_next(l.head, 0);
+    while nd.ptr != l.tail.ptr {
+      nodes.push(nd);
+      let next = nd.next_offset(&l.arena, 0);
+      nd.write_tower(&l.arena, 0, u32::MAX - 8, next);
+      for level in 1..nd.as_ref().height() as usize {
+        nd.write_tower(&l.arena, level, 0, u32::MAX - 8);
+      }
+      nd = l.get_next(nd, 0);
+    }
+  }
+  assert!(l.verify().is_err());
+
+  // a shared map cannot be rebuilt
+  let clone = l.clone();
+  assert_eq!(l.rebuild_index(), Err(Error::Shared(2)));
+  drop(clone);
+
+  assert_eq!(l.rebuild_index().unwrap(), 1000);
+  assert!(l.verify().is_ok());
+  assert_eq!(l.len(), 1000);
+  assert_eq!(l.height(), height);
+  for i in 0..1000 {
+    assert_eq!(l.get(0, &key(i)).unwrap().value(), new_value(i));
+  }
+  assert_eq!(l.iter(0).rev().count(), 1000);
+
+  // the links are not trusted, the nodes after a broken bottom level link are recovered
+  unsafe {
+    nodes[499].write_tower(&l.arena, 0, nodes[498].offset, u32::MAX - 8);
+    nodes[700].write_tower(&l.arena, 0, nodes[699].offset, nodes[100].offset);
+  }
+  l.poison(PoisonReason::Other);
+  assert_eq!(l.rebuild_index().unwrap(), 1000);
+  assert!(!l.is_poisoned());
+  assert!(l.verify().is_ok());
+  assert_eq!(l.len(), 1000);
+  for i in 0..1000 {
+    assert_eq!(l.get(0, &key(i)).unwrap().value(), new_value(i));
+  }
+
+  // a newer version and a removal are recovered as separate nodes
+  l.insert(1, &key(1000), &new_value(1000)).unwrap();
+  l.insert(2, &key(500), &new_value(0)).unwrap();
+  l.get_or_remove(3, &key(10)).unwrap();
+  unsafe {
+    l.head.write_tower(&l.arena, 0, 0, l.tail.offset);
+  }
+  assert_eq!(l.rebuild_index().unwrap(), 1003);
+  assert!(l.verify().is_ok());
+  assert_eq!(l.get(3, &key(500)).unwrap().value(), new_value(0));
+  assert_eq!(l.get(0, &key(500)).unwrap().value(), new_value(500));
+  assert!(l.get(3, &key(10)).is_none());
+  assert!(l.get(3, &key(1000)).is_some());
+  assert!(l.get(0, &key(1000)).is_none());
+}
+
+#[test]
+fn test_rebuild_index() {
+  run(|| rebuild_index(SkipMap::with_options(TEST_OPTIONS).unwrap()))
+}
+
+#[test]
+fn test_rebuild_index_unify() {
+  run(|| rebuild_index(SkipMap::with_options(UNIFY_TEST_OPTIONS).unwrap()))
+}
+
+fn cow_clone(l: SkipMap) {
+  for i in 0..100 {
+    l.insert(1, &key(i), &new_value(i)).unwrap();
+  }
+
+  let fork = l.cow_clone().unwrap();
+  assert_eq!(fork.fork_version(), 1);
+  for i in 0..50 {
+    fork.insert(2, &key(i), &make_value(i)).unwrap();
+  }
+  for i in 50..60 {
+    fork.remove(2, &key(i)).unwrap();
+  }
+  fork.insert(2, &key(100), &make_value(100)).unwrap();
+
+  // the later writes to the base are not visible in the fork
+  l.insert(3, &key(0), b"base").unwrap();
+  l.insert(3, &key(101), b"base").unwrap();
+
+  assert_eq!(fork.get(3, &key(0)).unwrap().value(), make_value(0));
+  assert_eq!(fork.get(1, &key(0)).unwrap().value(), new_value(0));
+  assert!(fork.get(3, &key(55)).is_none());
+  assert_eq!(fork.get(1, &key(55)).unwrap().value(), new_value(55));
+  assert_eq!(fork.get(3, &key(70)).unwrap().value(), new_value(70));
+  assert!(fork.contains_key(3, &key(100)));
+  assert!(!fork.contains_key(3, &key(101)));
+
+  let ents = fork.iter(3).collect::<Vec<_>>();
+  assert_eq!(ents.len(), 91);
+  for (i, ent) in ents.iter().enumerate() {
+    let i = if i < 50 { i } else { i + 10 };
+    assert_eq!(ent.key(), key(i));
+  }
+  assert_eq!(fork.iter(1).count(), 100);
+
+  // the base is not affected by the fork
+  assert_eq!(l.get(3, &key(1)).unwrap().value(), new_value(1));
+  assert!(l.contains_key(3, &key(55)));
+  assert!(!l.contains_key(3, &key(100)));
+  assert_eq!(fork.base().refs(), 2);
+  drop(fork);
+  assert_eq!(l.refs(), 1);
+}
+
+#[test]
+fn test_cow_clone() {
+  run(|| cow_clone(SkipMap::with_options(TEST_OPTIONS).unwrap()))
+}
+
+#[test]
+fn test_cow_clone_unify() {
+  run(|| cow_clone(SkipMap::with_options(UNIFY_TEST_OPTIONS).unwrap()))
+}
+
+fn key_fingerprints(l: SkipMap) {
+  for i in 0..500 {
+    for version in 1..4 {
+      l.insert(version, &key(i), &make_value(i + version as usize))
+        .unwrap();
+    }
+    if i % 3 == 0 {
+      l.compare_remove(4, &key(i), Ordering::AcqRel, Ordering::Relaxed)
+        .unwrap();
+    }
+  }
+  assert_eq!(l.verify(), Ok(()));
+
+  for i in 0..500 {
+    assert_eq!(l.get(2, &key(i)).unwrap().value(), make_value(i + 2));
+    assert_eq!(l.get(4, &key(i)).is_none(), i % 3 == 0);
+  }
+  assert_eq!(
+    l.lower_bound(1, Bound::Excluded(&key(10))).unwrap().value(),
+    make_value(12)
+  );
+  assert_eq!(
+    l.upper_bound(2, Bound::Excluded(&key(10))).unwrap().value(),
+    make_value(11)
+  );
+
+  assert_eq!(l.iter(3).count(), 500);
+  assert_eq!(l.iter(4).count(), 333);
+  assert_eq!(l.iter(4).rev().count(), 333);
+  assert_eq!(l.iter_all_versions(4).count(), 1667);
+}
+
+#[test]
+fn test_key_fingerprints() {
+  run(|| {
+    key_fingerprints(SkipMap::with_options(TEST_OPTIONS.with_key_fingerprints(true)).unwrap());
+    // the fingerprints follow the access stamps
+    key_fingerprints(
+      SkipMap::with_options(
+        TEST_OPTIONS
+          .with_key_fingerprints(true)
+          .with_access_stamps(true),
+      )
+      .unwrap(),
+    );
+  })
+}
+
+#[test]
+fn test_key_fingerprints_unify() {
+  run(|| {
+    key_fingerprints(SkipMap::with_options(UNIFY_TEST_OPTIONS.with_key_fingerprints(true)).unwrap())
+  })
+}
+
+#[test]
+fn test_basic_backend() {
+  run(|| basic_in(SkipMap::with_backend(crate::backend::Heap, TEST_OPTIONS).unwrap()))
+}
+
+#[test]
+#[cfg(feature = "memmap")]
+fn test_basic_backend_map_anon() {
+  use crate::backend::AnonymousMmap;
+
+  run(|| {
+    let map_options = MmapOptions::default().len(ARENA_SIZE as u32);
+    basic_in(SkipMap::with_backend(AnonymousMmap(map_options), Options::new()).unwrap());
+  })
+}
+
+#[test]
+#[cfg(feature = "memmap")]
+#[cfg_attr(miri, ignore)]
+fn test_reopen_backend_file_mmap() {
+  use crate::backend::FileMmap;
+
+  run(|| {
+    let dir = tempfile::tempdir().unwrap();
+    let p = dir.path().join("reopen_backend_skipmap");
+    {
+      let backend = FileMmap {
+        path: &p,
+        open_options: OpenOptions::default()
+          .create(Some(ARENA_SIZE as u32))
+          .read(true)
+          .write(true),
+        mmap_options: MmapOptions::default(),
+      };
+      // the meta is stored in the ARENA of a persistent backend
+      let l = SkipMap::with_backend(&backend, Options::new()).unwrap();
+      for i in 0..1000 {
+        l.insert(0, &key(i), &new_value(i)).unwrap();
+      }
+      l.flush().unwrap();
+    }
+
+    let open_options = OpenOptions::default().read(true);
+    let l = SkipMap::<u64>::map(&p, open_options, MmapOptions::default(), 0).unwrap();
+    assert_eq!(l.len(), 1000);
+    for i in 0..1000 {
+      assert_eq!(l.get(0, &key(i)).unwrap().value(), new_value(i));
+    }
+  })
+}
+
+fn reserve_aux(l: SkipMap) {
+  l.insert(0, b"a", b"a").unwrap();
+  let mut reserved = std::vec::Vec::new();
+  for (i, align) in [1, 3, 8, 64, 4096].into_iter().enumerate() {
+    let (offset, buf) = l.reserve_aux(100, align).unwrap();
+    assert_eq!(buf.len(), 100);
+    assert!(buf.iter().all(|b| *b == 0));
+    assert_eq!(
+      buf.as_ptr() as usize % (align as usize).next_power_of_two(),
+      0
+    );
+    buf.fill(i as u8 + 1);
+    reserved.push(offset);
+  }
+  l.insert(0, b"b", b"b").unwrap();
+  for align in [0, (1 << 31) + 1, u32::MAX] {
+    assert_eq!(
+      l.reserve_aux(100, align).unwrap_err(),
+      Error::InvalidAlignment(align as u64)
+    );
+  }
+
+  for (i, offset) in reserved.into_iter().enumerate() {
+    let buf = unsafe { l.aux(offset, 100) };
+    assert!(buf.iter().all(|b| *b == i as u8 + 1));
+  }
+  assert_eq!(l.verify(), Ok(()));
+  assert_eq!(l.get(0, b"a").unwrap().value(), b"a");
+  assert_eq!(l.len(), 2);
+}
+
+#[test]
+fn test_reserve_aux() {
+  run(|| reserve_aux(SkipMap::with_options(TEST_OPTIONS).unwrap()))
+}
+
+#[test]
+fn test_reserve_aux_unify() {
+  run(|| reserve_aux(SkipMap::with_options(UNIFY_TEST_OPTIONS).unwrap()))
+}
+
+#[test]
+#[cfg(feature = "memmap")]
+#[cfg_attr(miri, ignore)]
+fn test_reopen_aux() {
+  run(|| {
+    let dir = tempfile::tempdir().unwrap();
+    let p = dir.path().join("reopen_aux_skipmap");
+    {
+      let open_options = OpenOptions::default()
+        .create(Some(ARENA_SIZE as u32))
+        .read(true)
+        .write(true);
+      let l = SkipMap::map_mut(&p, open_options, MmapOptions::default()).unwrap();
+      let (offset, buf) = l.reserve_aux(8, 8).unwrap();
+      buf.copy_from_slice(&42u64.to_le_bytes());
+      l.insert(0, b"aux", &offset.to_le_bytes()).unwrap();
+      l.flush().unwrap();
+    }
+
+    let open_options = OpenOptions::default().read(true);
+    let l = SkipMap::<u64>::map(&p, open_options, MmapOptions::default(), 0).unwrap();
+    let offset = u32::from_le_bytes(l.get(0, b"aux").unwrap().value().try_into().unwrap());
+    let buf = unsafe { l.aux(offset, 8) };
+    assert_eq!(u64::from_le_bytes(buf.try_into().unwrap()), 42);
+    assert!(l.reserve_aux(8, 8).is_err());
+  })
+}
+
+fn multimap(l: SkipMap<u64, Multi>) {
+  // a key is a prefix of another one
+  let keys: [&[u8]; 3] = [b"a", b"ab", b"b"];
+  let l2 = l.clone();
+  let mut seqs = std::vec::Vec::new();
+  for i in 0..30 {
+    let map = if i % 2 == 0 { &l } else { &l2 };
+    seqs.push(map.insert_dup(0, keys[i % 3], &new_value(i)).unwrap());
+  }
+  // the clones share the sequence
+  assert_eq!(seqs, (0..30).collect::<std::vec::Vec<u64>>());
+  assert_eq!(l.len(), 30);
+  assert_eq!(l.verify(), Ok(()));
+
+  for (k, key) in keys.iter().enumerate() {
+    let dups = l.get_all(0, key).collect::<std::vec::Vec<_>>();
+    assert_eq!(dups.len(), 10);
+    for (ent, i) in dups.iter().zip((k..30).step_by(3)) {
+      assert_eq!(Multi::split(ent.key()), Some((*key, i as u64)));
+      assert_eq!(ent.value(), new_value(i));
+    }
+  }
+  assert_eq!(l.get_all(0, b"").count(), 0);
+  assert_eq!(l.get_all(0, b"c").count(), 0);
+
+  // the plain iterators yield every duplicate
+  let ents = l
+    .iter(0)
+    .map(|ent| Multi::split(ent.key()).unwrap().0.to_vec())
+    .collect::<std::vec::Vec<_>>();
+  assert_eq!(ents.len(), 30);
+  assert!(ents[..10].iter().all(|k| k == b"a"));
+  assert!(ents[10..20].iter().all(|k| k == b"ab"));
+
+  let removed = l.remove_dup(1, b"ab", 4).unwrap().unwrap();
+  assert_eq!(removed.value(), new_value(4));
+  assert!(l.get_dup(1, b"ab", 4).is_none());
+  assert_eq!(l.get_dup(0, b"ab", 4).unwrap().value(), new_value(4));
+  let seqs = l
+    .get_all(1, b"ab")
+    .map(|ent| Multi::split(ent.key()).unwrap().1)
+    .collect::<std::vec::Vec<_>>();
+  assert_eq!(seqs, [1, 7, 10, 13, 16, 19, 22, 25, 28]);
+  assert_eq!(l.get_all(0, b"ab").count(), 10);
+
+  // the keys without a sequence are never duplicates
+  l.insert(0, b"ab", b"plain").unwrap();
+  assert_eq!(Multi::split(b"ab"), None);
+  assert_eq!(l.get_all(0, b"a").count(), 10);
+  assert_eq!(l.get_all(0, b"ab").count(), 10);
+}
+
+#[test]
+fn test_multimap() {
+  run(|| multimap(SkipMap::with_options_and_comparator(TEST_OPTIONS, Multi::new()).unwrap()))
+}
+
+#[test]
+fn test_multimap_unify() {
+  run(|| multimap(SkipMap::with_options_and_comparator(UNIFY_TEST_OPTIONS, Multi::new()).unwrap()))
+}
+
+#[test]
+fn test_multimap_seed() {
+  run(|| {
+    let l = SkipMap::<u64, _>::with_options_and_comparator(TEST_OPTIONS, Multi::new()).unwrap();
+    // the duplicates which are not written by this comparator
+    l.insert(0, &Multi::key(b"a", 41), b"old").unwrap();
+    l.insert(0, b"b", b"plain").unwrap();
+    assert_eq!(l.insert_dup(0, b"a", b"new").unwrap(), 42);
+    assert_eq!(l.insert_dup(0, b"c", b"new").unwrap(), 43);
+
+    let values = l
+      .get_all(0, b"a")
+      .map(|ent| ent.value().to_vec())
+      .collect::<std::vec::Vec<_>>();
+    assert_eq!(values, [b"old".to_vec(), b"new".to_vec()]);
+  })
+}
+
+#[cfg(feature = "perf-stats")]
+fn latency_stats(l: SkipMap) {
+  for i in 0..100 {
+    l.insert(0, &key(i), &new_value(i)).unwrap();
+  }
+  l.compare_remove(1, &key(0), Ordering::AcqRel, Ordering::Relaxed)
+    .unwrap();
+  for i in 0..50 {
+    l.get(0, &key(i)).unwrap();
+  }
+
+  // the clones share the histograms
+  let l2 = l.clone();
+  l2.iter(0).seek_lower_bound(Bound::Included(&key(10)));
+  l2.iter(0).seek_upper_bound(Bound::Excluded(&key(10)));
+
+  let stats = l.latency_stats();
+  assert_eq!(stats.insert.count(), 101);
+  assert_eq!(stats.get.count(), 50);
+  assert_eq!(stats.seek.count(), 2);
+  assert!(stats.insert.percentile(50) <= stats.insert.percentile(99));
+}
+
+#[test]
+#[cfg(feature = "perf-stats")]
+fn test_latency_histogram_buckets() {
+  assert_eq!(LatencyHistogram::bucket_of(0), 0);
+  assert_eq!(LatencyHistogram::bucket_of(15), 15);
+  assert_eq!(LatencyHistogram::bucket_of(16), 16);
+  assert_eq!(LatencyHistogram::bucket_of(33), 32);
+  assert_eq!(LatencyHistogram::bucket_of(u32::MAX), LATENCY_BUCKETS - 1);
+  assert_eq!(
+    LatencyHistogram::bucket_range(LATENCY_BUCKETS - 1).end,
+    1 << 32
+  );
+  for idx in 0..LATENCY_BUCKETS {
+    let range = LatencyHistogram::bucket_range(idx);
+    assert_eq!(LatencyHistogram::bucket_of(range.start as u32), idx);
+    assert_eq!(LatencyHistogram::bucket_of((range.end - 1) as u32), idx);
+    if idx + 1 < LATENCY_BUCKETS {
+      assert_eq!(LatencyHistogram::bucket_range(idx + 1).start, range.end);
+    }
+    // the width of a bucket is at most 1/16 of its latencies
+    assert!((range.end - range.start) * 16 <= range.start.max(16));
+  }
+}
+
+#[test]
+#[cfg(feature = "perf-stats")]
+fn test_latency_stats() {
+  run(|| latency_stats(SkipMap::with_options(TEST_OPTIONS).unwrap()))
+}
+
+#[test]
+#[cfg(feature = "perf-stats")]
+fn test_latency_stats_unify() {
+  run(|| latency_stats(SkipMap::with_options(UNIFY_TEST_OPTIONS).unwrap()))
+}
+
+#[test]
+fn test_segmented_map() {
+  run(|| {
+    let l = SegmentedMap::<u64>::with_options(Options::new().with_capacity(4096)).unwrap();
+    for i in 0..1000 {
+      l.insert(0, &key(i), &new_value(i)).unwrap();
+    }
+    let segments = l.num_segments();
+    assert!(segments > 1);
+    // the older segments are never moved or grown
+    let maps = l.segments();
+    assert_eq!(maps.len(), segments);
+    assert_eq!(maps[segments - 1].capacity(), 4096);
+    assert!(maps[0].capacity() > maps[1].capacity());
+
+    // shadow the entries of the older segments
+    for i in 0..10 {
+      l.insert(1, &key(i), &make_value(i)).unwrap();
+      l.remove(1, &key(i + 10)).unwrap();
+    }
+    for i in 0..1000 {
+      let ent = l.get(1, &key(i));
+      match i {
+        0..=9 => assert_eq!(ent.unwrap().value(), make_value(i)),
+        10..=19 => assert!(ent.is_none()),
+        _ => assert_eq!(ent.unwrap().value(), new_value(i)),
+      }
+      assert_eq!(l.get(0, &key(i)).unwrap().value(), new_value(i));
+    }
+
+    let maps = l.segments();
+    let merged = MergeIterator::new(1, &maps).collect::<std::vec::Vec<_>>();
+    assert_eq!(merged.len(), 990);
+    assert_eq!(MergeIterator::new(0, &maps).count(), 1000);
+
+    // an entry larger than the newest segment
+    let big = std::vec![1; 1 << 20];
+    l.insert(2, &key(0), &big).unwrap();
+    assert_eq!(l.get(2, &key(0)).unwrap().value(), big.as_slice());
+    assert_eq!(l.num_segments(), segments + 1);
+  })
+}
+
+#[test]
+#[cfg(feature = "memmap")]
+#[cfg_attr(miri, ignore)]
+fn test_map_mut_with_capacity() {
+  run(|| {
+    let dir = tempfile::tempdir().unwrap();
+    let p = dir.path().join("test_skipmap_map_mut_with_capacity");
+    {
+      let l = SkipMap::map_mut_with_capacity(&p, ARENA_SIZE as u32).unwrap();
+      assert_eq!(l.capacity(), ARENA_SIZE);
+      for i in 0..100 {
+        l.insert(0, &key(i), &new_value(i)).unwrap();
+      }
+      l.flush().unwrap();
+    }
+
+    let l = SkipMap::<u64>::map_read_only(&p).unwrap();
+    assert_eq!(l.len(), 100);
+    for i in 0..100 {
+      assert_eq!(l.get(0, &key(i)).unwrap().value(), new_value(i));
+    }
+    assert!(l.insert(0, &key(0), &new_value(0)).is_err());
+  })
+}
+
+#[test]
+#[cfg(feature = "memmap")]
+#[cfg_attr(miri, ignore)]
+fn test_open() {
+  run(|| {
+    let dir = tempfile::tempdir().unwrap();
+    let p = dir.path().join("test_skipmap_open");
+    {
+      let l = SkipMap::map_mut_with_capacity(&p, ARENA_SIZE as u32).unwrap();
+      for i in 0..100 {
+        l.insert(i as u64, &key(i), &new_value(i)).unwrap();
+      }
+      l.flush().unwrap();
+    }
+
+    let l = SkipMap::<u64>::open(&p).unwrap();
+    assert_eq!(l.len(), 100);
+    assert_eq!(l.max_version(), 99);
+    assert_eq!(l.get(99, &key(42)).unwrap().value(), new_value(42));
+
+    let p = dir.path().join("test_skipmap_open_magic");
+    {
+      let open_options = OpenOptions::default()
+        .create(Some(ARENA_SIZE as u32))
+        .read(true)
+        .write(true);
+      let opts = Options::new().with_magic_version(1);
+      let l =
+        SkipMap::map_mut_with_options(&p, opts, open_options, MmapOptions::default()).unwrap();
+      l.flush().unwrap();
+    }
+    let err = SkipMap::<u64>::open(&p).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+  })
+}
+
+#[test]
+#[cfg(feature = "memmap")]
+#[cfg_attr(miri, ignore)]
+fn test_reopen_layout_mismatch() {
+  run(|| {
+    let dir = tempfile::tempdir().unwrap();
+    let layouts = [
+      Options::new().with_access_stamps(true),
+      Options::new()
+        .with_key_fingerprints(true)
+        .with_max_height(crate::u5::new(8)),
+    ];
+    for (n, opts) in layouts.iter().copied().enumerate() {
+      let p = dir
+        .path()
+        .join(std::format!("test_skipmap_reopen_layout_mismatch_{n}"));
+      {
+        let open_options = OpenOptions::default()
+          .create_new(Some(ARENA_SIZE as u32))
+          .read(true)
+          .write(true);
+        let l =
+          SkipMap::map_mut_with_options(&p, opts, open_options, MmapOptions::default()).unwrap();
+        for i in 0..100 {
+          l.insert(0, &key(i), &new_value(i)).unwrap();
+        }
+        l.flush().unwrap();
+      }
+
+      for other in [Options::new(), layouts[1 - n]] {
+        let open_options = OpenOptions::default().read(true);
+        let err = SkipMap::<u64>::map_with_options(&p, other, open_options, MmapOptions::default())
+          .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+      }
+      // `open` reads the layout from the file
+      let l = SkipMap::<u64>::open(&p).unwrap();
+      assert_eq!(l.opts.max_height(), opts.max_height());
+      assert_eq!(l.opts.access_stamps(), opts.access_stamps());
+      assert_eq!(l.opts.key_fingerprints(), opts.key_fingerprints());
+      assert_eq!(l.get(0, &key(42)).unwrap().value(), new_value(42));
+
+      let open_options = OpenOptions::default().read(true);
+      let l =
+        SkipMap::<u64>::map_with_options(&p, opts, open_options, MmapOptions::default()).unwrap();
+      assert_eq!(l.len(), 100);
+      assert_eq!(l.get(0, &key(42)).unwrap().value(), new_value(42));
+    }
+  })
+}
+
+fn remove_tombstone(l: SkipMap) {
+  for i in 0..100 {
+    l.insert(0, &key(i), &new_value(i)).unwrap();
+  }
+
+  for i in (0..100).step_by(2) {
+    assert_eq!(l.remove(1, &key(i)).unwrap().unwrap().value(), new_value(i));
+  }
+  // removing a removed key
+  assert!(l.remove(1, &key(0)).unwrap().is_none());
+
+  for i in 0..100 {
+    assert_eq!(l.get(1, &key(i)).is_none(), i % 2 == 0);
+    assert_eq!(l.get(0, &key(i)).unwrap().value(), new_value(i));
+  }
+  assert_eq!(l.iter(1).count(), 50);
+  assert_eq!(
+    l.iter_all_versions(1)
+      .filter(|ent| ent.is_removed())
+      .count(),
+    50
+  );
+}
+
+#[test]
+fn test_remove_tombstone() {
+  run(|| remove_tombstone(SkipMap::with_options(TEST_OPTIONS).unwrap()));
+}
+
+#[test]
+fn test_remove_tombstone_unify() {
+  run(|| remove_tombstone(SkipMap::with_options(UNIFY_TEST_OPTIONS).unwrap()));
+}
+
+fn range_bounds(l: SkipMap) {
+  const N: usize = 100;
+
+  for i in (0..N).rev() {
+    l.insert(0, &make_int_key(i), &make_value(i)).unwrap();
+  }
+
+  let k10 = make_int_key(10);
+  let k20 = make_int_key(20);
+  fn keys<'a>(it: impl Iterator<Item = EntryRef<'a, u64>>) -> std::vec::Vec<std::vec::Vec<u8>> {
+    it.map(|ent| ent.key().to_vec()).collect()
+  }
+  let expected = |r: core::ops::Range<usize>| r.map(make_int_key).collect::<std::vec::Vec<_>>();
+
+  assert_eq!(
+    keys(l.range(0, k10.as_slice()..k20.as_slice())),
+    expected(10..20)
+  );
+  assert_eq!(
+    keys(l.range(0, k10.as_slice()..=k20.as_slice())),
+    expected(10..21)
+  );
+  assert_eq!(
+    keys(l.range::<&[u8], _>(
+      0,
+      (
+        Bound::Excluded(k10.as_slice()),
+        Bound::Excluded(k20.as_slice())
+      )
+    )),
+    expected(11..20)
+  );
+  assert_eq!(
+    keys(l.range::<&[u8], _>(0, (Bound::Excluded(k10.as_slice()), Bound::Unbounded))),
+    expected(11..N)
+  );
+  assert_eq!(keys(l.range(0, ..k10.as_slice())), expected(0..10));
+  assert_eq!(
+    keys(l.range(0, k20.as_slice()..k10.as_slice())),
+    expected(0..0)
+  );
+
+  // the iterator stops at the upper bound, and can be reused after a seek
+  let mut it = l.range(0, k10.as_slice()..k20.as_slice());
+  assert_eq!(it.by_ref().count(), 10);
+  assert!(it.next().is_none());
+  let k15 = make_int_key(15);
+  assert_eq!(
+    it.seek_lower_bound(Bound::Included(k15.as_slice()))
+      .unwrap()
+      .key(),
+    k15
+  );
+  assert_eq!(it.count(), 4);
+}
+
+#[test]
+fn test_range_bounds() {
+  run(|| range_bounds(SkipMap::with_options(TEST_OPTIONS).unwrap()));
+}
+
+#[test]
+fn test_range_bounds_unify() {
+  run(|| range_bounds(SkipMap::with_options(UNIFY_TEST_OPTIONS).unwrap()));
+}
+
+fn iter_rev(l: SkipMap) {
+  const N: usize = 100;
+
+  for i in 0..N {
+    l.insert(0, &make_int_key(i), &make_value(i)).unwrap();
+    l.insert(2, &make_int_key(i), &new_value(i)).unwrap();
+  }
+  l.remove(1, &make_int_key(0)).unwrap();
+  l.remove(3, &make_int_key(1)).unwrap();
+
+  // the newest visible version of each key, the removed keys are skipped
+  let keys = |version, r: core::ops::Range<usize>| {
+    l.range(
+      version,
+      make_int_key(r.start).as_slice()..make_int_key(r.end).as_slice(),
+    )
+    .rev()
+    .map(|ent| (ent.key().to_vec(), ent.value().to_vec()))
+    .collect::<std::vec::Vec<_>>()
+  };
+  assert_eq!(
+    keys(1, 0..5),
+    (1..5)
+      .rev()
+      .map(|i| (make_int_key(i), make_value(i)))
+      .collect::<std::vec::Vec<_>>()
+  );
+  assert_eq!(
+    keys(3, 0..5),
+    (0..5)
+      .rev()
+      .filter(|i| *i != 1)
+      .map(|i| (make_int_key(i), new_value(i)))
+      .collect::<std::vec::Vec<_>>()
+  );
+  assert!(keys(3, 5..5).is_empty());
+
+  assert!(l
+    .iter(3)
+    .rev()
+    .map(|ent| ent.key().to_vec())
+    .eq((0..N).rev().filter(|i| *i != 1).map(make_int_key)));
+  assert_eq!(l.iter_all_versions(3).rev().count(), 2 * N + 2);
+}
+
+#[test]
+fn test_iter_rev() {
+  run(|| iter_rev(SkipMap::with_options(TEST_OPTIONS).unwrap()));
+}
+
+#[test]
+fn test_iter_rev_unify() {
+  run(|| iter_rev(SkipMap::with_options(UNIFY_TEST_OPTIONS).unwrap()));
+}
+
+#[test]
+fn test_iter_rev_descend() {
+  run(|| {
+    let l = SkipMap::with_options_and_comparator(TEST_OPTIONS, Descend).unwrap();
+    for i in 0..10 {
+      l.insert(0, &make_int_key(i), &make_value(i)).unwrap();
+    }
+
+    assert!(l
+      .iter(0)
+      .rev()
+      .map(|ent| ent.key().to_vec())
+      .eq((0..10).map(make_int_key)));
+    let (lower, upper) = (make_int_key(2), make_int_key(5));
+    assert!(l
+      .range(0, lower.as_slice()..upper.as_slice())
+      .rev()
+      .map(|ent| ent.key().to_vec())
+      .eq((2..5).map(make_int_key)));
+  })
+}
+
+fn cursor(l: SkipMap) {
+  const N: usize = 100;
+
+  for i in (0..N).step_by(2) {
+    l.insert(0, &make_int_key(i), &make_value(i)).unwrap();
+  }
+
+  let mut cursor = l.cursor(0);
+  assert!(!cursor.valid());
+  assert!(cursor.next().is_none());
+
+  assert!(cursor.seek(&make_int_key(11)));
+  assert_eq!(cursor.key().unwrap(), make_int_key(12));
+  for i in (0..12).step_by(2).rev() {
+    assert_eq!(cursor.prev().unwrap().value(), make_value(i));
+  }
+  assert!(cursor.prev().is_none());
+  // an invalid cursor does not move
+  assert!(cursor.next().is_none());
+
+  assert!(cursor.seek_for_prev(&make_int_key(11)));
+  assert_eq!(cursor.key().unwrap(), make_int_key(10));
+  for i in (12..N).step_by(2) {
+    assert_eq!(cursor.next().unwrap().key(), make_int_key(i));
+  }
+  assert!(cursor.next().is_none());
+  assert!(!cursor.seek(&make_int_key(N)));
+
+  assert!(cursor.seek_to_last());
+  assert_eq!(cursor.key().unwrap(), make_int_key(N - 2));
+  assert!(cursor.seek_to_first());
+  assert_eq!(cursor.key().unwrap(), make_int_key(0));
+  assert_eq!(cursor.next().unwrap().key(), make_int_key(2));
+  assert_eq!(cursor.prev().unwrap().key(), make_int_key(0));
+}
+
+#[test]
+fn test_cursor() {
+  run(|| cursor(SkipMap::with_options(TEST_OPTIONS).unwrap()));
+}
+
+#[test]
+fn test_cursor_unify() {
+  run(|| cursor(SkipMap::with_options(UNIFY_TEST_OPTIONS).unwrap()));
+}
+
+#[derive(Debug)]
+struct CaseInsensitiveComparator;
+
+impl Comparator for CaseInsensitiveComparator {
+  fn compare(&self, a: &[u8], b: &[u8]) -> cmp::Ordering {
+    a.iter()
+      .map(u8::to_ascii_lowercase)
+      .cmp(b.iter().map(u8::to_ascii_lowercase))
+  }
+
+  fn contains<'a, Q>(&self, range: &impl core::ops::RangeBounds<Q>, key: &'a [u8]) -> bool
+  where
+    &'a [u8]: PartialOrd<Q>,
+    Q: ?Sized + PartialOrd<&'a [u8]>,
+  {
+    range.contains(&key)
+  }
+
+  fn has_prefix(&self, key: &[u8], prefix: &[u8]) -> bool {
+    key.len() >= prefix.len() && key[..prefix.len()].eq_ignore_ascii_case(prefix)
+  }
+}
+
+#[test]
+fn test_prefix() {
+  run(|| {
+    let l = SkipMap::with_options(TEST_OPTIONS).unwrap();
+    for i in 0..100 {
+      l.insert(0, &key(i), &new_value(i)).unwrap();
+    }
+    l.remove(1, &key(15)).unwrap();
+
+    assert!(l
+      .prefix(1, b"0001")
+      .map(|ent| ent.key().to_vec())
+      .eq((10..20).filter(|i| *i != 15).map(key)));
+    assert_eq!(l.prefix(0, b"0001").count(), 10);
+    assert_eq!(l.prefix(0, b"").count(), 100);
+    assert_eq!(l.prefix(0, b"1").count(), 0);
+  })
+}
+
+#[test]
+fn test_prefix_comparator() {
+  run(|| {
+    let l = SkipMap::with_options_and_comparator(TEST_OPTIONS, CaseInsensitiveComparator).unwrap();
+    for k in [&b"Apple"[..], b"apricot", b"APRIL", b"banana"] {
+      l.insert(0, k, b"").unwrap();
+    }
+    assert!(l.get(0, b"BANANA").is_some());
+
+    let keys = l
+      .prefix(0, b"ap")
+      .map(|ent| ent.key().to_vec())
+      .collect::<std::vec::Vec<_>>();
+    assert_eq!(
+      keys,
+      [b"Apple".to_vec(), b"apricot".to_vec(), b"APRIL".to_vec()]
+    );
+    assert_eq!(l.prefix(0, b"APR").count(), 2);
+  })
+}
+
+fn get_versions(l: SkipMap) {
+  for i in 0..10 {
+    for v in 0..5 {
+      l.insert(v, &key(i), &make_value(i * 10 + v as usize))
+        .unwrap();
+    }
+  }
+  l.remove(5, &key(3)).unwrap();
+
+  let versions = |version, i| {
+    l.get_versions(version, &key(i))
+      .map(|ent| (ent.version(), ent.value().map(|v| v.to_vec())))
+      .collect::<std::vec::Vec<_>>()
+  };
+  assert_eq!(
+    versions(2, 3),
+    (0..=2)
+      .rev()
+      .map(|v| (v, Some(make_value(30 + v as usize))))
+      .collect::<std::vec::Vec<_>>()
+  );
+  assert_eq!(versions(5, 3)[0], (5, None));
+  assert_eq!(versions(5, 3).len(), 6);
+  assert_eq!(versions(5, 4).len(), 5);
+  assert!(l.get_versions(5, b"none").next().is_none());
+}
+
+#[test]
+fn test_get_versions() {
+  run(|| get_versions(SkipMap::with_options(TEST_OPTIONS).unwrap()));
+}
+
+#[test]
+fn test_get_versions_unify() {
+  run(|| get_versions(SkipMap::with_options(UNIFY_TEST_OPTIONS).unwrap()));
+}
+
+fn snapshot(l: SkipMap) {
+  for i in 0..10 {
+    l.insert(1, &key(i), &new_value(i)).unwrap();
+  }
+
+  let snapshot = l.snapshot();
+  assert_eq!(snapshot.version(), 1);
+
+  for i in 0..10 {
+    l.insert(2, &key(i), &make_value(i)).unwrap();
+  }
+  l.insert(2, &key(10), &make_value(10)).unwrap();
+  l.remove(2, &key(0)).unwrap();
+
+  for i in 0..10 {
+    assert_eq!(snapshot.get(&key(i)).unwrap().value(), new_value(i));
+  }
+  assert!(!snapshot.contains_key(&key(10)));
+  assert!(snapshot
+    .iter()
+    .map(|ent| ent.value().to_vec())
+    .eq((0..10).map(new_value)));
+  let (lower, upper) = (key(2), key(5));
+  assert_eq!(
+    snapshot.range(lower.as_slice()..upper.as_slice()).count(),
+    3
+  );
+  let mut cursor = snapshot.cursor();
+  assert!(cursor.seek_to_last());
+  assert_eq!(cursor.key().unwrap(), key(9));
+
+  let latest = l.snapshot();
+  assert!(latest.get(&key(0)).is_none());
+  assert_eq!(latest.iter().count(), 10);
+  assert_eq!(l.snapshot_at(0).iter().count(), 0);
+}
+
+#[test]
+fn test_snapshot() {
+  run(|| snapshot(SkipMap::with_options(TEST_OPTIONS).unwrap()));
+}
+
+#[test]
+fn test_snapshot_unify() {
+  run(|| snapshot(SkipMap::with_options(UNIFY_TEST_OPTIONS).unwrap()));
+}
+
+#[test]
+fn test_entry_ref_outlives() {
+  run(|| {
+    let l = SkipMap::with_options(TEST_OPTIONS).unwrap();
+    l.insert(0, b"a", b"a1").unwrap();
+    l.insert(0, b"b", b"b1").unwrap();
+
+    let value = l.get(0, b"a").unwrap().value();
+    let (key, ent) = {
+      let ent = l.iter_all_versions(0).nth(1).unwrap();
+      (ent.key(), ent.value())
+    };
+    assert_eq!(value, b"a1");
+    assert_eq!((key, ent), (&b"b"[..], Some(&b"b1"[..])));
+  })
+}
+
+fn compare_and_swap(l: SkipMap) {
+  l.insert(0, b"a", b"a1").unwrap();
+  l.insert(1, b"b", b"b1").unwrap();
+  l.get_or_remove(2, b"b").unwrap();
+
+  l.compare_and_swap(0, b"a", b"a1", b"a2", Ordering::AcqRel, Ordering::Acquire)
+    .unwrap()
+    .unwrap();
+  assert_eq!(l.get(0, b"a").unwrap().value(), b"a2");
+
+  // stale value
+  let ent = l
+    .compare_and_swap(0, b"a", b"a1", b"a3", Ordering::AcqRel, Ordering::Acquire)
+    .unwrap()
+    .unwrap_err()
+    .unwrap();
+  assert_eq!(ent.value(), b"a2");
+  assert_eq!(l.get(0, b"a").unwrap().value(), b"a2");
+
+  // missing version, missing key and removed entry
+  for (version, k) in [(1, &b"a"[..]), (0, &b"c"[..]), (2, &b"b"[..])] {
+    assert!(l
+      .compare_and_swap(version, k, b"b1", b"x", Ordering::AcqRel, Ordering::Acquire)
+      .unwrap()
+      .unwrap_err()
+      .is_none());
+  }
+  assert_eq!(l.get(1, b"b").unwrap().value(), b"b1");
+  assert!(l.get(2, b"b").is_none());
+  assert_eq!(l.len(), 3);
+}
+
+#[test]
+fn test_compare_and_swap() {
+  run(|| compare_and_swap(SkipMap::with_options(TEST_OPTIONS).unwrap()));
+}
+
+#[test]
+fn test_compare_and_swap_unify() {
+  run(|| compare_and_swap(SkipMap::with_options(UNIFY_TEST_OPTIONS).unwrap()));
+}
+
+#[cfg(feature = "std")]
+fn get_or_insert_race(l: Arc<SkipMap>) {
+  #[cfg(not(miri))]
+  const N: usize = 16;
+  #[cfg(miri)]
+  const N: usize = 4;
+
+  let handles = (0..N)
+    .map(|i| {
+      let l = l.clone();
+      std::thread::spawn(move || {
+        l.get_or_insert(0, b"a", &new_value(i))
+          .unwrap()
+          .map(|ent| ent.value().to_vec())
+      })
+    })
+    .collect::<std::vec::Vec<_>>();
+  let results = handles
+    .into_iter()
+    .map(|h| h.join().unwrap())
+    .collect::<std::vec::Vec<_>>();
+
+  // exactly one thread inserts, the others see its value
+  assert_eq!(results.iter().filter(|res| res.is_none()).count(), 1);
+  let winner = l.get(0, b"a").unwrap().value().to_vec();
+  assert!(results.iter().flatten().all(|v| *v == winner));
+  assert_eq!(l.len(), 1);
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn test_get_or_insert_race() {
+  run(|| get_or_insert_race(Arc::new(SkipMap::with_options(TEST_OPTIONS).unwrap())));
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn test_get_or_insert_race_unify() {
+  run(|| get_or_insert_race(Arc::new(SkipMap::with_options(UNIFY_TEST_OPTIONS).unwrap())));
+}
+
+fn fetch_update(l: SkipMap) {
+  fn incr(old: Option<&[u8]>) -> Option<[u8; 8]> {
+    let n = old.map_or(0, |v| u64::from_be_bytes(v.try_into().unwrap()));
+    Some((n + 1).to_be_bytes())
+  }
+
+  // inserts the absent key
+  let prev = l
+    .fetch_update(0, b"a", Ordering::AcqRel, Ordering::Acquire, incr)
+    .unwrap()
+    .unwrap();
+  assert!(prev.is_none());
+
+  let prev = l
+    .fetch_update(0, b"a", Ordering::AcqRel, Ordering::Acquire, incr)
+    .unwrap()
+    .unwrap()
+    .unwrap();
+  assert_eq!(prev.value(), 1u64.to_be_bytes());
+  assert_eq!(l.get(0, b"a").unwrap().value(), 2u64.to_be_bytes());
+
+  // aborted
+  let current = l
+    .fetch_update(0, b"a", Ordering::AcqRel, Ordering::Acquire, |_| {
+      None::<&[u8]>
+    })
+    .unwrap()
+    .unwrap_err()
+    .unwrap();
+  assert_eq!(current.value(), 2u64.to_be_bytes());
+
+  // revives the removed entry
+  l.get_or_remove(0, b"b").unwrap();
+  let prev = l
+    .fetch_update(0, b"b", Ordering::AcqRel, Ordering::Acquire, incr)
+    .unwrap()
+    .unwrap();
+  assert!(prev.is_none());
+  assert_eq!(l.get(0, b"b").unwrap().value(), 1u64.to_be_bytes());
+  assert_eq!(l.len(), 2);
+}
+
+#[test]
+fn test_fetch_update() {
+  run(|| fetch_update(SkipMap::with_options(TEST_OPTIONS).unwrap()));
+}
+
+#[test]
+fn test_fetch_update_unify() {
+  run(|| fetch_update(SkipMap::with_options(UNIFY_TEST_OPTIONS).unwrap()));
+}
+
+#[cfg(feature = "std")]
+fn fetch_update_concurrent(l: Arc<SkipMap>) {
+  #[cfg(not(miri))]
+  const N: u64 = 8;
+  #[cfg(miri)]
+  const N: u64 = 2;
+  #[cfg(not(miri))]
+  const M: u64 = 100;
+  #[cfg(miri)]
+  const M: u64 = 5;
+
+  let handles = (0..N)
+    .map(|_| {
+      let l = l.clone();
+      std::thread::spawn(move || {
+        for _ in 0..M {
+          l.fetch_update(0, b"counter", Ordering::AcqRel, Ordering::Acquire, |old| {
+            let n = old.map_or(0, |v| u64::from_be_bytes(v.try_into().unwrap()));
+            Some((n + 1).to_be_bytes())
+          })
+          .unwrap()
+          .unwrap();
+        }
+      })
+    })
+    .collect::<std::vec::Vec<_>>();
+  for h in handles {
+    h.join().unwrap();
+  }
+
+  assert_eq!(l.get(0, b"counter").unwrap().value(), (N * M).to_be_bytes());
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn test_fetch_update_concurrent() {
+  run(|| fetch_update_concurrent(Arc::new(SkipMap::with_options(TEST_OPTIONS).unwrap())));
+}
+
+fn load_sorted(l: SkipMap) {
+  const N: usize = 1000;
+
+  // two versions per key, the newer one first
+  let items = (0..N).flat_map(|i| {
+    [
+      (1, make_int_key(i), make_value(i + N)),
+      (0, make_int_key(i), make_value(i)),
+    ]
+  });
+  assert_eq!(l.load_sorted(items).unwrap(), 2 * N);
+  assert_eq!(l.len(), 2 * N);
+  assert!(l.verify().is_ok());
+
+  for i in 0..N {
+    assert_eq!(l.get(0, &make_int_key(i)).unwrap().value(), make_value(i));
+    assert_eq!(
+      l.get(1, &make_int_key(i)).unwrap().value(),
+      make_value(i + N)
+    );
+  }
+
+  // unsorted items are still loaded
+  let items = (N..2 * N)
+    .rev()
+    .map(|i| (0, make_int_key(i), make_value(i)));
+  assert_eq!(l.load_sorted(items).unwrap(), N);
+  assert!(l.verify().is_ok());
+  assert_eq!(l.iter(0).count(), 2 * N);
+}
+
+#[test]
+fn test_load_sorted() {
+  run(|| load_sorted(SkipMap::with_options(TEST_OPTIONS).unwrap()));
+}
+
+#[test]
+fn test_load_sorted_unify() {
+  run(|| load_sorted(SkipMap::with_options(UNIFY_TEST_OPTIONS).unwrap()));
+}
+
+fn insert_with_hint(l: SkipMap) {
+  const N: usize = 1000;
+
+  let mut hint = l.hint();
+  for i in 0..N {
+    assert!(l
+      .insert_with_hint(0, &make_int_key(i), &make_value(i), &mut hint)
+      .unwrap()
+      .is_none());
+  }
+  // out of order and existing keys with the same hint
+  for i in (0..N).step_by(7).rev() {
+    let old = l
+      .insert_with_hint(0, &make_int_key(i), &make_value(i + N), &mut hint)
+      .unwrap()
+      .unwrap();
+    assert_eq!(old.value(), make_value(i));
+  }
+
+  // a hint of another map is ignored
+  let other = SkipMap::with_options(TEST_OPTIONS).unwrap();
+  other.insert_with_hint(0, b"a", b"a", &mut hint).unwrap();
+  l.insert_with_hint(0, &make_int_key(N), &make_value(N), &mut hint)
+    .unwrap();
+
+  assert!(l.verify().is_ok());
+  assert_eq!(l.len(), N + 1);
+  assert_eq!(other.get(0, b"a").unwrap().value(), b"a");
+  for i in 0..N {
+    let expected = if i % 7 == 0 {
+      make_value(i + N)
+    } else {
+      make_value(i)
+    };
+    assert_eq!(l.get(0, &make_int_key(i)).unwrap().value(), expected);
+  }
+}
+
+#[test]
+fn test_insert_with_hint() {
+  run(|| insert_with_hint(SkipMap::with_options(TEST_OPTIONS).unwrap()));
+}
+
+#[test]
+fn test_insert_with_hint_unify() {
+  run(|| insert_with_hint(SkipMap::with_options(UNIFY_TEST_OPTIONS).unwrap()));
+}
+
+#[test]
+fn test_read_options() {
+  run(|| {
+    let l = SkipMap::<TtlTrailer>::with_options(TEST_OPTIONS).unwrap();
+    let trailer = |version, expires_at| TtlTrailer {
+      version,
+      expires_at,
+    };
+
+    l.insert(trailer(1, 0), b"a", b"a").unwrap();
+    // b: the newer version is expired, and shadows the older one
+    l.insert(trailer(1, 0), b"b", b"b1").unwrap();
+    l.insert(trailer(2, 10), b"b", b"b2").unwrap();
+    l.insert(trailer(1, 100), b"c", b"c").unwrap();
+    l.insert(trailer(1, 50), b"d", b"d").unwrap();
+
+    let opts = ReadOptions::new(50).with_ttl(|t: &TtlTrailer| {
+      if t.expires_at == 0 {
+        None
+      } else {
+        Some(t.expires_at)
+      }
+    });
+    assert_eq!(opts.now(), 50);
+    assert_eq!(l.get_with_options(2, b"a", opts).unwrap().value(), b"a");
+    assert!(l.get_with_options(2, b"b", opts).is_none());
+    assert_eq!(l.get_with_options(1, b"b", opts).unwrap().value(), b"b1");
+    assert!(l.get_with_options(2, b"c", opts).is_some());
+    // expires at the current time
+    assert!(l.get_with_options(2, b"d", opts).is_none());
+
+    fn keys<'a>(
+      it: impl Iterator<Item = EntryRef<'a, TtlTrailer>>,
+    ) -> std::vec::Vec<std::vec::Vec<u8>> {
+      it.map(|ent| ent.key().to_vec()).collect()
+    }
+    assert_eq!(
+      keys(l.iter_with_options(2, opts)),
+      [b"a".to_vec(), b"c".to_vec()]
+    );
+    assert_eq!(
+      keys(l.iter_with_options(2, opts).rev()),
+      [b"c".to_vec(), b"a".to_vec()]
+    );
+    assert_eq!(
+      keys(l.range_with_options(2, &b"b"[..]..=&b"d"[..], opts)),
+      [b"c".to_vec()]
+    );
+
+    // without the TTL, nothing expires
+    assert_eq!(l.iter_with_options(2, ReadOptions::new(50)).count(), 4);
+  })
+}
+
+#[test]
+fn test_touch() {
+  run(|| {
+    let l = SkipMap::<TtlTrailer>::with_options(TEST_OPTIONS).unwrap();
+    let trailer = |version, expires_at| TtlTrailer {
+      version,
+      expires_at,
+    };
+    let opts = ReadOptions::new(50).with_ttl(|t: &TtlTrailer| Some(t.expires_at));
+
+    l.insert(trailer(1, 10), b"a", b"a1").unwrap();
+    l.insert(trailer(1, 10), b"b", b"b1").unwrap();
+    l.get_or_remove(trailer(2, 10), b"b").unwrap();
+    assert!(l.get_with_options(1, b"a", opts).is_none());
+
+    let old = l.touch(trailer(1, 100), b"a").unwrap().unwrap();
+    assert_eq!(old.trailer().expires_at, 10);
+    assert_eq!(old.value(), b"a1");
+    let ent = l.get_with_options(1, b"a", opts).unwrap();
+    assert_eq!(ent.trailer().expires_at, 100);
+    assert_eq!(ent.value(), b"a1");
+
+    // missing version, missing key and removed entry
+    assert!(l.touch(trailer(2, 100), b"a").unwrap().is_none());
+    assert!(l.touch(trailer(1, 100), b"c").unwrap().is_none());
+    assert!(l.touch(trailer(2, 100), b"b").unwrap().is_none());
+    assert!(l.get(2, b"b").is_none());
+    assert_eq!(l.len(), 3);
+  })
+}
+
+#[test]
+fn test_expired() {
+  run(|| {
+    let l = SkipMap::<TtlTrailer>::with_options(TEST_OPTIONS).unwrap();
+    let trailer = |version, expires_at| TtlTrailer {
+      version,
+      expires_at,
+    };
+    let opts = ReadOptions::new(50).with_ttl(|t: &TtlTrailer| {
+      if t.expires_at == 0 {
+        None
+      } else {
+        Some(t.expires_at)
+      }
+    });
+
+    l.insert(trailer(1, 0), b"a", b"a").unwrap();
+    // b: only the latest version counts
+    l.insert(trailer(1, 100), b"b", b"b1").unwrap();
+    l.insert(trailer(2, 10), b"b", b"b2").unwrap();
+    l.insert(trailer(1, 10), b"c", b"c1").unwrap();
+    l.insert(trailer(2, 100), b"c", b"c2").unwrap();
+    l.insert(trailer(1, 50), b"d", b"d").unwrap();
+    // e: removed entries are not yielded
+    l.insert(trailer(1, 10), b"e", b"e").unwrap();
+    l.get_or_remove(trailer(2, 10), b"e").unwrap();
+
+    let keys = l
+      .expired(2, opts)
+      .map(|ent| (ent.key().to_vec(), ent.value().to_vec()))
+      .collect::<std::vec::Vec<_>>();
+    assert_eq!(
+      keys,
+      [
+        (b"b".to_vec(), b"b2".to_vec()),
+        (b"d".to_vec(), b"d".to_vec())
+      ]
+    );
+    assert_eq!(l.expired(2, opts).rev().count(), 2);
+    assert_eq!(l.expired(1, opts).count(), 3);
+    assert_eq!(
+      l.expired(2, opts).count() + l.iter_with_options(2, opts).count(),
+      l.iter(2).count()
+    );
+    assert_eq!(l.expired(2, ReadOptions::new(50)).count(), 0);
+  })
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn test_flush_to() {
+  use crate::flush::{FlushOptions, BLOCK_COMPRESSED, BLOCK_UNCOMPRESSED, FOOTER_SIZE, MAGIC};
+
+  run(|| {
+    let l = SkipMap::with_options(TEST_OPTIONS).unwrap();
+    for i in 0..100 {
+      l.insert(1, &make_int_key(i), &make_value(i)).unwrap();
+    }
+    l.get_or_remove(2, &make_int_key(0)).unwrap();
+    l.insert(3, &make_int_key(1), b"new").unwrap();
+
+    let mut buf = std::vec::Vec::new();
+    let stats = l
+      .flush_to(&mut buf, FlushOptions::new().with_block_size(256))
+      .unwrap();
+    assert_eq!(stats.entries, 102);
+    assert!(stats.blocks > 1);
+    assert_eq!(stats.compressed_blocks, 0);
+    assert_eq!(stats.bytes, buf.len() as u64);
+
+    let footer = &buf[buf.len() - FOOTER_SIZE..];
+    let index_offset = u64::from_le_bytes(footer[..8].try_into().unwrap()) as usize;
+    let index_size = u64::from_le_bytes(footer[8..16].try_into().unwrap()) as usize;
+    assert_eq!(u64::from_le_bytes(footer[16..].try_into().unwrap()), MAGIC);
+    assert_eq!(index_offset + index_size + FOOTER_SIZE, buf.len());
+    assert_eq!(buf[index_offset + index_size - 1], BLOCK_UNCOMPRESSED);
+    // one restart point per index entry
+    let index = &buf[index_offset..index_offset + index_size - 1];
+    let restarts = u32::from_le_bytes(index[index.len() - 4..].try_into().unwrap());
+    assert_eq!(restarts as usize, stats.blocks);
+
+    // the first entry of the first block is the removed version of the first key
+    let key = make_int_key(0);
+    assert_eq!(&buf[..3], [0, key.len() as u8, 0]);
+    assert_eq!(&buf[3..3 + key.len()], key.as_slice());
+    assert_eq!(&buf[3 + key.len()..11 + key.len()], 2u64.to_le_bytes());
+    assert_eq!(buf[11 + key.len()], 0);
+
+    // the versions greater than the flushed one are skipped
+    let mut buf = std::vec::Vec::new();
+    let stats = l
+      .flush_to(&mut buf, FlushOptions::new().with_version(1))
+      .unwrap();
+    assert_eq!((stats.entries, stats.blocks), (100, 1));
+
+    // compression
+    let mut buf = std::vec::Vec::new();
+    let stats = l
+      .flush_to(
+        &mut buf,
+        FlushOptions::new()
+          .with_block_size(256)
+          .with_compressor(|block| Some(block[..block.len() / 2].to_vec())),
+      )
+      .unwrap();
+    assert_eq!(stats.compressed_blocks, stats.blocks);
+    assert_eq!(stats.bytes, buf.len() as u64);
+    // the first index entry points to the first data block
+    let index_offset = u64::from_le_bytes(
+      buf[buf.len() - FOOTER_SIZE..buf.len() - 16]
+        .try_into()
+        .unwrap(),
+    ) as usize;
+    let handle = index_offset + 3 + key.len() + 9;
+    let first_size = u64::from_le_bytes(buf[handle + 8..handle + 16].try_into().unwrap()) as usize;
+    assert_eq!(
+      u64::from_le_bytes(buf[handle..handle + 8].try_into().unwrap()),
+      0
+    );
+    assert_eq!(buf[first_size - 1], BLOCK_COMPRESSED);
+  })
+}
+
+#[test]
+#[cfg(feature = "value-log")]
+fn test_badger_value_pointer() {
+  use crate::badger::{ValuePointer, ValueStruct, BIT_VALUE_POINTER};
+
+  run(|| {
+    let l = SkipMap::with_options(TEST_OPTIONS).unwrap();
+    let vp = ValuePointer::new(3, 1 << 20, 4096);
+    let encoded = vp.encode();
+    assert_eq!(
+      encoded,
+      *b"\x03\x00\x00\x00\x00\x00\x10\x00\x00\x10\x00\x00"
+    );
+    assert_eq!(ValuePointer::decode(&encoded), Some(vp));
+    assert_eq!(ValuePointer::decode(&encoded[1..]), None);
+
+    let vs = ValueStruct::new(b"")
+      .with_user_meta(2)
+      .with_expires_at(300)
+      .with_value_pointer(&encoded);
+    assert!(vs.is_value_pointer());
+    assert_eq!(vs.meta(), BIT_VALUE_POINTER);
+    l.insert_with_value(0, b"ptr", vs.encoded_size() as u32, |buf| vs.encode(buf))
+      .unwrap();
+
+    let inline = ValueStruct::new(b"alice");
+    l.insert_with_value(0, b"inline", inline.encoded_size() as u32, |buf| {
+      inline.encode(buf)
+    })
+    .unwrap();
+
+    let ent = l.get(0, b"ptr").unwrap();
+    let decoded = ValueStruct::decode(ent.value()).unwrap();
+    assert_eq!(decoded, vs);
+    assert_eq!(decoded.value_pointer(), Some(vp));
+    assert_eq!(decoded.user_meta(), 2);
+
+    let ent = l.get(0, b"inline").unwrap();
+    let decoded = ValueStruct::decode(ent.value()).unwrap();
+    assert!(!decoded.is_value_pointer());
+    assert_eq!(decoded.value_pointer(), None);
+    assert_eq!(decoded.value(), b"alice");
+  })
+}
+
+#[test]
+fn test_badger_value_meta() {
+  use crate::badger::{ValueStruct, BIT_DELETE, BIT_MERGE_ENTRY, BIT_TXN};
+
+  run(|| {
+    let l = SkipMap::with_options(TEST_OPTIONS).unwrap();
+    let metas = [BIT_DELETE, BIT_MERGE_ENTRY | BIT_TXN, 0];
+    for (i, meta) in metas.iter().enumerate() {
+      let vs = ValueStruct::new(b"v")
+        .with_meta(*meta)
+        .with_user_meta(i as u8);
+      l.insert_with_value(0, &key(i), vs.encoded_size() as u32, |buf| vs.encode(buf))
+        .unwrap();
+    }
+    l.insert(0, b"raw", b"").unwrap();
+
+    assert_eq!(
+      l.get(0, &key(1)).unwrap().value_struct().unwrap().meta(),
+      BIT_MERGE_ENTRY | BIT_TXN
+    );
+    let decoded = l
+      .iter(0)
+      .filter_map(|ent| ent.value_struct())
+      .map(|vs| (vs.meta(), vs.user_meta()))
+      .collect::<std::vec::Vec<_>>();
+    assert_eq!(
+      decoded,
+      [(BIT_DELETE, 0), (BIT_MERGE_ENTRY | BIT_TXN, 1), (0, 2)]
+    );
+    assert!(l.get(0, b"raw").unwrap().value_struct().is_none());
+  })
+}
+
+#[test]
+fn test_typed_map() {
+  use crate::typed::TypedMap;
+
+  run(|| {
+    let m = TypedMap::<i64, std::string::String>::with_options(TEST_OPTIONS).unwrap();
+    for i in (-50..50i64).rev() {
+      m.insert(0, &i, &format!("{}", i)).unwrap();
+    }
+    assert_eq!(m.len(), 100);
+    assert_eq!(m.get(0, &-7).as_deref(), Some("-7"));
+    assert!(m.get(0, &50).is_none());
+    assert!(m.iter(0).map(|(k, _)| k).eq(-50..50));
+    assert!(m.iter(0).rev().map(|(k, _)| k).eq((-50..50).rev()));
+
+    m.remove(1, &-7).unwrap();
+    assert!(!m.contains_key(1, &-7));
+    assert!(m.contains_key(0, &-7));
+
+    // the entries written with other encodings are skipped
+    m.as_inner().insert(0, b"raw", b"raw").unwrap();
+    assert_eq!(m.iter(0).count(), 100);
+
+    let m = TypedMap::<std::string::String, u64>::with_options(UNIFY_TEST_OPTIONS).unwrap();
+    m.insert(0, &"b".into(), &2).unwrap();
+    m.insert(0, &"a".into(), &1).unwrap();
+    assert_eq!(
+      m.iter(0).collect::<std::vec::Vec<_>>(),
+      [(std::string::String::from("a"), 1), ("b".into(), 2)]
+    );
+  })
+}
+
+fn inline_keys(l: SkipMap) {
+  let short = |i: usize| (i as u64).to_be_bytes();
+  let long = |i: usize| format!("long-{:010}", i).into_bytes();
+  for i in 0..500 {
+    for version in 1..3 {
+      l.insert(version, &short(i), &make_value(i)).unwrap();
+      l.insert(version, &long(i), &make_value(i)).unwrap();
+    }
+    if i % 3 == 0 {
+      l.compare_remove(3, &short(i), Ordering::AcqRel, Ordering::Relaxed)
+        .unwrap();
+    }
+  }
+  assert_eq!(l.verify(), Ok(()));
+
+  let mut inlined = 0;
+  unsafe {
+    let mut nd = l.get_next(l.head, 0);
+    while nd.ptr != l.tail.ptr {
+      let node = nd.as_ref();
+      if node.key_offset == l.inline_key_offset(nd.offset, node.height() as u32) {
+        assert_eq!(node.key_size(), 8);
+        inlined += 1;
+      }
+      nd = l.get_next(nd, 0);
+    }
+  }
+  // the versions of a key may share the key of another node
+  assert!(inlined >= 500);
+
+  for i in 0..500 {
+    assert_eq!(l.get(2, &short(i)).unwrap().value(), make_value(i));
+    assert_eq!(l.get(2, &long(i)).unwrap().value(), make_value(i));
+    assert_eq!(l.get(3, &short(i)).is_none(), i % 3 == 0);
+  }
+  assert_eq!(l.iter(2).count(), 1000);
+  assert_eq!(l.iter(3).count(), 833);
+  assert_eq!(l.iter(3).rev().count(), 833);
+}
+
+#[test]
+fn test_inline_keys() {
+  run(|| {
+    inline_keys(SkipMap::with_options(TEST_OPTIONS.with_inline_key_size(8)).unwrap());
+    // the inline keys follow the access stamps and the fingerprints
+    inline_keys(
+      SkipMap::with_options(
+        TEST_OPTIONS
+          .with_inline_key_size(8)
+          .with_key_fingerprints(true)
+          .with_access_stamps(true),
+      )
+      .unwrap(),
+    );
+  })
+}
+
+#[test]
+fn test_inline_keys_unify() {
+  run(|| inline_keys(SkipMap::with_options(UNIFY_TEST_OPTIONS.with_inline_key_size(8)).unwrap()))
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn test_single_writer() {
+  #[cfg(not(miri))]
+  const N: usize = 1000;
+  #[cfg(miri)]
+  const N: usize = 5;
+
+  let mut w = SingleWriterMap::with_options(TEST_OPTIONS).unwrap();
+  let readers = (0..4)
+    .map(|_| {
+      let r = w.reader();
+      std::thread::spawn(move || {
+        // the entries are inserted in order, so a visible entry implies the previous ones.
+        loop {
+          let len = r.snapshot(0).iter().count();
+          for i in 0..len {
+            assert_eq!(r.get(0, &key(i)).unwrap().value(), new_value(i));
+          }
+          if len == N {
+            break;
+          }
+        }
+      })
+    })
+    .collect::<std::vec::Vec<_>>();
+
+  for i in 0..N {
+    w.insert(0, &key(i), &new_value(i)).unwrap();
+  }
+  for r in readers {
+    r.join().unwrap();
+  }
+
+  w.remove(1, &key(0)).unwrap();
+  assert_eq!(w.len(), N + 1);
+  assert_eq!(w.snapshot(1).iter().count(), N - 1);
+  assert_eq!(w.reader().snapshot(1).iter().rev().count(), N - 1);
+  assert_eq!(w.map.verify(), Ok(()));
+}
+
+fn huge_pages(l: SkipMap) {
+  for i in 0..100 {
+    l.insert(0, &key(i), &new_value(i)).unwrap();
+  }
+  for i in 0..100 {
+    assert_eq!(l.get(0, &key(i)).unwrap().value(), new_value(i));
+  }
+  assert_eq!(l.iter(0).count(), 100);
+}
+
+#[test]
+fn test_huge_pages() {
+  run(|| huge_pages(SkipMap::with_options(TEST_OPTIONS.with_huge_pages(true)).unwrap()))
+}
+
+#[test]
+fn test_huge_pages_unify() {
+  run(|| huge_pages(SkipMap::with_options(UNIFY_TEST_OPTIONS.with_huge_pages(true)).unwrap()))
+}
+
+fn arena_stats(l: SkipMap) {
+  for i in 0..100 {
+    l.insert(0, &key(i), &std::vec![0; i]).unwrap();
+  }
+  // upsert only writes a new value
+  l.insert(0, &key(0), &[0; 1000]).unwrap();
+  // removal shares the key of the older version, and only writes a trailer
+  l.compare_remove(1, &key(1), Ordering::AcqRel, Ordering::Relaxed)
+    .unwrap();
+
+  let stats = l.arena_stats().unwrap();
+  assert_eq!(stats.cap, l.capacity());
+  assert_eq!(stats.allocated, l.allocated());
+  assert_eq!(stats.key_bytes, 100 * 5);
+  assert_eq!(stats.value_bytes, 102 * 8 + (0..100).sum::<u64>() + 1000);
+  assert!(stats.node_bytes >= 101 * (layout::NODE_SIZE + layout::LINK_SIZE) as u64);
+  assert_eq!(
+    stats.unused_tower_bytes,
+    2 * (l.max_height() - l.height()) as usize * layout::LINK_SIZE
+  );
+  assert!(
+    stats.node_bytes + stats.key_bytes + stats.value_bytes + stats.alignment_waste
+      <= stats.allocated as u64
+  );
+
+  // shared by the clones
+  let l2 = l.clone();
+  l2.insert(0, &key(100), b"").unwrap();
+  assert_eq!(l.arena_stats().unwrap().key_bytes, 101 * 5);
+}
+
+#[test]
+fn test_arena_stats() {
+  run(|| {
+    arena_stats(SkipMap::with_options(TEST_OPTIONS.with_arena_stats(true)).unwrap());
+    assert_eq!(
+      SkipMap::<u64>::with_options(TEST_OPTIONS)
+        .unwrap()
+        .arena_stats(),
+      None
+    );
+
+    // the values aligned to 16 bytes pad the `u64` trailers
+    let l =
+      SkipMap::with_options(TEST_OPTIONS.with_arena_stats(true).with_value_alignment(16)).unwrap();
+    for i in 0..10 {
+      l.insert(0, &key(i), &new_value(i)).unwrap();
+    }
+    assert!(l.arena_stats().unwrap().alignment_waste > 0);
+  })
+}
+
+#[test]
+fn test_arena_stats_unify() {
+  run(|| arena_stats(SkipMap::with_options(UNIFY_TEST_OPTIONS.with_arena_stats(true)).unwrap()))
+}
+
+fn reset(mut l: SkipMap) {
+  for i in 0..100 {
+    l.insert(0, &key(i), &new_value(i)).unwrap();
+  }
+  let allocated = l.allocated();
+
+  let l2 = l.clone();
+  assert_eq!(l.reset(), Err(Error::Shared(2)));
+  assert_eq!(l2.len(), 100);
+  drop(l2);
+
+  let arena = l.allocator().clone();
+  assert_eq!(l.reset(), Err(Error::Shared(2)));
+  drop(arena);
+
+  l.reset().unwrap();
+  assert!(l.is_empty());
+  assert!(l.get(0, &key(0)).is_none());
+  // the ARENA is reused from its start
+  assert!(l.allocated() < allocated);
+
+  for i in 0..100 {
+    l.insert(0, &key(i), &new_value(i)).unwrap();
+  }
+  assert_eq!(l.iter(0).count(), 100);
+}
+
+#[test]
+fn test_reset() {
+  run(|| reset(SkipMap::with_options(TEST_OPTIONS).unwrap()))
+}
+
+#[test]
+fn test_reset_unify() {
+  run(|| reset(SkipMap::with_options(UNIFY_TEST_OPTIONS).unwrap()))
+}
+
+fn freeze(l: SkipMap) {
+  for i in 0..100 {
+    l.insert(0, &key(i), &new_value(i)).unwrap();
+  }
+
+  let writer = l.clone();
+  assert!(!writer.is_frozen());
+  let frozen = l.freeze();
+  assert!(writer.is_frozen());
+  assert_eq!(
+    writer.insert(1, &key(100), &new_value(100)).unwrap_err(),
+    Error::read_only()
+  );
+  assert_eq!(writer.remove(1, &key(0)).unwrap_err(), Error::read_only());
+  assert_eq!(
+    writer
+      .get_or_insert(1, &key(100), &new_value(100))
+      .unwrap_err(),
+    Error::read_only()
+  );
+
+  // Every write goes through the same check, whichever API it comes from.
+  let k = key(100);
+  let write_key = |buf: &mut VacantBuffer<'_>| {
+    buf.write(&k).unwrap();
+    Ok::<_, ()>(())
+  };
+  let write_value = |buf: &mut VacantBuffer<'_>| {
+    buf.write(b"value").unwrap();
+    Ok::<_, ()>(())
+  };
+  let key_size = u27::new(k.len() as u32);
+  assert!(matches!(
+    writer.insert_with(1, key_size, write_key, 5, write_value),
+    Err(Either::Right(e)) if e == Error::read_only()
+  ));
+  assert!(matches!(
+    writer.get_or_insert_with(1, key_size, write_key, 5, write_value),
+    Err(Either::Right(e)) if e == Error::read_only()
+  ));
+  assert!(matches!(
+    writer.get_or_remove_with(1, key_size, write_key),
+    Err(Either::Right(e)) if e == Error::read_only()
+  ));
+  assert_eq!(
+    writer
+      .compare_remove(1, &key(0), Ordering::AcqRel, Ordering::Relaxed)
+      .unwrap_err(),
+    Error::read_only()
+  );
+  assert_eq!(
+    writer.get_or_remove(1, &key(0)).unwrap_err(),
+    Error::read_only()
+  );
+  // the remove of an existing version clears the value of its node in place
+  assert_eq!(writer.remove(0, &key(0)).unwrap_err(), Error::read_only());
+  assert_eq!(writer.pop_first(1).unwrap_err(), Error::read_only());
+  assert_eq!(writer.pop_last(1).unwrap_err(), Error::read_only());
+  assert_eq!(
+    writer
+      .compare_and_swap(
+        0,
+        &key(0),
+        &new_value(0),
+        b"x",
+        Ordering::AcqRel,
+        Ordering::Relaxed
+      )
+      .err(),
+    Some(Error::read_only())
+  );
+  assert_eq!(
+    writer
+      .fetch_update(0, &key(0), Ordering::AcqRel, Ordering::Relaxed, |_| {
+        Some(b"x")
+      })
+      .err(),
+    Some(Error::read_only())
+  );
+  assert_eq!(writer.get(0, &key(0)).unwrap().value(), new_value(0));
+
+  assert_eq!(frozen.len(), 100);
+  assert_eq!(frozen.get(0, &key(0)).unwrap().value(), new_value(0));
+  assert!(frozen.contains_key(0, &key(99)));
+  assert!(!frozen.contains_key(0, &key(100)));
+  assert_eq!(frozen.first(0).unwrap().key(), key(0));
+  assert_eq!(frozen.last(0).unwrap().key(), key(99));
+  assert_eq!(frozen.iter(0).count(), 100);
+  let (lower, upper) = (key(10), key(20));
+  assert_eq!(
+    frozen.range(0, lower.as_slice()..upper.as_slice()).count(),
+    10
+  );
+  assert_eq!(frozen.clone().snapshot(0).iter().count(), 100);
+}
+
+#[test]
+fn test_freeze() {
+  run(|| freeze(SkipMap::with_options(TEST_OPTIONS).unwrap()))
+}
+
+#[test]
+fn test_freeze_unify() {
+  run(|| freeze(SkipMap::with_options(UNIFY_TEST_OPTIONS).unwrap()))
+}
+
+#[test]
+#[cfg(feature = "compat-crossbeam")]
+fn test_compat_crossbeam_map() {
+  run(|| {
+    let m = crate::compat::CrossbeamMap::with_options(TEST_OPTIONS).unwrap();
+    assert!(m.front().is_none());
+    for i in (0..100).rev() {
+      m.insert(&key(i), &new_value(i)).unwrap();
+    }
+    assert_eq!(m.len(), 100);
+
+    assert_eq!(m.get(&key(1)).unwrap().value(), new_value(1));
+    assert_eq!(m.front().unwrap().key(), key(0));
+    assert_eq!(m.back().unwrap().key(), key(99));
+    assert_eq!(
+      m.lower_bound(Bound::Excluded(key(10).as_slice()))
+        .unwrap()
+        .key(),
+      key(11)
+    );
+    assert_eq!(
+      m.upper_bound(Bound::Excluded(key(10).as_slice()))
+        .unwrap()
+        .key(),
+      key(9)
+    );
+
+    for (i, ent) in m.iter().enumerate() {
+      assert_eq!(ent.key(), key(i));
+      assert_eq!(ent.value(), new_value(i));
+    }
+    let lower = key(10);
+    let upper = key(20);
+    assert_eq!(m.range(lower.as_slice()..upper.as_slice()).count(), 10);
+
+    m.insert(&key(1), &make_value(1)).unwrap();
+    assert_eq!(m.get(&key(1)).unwrap().value(), make_value(1));
+
+    let old = m.remove(&key(1)).unwrap().unwrap();
+    assert_eq!(old.value(), make_value(1));
+    assert!(!m.contains_key(&key(1)));
+    assert!(m.remove(&key(1)).unwrap().is_none());
+    assert_eq!(m.iter().count(), 99);
+  })
+}
+
+fn pop_first_last(l: SkipMap) {
+  assert!(l.pop_first(0).unwrap().is_none());
+  assert!(l.pop_last(0).unwrap().is_none());
+
+  for i in 0..100 {
+    l.insert(0, &key(i), &new_value(i)).unwrap();
+  }
+  assert_eq!(l.first(0).unwrap().key(), key(0));
+  assert_eq!(l.last(0).unwrap().key(), key(99));
+
+  for i in 0..50 {
+    let first = l.pop_first(1).unwrap().unwrap();
+    assert_eq!(first.key(), key(i));
+    assert_eq!(first.value(), new_value(i));
+    let last = l.pop_last(1).unwrap().unwrap();
+    assert_eq!(last.key(), key(99 - i));
+  }
+  assert!(l.pop_first(1).unwrap().is_none());
+  assert!(l.pop_last(1).unwrap().is_none());
+  assert!(l.first(1).is_none());
+  assert!(l.last(1).is_none());
+
+  // the older version is not affected
+  assert_eq!(l.first(0).unwrap().key(), key(0));
+  assert_eq!(l.last(0).unwrap().key(), key(99));
+}
+
+#[test]
+fn test_pop_first_last() {
+  run(|| pop_first_last(SkipMap::with_options(TEST_OPTIONS).unwrap()))
+}
+
+#[test]
+fn test_pop_first_last_unify() {
+  run(|| pop_first_last(SkipMap::with_options(UNIFY_TEST_OPTIONS).unwrap()))
+}
+
+fn fused_iterators(l: SkipMap) {
+  fn assert_fused<I: core::iter::FusedIterator>(_: &I) {}
+
+  for i in 0..10 {
+    l.insert(0, &key(i), &new_value(i)).unwrap();
+  }
+
+  let mut it = l.iter(0);
+  assert_fused(&it);
+  assert_eq!(it.by_ref().count(), 10);
+  assert!(it.next().is_none());
+  assert!(it.next().is_none());
+
+  // exhausted backward, the iterator does not start from the end again.
+  let mut it = l.iter_all_versions(0).rev();
+  assert_fused(&it);
+  assert_eq!(it.by_ref().count(), 10);
+  assert!(it.next().is_none());
+  assert!(it.next().is_none());
+
+  // the combinators see the same entries in both directions
+  for (i, (a, b)) in l.iter(0).zip(l.iter(0).rev()).enumerate() {
+    assert_eq!(a.key(), key(i));
+    assert_eq!(b.key(), key(9 - i));
+  }
+  assert!(l
+    .iter(0)
+    .rev()
+    .take_while(|ent| ent.key() >= key(5).as_slice())
+    .map(|ent| ent.key().to_vec())
+    .eq((5..10).rev().map(key)));
+
+  let mut prefix = l.prefix(0, b"0000");
+  assert_fused(&prefix);
+  assert_eq!(prefix.size_hint(), (1, None));
+  assert_eq!(prefix.by_ref().count(), 10);
+  assert_eq!(prefix.size_hint(), (0, Some(0)));
+  assert!(prefix.next().is_none());
+
+  // the ends meet in the middle, every entry is yielded once
+  let mut it = l.iter(0);
+  assert_eq!(it.size_hint(), (0, None));
+  assert_eq!(it.next().unwrap().key(), key(0));
+  assert_eq!(it.next_back().unwrap().key(), key(9));
+  assert_eq!(it.next_back().unwrap().key(), key(8));
+  let rest = it
+    .by_ref()
+    .map(|ent| ent.key().to_vec())
+    .collect::<std::vec::Vec<_>>();
+  assert_eq!(rest, (1..8).map(key).collect::<std::vec::Vec<_>>());
+  assert!(it.next_back().is_none());
+  assert!(it.next().is_none());
+  assert_eq!(it.size_hint(), (0, Some(0)));
+
+  let mut it = l.iter(0);
+  let mut keys = std::vec::Vec::new();
+  while let Some(ent) = it.next() {
+    keys.push(ent.key().to_vec());
+    if let Some(ent) = it.next_back() {
+      keys.push(ent.key().to_vec());
+    }
+  }
+  assert!(it.next_back().is_none());
+  keys.sort();
+  assert_eq!(keys, (0..10).map(key).collect::<std::vec::Vec<_>>());
+  assert_eq!(l.iter(0).last().unwrap().key(), key(9));
+
+  // all the versions of a key are distinct entries of the ends
+  let k = key(4);
+  l.insert(1, &k, &make_value(4)).unwrap();
+  let mut it = l.range_all_versions(1, k.as_slice()..=k.as_slice());
+  let newest = it.next().unwrap();
+  assert_eq!((newest.key(), newest.version()), (k.as_slice(), 1));
+  let oldest = it.next_back().unwrap();
+  assert_eq!((oldest.key(), oldest.version()), (k.as_slice(), 0));
+  assert!(it.next_back().is_none());
+  assert!(it.next().is_none());
+
+  // the iterators of a read-only map are bounded by the number of its nodes
+  let len = l.len();
+  let frozen = l.clone().freeze();
+  assert_eq!(l.iter(1).size_hint(), (0, Some(len)));
+  assert_eq!(frozen.iter(1).count(), 10);
+}
+
+#[test]
+fn test_fused_iterators() {
+  run(|| fused_iterators(SkipMap::with_options(TEST_OPTIONS).unwrap()))
+}
+
+#[test]
+fn test_fused_iterators_unify() {
+  run(|| fused_iterators(SkipMap::with_options(UNIFY_TEST_OPTIONS).unwrap()))
+}
+
+fn into_iter(l: SkipMap) {
+  for i in 0..100 {
+    l.insert(0, &key(i), &new_value(i)).unwrap();
+  }
+  l.insert(1, &key(0), &make_value(0)).unwrap();
+  l.remove(1, &key(1)).unwrap();
+
+  let entries = l.clone().into_iter_at(0).collect::<std::vec::Vec<_>>();
+  assert_eq!(entries.len(), 100);
+  assert_eq!(entries[0].value(), new_value(0));
+
+  assert_eq!(l.clone().into_iter().next_back().unwrap().key(), key(99));
+
+  let mut it = l.into_iter();
+  let first = it.next().unwrap();
+  assert_eq!(first.key(), key(0));
+  assert_eq!(first.value(), make_value(0));
+  // the removed key is skipped
+  assert_eq!(it.next().unwrap().key(), key(2));
+
+  let rest = it.collect::<std::vec::Vec<_>>();
+  assert_eq!(rest.len(), 97);
+  for (i, ent) in rest.iter().enumerate() {
+    assert_eq!(ent.key(), key(i + 3));
+    assert_eq!(ent.value(), new_value(i + 3));
+  }
+
+  // the entries keep the ARENA alive after the map and the iterator are dropped
+  drop(entries);
+  assert_eq!(first.key(), key(0));
+}
+
+#[test]
+fn test_into_iter() {
+  run(|| into_iter(SkipMap::with_options(TEST_OPTIONS).unwrap()))
+}
+
+#[test]
+fn test_into_iter_unify() {
+  run(|| into_iter(SkipMap::with_options(UNIFY_TEST_OPTIONS).unwrap()))
+}
+
+#[test]
+#[cfg(feature = "rayon")]
+fn test_par_iter() {
+  use rayon::iter::ParallelIterator;
+
+  run(|| {
+    let l = SkipMap::with_options(BIG_TEST_OPTIONS).unwrap();
+    assert_eq!(l.par_iter(0).count(), 0);
+
+    for i in 0..5000 {
+      l.insert(0, &key(i), &make_value(i)).unwrap();
+      if i % 2 == 0 {
+        l.insert(1, &key(i), &new_value(i)).unwrap();
+      }
+    }
+    l.remove(2, &key(42)).unwrap();
+
+    let mut entries = l
+      .par_iter(1)
+      .map(|ent| (ent.key().to_vec(), ent.value().to_vec()))
+      .collect::<Vec<_>>();
+    entries.sort();
+    assert_eq!(entries.len(), 5000);
+    for (i, (k, v)) in entries.iter().enumerate() {
+      assert_eq!(k, &key(i));
+      if i % 2 == 0 {
+        assert_eq!(v, &new_value(i));
+      } else {
+        assert_eq!(v, &make_value(i));
+      }
+    }
+
+    assert_eq!(l.par_iter(0).count(), 5000);
+    assert_eq!(l.par_iter(2).count(), 4999);
+
+    // The split ranges follow the order of the comparator, not the bytewise order.
+    let l = SkipMap::with_options_and_comparator(BIG_TEST_OPTIONS, Descend).unwrap();
+    for i in 0..5000 {
+      l.insert(0, &key(i), &make_value(i)).unwrap();
+    }
+    let mut keys = l
+      .par_iter(0)
+      .map(|ent| ent.key().to_vec())
+      .collect::<Vec<_>>();
+    keys.sort();
+    assert_eq!(keys, (0..5000).map(key).collect::<Vec<_>>());
+  })
+}
+
+#[cfg(feature = "async")]
+fn stream(l: SkipMap) {
+  use core::{
+    pin::Pin,
+    task::{Context, Poll, RawWaker, RawWakerVTable, Waker},
+  };
+  use futures_core::Stream;
+
+  fn noop_raw_waker() -> RawWaker {
+    fn clone(_: *const ()) -> RawWaker {
+      noop_raw_waker()
+    }
+    fn noop(_: *const ()) {}
+    static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+    RawWaker::new(core::ptr::null(), &VTABLE)
+  }
+
+  for i in 0..100 {
+    l.insert(0, &key(i), &make_value(i)).unwrap();
+  }
+  l.remove(1, &key(50)).unwrap();
+
+  let waker = unsafe { Waker::from_raw(noop_raw_waker()) };
+  let mut cx = Context::from_waker(&waker);
+  let lower = key(10);
+  let mut s = l.stream(1, lower.as_slice()..).yield_every(8);
+  let (mut keys, mut pending) = (Vec::new(), 0);
+  loop {
+    match Pin::new(&mut s).poll_next(&mut cx) {
+      Poll::Ready(Some(ent)) => keys.push(ent.key().to_vec()),
+      Poll::Ready(None) => break,
+      Poll::Pending => pending += 1,
+    }
+  }
+  assert_eq!(keys.len(), 89);
+  assert_eq!(keys[0], key(10));
+  assert!(!keys.contains(&key(50)));
+  assert_eq!(pending, 89 / 8);
+
+  let mut s = l.stream::<&[u8], _>(1, ..).yield_every(0);
+  let mut n = 0;
+  while let Poll::Ready(Some(_)) = Pin::new(&mut s).poll_next(&mut cx) {
+    n += 1;
+  }
+  assert_eq!(n, 99);
+}
+
+#[test]
+#[cfg(feature = "async")]
+fn test_stream() {
+  run(|| stream(SkipMap::with_options(TEST_OPTIONS).unwrap()))
+}
+
+#[test]
+#[cfg(feature = "async")]
+fn test_stream_unify() {
+  run(|| stream(SkipMap::with_options(UNIFY_TEST_OPTIONS).unwrap()))
+}
+
+#[cfg(feature = "serde")]
+fn serde_roundtrip(l: SkipMap) {
+  for i in 0..100 {
+    l.insert(1, &key(i), &new_value(i)).unwrap();
+  }
+  for i in 0..10 {
+    l.insert(2, &key(i), &make_value(i)).unwrap();
+  }
+  l.remove(3, &key(50)).unwrap();
+
+  let json = serde_json::to_vec(&l).unwrap();
+  let l2: SkipMap = serde_json::from_slice(&json).unwrap();
+  assert_eq!(l2.verify(), Ok(()));
+  assert_eq!(l2.iter_all_versions(3).count(), 111);
+  for version in 1..=3 {
+    for i in 0..100 {
+      let v1 = l.get(version, &key(i)).map(|ent| ent.value().to_vec());
+      let v2 = l2.get(version, &key(i)).map(|ent| ent.value().to_vec());
+      assert_eq!(v1, v2);
+    }
+  }
+  assert!(l2.get(3, &key(50)).is_none());
+
+  // the bytes can also be read as the sequences of `u8`
+  let json = r#"[[[97],0,[98]],[[99],0,null]]"#;
+  let l3: SkipMap = serde_json::from_str(json).unwrap();
+  assert_eq!(l3.get(0, b"a").unwrap().value(), b"b");
+  assert!(l3.get(0, b"c").is_none());
+  assert_eq!(l3.iter_all_versions(0).count(), 2);
+
+  assert!(serde_json::from_str::<SkipMap>(r#"[[[97],0]]"#).is_err());
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn test_serde_roundtrip() {
+  run(|| serde_roundtrip(SkipMap::with_options(TEST_OPTIONS).unwrap()))
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn test_serde_roundtrip_unify() {
+  run(|| serde_roundtrip(SkipMap::with_options(UNIFY_TEST_OPTIONS).unwrap()))
+}
+
+#[test]
+#[cfg(feature = "ffi")]
+fn test_ffi() {
+  use crate::ffi::*;
+  use core::ptr;
+
+  unsafe {
+    let map = skl_new(ARENA_SIZE as u32);
+    assert!(!map.is_null());
+    for i in 0..100 {
+      let (k, v) = (key(i), new_value(i));
+      assert_eq!(
+        skl_insert(map, 0, k.as_ptr(), k.len(), v.as_ptr(), v.len()),
+        SKL_OK
+      );
+    }
+    let k = key(50);
+    assert_eq!(skl_remove(map, 1, k.as_ptr(), k.len()), SKL_OK);
+    assert_eq!(skl_len(map), 101);
+
+    // null arguments
+    assert_eq!(skl_len(ptr::null()), 0);
+    assert_eq!(
+      skl_insert(map, 0, ptr::null(), 1, ptr::null(), 0),
+      SKL_ERROR
+    );
+    assert!(skl_iter_new(ptr::null(), 0).is_null());
+
+    let (mut value, mut value_len) = (ptr::null(), 0);
+    let k = key(42);
+    assert_eq!(
+      skl_get(map, 1, k.as_ptr(), k.len(), &mut value, &mut value_len),
+      1
+    );
+    assert_eq!(core::slice::from_raw_parts(value, value_len), new_value(42));
+    let k = key(50);
+    assert_eq!(
+      skl_get(map, 1, k.as_ptr(), k.len(), &mut value, &mut value_len),
+      0
+    );
+    assert_eq!(
+      skl_get(
+        map,
+        0,
+        k.as_ptr(),
+        k.len(),
+        ptr::null_mut(),
+        ptr::null_mut()
+      ),
+      1
+    );
+
+    let iter = skl_iter_new(map, 1);
+    // the iterator keeps the ARENA alive
+    skl_free(map);
+
+    let (mut k, mut k_len, mut version) = (ptr::null(), 0, u64::MAX);
+    let mut i = 0;
+    while skl_iter_next(
+      iter,
+      &mut k,
+      &mut k_len,
+      &mut value,
+      &mut value_len,
+      &mut version,
+    ) == 1
+    {
+      if i == 50 {
+        i += 1;
+      }
+      assert_eq!(core::slice::from_raw_parts(k, k_len), key(i));
+      assert_eq!(core::slice::from_raw_parts(value, value_len), new_value(i));
+      assert_eq!(version, 0);
+      i += 1;
+    }
+    assert_eq!(i, 100);
+    assert_eq!(
+      skl_iter_next(
+        iter,
+        ptr::null_mut(),
+        ptr::null_mut(),
+        ptr::null_mut(),
+        ptr::null_mut(),
+        ptr::null_mut()
+      ),
+      0
+    );
+    skl_iter_free(iter);
+  }
+}
+
+#[cfg(feature = "perf-stats")]
+fn metrics(l: SkipMap) {
+  for i in 0..100 {
+    l.insert(0, &key(i), &new_value(i)).unwrap();
+  }
+  l.remove(1, &key(0)).unwrap();
+  for i in 0..50 {
+    l.get(0, &key(i)).unwrap();
+  }
+
+  // the clones share the counters
+  let l2 = l.clone();
+  l2.iter(0).seek_lower_bound(Bound::Included(&key(10)));
+
+  let metrics = l.metrics();
+  assert_eq!(metrics.inserts, 101);
+  assert_eq!(metrics.heights.iter().sum::<u64>(), 101);
+  assert_eq!(metrics.heights[metrics.heights.len() - 1], 0);
+  assert!(metrics.heights[0] > 0);
+  assert_eq!(metrics.retries, l.retries());
+  assert_eq!(metrics.comparisons.count(), 51);
+  assert!(metrics.comparisons.sum() >= 51);
+  assert_eq!(metrics.arena_allocated, l.allocated());
+  assert_eq!(metrics.arena_capacity, l.capacity());
+}
+
+#[test]
+#[cfg(feature = "perf-stats")]
+fn test_metrics() {
+  run(|| metrics(SkipMap::with_options(TEST_OPTIONS).unwrap()))
+}
+
+#[test]
+#[cfg(feature = "perf-stats")]
+fn test_metrics_unify() {
+  run(|| metrics(SkipMap::with_options(UNIFY_TEST_OPTIONS).unwrap()))
+}
+
+#[test]
+#[cfg(feature = "testing")]
+fn test_testing_driver() {
+  use crate::testing::{Driver, Op};
+
+  run(|| {
+    let mut driver = Driver::new().unwrap();
+    for op in [
+      Op::Insert {
+        version: 1,
+        key: b"a".to_vec(),
+        value: b"a1".to_vec(),
+      },
+      Op::Insert {
+        version: 1,
+        key: b"ab".to_vec(),
+        value: b"ab1".to_vec(),
+      },
+      Op::Remove {
+        version: 2,
+        key: b"a".to_vec(),
+      },
+      Op::Insert {
+        version: 3,
+        key: b"a".to_vec(),
+        value: b"a3".to_vec(),
+      },
+      Op::Insert {
+        version: 1,
+        key: std::vec![],
+        value: std::vec![],
+      },
+      Op::Get {
+        version: 2,
+        key: b"a".to_vec(),
+      },
+      Op::Iter { version: 2 },
+      Op::Range {
+        version: 3,
+        lower: b"aa".to_vec(),
+      },
+    ] {
+      driver.apply(&op);
+    }
+    driver.check();
+    assert_eq!(driver.map().len(), 5);
+
+    // the decoding consumes the whole input, and stops at a truncated operation
+    assert_eq!(
+      Op::decode(&[0, 1, 1, b'k', 2, b'v', b'w', 3, 9]),
+      [
+        Op::Insert {
+          version: 1,
+          key: b"k".to_vec(),
+          value: b"vw".to_vec(),
+        },
+        Op::Iter { version: 1 },
+      ]
+    );
+    assert!(Op::decode(&[0, 1]).is_empty());
+
+    // pseudo-random inputs (xorshift), long enough to fill the ARENA
+    let mut state = 0x2545_f491_4f6c_dd1du64;
+    for len in [0, 1, 16, 256, 4096, 1 << 16] {
+      let data = (0..len)
+        .map(|_| {
+          state ^= state << 13;
+          state ^= state >> 7;
+          state ^= state << 17;
+          state as u8
+        })
+        .collect::<Vec<_>>();
+      Driver::run(&data);
+    }
+  })
+}
@@ -0,0 +1,67 @@
+use std::boxed::Box;
+
+use super::{map::EntryRef, Comparator, SkipMap, Trailer};
+
+/// A stable abstraction of the memtable of LSM-Tree, LSM frameworks can bind to this trait
+/// instead of the concrete types of this crate.
+pub trait MemTable {
+  /// The trailer stored with each entry.
+  type Trailer: Trailer;
+
+  /// The error returned by the write operations.
+  type Error;
+
+  /// Upserts a key-value pair.
+  fn insert(&self, trailer: Self::Trailer, key: &[u8], value: &[u8]) -> Result<(), Self::Error>;
+
+  /// Returns the latest entry of the key whose version is less or equal to the given version.
+  fn get<'a>(&'a self, version: u64, key: &[u8]) -> Option<EntryRef<'a, Self::Trailer>>;
+
+  /// Returns the approximate size in bytes of the memtable.
+  fn approximate_size(&self) -> usize;
+
+  /// Returns an iterator over the latest version (less or equal to the given version) of all entries.
+  fn iter<'a>(&'a self, version: u64)
+    -> Box<dyn Iterator<Item = EntryRef<'a, Self::Trailer>> + 'a>;
+
+  /// Flushes the memtable to the backing storage.
+  ///
+  /// The default implementation does nothing, which is suitable for heap backed memtables.
+  #[cfg(feature = "std")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+  fn flush(&self) -> std::io::Result<()> {
+    Ok(())
+  }
+}
+
+impl<T: Trailer, C: Comparator> MemTable for SkipMap<T, C> {
+  type Trailer = T;
+
+  type Error = super::map::Error;
+
+  #[inline]
+  fn insert(&self, trailer: T, key: &[u8], value: &[u8]) -> Result<(), Self::Error> {
+    SkipMap::insert(self, trailer, key, value).map(|_| ())
+  }
+
+  #[inline]
+  fn get<'a>(&'a self, version: u64, key: &[u8]) -> Option<EntryRef<'a, T>> {
+    self.get_in(version, key)
+  }
+
+  #[inline]
+  fn approximate_size(&self) -> usize {
+    self.allocated()
+  }
+
+  #[inline]
+  fn iter<'a>(&'a self, version: u64) -> Box<dyn Iterator<Item = EntryRef<'a, T>> + 'a> {
+    Box::new(SkipMap::iter(self, version))
+  }
+
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[inline]
+  fn flush(&self) -> std::io::Result<()> {
+    SkipMap::flush(self)
+  }
+}
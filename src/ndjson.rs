@@ -0,0 +1,146 @@
+use core::sync::atomic::Ordering;
+use std::{
+  io::{BufRead, Write},
+  string::String,
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use super::{map::Error, Comparator, SkipMap, Trailer};
+
+/// One line of the NDJSON dump.
+#[derive(Serialize, Deserialize)]
+struct Record<T> {
+  /// Base64 encoded key.
+  key: String,
+  /// Base64 encoded value, `null` means the entry is removed.
+  value: Option<String>,
+  trailer: T,
+}
+
+/// An error which may occur when loading the NDJSON dump.
+#[derive(Debug)]
+pub enum NdjsonError {
+  /// Failed to read the dump.
+  Io(std::io::Error),
+  /// The line is not a valid record.
+  Json(serde_json::Error),
+  /// The key or value is not valid base64.
+  Base64(base64::DecodeError),
+  /// Failed to insert the entry to the map.
+  Map(Error),
+}
+
+impl core::fmt::Display for NdjsonError {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    match self {
+      Self::Io(e) => write!(f, "{e}"),
+      Self::Json(e) => write!(f, "{e}"),
+      Self::Base64(e) => write!(f, "{e}"),
+      Self::Map(e) => write!(f, "{e}"),
+    }
+  }
+}
+
+impl std::error::Error for NdjsonError {}
+
+impl From<std::io::Error> for NdjsonError {
+  #[inline]
+  fn from(e: std::io::Error) -> Self {
+    Self::Io(e)
+  }
+}
+
+impl From<serde_json::Error> for NdjsonError {
+  #[inline]
+  fn from(e: serde_json::Error) -> Self {
+    Self::Json(e)
+  }
+}
+
+impl From<base64::DecodeError> for NdjsonError {
+  #[inline]
+  fn from(e: base64::DecodeError) -> Self {
+    Self::Base64(e)
+  }
+}
+
+impl From<Error> for NdjsonError {
+  #[inline]
+  fn from(e: Error) -> Self {
+    Self::Map(e)
+  }
+}
+
+impl<T: Trailer, C: Comparator> SkipMap<T, C> {
+  /// Writes all versions (less or equal to `version`) of all entries to the writer,
+  /// one entry per line, returns how many entries are written.
+  ///
+  /// Each line is a JSON object with the base64 encoded `key` and `value` (`null` for
+  /// the removed entries), and the `trailer` of the entry.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use skl::SkipMap;
+  ///
+  /// let l = SkipMap::new().unwrap();
+  /// l.insert(1, b"alice", b"a").unwrap();
+  ///
+  /// let mut buf = Vec::new();
+  /// assert_eq!(l.dump_ndjson(1, &mut buf).unwrap(), 1);
+  /// assert_eq!(buf, b"{\"key\":\"YWxpY2U=\",\"value\":\"YQ==\",\"trailer\":1}\n");
+  ///
+  /// let l2 = SkipMap::<u64>::new().unwrap();
+  /// assert_eq!(l2.load_ndjson(buf.as_slice()).unwrap(), 1);
+  /// assert_eq!(l2.get(1, b"alice").unwrap().value(), b"a");
+  /// ```
+  pub fn dump_ndjson<W: Write>(&self, version: u64, mut writer: W) -> std::io::Result<usize>
+  where
+    T: Serialize,
+  {
+    let mut written = 0;
+    for ent in self.iter_all_versions(version) {
+      let record = Record {
+        key: STANDARD.encode(ent.key()),
+        value: ent.value().map(|v| STANDARD.encode(v)),
+        trailer: *ent.trailer(),
+      };
+      serde_json::to_writer(&mut writer, &record)?;
+      writer.write_all(b"\n")?;
+      written += 1;
+    }
+    writer.flush()?;
+    Ok(written)
+  }
+
+  /// Loads the entries written by [`dump_ndjson`](SkipMap::dump_ndjson) from the reader,
+  /// returns how many entries are loaded. Empty lines are skipped.
+  pub fn load_ndjson<R: BufRead>(&self, reader: R) -> Result<usize, NdjsonError>
+  where
+    T: DeserializeOwned,
+  {
+    let mut loaded = 0;
+    for line in reader.lines() {
+      let line = line?;
+      if line.trim().is_empty() {
+        continue;
+      }
+
+      let record: Record<T> = serde_json::from_str(&line)?;
+      let key = STANDARD.decode(record.key)?;
+      match record.value {
+        Some(value) => {
+          let value = STANDARD.decode(value)?;
+          self.insert(record.trailer, &key, &value)?;
+        }
+        None => {
+          self.compare_remove(record.trailer, &key, Ordering::AcqRel, Ordering::Relaxed)?;
+        }
+      }
+      loaded += 1;
+    }
+    Ok(loaded)
+  }
+}
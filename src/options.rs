@@ -14,6 +14,18 @@ pub struct Options {
   max_height: u5,
   magic_version: u16,
   capacity: u32,
+  expected_entries: u32,
+  backoff_spin_limit: u8,
+  backoff_yield_limit: u8,
+  value_alignment: u32,
+  poison_on_corruption: bool,
+  validate_on_open: bool,
+  size_histograms: bool,
+  arena_stats: bool,
+  access_stamps: bool,
+  key_fingerprints: bool,
+  inline_key_size: u8,
+  huge_pages: bool,
   unify: bool,
   freelist: Freelist,
 }
@@ -34,6 +46,18 @@ impl Options {
       max_key_size: u27::MAX,
       max_height: u5::new(20),
       capacity: 1024,
+      expected_entries: 0,
+      backoff_spin_limit: 6,
+      backoff_yield_limit: 10,
+      value_alignment: 1,
+      poison_on_corruption: false,
+      validate_on_open: false,
+      size_histograms: false,
+      arena_stats: false,
+      access_stamps: false,
+      key_fingerprints: false,
+      inline_key_size: 0,
+      huge_pages: false,
       unify: false,
       magic_version: 0,
       freelist: Freelist::Optimistic,
@@ -141,6 +165,10 @@ impl Options {
   ///
   /// Default is `20`. The maximum height is `31`. The minimum height is `1`.
   ///
+  /// The tower of each node is allocated with the node's own height, only the head and the tail
+  /// nodes have the full tower, so a small maximum height (e.g. `4` for embedded users) makes
+  /// the header and the nodes proportionally smaller, with no runtime cost.
+  ///
   /// # Example
   ///
   /// ```
@@ -171,6 +199,273 @@ impl Options {
     self
   }
 
+  /// Sets the expected number of entries of the [`SkipMap`](super::SkipMap).
+  ///
+  /// When set, the height of newly generated towers is capped at roughly `log_{1/p}(n)`,
+  /// where `n` is the larger one of the expected number of entries and the current number of entries.
+  /// The cap never exceeds [`max_height`](Options::max_height).
+  ///
+  /// Default is `0`, which means the height is only capped by [`max_height`](Options::max_height).
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use skl::Options;
+  ///
+  /// let options = Options::new().with_expected_entries(1_000_000);
+  /// ```
+  #[inline]
+  pub const fn with_expected_entries(mut self, entries: u32) -> Self {
+    self.expected_entries = entries;
+    self
+  }
+
+  /// Sets the spin limit of the backoff used when the CAS operations in the insert path have to be retried.
+  ///
+  /// The `n`-th retry spins for `2^n` iterations until `n` reaches the spin limit,
+  /// after that the current thread yields (under `std`). The spin limit is capped at `16`.
+  ///
+  /// Default is `6`.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use skl::Options;
+  ///
+  /// let options = Options::new().with_backoff_spin_limit(4);
+  /// ```
+  #[inline]
+  pub const fn with_backoff_spin_limit(mut self, limit: u8) -> Self {
+    self.backoff_spin_limit = limit;
+    self
+  }
+
+  /// Sets the yield limit of the backoff used when the CAS operations in the insert path have to be retried.
+  ///
+  /// After this many retries, the backoff stops growing.
+  ///
+  /// Default is `10`.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use skl::Options;
+  ///
+  /// let options = Options::new().with_backoff_yield_limit(8);
+  /// ```
+  #[inline]
+  pub const fn with_backoff_yield_limit(mut self, limit: u8) -> Self {
+    self.backoff_yield_limit = limit;
+    self
+  }
+
+  /// Sets the default alignment of the values stored in the [`SkipMap`](super::SkipMap).
+  ///
+  /// This is useful when the values will be reinterpreted as structs via zero-copy casts.
+  /// Values are never aligned less than the trailer of the [`SkipMap`](super::SkipMap).
+  ///
+  /// `align` will be rounded up to the next power of two. If it cannot be rounded up in `u32`
+  /// (it is larger than `2^31`), the map fails to be created with
+  /// [`Error::InvalidAlignment`](super::Error::InvalidAlignment).
+  ///
+  /// Default is `1`.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use skl::Options;
+  ///
+  /// let options = Options::new().with_value_alignment(8);
+  /// ```
+  #[inline]
+  pub const fn with_value_alignment(mut self, align: u32) -> Self {
+    self.value_alignment = match align.checked_next_power_of_two() {
+      Some(align) => align,
+      None => 0,
+    };
+    self
+  }
+
+  /// Sets whether to poison the [`SkipMap`](super::SkipMap) when [`SkipMap::verify`](super::SkipMap::verify)
+  /// finds an inconsistency, so subsequent write operations return an error rather than
+  /// continuing to modify possibly corrupted data.
+  ///
+  /// Default is `false`.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use skl::Options;
+  ///
+  /// let options = Options::new().with_poison_on_corruption(true);
+  /// ```
+  #[inline]
+  pub const fn with_poison_on_corruption(mut self, poison: bool) -> Self {
+    self.poison_on_corruption = poison;
+    self
+  }
+
+  /// Sets whether to treat the ARENA as untrusted when opening an existing file, and run
+  /// [`SkipMap::verify`](super::SkipMap::verify) before returning the [`SkipMap`](super::SkipMap),
+  /// so a malformed file is rejected instead of being read out of bounds later.
+  ///
+  /// The pass walks all the levels, so it is linear in the number of entries.
+  ///
+  /// Default is `false`.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use skl::Options;
+  ///
+  /// let options = Options::new().with_validate_on_open(true);
+  /// ```
+  #[inline]
+  pub const fn with_validate_on_open(mut self, validate: bool) -> Self {
+    self.validate_on_open = validate;
+    self
+  }
+
+  /// Sets whether to track the histograms of the key and value sizes, which are returned by
+  /// [`SkipMap::size_stats`](super::SkipMap::size_stats).
+  ///
+  /// Every write pays a few relaxed atomic increments when enabled.
+  ///
+  /// Default is `false`.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use skl::Options;
+  ///
+  /// let options = Options::new().with_size_histograms(true);
+  /// ```
+  #[inline]
+  pub const fn with_size_histograms(mut self, enable: bool) -> Self {
+    self.size_histograms = enable;
+    self
+  }
+
+  /// Sets whether to count the bytes the map allocates for the nodes, the keys, the values
+  /// and the alignment paddings, which are returned by
+  /// [`SkipMap::arena_stats`](super::SkipMap::arena_stats).
+  ///
+  /// Every write pays a few relaxed atomic increments when enabled.
+  ///
+  /// Default is `false`.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use skl::Options;
+  ///
+  /// let options = Options::new().with_arena_stats(true);
+  /// ```
+  #[inline]
+  pub const fn with_arena_stats(mut self, enable: bool) -> Self {
+    self.arena_stats = enable;
+    self
+  }
+
+  /// Sets whether to record a coarse last-access stamp per node, which is returned by
+  /// [`SkipMap::last_access`](super::SkipMap::last_access), so approximate-LRU eviction
+  /// policies can be built without an external tracking map.
+  ///
+  /// Every node pays 4 more bytes when enabled, and every hit of
+  /// [`SkipMap::get`](super::SkipMap::get) pays a relaxed store. The value is recorded in the
+  /// ARENA, and reopening a file backed map with a different value fails with
+  /// [`Error::LayoutMismatch`](super::Error::LayoutMismatch).
+  ///
+  /// Default is `false`.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use skl::Options;
+  ///
+  /// let options = Options::new().with_access_stamps(true);
+  /// ```
+  #[inline]
+  pub const fn with_access_stamps(mut self, enable: bool) -> Self {
+    self.access_stamps = enable;
+    self
+  }
+
+  /// Sets whether to store a 4-byte fingerprint (hash) of the key in every node, the keys of
+  /// two nodes are only compared if their fingerprints are equal, which skips most of the
+  /// comparisons and their cache misses when the versions of a key are searched by the lookups
+  /// and the seeks, and when the iterators skip the older versions.
+  ///
+  /// The fingerprint is computed from the bytes of the key, so this must only be enabled if
+  /// the [`Comparator`](crate::Comparator) considers two keys equal only when their bytes are
+  /// equal, which holds for [`Ascend`](crate::Ascend) and [`Descend`](crate::Descend).
+  ///
+  /// Every node pays 4 more bytes when enabled, and every write pays a hash of the key. The
+  /// value is recorded in the ARENA, and reopening a file backed map with a different value
+  /// fails with [`Error::LayoutMismatch`](super::Error::LayoutMismatch).
+  ///
+  /// Default is `false`.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use skl::Options;
+  ///
+  /// let options = Options::new().with_key_fingerprints(true);
+  /// ```
+  #[inline]
+  pub const fn with_key_fingerprints(mut self, enable: bool) -> Self {
+    self.key_fingerprints = enable;
+    self
+  }
+
+  /// Sets the maximum size of the keys which are stored inline, in the same allocation as
+  /// their node (after the tower and the optional fields), instead of in a separate
+  /// allocation, e.g. `8` for the `u64` keys of the time-series or ID-indexed workloads.
+  ///
+  /// The inline keys save an allocation per entry, and are read from the cache lines of the
+  /// node when the searches compare them. The longer keys, and the keys shared by the versions
+  /// of a key, are stored as usual, so this can be changed when a file backed map is reopened.
+  ///
+  /// Default is `0`, which means no key is stored inline.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use skl::Options;
+  ///
+  /// let options = Options::new().with_inline_key_size(8);
+  /// ```
+  #[inline]
+  pub const fn with_inline_key_size(mut self, size: u8) -> Self {
+    self.inline_key_size = size;
+    self
+  }
+
+  /// Sets whether to advise the kernel to back the ARENA with transparent huge pages (2 MiB)
+  /// when the map is created, which saves most of the TLB misses of the tower descents of the
+  /// large maps.
+  ///
+  /// The advice is `madvise(MADV_HUGEPAGE)` on Linux, on the part of the ARENA which is aligned
+  /// to the huge pages, so the ARENA should be at least a few MiB. It is best effort: the map
+  /// works the same if the kernel does not support or rejects it, and it is ignored on the
+  /// other platforms.
+  ///
+  /// Default is `false`.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use skl::Options;
+  ///
+  /// let options = Options::new().with_capacity(64 << 20).with_huge_pages(true);
+  /// ```
+  #[inline]
+  pub const fn with_huge_pages(mut self, enable: bool) -> Self {
+    self.huge_pages = enable;
+    self
+  }
+
   /// Returns the maximum size of the value.
   ///
   /// Default is `u32::MAX`.
@@ -241,6 +536,223 @@ impl Options {
     self.capacity
   }
 
+  /// Returns the expected number of entries of the [`SkipMap`](super::SkipMap).
+  ///
+  /// Default is `0`.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use skl::Options;
+  ///
+  /// let options = Options::new().with_expected_entries(1_000_000);
+  ///
+  /// assert_eq!(options.expected_entries(), 1_000_000);
+  /// ```
+  #[inline]
+  pub const fn expected_entries(&self) -> u32 {
+    self.expected_entries
+  }
+
+  /// Returns the spin limit of the backoff used when the CAS operations in the insert path have to be retried.
+  ///
+  /// Default is `6`.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use skl::Options;
+  ///
+  /// let options = Options::new().with_backoff_spin_limit(4);
+  ///
+  /// assert_eq!(options.backoff_spin_limit(), 4);
+  /// ```
+  #[inline]
+  pub const fn backoff_spin_limit(&self) -> u8 {
+    self.backoff_spin_limit
+  }
+
+  /// Returns the yield limit of the backoff used when the CAS operations in the insert path have to be retried.
+  ///
+  /// Default is `10`.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use skl::Options;
+  ///
+  /// let options = Options::new().with_backoff_yield_limit(8);
+  ///
+  /// assert_eq!(options.backoff_yield_limit(), 8);
+  /// ```
+  #[inline]
+  pub const fn backoff_yield_limit(&self) -> u8 {
+    self.backoff_yield_limit
+  }
+
+  /// Returns the default alignment of the values stored in the [`SkipMap`](super::SkipMap).
+  ///
+  /// Default is `1`.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use skl::Options;
+  ///
+  /// let options = Options::new().with_value_alignment(8);
+  ///
+  /// assert_eq!(options.value_alignment(), 8);
+  /// ```
+  #[inline]
+  pub const fn value_alignment(&self) -> u32 {
+    self.value_alignment
+  }
+
+  /// Returns whether to poison the [`SkipMap`](super::SkipMap) when [`SkipMap::verify`](super::SkipMap::verify)
+  /// finds an inconsistency.
+  ///
+  /// Default is `false`.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use skl::Options;
+  ///
+  /// let options = Options::new().with_poison_on_corruption(true);
+  ///
+  /// assert_eq!(options.poison_on_corruption(), true);
+  /// ```
+  #[inline]
+  pub const fn poison_on_corruption(&self) -> bool {
+    self.poison_on_corruption
+  }
+
+  /// Returns whether to validate the ARENA when opening an existing file.
+  ///
+  /// Default is `false`.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use skl::Options;
+  ///
+  /// let options = Options::new().with_validate_on_open(true);
+  ///
+  /// assert_eq!(options.validate_on_open(), true);
+  /// ```
+  #[inline]
+  pub const fn validate_on_open(&self) -> bool {
+    self.validate_on_open
+  }
+
+  /// Returns whether to track the histograms of the key and value sizes.
+  ///
+  /// Default is `false`.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use skl::Options;
+  ///
+  /// let options = Options::new().with_size_histograms(true);
+  ///
+  /// assert_eq!(options.size_histograms(), true);
+  /// ```
+  #[inline]
+  pub const fn size_histograms(&self) -> bool {
+    self.size_histograms
+  }
+
+  /// Returns whether to count the bytes the map allocates.
+  ///
+  /// Default is `false`.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use skl::Options;
+  ///
+  /// let options = Options::new().with_arena_stats(true);
+  ///
+  /// assert_eq!(options.arena_stats(), true);
+  /// ```
+  #[inline]
+  pub const fn arena_stats(&self) -> bool {
+    self.arena_stats
+  }
+
+  /// Returns whether to record a coarse last-access stamp per node.
+  ///
+  /// Default is `false`.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use skl::Options;
+  ///
+  /// let options = Options::new().with_access_stamps(true);
+  ///
+  /// assert_eq!(options.access_stamps(), true);
+  /// ```
+  #[inline]
+  pub const fn access_stamps(&self) -> bool {
+    self.access_stamps
+  }
+
+  /// Returns whether to store a fingerprint of the key in every node.
+  ///
+  /// Default is `false`.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use skl::Options;
+  ///
+  /// let options = Options::new().with_key_fingerprints(true);
+  ///
+  /// assert_eq!(options.key_fingerprints(), true);
+  /// ```
+  #[inline]
+  pub const fn key_fingerprints(&self) -> bool {
+    self.key_fingerprints
+  }
+
+  /// Returns the maximum size of the keys which are stored inline.
+  ///
+  /// Default is `0`.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use skl::Options;
+  ///
+  /// let options = Options::new().with_inline_key_size(8);
+  ///
+  /// assert_eq!(options.inline_key_size(), 8);
+  /// ```
+  #[inline]
+  pub const fn inline_key_size(&self) -> u8 {
+    self.inline_key_size
+  }
+
+  /// Returns whether to advise the kernel to back the ARENA with huge pages.
+  ///
+  /// Default is `false`.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use skl::Options;
+  ///
+  /// let options = Options::new().with_huge_pages(true);
+  ///
+  /// assert_eq!(options.huge_pages(), true);
+  /// ```
+  #[inline]
+  pub const fn huge_pages(&self) -> bool {
+    self.huge_pages
+  }
+
   /// Get if use the unify memory layout of the [`SkipMap`](super::SkipMap).
   ///
   /// File backed [`SkipMap`](super::SkipMap) has different memory layout with other kind backed [`SkipMap`](super::SkipMap),
@@ -0,0 +1,160 @@
+use core::num::NonZeroU64;
+use std::{collections::BTreeMap, sync::RwLock, vec::Vec};
+
+use super::{
+  map::{Entry, Error},
+  Ascend, Comparator, Options, SkipMap, Trailer,
+};
+
+/// A set of [`SkipMap`]s partitioned by time, for time-series ingestion.
+///
+/// The inserts are routed by the timestamp into the partition (bucket) which covers
+/// `start..start + bucket_width`, the partitions are created on demand with the same [`Options`].
+/// The retention is enforced by dropping whole partitions with
+/// [`drop_before`](TimePartitionedList::drop_before), instead of expiring the keys one by one.
+///
+/// # Example
+///
+/// ```rust
+/// use core::num::NonZeroU64;
+/// use skl::{partition::TimePartitionedList, Options};
+///
+/// let list = TimePartitionedList::new(NonZeroU64::new(60).unwrap(), Options::new());
+/// list.insert(10, 0, b"cpu", b"0.5").unwrap();
+/// list.insert(70, 0, b"cpu", b"0.7").unwrap();
+/// list.insert(130, 0, b"cpu", b"0.9").unwrap();
+/// assert_eq!(list.partition_starts(), [0, 60, 120]);
+///
+/// assert_eq!(list.get(70, 0, b"cpu").unwrap().value(), b"0.7");
+///
+/// // the partitions which end before `120` are dropped
+/// assert_eq!(list.drop_before(120), 2);
+/// assert!(list.get(70, 0, b"cpu").is_none());
+/// assert_eq!(list.partition_starts(), [120]);
+/// ```
+#[derive(Debug)]
+pub struct TimePartitionedList<T = u64, C = Ascend> {
+  bucket_width: NonZeroU64,
+  opts: Options,
+  cmp: C,
+  partitions: RwLock<BTreeMap<u64, SkipMap<T, C>>>,
+}
+
+impl<T> TimePartitionedList<T> {
+  /// Creates a new list, each partition covers `bucket_width` units of time, and is created
+  /// with the [`Options`].
+  #[inline]
+  pub fn new(bucket_width: NonZeroU64, opts: Options) -> Self {
+    Self::with_comparator(bucket_width, opts, Ascend)
+  }
+}
+
+impl<T, C> TimePartitionedList<T, C> {
+  /// Like [`TimePartitionedList::new`], but with a custom [`Comparator`].
+  #[inline]
+  pub fn with_comparator(bucket_width: NonZeroU64, opts: Options, cmp: C) -> Self {
+    Self {
+      bucket_width,
+      opts,
+      cmp,
+      partitions: RwLock::new(BTreeMap::new()),
+    }
+  }
+
+  /// Returns the width of the partitions.
+  #[inline]
+  pub const fn bucket_width(&self) -> NonZeroU64 {
+    self.bucket_width
+  }
+
+  /// Returns the start of the partition which covers the timestamp.
+  #[inline]
+  pub const fn partition_start(&self, timestamp: u64) -> u64 {
+    timestamp - timestamp % self.bucket_width.get()
+  }
+
+  /// Returns the starts of the partitions, in ascending order.
+  pub fn partition_starts(&self) -> Vec<u64> {
+    self
+      .partitions
+      .read()
+      .unwrap_or_else(|e| e.into_inner())
+      .keys()
+      .copied()
+      .collect()
+  }
+
+  /// Returns how many partitions are alive.
+  pub fn num_partitions(&self) -> usize {
+    self
+      .partitions
+      .read()
+      .unwrap_or_else(|e| e.into_inner())
+      .len()
+  }
+
+  /// Drops all the partitions which end at or before `cutoff`, returns how many partitions
+  /// are dropped.
+  ///
+  /// The ARENA of a dropped partition is freed once the clones of the [`SkipMap`] (returned by
+  /// [`partition`](TimePartitionedList::partition)) and the owned entries are dropped.
+  pub fn drop_before(&self, cutoff: u64) -> usize {
+    // the first partition which ends after the cutoff
+    let first_kept = self.partition_start(cutoff);
+    let dropped = {
+      let mut partitions = self.partitions.write().unwrap_or_else(|e| e.into_inner());
+      let kept = partitions.split_off(&first_kept);
+      core::mem::replace(&mut *partitions, kept)
+    };
+    dropped.len()
+  }
+}
+
+impl<T, C: Clone> TimePartitionedList<T, C> {
+  /// Returns the partition which covers the timestamp.
+  pub fn partition(&self, timestamp: u64) -> Option<SkipMap<T, C>> {
+    self
+      .partitions
+      .read()
+      .unwrap_or_else(|e| e.into_inner())
+      .get(&self.partition_start(timestamp))
+      .cloned()
+  }
+
+  /// Returns the partition which covers the timestamp, creates it if it does not exist.
+  pub fn get_or_create_partition(&self, timestamp: u64) -> Result<SkipMap<T, C>, Error> {
+    if let Some(map) = self.partition(timestamp) {
+      return Ok(map);
+    }
+
+    let start = self.partition_start(timestamp);
+    let mut partitions = self.partitions.write().unwrap_or_else(|e| e.into_inner());
+    // another thread may have created it
+    if let Some(map) = partitions.get(&start) {
+      return Ok(map.clone());
+    }
+
+    let map = SkipMap::with_options_and_comparator(self.opts, self.cmp.clone())?;
+    partitions.insert(start, map.clone());
+    Ok(map)
+  }
+}
+
+impl<T: Trailer, C: Comparator + Clone> TimePartitionedList<T, C> {
+  /// Upserts the key-value pair into the partition which covers the timestamp.
+  pub fn insert(&self, timestamp: u64, trailer: T, key: &[u8], value: &[u8]) -> Result<(), Error> {
+    self
+      .get_or_create_partition(timestamp)?
+      .insert(trailer, key, value)
+      .map(|_| ())
+  }
+
+  /// Returns the entry of the key (with the version less or equal to `version`) in the
+  /// partition which covers the timestamp.
+  pub fn get(&self, timestamp: u64, version: u64, key: &[u8]) -> Option<Entry<T>> {
+    self
+      .partition(timestamp)?
+      .get(version, key)
+      .map(|ent| ent.to_owned())
+  }
+}
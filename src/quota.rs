@@ -0,0 +1,305 @@
+use std::{sync::Mutex, vec::Vec};
+
+use super::{
+  map::{EntryRef, Error},
+  Ascend, Comparator, Options, SkipMap, Trailer,
+};
+
+/// The limits of a [`BoundedMap`], counted over the latest versions of the live entries.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Quota {
+  max_entries: Option<usize>,
+  max_live_bytes: Option<u64>,
+}
+
+impl Quota {
+  /// Creates a quota without any limit.
+  #[inline]
+  pub const fn new() -> Self {
+    Self {
+      max_entries: None,
+      max_live_bytes: None,
+    }
+  }
+
+  /// Sets the maximum number of the live entries.
+  ///
+  /// Default is `None`, which means no limit.
+  #[inline]
+  pub const fn with_max_entries(mut self, max_entries: usize) -> Self {
+    self.max_entries = Some(max_entries);
+    self
+  }
+
+  /// Sets the maximum total size of the keys and values of the live entries.
+  ///
+  /// The ARENA is append-only, so the evicted entries are removed but their memory is not
+  /// reclaimed, the capacity of the ARENA should leave room for the churn.
+  ///
+  /// Default is `None`, which means no limit.
+  #[inline]
+  pub const fn with_max_live_bytes(mut self, max_live_bytes: u64) -> Self {
+    self.max_live_bytes = Some(max_live_bytes);
+    self
+  }
+
+  /// Returns the maximum number of the live entries.
+  #[inline]
+  pub const fn max_entries(&self) -> Option<usize> {
+    self.max_entries
+  }
+
+  /// Returns the maximum total size of the keys and values of the live entries.
+  #[inline]
+  pub const fn max_live_bytes(&self) -> Option<u64> {
+    self.max_live_bytes
+  }
+
+  #[inline]
+  fn allows(&self, usage: Usage) -> bool {
+    self.max_entries.map_or(true, |max| usage.entries <= max)
+      && self.max_live_bytes.map_or(true, |max| usage.bytes <= max)
+  }
+}
+
+/// What a [`BoundedMap`] does when a write would exceed the [`Quota`].
+pub enum EvictionPolicy<T, C = Ascend> {
+  /// Rejects the write with [`Error::QuotaExceeded`].
+  Reject,
+  /// Evicts the entries which expire the soonest, the function extracts the expiration time
+  /// from the trailer, see [`SkipMap::iter_by_expiry`]. The entries without an expiration
+  /// time are never evicted.
+  EvictSoonestExpiry(fn(&T) -> Option<u64>),
+  /// Evicts the key returned by the callback, which is called with the map and the version
+  /// of the write, until the write fits. Returning `None` rejects the write.
+  EvictWith(fn(&SkipMap<T, C>, u64) -> Option<Vec<u8>>),
+}
+
+impl<T, C> Clone for EvictionPolicy<T, C> {
+  fn clone(&self) -> Self {
+    *self
+  }
+}
+
+impl<T, C> Copy for EvictionPolicy<T, C> {}
+
+impl<T, C> core::fmt::Debug for EvictionPolicy<T, C> {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    match self {
+      Self::Reject => f.write_str("Reject"),
+      Self::EvictSoonestExpiry(_) => f.write_str("EvictSoonestExpiry"),
+      Self::EvictWith(_) => f.write_str("EvictWith"),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Usage {
+  entries: usize,
+  bytes: u64,
+}
+
+/// A memory-bounded [`SkipMap`], which enforces a [`Quota`] on every write with an
+/// [`EvictionPolicy`].
+///
+/// The writes are serialized to keep the usage exact, the reads go to the [`SkipMap`]
+/// directly and are lock-free. The entries are evicted by removing them with the trailer
+/// of the write which triggers the eviction.
+///
+/// # Example
+///
+/// ```rust
+/// use skl::{
+///   quota::{BoundedMap, EvictionPolicy, Quota},
+///   Options,
+/// };
+///
+/// // use the version as the expiration time
+/// let l = BoundedMap::with_options(
+///   Options::new(),
+///   Quota::new().with_max_entries(2),
+///   EvictionPolicy::EvictSoonestExpiry(|t| Some(*t)),
+/// )
+/// .unwrap();
+/// l.insert(3, b"a", b"a").unwrap();
+/// l.insert(1, b"b", b"b").unwrap();
+/// l.insert(5, b"c", b"c").unwrap();
+///
+/// // `b` expires the soonest, so it is evicted
+/// assert_eq!(l.entries(), 2);
+/// assert!(l.get(5, b"b").is_none());
+/// assert!(l.get(5, b"a").is_some());
+/// ```
+#[derive(Debug)]
+pub struct BoundedMap<T = u64, C = Ascend> {
+  map: SkipMap<T, C>,
+  quota: Quota,
+  policy: EvictionPolicy<T, C>,
+  usage: Mutex<Usage>,
+}
+
+impl<T> BoundedMap<T> {
+  /// Creates a new bounded map with the [`Options`].
+  #[inline]
+  pub fn with_options(
+    opts: Options,
+    quota: Quota,
+    policy: EvictionPolicy<T>,
+  ) -> Result<Self, Error> {
+    Self::with_options_and_comparator(opts, Ascend, quota, policy)
+  }
+}
+
+impl<T, C> BoundedMap<T, C> {
+  /// Like [`BoundedMap::with_options`], but with a custom [`Comparator`].
+  pub fn with_options_and_comparator(
+    opts: Options,
+    cmp: C,
+    quota: Quota,
+    policy: EvictionPolicy<T, C>,
+  ) -> Result<Self, Error> {
+    Ok(Self {
+      map: SkipMap::with_options_and_comparator(opts, cmp)?,
+      quota,
+      policy,
+      usage: Mutex::new(Usage::default()),
+    })
+  }
+
+  /// Returns the underlying map, the writes must go through the [`BoundedMap`] to keep the
+  /// usage in sync.
+  #[inline]
+  pub const fn map(&self) -> &SkipMap<T, C> {
+    &self.map
+  }
+
+  /// Returns the quota.
+  #[inline]
+  pub const fn quota(&self) -> Quota {
+    self.quota
+  }
+
+  /// Returns the number of the live entries.
+  #[inline]
+  pub fn entries(&self) -> usize {
+    self.usage.lock().unwrap_or_else(|e| e.into_inner()).entries
+  }
+
+  /// Returns the total size of the keys and values of the live entries.
+  #[inline]
+  pub fn live_bytes(&self) -> u64 {
+    self.usage.lock().unwrap_or_else(|e| e.into_inner()).bytes
+  }
+}
+
+impl<T: Trailer, C: Comparator> BoundedMap<T, C> {
+  /// Upserts a key-value pair, evicts the entries by the [`EvictionPolicy`] if the write
+  /// would exceed the [`Quota`].
+  ///
+  /// Returns [`Error::QuotaExceeded`] if the write does not fit even after the eviction.
+  pub fn insert(&self, trailer: T, key: &[u8], value: &[u8]) -> Result<(), Error> {
+    let mut usage = self.usage.lock().unwrap_or_else(|e| e.into_inner());
+    let version = trailer.version();
+    let mut next = *usage;
+    match self.map.get(version, key) {
+      Some(old) => next.bytes -= entry_bytes(&old),
+      None => next.entries += 1,
+    }
+    next.bytes += (key.len() + value.len()) as u64;
+
+    if !self.quota.allows(next) {
+      self.evict(trailer, key, &mut usage, &mut next)?;
+    }
+
+    self.map.insert(trailer, key, value)?;
+    *usage = next;
+    Ok(())
+  }
+
+  /// Removes the key.
+  pub fn remove(&self, trailer: T, key: &[u8]) -> Result<(), Error> {
+    let mut usage = self.usage.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(old) = self.map.get(trailer.version(), key) {
+      let bytes = entry_bytes(&old);
+      self.remove_in(trailer, key)?;
+      usage.entries -= 1;
+      usage.bytes -= bytes;
+    }
+    Ok(())
+  }
+
+  /// Returns the latest entry of the key whose version is less or equal to the given version.
+  #[inline]
+  pub fn get<'a, 'b: 'a>(&'a self, version: u64, key: &'b [u8]) -> Option<EntryRef<'a, T>> {
+    self.map.get(version, key)
+  }
+
+  /// Evicts the entries until `next` fits, `usage` is kept in sync with the evictions even if
+  /// the write is rejected at last.
+  fn evict(
+    &self,
+    trailer: T,
+    key: &[u8],
+    usage: &mut Usage,
+    next: &mut Usage,
+  ) -> Result<(), Error> {
+    let version = trailer.version();
+    let victims = match self.policy {
+      EvictionPolicy::Reject => return Err(Error::QuotaExceeded),
+      EvictionPolicy::EvictSoonestExpiry(ttl) => self
+        .map
+        .iter_by_expiry(version, ttl)
+        .map(|(_, ent)| (ent.key().to_vec(), entry_bytes(&ent)))
+        .collect(),
+      EvictionPolicy::EvictWith(_) => Vec::new(),
+    };
+
+    let mut victims = victims.into_iter();
+    while !self.quota.allows(*next) {
+      let (victim, bytes) = match self.policy {
+        EvictionPolicy::EvictWith(f) => {
+          let victim = f(&self.map, version).ok_or(Error::QuotaExceeded)?;
+          let bytes = self
+            .map
+            .get(version, &victim)
+            .map(|ent| entry_bytes(&ent))
+            .ok_or(Error::QuotaExceeded)?;
+          (victim, bytes)
+        }
+        _ => victims.next().ok_or(Error::QuotaExceeded)?,
+      };
+
+      // the written key is never evicted for itself
+      if self.map.comparator().compare(&victim, key) == core::cmp::Ordering::Equal {
+        match self.policy {
+          EvictionPolicy::EvictWith(_) => return Err(Error::QuotaExceeded),
+          _ => continue,
+        }
+      }
+
+      self.remove_in(trailer, &victim)?;
+      for u in [&mut *usage, &mut *next] {
+        u.entries -= 1;
+        u.bytes -= bytes;
+      }
+    }
+    Ok(())
+  }
+
+  fn remove_in(&self, trailer: T, key: &[u8]) -> Result<(), Error> {
+    self
+      .map
+      .compare_remove(
+        trailer,
+        key,
+        core::sync::atomic::Ordering::AcqRel,
+        core::sync::atomic::Ordering::Relaxed,
+      )
+      .map(|_| ())
+  }
+}
+
+#[inline]
+fn entry_bytes<T>(ent: &EntryRef<'_, T>) -> u64 {
+  (ent.key().len() + ent.value().len()) as u64
+}
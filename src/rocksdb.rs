@@ -0,0 +1,183 @@
+use core::{cmp, ops::RangeBounds};
+use std::{
+  ffi::CStr,
+  os::raw::{c_char, c_int, c_void},
+};
+
+use super::Comparator;
+
+/// The compare function of a RocksDB-style C comparator.
+///
+/// Returns a negative value if `a < b`, `0` if `a == b`, and a positive value if `a > b`.
+pub type CompareFn = unsafe extern "C" fn(
+  state: *mut c_void,
+  a: *const c_char,
+  alen: usize,
+  b: *const c_char,
+  blen: usize,
+) -> c_int;
+
+/// The name function of a RocksDB-style C comparator.
+pub type NameFn = unsafe extern "C" fn(state: *mut c_void) -> *const c_char;
+
+/// The destructor of the state of a RocksDB-style C comparator.
+pub type DestructorFn = unsafe extern "C" fn(state: *mut c_void);
+
+/// An adapter which wraps a RocksDB-style C comparator (the arguments of `rocksdb_comparator_create`)
+/// into a [`Comparator`], so engines already configured with custom RocksDB comparators can keep
+/// byte-identical ordering in the memtable.
+///
+/// The range APIs compare the keys with the bounds of the range by the `PartialOrd` of the
+/// bounds, which is bytewise for byte slices, so the ranges of a map ordered by a custom
+/// comparator must be bounded by [`RocksDbKey`]s (see [`RocksDbComparator::key`]) to follow
+/// the custom order.
+pub struct RocksDbComparator {
+  state: *mut c_void,
+  destructor: Option<DestructorFn>,
+  compare: CompareFn,
+  name: NameFn,
+}
+
+// Safety: the caller of `RocksDbComparator::new` guarantees the comparator is thread-safe.
+unsafe impl Send for RocksDbComparator {}
+unsafe impl Sync for RocksDbComparator {}
+
+impl RocksDbComparator {
+  /// Creates a new adapter, the `destructor` will be invoked with `state` when the adapter is dropped.
+  ///
+  /// # Safety
+  /// - `compare` and `name` must be safe to be invoked with `state` concurrently from multiple threads.
+  /// - `name` must return a valid nul-terminated string which lives as long as `state`.
+  #[inline]
+  pub const unsafe fn new(
+    state: *mut c_void,
+    destructor: Option<DestructorFn>,
+    compare: CompareFn,
+    name: NameFn,
+  ) -> Self {
+    Self {
+      state,
+      destructor,
+      compare,
+      name,
+    }
+  }
+
+  /// Returns the name of the comparator.
+  #[inline]
+  pub fn name(&self) -> &CStr {
+    // Safety: the caller of `new` guarantees the name is a valid nul-terminated string.
+    unsafe { CStr::from_ptr((self.name)(self.state)) }
+  }
+
+  /// Returns the key as a bound of the range APIs, which is ordered by this comparator.
+  ///
+  /// # Example
+  ///
+  /// ```rust,ignore
+  /// let cmp = l.comparator();
+  /// let range = l.range(0, cmp.key(b"a")..cmp.key(b"c"));
+  /// ```
+  #[inline]
+  pub const fn key<'a>(&'a self, key: &'a [u8]) -> RocksDbKey<'a> {
+    RocksDbKey { cmp: self, key }
+  }
+}
+
+/// A key ordered by a [`RocksDbComparator`], the bound type of the ranges of the maps ordered
+/// by the comparator, see [`RocksDbComparator::key`].
+#[derive(Clone, Copy)]
+pub struct RocksDbKey<'a> {
+  cmp: &'a RocksDbComparator,
+  key: &'a [u8],
+}
+
+impl<'a> RocksDbKey<'a> {
+  /// Returns the bytes of the key.
+  #[inline]
+  pub const fn as_bytes(&self) -> &'a [u8] {
+    self.key
+  }
+}
+
+impl core::fmt::Debug for RocksDbKey<'_> {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    f.debug_tuple("RocksDbKey").field(&self.key).finish()
+  }
+}
+
+impl PartialEq<&[u8]> for RocksDbKey<'_> {
+  #[inline]
+  fn eq(&self, other: &&[u8]) -> bool {
+    self.cmp.compare(self.key, other) == cmp::Ordering::Equal
+  }
+}
+
+impl PartialOrd<&[u8]> for RocksDbKey<'_> {
+  #[inline]
+  fn partial_cmp(&self, other: &&[u8]) -> Option<cmp::Ordering> {
+    Some(self.cmp.compare(self.key, other))
+  }
+}
+
+impl PartialEq<RocksDbKey<'_>> for &[u8] {
+  #[inline]
+  fn eq(&self, other: &RocksDbKey<'_>) -> bool {
+    other.cmp.compare(self, other.key) == cmp::Ordering::Equal
+  }
+}
+
+impl PartialOrd<RocksDbKey<'_>> for &[u8] {
+  #[inline]
+  fn partial_cmp(&self, other: &RocksDbKey<'_>) -> Option<cmp::Ordering> {
+    Some(other.cmp.compare(self, other.key))
+  }
+}
+
+impl core::fmt::Debug for RocksDbComparator {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    f.debug_struct("RocksDbComparator")
+      .field("name", &self.name())
+      .finish()
+  }
+}
+
+impl Drop for RocksDbComparator {
+  fn drop(&mut self) {
+    if let Some(destructor) = self.destructor {
+      // Safety: the state is owned by the adapter.
+      unsafe { destructor(self.state) }
+    }
+  }
+}
+
+impl Comparator for RocksDbComparator {
+  #[inline]
+  fn compare(&self, a: &[u8], b: &[u8]) -> cmp::Ordering {
+    // Safety: the caller of `new` guarantees the compare function is safe to be invoked.
+    let res = unsafe {
+      (self.compare)(
+        self.state,
+        a.as_ptr() as *const c_char,
+        a.len(),
+        b.as_ptr() as *const c_char,
+        b.len(),
+      )
+    };
+    res.cmp(&0)
+  }
+
+  /// The bounds are compared with the key by their `PartialOrd`, so the membership follows
+  /// [`compare`](Comparator::compare) when the range is bounded by [`RocksDbKey`]s, and is
+  /// bytewise when it is bounded by byte slices. The bytewise membership is only contiguous
+  /// (see [`Comparator::contains`]) if the comparator orders the keys bytewise, so the range
+  /// iterators of the other orders must be bounded by [`RocksDbKey`]s.
+  #[inline]
+  fn contains<'a, Q>(&self, range: &impl RangeBounds<Q>, key: &'a [u8]) -> bool
+  where
+    &'a [u8]: PartialOrd<Q>,
+    Q: ?Sized + PartialOrd<&'a [u8]>,
+  {
+    range.contains(&key)
+  }
+}
@@ -0,0 +1,348 @@
+//! A differential testing driver of the [`SkipMap`], shared by the fuzz targets of the crate
+//! and by the applications which fuzz their own configurations.
+//!
+//! The input bytes are decoded into a sequence of [`Op`]s, which are applied to both a
+//! [`SkipMap`] and a [`BTreeMap`] oracle, and every read is asserted to return the same result
+//! from both, so any divergence panics, which is what the fuzzers report.
+//!
+//! # Example
+//!
+//! A `cargo fuzz` target is a one-liner:
+//!
+//! ```rust,ignore
+//! #![no_main]
+//! libfuzzer_sys::fuzz_target!(|data: &[u8]| skl::testing::Driver::run(data));
+//! ```
+//!
+//! And the driver can be run on any input:
+//!
+//! ```rust
+//! use skl::testing::{Driver, Op};
+//!
+//! let mut driver = Driver::new().unwrap();
+//! driver.apply(&Op::Insert { version: 1, key: b"a".to_vec(), value: b"a1".to_vec() });
+//! driver.apply(&Op::Remove { version: 2, key: b"a".to_vec() });
+//! driver.apply(&Op::Get { version: 1, key: b"a".to_vec() });
+//! driver.check();
+//!
+//! Driver::run(b"any bytes decode to some ops");
+//! ```
+
+use std::{collections::BTreeMap, vec::Vec};
+
+use super::{map::Error, Options, SkipMap};
+
+/// The largest version of the decoded operations, the versions are kept small so the
+/// operations often hit the same versions of the same keys.
+const MAX_VERSION: u8 = 7;
+
+/// The maximum length of the decoded keys.
+const MAX_KEY_LEN: u8 = 8;
+
+/// The maximum length of the decoded values.
+const MAX_VALUE_LEN: u8 = 32;
+
+/// The capacity of the ARENA of [`Driver::new`], small enough for the fuzzers to fill it.
+const CAPACITY: u32 = 64 << 10;
+
+/// An operation applied by the [`Driver`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Op {
+  /// Upserts the value of the key with the version, see [`SkipMap::insert`].
+  Insert {
+    /// The version of the entry.
+    version: u64,
+    /// The key of the entry.
+    key: Vec<u8>,
+    /// The value of the entry.
+    value: Vec<u8>,
+  },
+  /// Writes a removed entry of the key with the version, see [`SkipMap::remove`].
+  Remove {
+    /// The version of the entry.
+    version: u64,
+    /// The key of the entry.
+    key: Vec<u8>,
+  },
+  /// Looks up the key, see [`SkipMap::get`].
+  Get {
+    /// The version of the read.
+    version: u64,
+    /// The key to look up.
+    key: Vec<u8>,
+  },
+  /// Iterates all the entries forward and backward, see [`SkipMap::iter`].
+  Iter {
+    /// The version of the read.
+    version: u64,
+  },
+  /// Iterates the entries greater or equal to the key, see [`SkipMap::range`].
+  Range {
+    /// The version of the read.
+    version: u64,
+    /// The inclusive lower bound of the range.
+    lower: Vec<u8>,
+  },
+}
+
+/// Reads the fields of the operations from the input bytes.
+struct Reader<'a> {
+  data: &'a [u8],
+}
+
+impl Reader<'_> {
+  #[inline]
+  fn byte(&mut self) -> Option<u8> {
+    let (b, rest) = self.data.split_first()?;
+    self.data = rest;
+    Some(*b)
+  }
+
+  #[inline]
+  fn version(&mut self) -> Option<u64> {
+    self.byte().map(|b| (b % (MAX_VERSION + 1)) as u64)
+  }
+
+  /// Reads a length prefixed byte string, which is cut short at the end of the input.
+  fn bytes(&mut self, max_len: u8) -> Option<Vec<u8>> {
+    let len = (self.byte()? % (max_len + 1)) as usize;
+    let (bytes, rest) = self.data.split_at(len.min(self.data.len()));
+    self.data = rest;
+    Some(bytes.to_vec())
+  }
+}
+
+impl Op {
+  /// Decodes the operations from the input bytes, every input decodes to a (possibly empty)
+  /// sequence of operations, so the fuzzers do not waste the inputs.
+  pub fn decode(data: &[u8]) -> Vec<Op> {
+    let mut reader = Reader { data };
+    let mut ops = Vec::new();
+    while let Some(op) = Self::decode_one(&mut reader) {
+      ops.push(op);
+    }
+    ops
+  }
+
+  fn decode_one(r: &mut Reader<'_>) -> Option<Op> {
+    Some(match r.byte()? % 5 {
+      0 => Op::Insert {
+        version: r.version()?,
+        key: r.bytes(MAX_KEY_LEN)?,
+        value: r.bytes(MAX_VALUE_LEN)?,
+      },
+      1 => Op::Remove {
+        version: r.version()?,
+        key: r.bytes(MAX_KEY_LEN)?,
+      },
+      2 => Op::Get {
+        version: r.version()?,
+        key: r.bytes(MAX_KEY_LEN)?,
+      },
+      3 => Op::Iter {
+        version: r.version()?,
+      },
+      _ => Op::Range {
+        version: r.version()?,
+        lower: r.bytes(MAX_KEY_LEN)?,
+      },
+    })
+  }
+}
+
+/// Applies the [`Op`]s to a [`SkipMap`] and to a [`BTreeMap`] oracle, and panics if they
+/// diverge.
+///
+/// A write which fails because the ARENA is full is skipped on both sides, any other error
+/// panics.
+#[derive(Debug)]
+pub struct Driver {
+  map: SkipMap,
+  /// The versions of every key, `None` is a removed entry.
+  oracle: BTreeMap<Vec<u8>, BTreeMap<u64, Option<Vec<u8>>>>,
+}
+
+impl Driver {
+  /// Creates a driver whose ARENA has `64 KiB`, so the fuzzers also reach a full ARENA.
+  #[inline]
+  pub fn new() -> Result<Self, Error> {
+    Self::with_options(Options::new().with_capacity(CAPACITY))
+  }
+
+  /// Creates a driver with the given options, e.g. to fuzz the configurations of an
+  /// application.
+  #[inline]
+  pub fn with_options(opts: Options) -> Result<Self, Error> {
+    SkipMap::with_options(opts).map(|map| Self {
+      map,
+      oracle: BTreeMap::new(),
+    })
+  }
+
+  /// Returns the map under test.
+  #[inline]
+  pub const fn map(&self) -> &SkipMap {
+    &self.map
+  }
+
+  /// Decodes the operations from the input bytes, applies them to a new [`Driver`], and
+  /// [`check`](Driver::check)s the final state, the entry point of the fuzz targets.
+  ///
+  /// # Panics
+  ///
+  /// Panics if the map diverges from the oracle.
+  pub fn run(data: &[u8]) {
+    let mut driver = Self::new().expect("failed to create the map");
+    for op in Op::decode(data) {
+      driver.apply(&op);
+    }
+    driver.check();
+  }
+
+  /// Applies the operation to the map and to the oracle.
+  ///
+  /// # Panics
+  ///
+  /// Panics if the map diverges from the oracle.
+  pub fn apply(&mut self, op: &Op) {
+    match op {
+      Op::Insert {
+        version,
+        key,
+        value,
+      } => {
+        if self.write(*version, key, Some(value.as_slice())) {
+          self
+            .oracle
+            .entry(key.clone())
+            .or_default()
+            .insert(*version, Some(value.clone()));
+        }
+      }
+      Op::Remove { version, key } => {
+        if self.write(*version, key, None) {
+          self
+            .oracle
+            .entry(key.clone())
+            .or_default()
+            .insert(*version, None);
+        }
+      }
+      Op::Get { version, key } => {
+        assert_eq!(
+          self.map.get(*version, key).map(|ent| ent.value().to_vec()),
+          self.oracle_get(*version, key),
+          "get({}, {:?})",
+          version,
+          key
+        );
+        assert_eq!(
+          self.map.contains_key(*version, key),
+          self.oracle_get(*version, key).is_some(),
+          "contains_key({}, {:?})",
+          version,
+          key
+        );
+      }
+      Op::Iter { version } => {
+        let expected = self.oracle_range(*version, &[]);
+        let forward = self
+          .map
+          .iter(*version)
+          .map(|ent| (ent.key().to_vec(), ent.value().to_vec()))
+          .collect::<Vec<_>>();
+        assert_eq!(forward, expected, "iter({})", version);
+
+        let mut backward = self
+          .map
+          .iter(*version)
+          .rev()
+          .map(|ent| (ent.key().to_vec(), ent.value().to_vec()))
+          .collect::<Vec<_>>();
+        backward.reverse();
+        assert_eq!(backward, expected, "iter({}).rev()", version);
+      }
+      Op::Range { version, lower } => {
+        let actual = self
+          .map
+          .range(*version, lower.as_slice()..)
+          .map(|ent| (ent.key().to_vec(), ent.value().to_vec()))
+          .collect::<Vec<_>>();
+        assert_eq!(
+          actual,
+          self.oracle_range(*version, lower),
+          "range({}, {:?}..)",
+          version,
+          lower
+        );
+      }
+    }
+  }
+
+  /// Checks the whole state: the structure of the map is verified, and all the versions of
+  /// all the keys are compared to the oracle.
+  ///
+  /// # Panics
+  ///
+  /// Panics if the map is corrupted or diverges from the oracle.
+  pub fn check(&self) {
+    if let Err(e) = self.map.verify() {
+      panic!("the map is corrupted: {}", e);
+    }
+
+    let expected = self
+      .oracle
+      .iter()
+      .flat_map(|(key, versions)| {
+        versions
+          .iter()
+          .rev()
+          .map(move |(version, value)| (key.clone(), *version, value.clone()))
+      })
+      .collect::<Vec<_>>();
+    let actual = self
+      .map
+      .iter_all_versions(u64::MAX)
+      .map(|ent| {
+        (
+          ent.key().to_vec(),
+          ent.version(),
+          ent.value().map(|v| v.to_vec()),
+        )
+      })
+      .collect::<Vec<_>>();
+    assert_eq!(actual, expected, "iter_all_versions");
+    assert_eq!(self.map.len(), expected.len(), "len");
+  }
+
+  /// Writes the entry to the map, returns `false` if the ARENA is full.
+  fn write(&self, version: u64, key: &[u8], value: Option<&[u8]>) -> bool {
+    let res = match value {
+      Some(value) => self.map.insert(version, key, value).map(|_| ()),
+      None => self.map.remove(version, key).map(|_| ()),
+    };
+    match res {
+      Ok(()) => true,
+      Err(e) if e.is_arena_full() => false,
+      Err(e) => panic!("failed to write {:?} at version {}: {}", key, version, e),
+    }
+  }
+
+  fn oracle_get(&self, version: u64, key: &[u8]) -> Option<Vec<u8>> {
+    self
+      .oracle
+      .get(key)?
+      .range(..=version)
+      .next_back()
+      .and_then(|(_, value)| value.clone())
+  }
+
+  /// Returns the visible entries of the keys greater or equal to `lower`.
+  fn oracle_range(&self, version: u64, lower: &[u8]) -> Vec<(Vec<u8>, Vec<u8>)> {
+    self
+      .oracle
+      .range::<[u8], _>(lower..)
+      .filter_map(|(key, _)| Some((key.clone(), self.oracle_get(version, key)?)))
+      .collect()
+  }
+}
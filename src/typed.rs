@@ -0,0 +1,350 @@
+use core::marker::PhantomData;
+
+use either::Either;
+
+use super::{
+  badger::ValueStruct,
+  map::{EntryRef, Error},
+  Options, SkipMap, TooLarge, Trailer, VacantBuffer,
+};
+use std::{string::String, vec::Vec};
+
+/// A type which can be encoded into the ARENA.
+pub trait Encode {
+  /// Returns the encoded size of the value.
+  fn encoded_size(&self) -> usize;
+
+  /// Encodes the value to the buffer, which has at least
+  /// [`encoded_size`](Encode::encoded_size) bytes of remaining space.
+  fn encode(&self, buf: &mut VacantBuffer<'_>) -> Result<(), TooLarge>;
+}
+
+/// A type which can be decoded from the bytes in the ARENA, the decoded value may borrow
+/// the bytes.
+pub trait Decode<'a>: Sized {
+  /// Decodes the value from the bytes, returns `None` if the bytes are corrupted.
+  fn decode(src: &'a [u8]) -> Option<Self>;
+}
+
+/// A type which can be the key of a [`TypedMap`].
+///
+/// The map compares the encoded keys byte-wise, like [`Ascend`](crate::Ascend), so the
+/// encoding must preserve the order of [`Ord`]: `a < b` if and only if the encoded `a` is
+/// lexicographically less than the encoded `b`.
+pub trait Key: Encode + Ord {}
+
+impl<E: Encode + ?Sized> Encode for &E {
+  #[inline]
+  fn encoded_size(&self) -> usize {
+    (**self).encoded_size()
+  }
+
+  #[inline]
+  fn encode(&self, buf: &mut VacantBuffer<'_>) -> Result<(), TooLarge> {
+    (**self).encode(buf)
+  }
+}
+
+impl<K: Key + ?Sized> Key for &K {}
+
+macro_rules! impl_bytes {
+  ($($ty:ty => $as_bytes:ident),+ $(,)?) => {
+    $(
+      impl Encode for $ty {
+        #[inline]
+        fn encoded_size(&self) -> usize {
+          self.len()
+        }
+
+        #[inline]
+        fn encode(&self, buf: &mut VacantBuffer<'_>) -> Result<(), TooLarge> {
+          buf.write(self.$as_bytes())
+        }
+      }
+
+      impl Key for $ty {}
+    )+
+  };
+}
+
+impl_bytes!(
+  [u8] => as_ref,
+  Vec<u8> => as_slice,
+  str => as_bytes,
+  String => as_bytes,
+);
+
+impl<'a> Decode<'a> for &'a [u8] {
+  #[inline]
+  fn decode(src: &'a [u8]) -> Option<Self> {
+    Some(src)
+  }
+}
+
+impl<'a> Decode<'a> for Vec<u8> {
+  #[inline]
+  fn decode(src: &'a [u8]) -> Option<Self> {
+    Some(src.to_vec())
+  }
+}
+
+impl<'a> Decode<'a> for &'a str {
+  #[inline]
+  fn decode(src: &'a [u8]) -> Option<Self> {
+    core::str::from_utf8(src).ok()
+  }
+}
+
+impl<'a> Decode<'a> for String {
+  #[inline]
+  fn decode(src: &'a [u8]) -> Option<Self> {
+    core::str::from_utf8(src).ok().map(String::from)
+  }
+}
+
+macro_rules! impl_unsigned {
+  ($($ty:ty),+ $(,)?) => {
+    $(
+      impl Encode for $ty {
+        #[inline]
+        fn encoded_size(&self) -> usize {
+          core::mem::size_of::<$ty>()
+        }
+
+        #[inline]
+        fn encode(&self, buf: &mut VacantBuffer<'_>) -> Result<(), TooLarge> {
+          buf.write(&self.to_be_bytes())
+        }
+      }
+
+      impl<'a> Decode<'a> for $ty {
+        #[inline]
+        fn decode(src: &'a [u8]) -> Option<Self> {
+          src.try_into().ok().map(<$ty>::from_be_bytes)
+        }
+      }
+
+      impl Key for $ty {}
+    )+
+  };
+}
+
+impl_unsigned!(u8, u16, u32, u64, u128);
+
+// The signed integers are encoded as big endian with the sign bit flipped, so the negative
+// integers sort before the positive ones.
+macro_rules! impl_signed {
+  ($($ty:ty => $unsigned:ty),+ $(,)?) => {
+    $(
+      impl Encode for $ty {
+        #[inline]
+        fn encoded_size(&self) -> usize {
+          core::mem::size_of::<$ty>()
+        }
+
+        #[inline]
+        fn encode(&self, buf: &mut VacantBuffer<'_>) -> Result<(), TooLarge> {
+          buf.write(&((*self as $unsigned) ^ (1 << (<$unsigned>::BITS - 1))).to_be_bytes())
+        }
+      }
+
+      impl<'a> Decode<'a> for $ty {
+        #[inline]
+        fn decode(src: &'a [u8]) -> Option<Self> {
+          src
+            .try_into()
+            .ok()
+            .map(|b| (<$unsigned>::from_be_bytes(b) ^ (1 << (<$unsigned>::BITS - 1))) as $ty)
+        }
+      }
+
+      impl Key for $ty {}
+    )+
+  };
+}
+
+impl_signed!(i8 => u8, i16 => u16, i32 => u32, i64 => u64, i128 => u128);
+
+impl<'b> Encode for ValueStruct<'b> {
+  #[inline]
+  fn encoded_size(&self) -> usize {
+    ValueStruct::encoded_size(self)
+  }
+
+  #[inline]
+  fn encode(&self, buf: &mut VacantBuffer<'_>) -> Result<(), TooLarge> {
+    ValueStruct::encode(self, buf)
+  }
+}
+
+impl<'a> Decode<'a> for ValueStruct<'a> {
+  #[inline]
+  fn decode(src: &'a [u8]) -> Option<Self> {
+    ValueStruct::decode(src)
+  }
+}
+
+fn encode_to_vec<E: Encode + ?Sized>(e: &E) -> Result<Vec<u8>, TooLarge> {
+  let mut bytes = std::vec![0; e.encoded_size()];
+  let cap = bytes.len();
+  let mut buf = VacantBuffer::new(cap, 0, &mut bytes);
+  e.encode(&mut buf)?;
+  let len = buf.len();
+  bytes.truncate(len);
+  Ok(bytes)
+}
+
+/// A wrapper of [`SkipMap`] with typed keys and values, which are encoded by [`Encode`] into
+/// the ARENA and decoded by [`Decode`] on the reads.
+///
+/// The values are encoded directly into the ARENA, the keys are encoded into a temporary
+/// buffer first, so the byte slices, which are copied as they are, stay the fast path: use
+/// the inner [`SkipMap`] directly when the keys and the values are already bytes.
+///
+/// # Example
+///
+/// ```rust
+/// use skl::typed::TypedMap;
+///
+/// let map = TypedMap::<i64, String>::new().unwrap();
+/// map.insert(0, &-1, &"minus one".to_string()).unwrap();
+/// map.insert(0, &1, &"one".to_string()).unwrap();
+/// map.insert(0, &0, &"zero".to_string()).unwrap();
+///
+/// assert_eq!(map.get(0, &1).as_deref(), Some("one"));
+/// assert_eq!(map.iter(0).map(|(k, _)| k).collect::<Vec<_>>(), [-1, 0, 1]);
+/// ```
+pub struct TypedMap<K, V, T = u64> {
+  map: SkipMap<T>,
+  _m: PhantomData<fn(K, V) -> (K, V)>,
+}
+
+impl<K, V, T> core::fmt::Debug for TypedMap<K, V, T> {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    f.debug_struct("TypedMap")
+      .field("len", &self.map.len())
+      .finish()
+  }
+}
+
+impl<K, V, T> Clone for TypedMap<K, V, T> {
+  #[inline]
+  fn clone(&self) -> Self {
+    Self {
+      map: self.map.clone(),
+      _m: PhantomData,
+    }
+  }
+}
+
+impl<K, V, T> From<SkipMap<T>> for TypedMap<K, V, T> {
+  #[inline]
+  fn from(map: SkipMap<T>) -> Self {
+    Self {
+      map,
+      _m: PhantomData,
+    }
+  }
+}
+
+impl<K, V, T> TypedMap<K, V, T> {
+  /// Creates a new map with the default options.
+  #[inline]
+  pub fn new() -> Result<Self, Error> {
+    SkipMap::new().map(Self::from)
+  }
+
+  /// Creates a new map with the given options.
+  #[inline]
+  pub fn with_options(opts: Options) -> Result<Self, Error> {
+    SkipMap::with_options(opts).map(Self::from)
+  }
+
+  /// Returns the inner [`SkipMap`].
+  #[inline]
+  pub const fn as_inner(&self) -> &SkipMap<T> {
+    &self.map
+  }
+
+  /// Consumes the map and returns the inner [`SkipMap`].
+  #[inline]
+  pub fn into_inner(self) -> SkipMap<T> {
+    self.map
+  }
+
+  /// Returns the number of entries in the map.
+  #[inline]
+  pub fn len(&self) -> usize {
+    self.map.len()
+  }
+
+  /// Returns `true` if the map contains no entries.
+  #[inline]
+  pub fn is_empty(&self) -> bool {
+    self.map.is_empty()
+  }
+}
+
+impl<K: Key, V: Encode, T: Trailer> TypedMap<K, V, T> {
+  /// Upserts a key-value pair into the map.
+  ///
+  /// See [`SkipMap::insert`] for more details.
+  pub fn insert(&self, trailer: T, key: &K, value: &V) -> Result<(), Either<TooLarge, Error>> {
+    let key = encode_to_vec(key).map_err(Either::Left)?;
+    self
+      .map
+      .insert_with_value(trailer, &key, value.encoded_size() as u32, |buf| {
+        value.encode(buf)
+      })
+      .map(|_| ())
+  }
+
+  /// Removes the key by writing a removed entry.
+  ///
+  /// See [`SkipMap::remove`] for more details.
+  pub fn remove(&self, trailer: T, key: &K) -> Result<(), Either<TooLarge, Error>> {
+    let key = encode_to_vec(key).map_err(Either::Left)?;
+    self
+      .map
+      .remove(trailer, &key)
+      .map(|_| ())
+      .map_err(Either::Right)
+  }
+
+  /// Returns true if the key exists in the map.
+  #[inline]
+  pub fn contains_key(&self, version: u64, key: &K) -> bool {
+    self.get_entry(version, key).is_some()
+  }
+
+  /// Returns the decoded value of the key, `None` if the key does not exist or the value
+  /// cannot be decoded.
+  pub fn get<'a>(&'a self, version: u64, key: &K) -> Option<V>
+  where
+    V: Decode<'a>,
+  {
+    self
+      .get_entry(version, key)
+      .and_then(|ent| V::decode(ent.value()))
+  }
+
+  /// Returns an iterator over the decoded entries of the map, in the order of the keys.
+  ///
+  /// The entries which cannot be decoded, e.g. the ones written through the inner
+  /// [`SkipMap`] with other encodings, are skipped.
+  pub fn iter<'a>(&'a self, version: u64) -> impl DoubleEndedIterator<Item = (K, V)> + 'a
+  where
+    K: Decode<'a> + 'a,
+    V: Decode<'a> + 'a,
+  {
+    self.map.iter(version).filter_map(|ent| {
+      let (k, v) = ent.into_key_value();
+      Some((K::decode(k)?, V::decode(v)?))
+    })
+  }
+
+  fn get_entry(&self, version: u64, key: &K) -> Option<EntryRef<'_, T>> {
+    let key = encode_to_vec(key).ok()?;
+    self.map.get_in(version, &key)
+  }
+}